@@ -1,4 +1,6 @@
 use crate::errors::cache::CacheError;
+use crate::types::kam_toml::sections::VersionSpec;
+use sha2::{Digest, Sha256};
 
 /// # Kam Cache System
 ///
@@ -10,7 +12,9 @@ use crate::errors::cache::CacheError;
 /// ~/.kam/ (or /data/adb/kam on Android)
 /// ├── bin/      # Executable binary files (provided by library modules)
 /// ├── lib/      # Library modules (extracted dependencies, not compressed)
+/// ├── locks/    # Lock files held by running kam processes
 /// ├── log/      # Log files
+/// ├── pins.toml # lib/ entries kept by `kam cache gc` and `clear-dir lib`
 /// ├── profile/  # template module archives
 /// ├── repo/     # Repository index cache (synced from kam_repo_index)
 /// └── tmpl/     # built-in templates extracted from assets/tmpl
@@ -29,7 +33,55 @@ use crate::errors::cache::CacheError;
 /// let bin_path = cache.bin_dir();
 /// # Ok::<(), Box<dyn std::error::Error>>(())
 /// ```
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs::{File, TryLockError};
+use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::thread::ThreadId;
+use std::time::{Duration, Instant};
+
+/// Environment variable overriding how long [`KamCache::lock`] waits, in
+/// seconds
+pub const LOCK_TIMEOUT_ENV: &str = "KAM_LOCK_TIMEOUT";
+
+/// Default time [`KamCache::lock`] waits for another holder
+pub const DEFAULT_LOCK_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Cache directories carried over by [`KamCache::move_to`]
+const DATA_DIRS: &[&str] = &[
+    "bin", "lib", "lib64", "log", "profile", "repo", "tmpl", "index", "git",
+];
+
+/// How long [`KamCache::lock`] waits: `KAM_LOCK_TIMEOUT` seconds, or
+/// [`DEFAULT_LOCK_TIMEOUT`]
+fn lock_timeout() -> Duration {
+    std::env::var(LOCK_TIMEOUT_ENV)
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_LOCK_TIMEOUT)
+}
+
+/// File in the cache root listing pinned library modules
+const PINS_FILE: &str = "pins.toml";
+
+/// Cache locks held by this process, keyed by lock file path. The OS lock
+/// only excludes other processes, so threads of this process are excluded
+/// here, while the owning thread may re-acquire the lock it holds.
+static HELD_LOCKS: Mutex<BTreeMap<PathBuf, HeldLock>> = Mutex::new(BTreeMap::new());
+static LOCK_RELEASED: Condvar = Condvar::new();
+
+struct HeldLock {
+    file: File,
+    owner: ThreadId,
+    depth: usize,
+}
+
+fn held_locks() -> MutexGuard<'static, BTreeMap<PathBuf, HeldLock>> {
+    HELD_LOCKS.lock().unwrap_or_else(|e| e.into_inner())
+}
 
 // CacheError is defined in `src/errors/cache.rs` and re-exported here for
 // backwards compatibility as `crate::cache::CacheError`.
@@ -166,6 +218,21 @@ impl KamCache {
         self.root.join("lib64")
     }
 
+    /// Get the git directory (clones of git sources)
+    ///
+    /// `db/` holds one clone per repository URL, updated by later fetches;
+    /// `checkouts/` holds the files of pinned commits, which never change.
+    pub fn git_dir(&self) -> PathBuf {
+        self.root.join("git")
+    }
+
+    /// Get the locks directory
+    ///
+    /// Each lock is a `<name>.lock` file recording the owning process.
+    pub fn locks_dir(&self) -> PathBuf {
+        self.root.join("locks")
+    }
+
     /// Ensure all cache directories exist
     ///
     /// Creates the cache root and all subdirectories if they don't exist.
@@ -178,6 +245,7 @@ impl KamCache {
     /// cache.ensure_dirs().unwrap();
     /// ```
     pub fn ensure_dirs(&self) -> Result<(), CacheError> {
+        let _guard = self.lock()?;
         std::fs::create_dir_all(&self.root)?;
         std::fs::create_dir_all(self.bin_dir())?;
         std::fs::create_dir_all(self.lib_dir())?;
@@ -208,6 +276,41 @@ impl KamCache {
         self.lib_dir().join(format!("{}-{}", id, version))
     }
 
+    /// List the version codes of a library present in the cache
+    ///
+    /// Library directories are named `<id>-<versionCode>`; entries whose
+    /// suffix is not a version code are ignored.
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// use kam::cache::KamCache;
+    /// let cache = KamCache::new().unwrap();
+    /// let versions = cache.cached_versions("core-lib");
+    /// ```
+    pub fn cached_versions(&self, id: &str) -> Vec<i64> {
+        module_versions_in(&self.lib_dir(), id)
+    }
+
+    /// The `prop.version` recorded in a cached library's `kam.toml`
+    ///
+    /// Returns `None` when the module is not cached or its `kam.toml` has no
+    /// version string.
+    pub fn cached_version_name(&self, id: &str, version_code: i64) -> Option<String> {
+        module_version_name(&self.lib_module_path(id, &version_code.to_string()))
+    }
+
+    /// Resolve a dependency version spec to a concrete version code
+    ///
+    /// Exact specs resolve to themselves. Ranges resolve to the highest
+    /// cached version inside the range, falling back to the range's lower
+    /// bound. Semver requirements resolve to the highest cached version
+    /// whose `prop.version` matches. Returns `None` when no version can be
+    /// determined.
+    pub fn resolve_version(&self, id: &str, spec: Option<&VersionSpec>) -> Option<i64> {
+        VersionResolver::new(self).resolve(id, spec)
+    }
+
     /// Get the path to a binary in the cache
     ///
     /// ## Arguments
@@ -243,6 +346,166 @@ impl KamCache {
         self.profile_dir().join(format!("{}-{}.zip", id, version))
     }
 
+    /// Take the cache-wide advisory lock (`locks/cache.lock`)
+    ///
+    /// Blocks until other `kam` processes (and other threads of this one)
+    /// release it, for up to `KAM_LOCK_TIMEOUT` seconds (default 60). The
+    /// thread holding the lock may take it again; it is released when the
+    /// last [`CacheGuard`] is dropped.
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// use kam::cache::KamCache;
+    /// let cache = KamCache::new().unwrap();
+    /// let _guard = cache.lock().unwrap();
+    /// // ... modify the cache ...
+    /// ```
+    pub fn lock(&self) -> Result<CacheGuard, CacheError> {
+        self.lock_with_timeout(lock_timeout())
+    }
+
+    /// [`KamCache::lock`] with an explicit timeout
+    pub fn lock_with_timeout(&self, timeout: Duration) -> Result<CacheGuard, CacheError> {
+        self.lock_named_with_timeout("cache", timeout)
+    }
+
+    /// Take the advisory lock `locks/<name>.lock`, which guards one entry of
+    /// the cache (a git clone, say) instead of all of it, so long-running
+    /// work on that entry doesn't hold up the rest. Waits like
+    /// [`KamCache::lock`].
+    pub fn lock_named(&self, name: &str) -> Result<CacheGuard, CacheError> {
+        self.lock_named_with_timeout(name, lock_timeout())
+    }
+
+    fn lock_named_with_timeout(
+        &self,
+        name: &str,
+        timeout: Duration,
+    ) -> Result<CacheGuard, CacheError> {
+        let path = self.locks_dir().join(format!("{}.lock", name));
+        let deadline = Instant::now() + timeout;
+        let me = std::thread::current().id();
+        let timed_out = |path: &Path| {
+            let content = std::fs::read_to_string(path).unwrap_or_default();
+            let holder = LockInfo::parse(path, &content);
+            CacheError::LockTimeout {
+                path: path.to_path_buf(),
+                seconds: timeout.as_secs(),
+                holder: holder
+                    .pid
+                    .map(|pid| {
+                        format!(
+                            "pid {}{}",
+                            pid,
+                            holder
+                                .command
+                                .map(|c| format!(" ({})", c))
+                                .unwrap_or_default()
+                        )
+                    })
+                    .unwrap_or_else(|| "another thread of this process".to_string()),
+            }
+        };
+
+        // Wait for other threads of this process
+        let mut held = held_locks();
+        loop {
+            match held.get_mut(&path) {
+                Some(lock) if lock.owner == me => {
+                    lock.depth += 1;
+                    return Ok(CacheGuard { path });
+                }
+                Some(_) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Err(timed_out(&path));
+                    }
+                    held = LOCK_RELEASED
+                        .wait_timeout(held, deadline - now)
+                        .unwrap_or_else(|e| e.into_inner())
+                        .0;
+                }
+                None => break,
+            }
+        }
+
+        // Then for other processes
+        std::fs::create_dir_all(self.locks_dir())?;
+        let mut file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+        loop {
+            match file.try_lock() {
+                Ok(()) => break,
+                Err(TryLockError::WouldBlock) if Instant::now() < deadline => {
+                    std::thread::sleep(Duration::from_millis(100));
+                }
+                Err(TryLockError::WouldBlock) => return Err(timed_out(&path)),
+                Err(TryLockError::Error(e)) => return Err(e.into()),
+            }
+        }
+
+        // Record the holder for `kam cache lock-status`
+        file.set_len(0)?;
+        write!(
+            file,
+            "pid={}\nacquired={}\ncommand={}\n",
+            std::process::id(),
+            chrono::Utc::now().to_rfc3339(),
+            std::env::args().collect::<Vec<_>>().join(" ")
+        )?;
+        held.insert(
+            path.clone(),
+            HeldLock {
+                file,
+                owner: me,
+                depth: 1,
+            },
+        );
+        Ok(CacheGuard { path })
+    }
+
+    /// List the lock files currently present in the cache
+    ///
+    /// Lock files that cannot be parsed are still reported (with no owner)
+    /// so they can be cleared. Released locks are left empty and are not
+    /// listed.
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// use kam::cache::KamCache;
+    /// let cache = KamCache::new().unwrap();
+    /// for lock in cache.list_locks().unwrap() {
+    ///     println!("{} held by {:?}", lock.name, lock.pid);
+    /// }
+    /// ```
+    pub fn list_locks(&self) -> Result<Vec<LockInfo>, CacheError> {
+        let dir = self.locks_dir();
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut locks = Vec::new();
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("lock") {
+                continue;
+            }
+            let content = std::fs::read_to_string(&path).unwrap_or_default();
+            if content.is_empty() {
+                continue;
+            }
+            locks.push(LockInfo::parse(&path, &content));
+        }
+        locks.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(locks)
+    }
+
     /// Clear the entire cache
     ///
     /// **Warning**: This removes all cached modules, binaries, and logs.
@@ -263,6 +526,8 @@ impl KamCache {
 
     /// Clear a specific cache directory
     ///
+    /// Pinned modules (see [`KamCache::pin`]) are kept when clearing `lib`.
+    ///
     /// ## Arguments
     ///
     /// - `dir`: Directory type ("bin", "lib", "log", "profile", or "tmpl")
@@ -284,6 +549,7 @@ impl KamCache {
             "profile" => self.profile_dir(),
             "repo" => self.repo_dir(),
             "tmpl" => self.tmpl_dir(),
+            "git" => self.git_dir(),
             _ => {
                 return Err(CacheError::InvalidPath(format!(
                     "Unknown cache directory: {}",
@@ -292,14 +558,178 @@ impl KamCache {
             }
         };
 
-        if path.exists() {
+        let pinned = if dir == "lib" {
+            self.pins()?
+        } else {
+            BTreeSet::new()
+        };
+        if path.exists() && pinned.is_empty() {
             std::fs::remove_dir_all(&path)?;
             std::fs::create_dir_all(&path)?;
+        } else if path.exists() {
+            for entry in std::fs::read_dir(&path)? {
+                let entry = entry?;
+                if pinned.contains(entry.file_name().to_string_lossy().as_ref()) {
+                    continue;
+                }
+                if entry.file_type()?.is_dir() {
+                    std::fs::remove_dir_all(entry.path())?;
+                } else {
+                    std::fs::remove_file(entry.path())?;
+                }
+            }
         }
 
         Ok(())
     }
 
+    /// Path of `pins.toml`, which lists the pinned library modules
+    pub fn pins_path(&self) -> PathBuf {
+        self.root.join(PINS_FILE)
+    }
+
+    /// `<id>-<versionCode>` names of the pinned library modules
+    ///
+    /// Pinned modules count as referenced for every pruning operation, so
+    /// [`KamCache::gc`] and [`KamCache::clear_dir`] never remove them.
+    /// Returns an empty set when nothing was ever pinned.
+    pub fn pins(&self) -> Result<BTreeSet<String>, CacheError> {
+        let path = self.pins_path();
+        if !path.exists() {
+            return Ok(BTreeSet::new());
+        }
+        let invalid =
+            |reason: String| CacheError::InvalidPins(format!("{}: {}", path.display(), reason));
+        let table: toml::Table = std::fs::read_to_string(&path)?
+            .parse()
+            .map_err(|e: toml::de::Error| invalid(e.message().to_string()))?;
+        let Some(pinned) = table.get("pinned") else {
+            return Ok(BTreeSet::new());
+        };
+        pinned
+            .as_array()
+            .ok_or_else(|| invalid("`pinned` must be an array of strings".to_string()))?
+            .iter()
+            .map(|v| {
+                v.as_str()
+                    .map(str::to_string)
+                    .ok_or_else(|| invalid("`pinned` must be an array of strings".to_string()))
+            })
+            .collect()
+    }
+
+    /// Pin the cached library module `<id>-<versionCode>` so pruning keeps
+    /// it. The module must be installed under `lib/`. Returns `false` when
+    /// it was already pinned.
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// use kam::cache::KamCache;
+    /// let cache = KamCache::new().unwrap();
+    /// cache.pin("core-lib", 1000).unwrap();
+    /// ```
+    pub fn pin(&self, id: &str, version_code: i64) -> Result<bool, CacheError> {
+        let name = format!("{}-{}", id, version_code);
+        if !self.lib_dir().join(&name).is_dir() {
+            return Err(CacheError::NotCached(format!(
+                "{} (looked in {})",
+                name,
+                self.lib_dir().display()
+            )));
+        }
+        let _guard = self.lock()?;
+        let mut pins = self.pins()?;
+        let added = pins.insert(name);
+        if added {
+            self.write_pins(&pins)?;
+        }
+        Ok(added)
+    }
+
+    /// Remove the pin of `<id>-<versionCode>`. Returns `false` when it was
+    /// not pinned.
+    pub fn unpin(&self, id: &str, version_code: i64) -> Result<bool, CacheError> {
+        let _guard = self.lock()?;
+        let mut pins = self.pins()?;
+        let removed = pins.remove(&format!("{}-{}", id, version_code));
+        if removed {
+            self.write_pins(&pins)?;
+        }
+        Ok(removed)
+    }
+
+    fn write_pins(&self, pins: &BTreeSet<String>) -> Result<(), CacheError> {
+        let mut content = String::from(
+            "# Library modules kept by `kam cache gc` and `kam cache clear-dir lib`\n",
+        );
+        let mut table = toml::Table::new();
+        table.insert(
+            "pinned".to_string(),
+            toml::Value::Array(pins.iter().cloned().map(toml::Value::String).collect()),
+        );
+        content.push_str(&table.to_string());
+        std::fs::create_dir_all(&self.root)?;
+        std::fs::write(self.pins_path(), content)?;
+        Ok(())
+    }
+
+    /// Move the cache to `new_root`, an absolute path (see
+    /// [`KamCache::with_root`])
+    ///
+    /// The cache directories (`bin/`, `lib/`, `log/`, `profile/`, `tmpl/`,
+    /// ...) are renamed into the new root, or copied and removed when it is
+    /// on another filesystem. A non-empty `new_root` is refused unless
+    /// `force`, in which case directories of the same name there are
+    /// replaced. Returns the names of the directories moved.
+    pub fn move_to(&self, new_root: &Path, force: bool) -> Result<Vec<&'static str>, CacheError> {
+        let target = KamCache::with_root(new_root)?;
+        let old_root = self
+            .root
+            .canonicalize()
+            .unwrap_or_else(|_| self.root.clone());
+        let new_root = target
+            .root
+            .canonicalize()
+            .unwrap_or_else(|_| target.root.clone());
+        if new_root.starts_with(&old_root) || old_root.starts_with(&new_root) {
+            return Err(CacheError::InvalidPath(format!(
+                "cannot move the cache at {} into {}",
+                old_root.display(),
+                new_root.display()
+            )));
+        }
+        let occupied = std::fs::read_dir(&new_root).is_ok_and(|mut d| d.next().is_some());
+        if occupied && !force {
+            return Err(CacheError::InvalidPath(format!(
+                "{} is not empty; use --force to move the cache there anyway",
+                new_root.display()
+            )));
+        }
+
+        let mut moved = Vec::new();
+        {
+            let _guard = self.lock()?;
+            std::fs::create_dir_all(&new_root)?;
+            for name in DATA_DIRS {
+                let src = self.root.join(name);
+                if !src.exists() {
+                    continue;
+                }
+                let dst = new_root.join(name);
+                if dst.exists() {
+                    std::fs::remove_dir_all(&dst)?;
+                }
+                move_dir(&src, &dst)?;
+                moved.push(*name);
+            }
+        }
+        // Leave nothing behind but files the cache doesn't own
+        let _ = std::fs::remove_dir_all(self.locks_dir());
+        let _ = std::fs::remove_dir(&self.root);
+        Ok(moved)
+    }
+
     /// Get cache statistics
     ///
     /// Returns the total size and number of files in the cache.
@@ -323,6 +753,232 @@ impl KamCache {
         Ok(stats)
     }
 
+    /// Get statistics for one directory of the cache, such as `bin_dir()`
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// use kam::cache::KamCache;
+    /// let cache = KamCache::new().unwrap();
+    /// let stats = cache.dir_stats(&cache.lib_dir()).unwrap();
+    /// println!("lib/: {}", stats.format_size());
+    /// ```
+    pub fn dir_stats(&self, path: &Path) -> Result<CacheStats, CacheError> {
+        let mut stats = CacheStats::default();
+        Self::compute_dir_stats(path, &mut stats)?;
+        Ok(stats)
+    }
+
+    /// Number of library modules installed under `lib/` (`<id>-<versionCode>`
+    /// directories; staging directories of running syncs are not counted)
+    pub fn installed_module_count(&self) -> usize {
+        let Ok(entries) = std::fs::read_dir(self.lib_dir()) else {
+            return 0;
+        };
+        entries
+            .flatten()
+            .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+            .filter_map(|e| e.file_name().to_str().map(|s| s.to_string()))
+            .filter(|name| {
+                !name.starts_with('.')
+                    && name
+                        .rsplit_once('-')
+                        .is_some_and(|(id, v)| !id.is_empty() && v.parse::<i64>().is_ok())
+            })
+            .count()
+    }
+
+    /// Get statistics for a single cached library module
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// use kam::cache::KamCache;
+    /// let cache = KamCache::new().unwrap();
+    /// let stats = cache.module_stats("core-lib", "1000").unwrap();
+    /// println!("{}", stats.format_size());
+    /// ```
+    pub fn module_stats(&self, id: &str, version: &str) -> Result<CacheStats, CacheError> {
+        let mut stats = CacheStats::default();
+        Self::compute_dir_stats(&self.lib_module_path(id, version), &mut stats)?;
+        Ok(stats)
+    }
+
+    /// Content hash of a cached library module, as `sha256:<hex>`
+    ///
+    /// Covers the relative path and contents of every file in the module
+    /// directory (in sorted order), except the `.synced` marker, so the same
+    /// archive always hashes the same however it was fetched.
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// use kam::cache::KamCache;
+    /// let cache = KamCache::new().unwrap();
+    /// let checksum = cache.module_checksum("core-lib", "1000").unwrap();
+    /// ```
+    pub fn module_checksum(&self, id: &str, version: &str) -> Result<String, CacheError> {
+        let module_path = self.lib_module_path(id, version);
+        let mut files: Vec<PathBuf> = walkdir::WalkDir::new(&module_path)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file() && e.file_name() != ".synced")
+            .map(|e| e.into_path())
+            .collect();
+        files.sort();
+
+        let mut hasher = Sha256::new();
+        for file in files {
+            let rel = file.strip_prefix(&module_path).unwrap_or(&file);
+            hasher.update(rel.to_string_lossy().replace('\\', "/").as_bytes());
+            hasher.update([0]);
+            hasher.update(std::fs::read(&file)?);
+            hasher.update([0]);
+        }
+        Ok(format!("sha256:{:x}", hasher.finalize()))
+    }
+
+    /// Remove cached library modules that are not referenced
+    ///
+    /// `referenced` holds `<id>-<version>` directory names under `lib/` that
+    /// must be kept. Besides those, pinned modules (see [`KamCache::pin`])
+    /// and the newest `keep_latest` versions of each module id are kept. Everything else under `lib/` is removed (or only
+    /// reported when `dry_run` is set); `bin/`, `log/` and `tmpl/` are never
+    /// touched.
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// use kam::cache::KamCache;
+    /// use std::collections::HashSet;
+    /// let cache = KamCache::new().unwrap();
+    /// let referenced: HashSet<String> = ["core-lib-1000".to_string()].into();
+    /// let report = cache.gc(&referenced, 1, true).unwrap();
+    /// println!("would free {}", report.freed.format_size());
+    /// ```
+    pub fn gc(
+        &self,
+        referenced: &HashSet<String>,
+        keep_latest: usize,
+        dry_run: bool,
+    ) -> Result<GcReport, CacheError> {
+        let lib_dir = self.lib_dir();
+        let mut names: Vec<String> = match std::fs::read_dir(&lib_dir) {
+            Ok(entries) => entries
+                .flatten()
+                .filter_map(|e| e.file_name().to_str().map(|s| s.to_string()))
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        names.sort();
+
+        // Newest `keep_latest` versions of each id
+        let mut by_id: HashMap<&str, Vec<(i64, &str)>> = HashMap::new();
+        for name in &names {
+            if let Some((id, code)) = name
+                .rsplit_once('-')
+                .and_then(|(id, v)| v.parse::<i64>().ok().map(|code| (id, code)))
+            {
+                by_id.entry(id).or_default().push((code, name));
+            }
+        }
+        let mut kept: HashSet<&str> = HashSet::new();
+        for versions in by_id.values_mut() {
+            versions.sort_unstable_by_key(|(code, _)| std::cmp::Reverse(*code));
+            kept.extend(versions.iter().take(keep_latest).map(|(_, name)| *name));
+        }
+
+        let pinned = self.pins()?;
+        let mut report = GcReport::default();
+        for name in &names {
+            if referenced.contains(name) || kept.contains(name.as_str()) {
+                continue;
+            }
+            if pinned.contains(name) {
+                report.pinned.push(name.clone());
+                continue;
+            }
+            let path = lib_dir.join(name);
+            if path.is_dir() {
+                Self::compute_dir_stats(&path, &mut report.freed)?;
+                if !dry_run {
+                    std::fs::remove_dir_all(&path)?;
+                }
+            } else {
+                report.freed.file_count += 1;
+                report.freed.total_size += std::fs::symlink_metadata(&path)?.len();
+                if !dry_run {
+                    std::fs::remove_file(&path)?;
+                }
+            }
+            report.removed.push(name.clone());
+        }
+        Ok(report)
+    }
+
+    /// Check every module installed under `lib/`.
+    ///
+    /// A `<id>-<versionCode>` directory is healthy when it has a loadable
+    /// `kam.toml` for that id and version code and the `.synced` marker that
+    /// is written once an install completes. Leftover `.partial` staging
+    /// directories of interrupted syncs are always broken. With `repair`,
+    /// broken entries are removed (under the cache lock) so the next
+    /// `kam sync` fetches them again.
+    pub fn verify(&self, repair: bool) -> Result<VerifyReport, CacheError> {
+        let lib_dir = self.lib_dir();
+        let mut names: Vec<String> = match std::fs::read_dir(&lib_dir) {
+            Ok(entries) => entries
+                .flatten()
+                .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+                .filter_map(|e| e.file_name().to_str().map(|s| s.to_string()))
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        names.sort();
+
+        let mut report = VerifyReport::default();
+        for name in names {
+            let problem = if name.starts_with('.') && name.contains(".partial-") {
+                Some("incomplete download (leftover staging directory)".to_string())
+            } else if let Some((id, code)) = name
+                .rsplit_once('-')
+                .and_then(|(id, v)| v.parse::<i64>().ok().map(|code| (id, code)))
+            {
+                let module_dir = lib_dir.join(&name);
+                match crate::types::kam_toml::KamToml::load_from_dir(&module_dir) {
+                    Err(_) if !module_dir.join("kam.toml").is_file() => {
+                        Some("missing kam.toml".to_string())
+                    }
+                    Err(e) => Some(format!("kam.toml cannot be loaded: {}", e)),
+                    Ok(kt) if kt.prop.id != id || kt.prop.versionCode != code => Some(format!(
+                        "kam.toml is for {}-{}",
+                        kt.prop.id, kt.prop.versionCode
+                    )),
+                    Ok(_) if !module_dir.join(".synced").is_file() => {
+                        Some("missing .synced marker".to_string())
+                    }
+                    Ok(_) => None,
+                }
+            } else {
+                // Not a module directory (e.g. library artifacts)
+                continue;
+            };
+            match problem {
+                None => report.healthy.push(name),
+                Some(reason) => report.broken.push((name, reason)),
+            }
+        }
+
+        if repair && !report.broken.is_empty() {
+            let _guard = self.lock()?;
+            for (name, _) in &report.broken {
+                std::fs::remove_dir_all(lib_dir.join(name))?;
+                report.repaired.push(name.clone());
+            }
+        }
+        Ok(report)
+    }
+
     /// Recursively compute directory statistics
     fn compute_dir_stats(path: &Path, stats: &mut CacheStats) -> Result<(), CacheError> {
         if !path.exists() {
@@ -345,6 +1001,171 @@ impl KamCache {
     }
 }
 
+/// Memoizing front-end to [`KamCache::resolve_version`]
+///
+/// Lives for the duration of a single command so that each `id@spec` is
+/// resolved, and each library directory listing is read, only once even when
+/// the same module appears many times in the dependency graph.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use kam::cache::{KamCache, VersionResolver};
+/// let cache = KamCache::new().unwrap();
+/// let resolver = VersionResolver::new(&cache);
+/// let version = resolver.resolve("core-lib", None);
+/// ```
+pub struct VersionResolver<'a> {
+    cache: &'a KamCache,
+    versions: RefCell<HashMap<String, Vec<i64>>>,
+    resolved: RefCell<HashMap<String, Option<i64>>>,
+}
+
+impl<'a> VersionResolver<'a> {
+    /// Create an empty resolver over `cache`
+    pub fn new(cache: &'a KamCache) -> Self {
+        Self {
+            cache,
+            versions: RefCell::new(HashMap::new()),
+            resolved: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// The underlying cache
+    pub fn cache(&self) -> &KamCache {
+        self.cache
+    }
+
+    /// Same as [`KamCache::cached_versions`], read once per id
+    pub fn cached_versions(&self, id: &str) -> Vec<i64> {
+        self.versions
+            .borrow_mut()
+            .entry(id.to_string())
+            .or_insert_with(|| self.cache.cached_versions(id))
+            .clone()
+    }
+
+    /// Same as [`KamCache::resolve_version`], resolved once per `id@spec`
+    pub fn resolve(&self, id: &str, spec: Option<&VersionSpec>) -> Option<i64> {
+        let key = format!(
+            "{}@{}",
+            id,
+            spec.map(|s| s.as_display()).unwrap_or_default()
+        );
+        if let Some(v) = self.resolved.borrow().get(&key) {
+            return *v;
+        }
+        let version = match spec? {
+            VersionSpec::Exact(v) => Some(*v),
+            spec => self
+                .cached_versions(id)
+                .into_iter()
+                .rev()
+                .find(|v| {
+                    let name = match spec {
+                        VersionSpec::Semver(_) => self.cache.cached_version_name(id, *v),
+                        _ => None,
+                    };
+                    spec.matches(name.as_deref().unwrap_or_default(), *v)
+                })
+                .or_else(|| spec.lower_bound()),
+        };
+        self.resolved.borrow_mut().insert(key, version);
+        version
+    }
+}
+
+/// Sorted version codes of the `<id>-<versionCode>` module directories in
+/// `dir` (the cache's `lib/`, or a project's vendor directory)
+pub fn module_versions_in(dir: &Path, id: &str) -> Vec<i64> {
+    let prefix = format!("{}-", id);
+    let mut versions: Vec<i64> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|e| {
+                    e.file_name()
+                        .to_str()
+                        .and_then(|name| name.strip_prefix(&prefix))
+                        .and_then(|rest| rest.parse::<i64>().ok())
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    versions.sort_unstable();
+    versions
+}
+
+/// The `prop.version` in the `kam.toml` of an extracted module directory
+pub fn module_version_name(module_dir: &Path) -> Option<String> {
+    let path = module_dir.join("kam.toml");
+    let value: toml::Table = std::fs::read_to_string(path).ok()?.parse().ok()?;
+    value
+        .get("prop")?
+        .get("version")?
+        .as_str()
+        .map(|s| s.to_string())
+}
+
+/// Rename `src` to `dst`, or copy it and remove the original when they are
+/// on different filesystems
+fn move_dir(src: &Path, dst: &Path) -> std::io::Result<()> {
+    match std::fs::rename(src, dst) {
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+            crate::venv::copy_dir_all(src, dst)?;
+            std::fs::remove_dir_all(src)
+        }
+        result => result,
+    }
+}
+
+/// Holds the cache lock taken by [`KamCache::lock`]; releases it on drop
+#[derive(Debug)]
+pub struct CacheGuard {
+    path: PathBuf,
+}
+
+impl Drop for CacheGuard {
+    fn drop(&mut self) {
+        let mut held = held_locks();
+        let Some(lock) = held.get_mut(&self.path) else {
+            return;
+        };
+        lock.depth -= 1;
+        if lock.depth == 0
+            && let Some(lock) = held.remove(&self.path)
+        {
+            // Empty the file so the lock no longer shows as held, then close
+            // it, which releases the OS lock
+            let _ = lock.file.set_len(0);
+            drop(lock.file);
+            LOCK_RELEASED.notify_all();
+        }
+    }
+}
+
+/// Result of [`KamCache::gc`]
+#[derive(Debug, Default, Clone)]
+pub struct GcReport {
+    /// `lib/` entries removed (or that would be removed on a dry run)
+    pub removed: Vec<String>,
+    /// Space taken by the removed entries
+    pub freed: CacheStats,
+    /// Unreferenced entries kept because they are pinned
+    pub pinned: Vec<String>,
+}
+
+/// Result of [`KamCache::verify`]
+#[derive(Debug, Default, Clone)]
+pub struct VerifyReport {
+    /// Module directories that passed every check
+    pub healthy: Vec<String>,
+    /// Broken entries with the reason they failed
+    pub broken: Vec<(String, String)>,
+    /// Broken entries that were removed
+    pub repaired: Vec<String>,
+}
+
 /// Cache statistics
 #[derive(Debug, Default, Clone)]
 pub struct CacheStats {
@@ -378,3 +1199,163 @@ impl CacheStats {
         format!("{:.2} {}", size, UNITS[unit_idx])
     }
 }
+
+/// A lock file found in the cache `locks/` directory
+///
+/// Lock files are plain `key=value` lines:
+///
+/// ```text
+/// pid=12345
+/// acquired=2025-01-01T12:00:00+00:00
+/// command=sync
+/// ```
+#[derive(Debug, Clone)]
+pub struct LockInfo {
+    /// Lock name (file stem)
+    pub name: String,
+    /// Path of the lock file
+    pub path: PathBuf,
+    /// Pid of the owning process
+    pub pid: Option<u32>,
+    /// When the lock was acquired (RFC 3339)
+    pub acquired: Option<String>,
+    /// Command that acquired the lock
+    pub command: Option<String>,
+}
+
+impl LockInfo {
+    /// Parse the contents of a lock file
+    pub fn parse(path: &Path, content: &str) -> Self {
+        let mut info = LockInfo {
+            name: path
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            path: path.to_path_buf(),
+            pid: None,
+            acquired: None,
+            command: None,
+        };
+        for line in content.lines() {
+            match line.split_once('=') {
+                Some(("pid", v)) => info.pid = v.trim().parse().ok(),
+                Some(("acquired", v)) => info.acquired = Some(v.trim().to_string()),
+                Some(("command", v)) => info.command = Some(v.trim().to_string()),
+                _ => {}
+            }
+        }
+        info
+    }
+
+    /// Whether the owning process is still running
+    ///
+    /// Returns `None` when liveness cannot be determined on this platform.
+    /// A lock without a readable pid is treated as abandoned.
+    pub fn owner_alive(&self) -> Option<bool> {
+        let Some(pid) = self.pid else {
+            return Some(false);
+        };
+        let proc_dir = Path::new("/proc");
+        if cfg!(unix) && proc_dir.is_dir() {
+            Some(proc_dir.join(pid.to_string()).exists())
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Whether another process could take the lock file at `path` now
+    fn lockable(path: &Path) -> bool {
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .try_lock()
+            .is_ok()
+    }
+
+    #[test]
+    fn test_lock_is_reentrant_and_released_on_drop() {
+        let root = tempfile::tempdir().unwrap();
+        let cache = KamCache::with_root(root.path()).unwrap();
+        let path = cache.locks_dir().join("cache.lock");
+
+        let outer = cache.lock().unwrap();
+        let inner = cache.lock_with_timeout(Duration::ZERO).unwrap();
+        let locks = cache.list_locks().unwrap();
+        assert_eq!(locks.len(), 1);
+        assert_eq!(locks[0].name, "cache");
+        assert_eq!(locks[0].pid, Some(std::process::id()));
+        assert!(locks[0].acquired.is_some() && locks[0].command.is_some());
+
+        // Held until the outermost guard goes
+        drop(inner);
+        assert!(!lockable(&path));
+        drop(outer);
+        assert!(lockable(&path));
+        assert!(cache.list_locks().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_lock_blocks_other_threads() {
+        let root = tempfile::tempdir().unwrap();
+        let cache = KamCache::with_root(root.path()).unwrap();
+        let guard = cache.lock().unwrap();
+
+        let cache = &cache;
+        std::thread::scope(|scope| {
+            let timeout = Duration::from_millis(200);
+            let started = Instant::now();
+            let result = scope
+                .spawn(move || cache.lock_with_timeout(timeout).map(drop))
+                .join()
+                .unwrap();
+            assert!(matches!(result, Err(CacheError::LockTimeout { .. })));
+            assert!(started.elapsed() >= timeout);
+
+            // A waiting thread gets the lock once it is released
+            let waiter = scope.spawn(|| cache.lock_with_timeout(Duration::from_secs(10)).map(drop));
+            std::thread::sleep(Duration::from_millis(100));
+            drop(guard);
+            waiter.join().unwrap().unwrap();
+        });
+    }
+
+    #[test]
+    fn test_lock_reports_other_process_holder() {
+        let root = tempfile::tempdir().unwrap();
+        let cache = KamCache::with_root(root.path()).unwrap();
+        std::fs::create_dir_all(cache.locks_dir()).unwrap();
+        let path = cache.locks_dir().join("cache.lock");
+        // A lock taken through another open file, as another process would
+        let mut other = File::create(&path).unwrap();
+        other.try_lock().unwrap();
+        write!(
+            other,
+            "pid=4242\nacquired=2026-01-01T00:00:00+00:00\ncommand=kam sync\n"
+        )
+        .unwrap();
+
+        let locks = cache.list_locks().unwrap();
+        assert_eq!(locks.len(), 1);
+        assert_eq!(locks[0].pid, Some(4242));
+        assert_eq!(
+            locks[0].acquired.as_deref(),
+            Some("2026-01-01T00:00:00+00:00")
+        );
+        assert_eq!(locks[0].command.as_deref(), Some("kam sync"));
+
+        match cache.lock_with_timeout(Duration::from_millis(150)) {
+            Err(CacheError::LockTimeout { holder, .. }) => {
+                assert_eq!(holder, "pid 4242 (kam sync)")
+            }
+            other => panic!("expected a timeout, got {:?}", other.map(drop)),
+        }
+        drop(other);
+        drop(cache.lock_with_timeout(Duration::from_secs(10)).unwrap());
+    }
+}
@@ -3,7 +3,18 @@ pub mod build;
 pub mod cache;
 pub mod check;
 pub mod dev;
+pub mod doctor;
+pub mod export_prop;
+pub mod fmt;
+pub mod info;
 pub mod init;
+pub mod migrate;
 pub mod publish;
+pub mod remove;
+pub mod run;
+pub mod search;
 pub mod sync;
+pub mod tree;
+pub mod update;
 pub mod venv;
+pub mod why;
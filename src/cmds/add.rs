@@ -1,11 +1,13 @@
 use crate::cache::KamCache;
 use crate::errors::KamError;
 use crate::types::kam_toml::KamToml;
+use crate::types::kam_toml::sections::Registries;
 use crate::types::kam_toml::sections::dependency::{Dependency, VersionSpec};
-use crate::types::source::Source;
-use crate::types::modules::ModuleBackend;
+use crate::types::modules::{ArchiveFormat, KamModule, ModuleBackend, extract_archive};
+use crate::types::source::{SHORTHAND_HOSTS, Source};
 
 use crate::venv::KamVenv;
+use crate::{detail, status};
 use clap::Args;
 use colored::Colorize;
 use std::fs;
@@ -15,21 +17,36 @@ use tempfile;
 /// Arguments for the add command
 #[derive(Args, Debug)]
 pub struct AddArgs {
-    /// Library module ID to add or workspace member path
-    pub library: Option<String>,
-
-    /// Version of the library (default: latest)
-    #[arg(short, long, default_value = "latest")]
-    pub version: String,
+    /// Library modules to add (`id`, `id@version`, optionally `id[feature,...]`)
+    /// or workspace member paths
+    pub library: Vec<String>,
+
+    /// Version of the library or release channel: latest, latest-prerelease, stable
+    /// (default: latest; `id@version` in the library spec takes precedence).
+    /// Only allowed when adding a single library. `-v` is this flag: the
+    /// global `--verbose` has no short form
+    #[arg(short, long)]
+    pub version: Option<String>,
 
-    /// Path to the project (default: current directory)
-    #[arg(default_value = ".")]
+    /// Path to the project (default: current directory). Given with
+    /// `-p/--path`, since every positional argument is a library:
+    /// `kam add mylib ../my-module` now adds two libraries
+    #[arg(short, long, default_value = ".")]
     pub path: String,
 
     /// Add as development dependency
     #[arg(short, long)]
     pub dev: bool,
 
+    /// Add as peer dependency (must be provided by the project that installs this module)
+    #[arg(long, conflicts_with = "dev")]
+    pub peer: bool,
+
+    /// Add as optional dependency: recorded in kam.toml but only synced when
+    /// enabled (`kam sync --with <id>` or a feature gating it)
+    #[arg(long, conflicts_with = "peer")]
+    pub optional: bool,
+
     /// Force download even if already cached
     #[arg(short, long)]
     pub force: bool,
@@ -45,6 +62,16 @@ pub struct AddArgs {
     /// Add workspace member instead of dependency
     #[arg(long)]
     pub workspace: bool,
+
+    /// Depend on a module in a git repository: `<url>[#branch|tag|commit]`.
+    /// Only allowed when adding a single library
+    #[arg(long, value_name = "URL", conflicts_with_all = ["repo", "version", "workspace"])]
+    pub git: Option<String>,
+
+    /// Resolve the libraries and report what would be added, without
+    /// changing kam.toml, the cache or the venv
+    #[arg(long, conflicts_with = "workspace")]
+    pub dry_run: bool,
 }
 
 /// Run the add command
@@ -55,75 +82,213 @@ pub fn run(args: AddArgs) -> Result<(), KamError> {
         return add_workspace_member(&args, project_path);
     }
 
-    let library = args.library.as_deref().unwrap_or_else(|| {
+    if args.library.is_empty() {
         eprintln!("Error: library ID is required when not using --workspace");
         std::process::exit(1);
-    });
-
-    println!(
-        "{} Adding library: {}@{}",
-        "→".cyan(),
-        library.bold(),
-        args.version
-    );
-
+    }
+    if args.version.is_some() && args.library.len() > 1 {
+        return Err(KamError::InvalidConfig(
+            "--version can only be used when adding a single library; use id@version instead"
+                .to_string(),
+        ));
+    }
+    let default_version = args.version.as_deref().unwrap_or("latest");
+
+    // `--git <url>[#rev]` is recorded as a `git+<url>[#rev]` source, which
+    // `sync` clones again on other machines
+    let git_source = match args.git.as_deref() {
+        Some(_) if args.library.len() > 1 => {
+            return Err(KamError::InvalidConfig(
+                "--git can only be used when adding a single library".to_string(),
+            ));
+        }
+        Some(git) => Some(git_source_spec(git)?),
+        None => None,
+    };
 
+    // Reject malformed repository specs up front so typos get a suggestion
+    if let Some(repo) = args.repo.as_deref() {
+        Source::parse(repo)?;
+    }
 
     // Initialize cache
     let cache = KamCache::new()?;
-    cache.ensure_dirs()?;
-
-
-
-    let (actual_version, mut kam_toml) = fetch_library(&cache, library, &args.version, args.repo.as_deref())?;
-
-    // Extract library metadata
-    let lib_info = LibraryInfo {
-        version: kam_toml.prop.version.clone(),
-        versionCode: kam_toml.prop.versionCode,
-    };
+    if !args.dry_run {
+        cache.ensure_dirs()?;
+    }
 
-    // Create dependency entry
-    let dependency_entry = Dependency {
-        id: library.to_string(),
-        versionCode: Some(VersionSpec::Exact(lib_info.versionCode)),
-        source: args.repo.clone(),
+    // The dependencies are recorded in the project's kam.toml, which is
+    // written once after every library has been fetched
+    let mut kam_toml = KamToml::load_from_dir(project_path)?;
+    let group = if args.peer {
+        "peer"
+    } else if args.dev {
+        "dev"
+    } else {
+        "runtime"
     };
-
-    if args.dev {
-        println!("  {} Adding to dev dependencies", "•".dimmed());
-        let devs = kam_toml
+    let registries = kam_toml.registries();
+
+    let mut added: Vec<(String, String, String)> = Vec::new();
+    let mut already_present: Vec<String> = Vec::new();
+    for library_spec in &args.library {
+        // `gh:owner/repo[@tag]`: the repository name is the module id and
+        // the module is fetched from that repository's releases
+        let (library_spec, repo) = match shorthand_library(library_spec)? {
+            Some((library_spec, repo)) => (library_spec, Some(repo)),
+            None => (library_spec.clone(), args.repo.clone()),
+        };
+        // `mylib[net,tls]@stable`: features of the dependency and an optional
+        // version or release channel overriding --version
+        let (library_spec, version) = match library_spec.rsplit_once('@') {
+            Some((spec, version)) if !version.is_empty() => (spec, version),
+            _ => (library_spec.as_str(), default_version),
+        };
+        let (library, features) = Dependency::parse_spec(library_spec)?;
+        let library = library.as_str();
+
+        // An existing dependency is left alone, so there is nothing to fetch
+        let present = kam_toml
             .kam
             .dependency
-            .get_or_insert_with(Default::default)
-            .dev
-            .get_or_insert_with(Vec::new);
+            .as_ref()
+            .and_then(|section| match group {
+                "peer" => section.peer.as_ref(),
+                "dev" => section.dev.as_ref(),
+                _ => section.kam.as_ref(),
+            });
+        if present.is_some_and(|deps| deps.iter().any(|d| d.id == library)) {
+            println!(
+                "  {} {} is already a {} dependency",
+                "!".yellow(),
+                library,
+                group
+            );
+            already_present.push(library.to_string());
+            continue;
+        }
 
-        // Check if already exists
-        if !devs.iter().any(|d| d.id == dependency_entry.id) {
-            devs.push(dependency_entry);
+        let (actual_version, lib_toml) = match git_source.as_deref() {
+            Some(git) => {
+                status!(
+                    "{} Adding library: {} ({})",
+                    "→".cyan(),
+                    library.bold(),
+                    git
+                );
+                fetch_git_library(&cache, library, git, args.dry_run)?
+            }
+            None => {
+                status!(
+                    "{} Adding library: {}@{}",
+                    "→".cyan(),
+                    library.bold(),
+                    version
+                );
+                let public_key = kam_toml.trusted_key(library).map(str::to_string);
+                fetch_library(
+                    &cache,
+                    library,
+                    version,
+                    repo.as_deref(),
+                    &registries,
+                    public_key.as_deref(),
+                    args.dry_run,
+                )?
+            }
+        };
+
+        // Extract library metadata
+        let lib_info = LibraryInfo {
+            version: lib_toml.prop.version.clone(),
+            versionCode: lib_toml.prop.versionCode,
+        };
+        if let Some(channel) = ReleaseChannel::parse(version)
+            && git_source.is_none()
+        {
+            status!(
+                "  {} Resolved @{} to {} ({})",
+                "•".dimmed(),
+                channel.as_str(),
+                lib_info.version,
+                lib_info.versionCode
+            );
         }
-    } else {
-        println!("  {} Adding to runtime dependencies", "•".dimmed());
-        let deps = kam_toml
-            .kam
-            .dependency
-            .get_or_insert_with(Default::default)
-            .kam
-            .get_or_insert_with(Vec::new);
 
-        // Check if already exists
-        if !deps.iter().any(|d| d.id == dependency_entry.id) {
-            deps.push(dependency_entry);
+        if args.dry_run {
+            let cached = cache
+                .cached_versions(library)
+                .contains(&lib_info.versionCode);
+            status!(
+                "  {} {} {} ({}) is {}",
+                "•".dimmed(),
+                library,
+                lib_info.version,
+                lib_info.versionCode,
+                if cached {
+                    "already cached"
+                } else {
+                    "not cached yet"
+                }
+            );
         }
+
+        // Requested features must be declared by the library
+        if !features.is_empty() {
+            lib_toml
+                .kam
+                .dependency
+                .clone()
+                .unwrap_or_default()
+                .feature_dependencies(library, &features)?;
+        }
+
+        // Create dependency entry
+        let dependency_entry = Dependency {
+            id: library.to_string(),
+            versionCode: Some(VersionSpec::Exact(lib_info.versionCode)),
+            source: git_source.clone().or(repo),
+            registry: None,
+            features: (!features.is_empty()).then_some(features),
+            optional: args.optional,
+        };
+
+        status!("  {} Adding to {} dependencies", "•".dimmed(), group);
+        let section = kam_toml.kam.dependency.get_or_insert_with(Default::default);
+        let deps = match group {
+            "peer" => &mut section.peer,
+            "dev" => &mut section.dev,
+            _ => &mut section.kam,
+        }
+        .get_or_insert_with(Vec::new);
+        deps.push(dependency_entry);
+        added.push((library.to_string(), actual_version, lib_info.version));
+    }
+
+    if args.dry_run {
+        status!();
+        println!("{}", "Dry run, nothing was changed:".bold());
+        for (library, _, version) in &added {
+            println!(
+                "  {} Would add {}@{} to {} dependencies",
+                "•".dimmed(),
+                library,
+                version,
+                group
+            );
+        }
+        return Ok(());
     }
 
     // Save updated kam.toml
-    kam_toml.write_to_dir(project_path)?;
-    println!("  {} Updated kam.toml", "✓".green());
+    if !added.is_empty() {
+        kam_toml.write_dependencies_to_dir(project_path)?;
+        status!("  {} Updated kam.toml", "✓".green());
+    }
 
-    // Link to virtual environment if requested
-    if !args.no_link {
+    // Link to virtual environment if requested; optional dependencies are
+    // only linked once enabled
+    if !args.no_link && !args.optional && !added.is_empty() {
         let venv_path = project_path.join(".kam_venv");
         if venv_path.exists() {
             let venv = KamVenv::load(&venv_path)?;
@@ -133,14 +298,16 @@ pub fn run(args: AddArgs) -> Result<(), KamError> {
                 for entry in entries.flatten() {
                     if let Some(name_str) = entry.file_name().to_str() {
                         venv.link_binary(cache.bin_path(name_str).as_path())?;
-                        println!("  {} Linked binary: {}", "✓".green(), name_str);
+                        status!("  {} Linked binary: {}", "✓".green(), name_str);
                     }
                 }
             }
 
             // Link libraries
-            venv.link_library(library, &actual_version, &cache)?;
-            println!("  {} Linked library to venv", "✓".green());
+            for (library, actual_version, _) in &added {
+                venv.link_library(library, actual_version, &cache)?;
+                status!("  {} Linked {} to venv", "✓".green(), library);
+            }
         } else {
             println!(
                 "  {} No virtual environment found, skipping linking",
@@ -149,24 +316,118 @@ pub fn run(args: AddArgs) -> Result<(), KamError> {
         }
     }
 
-    println!(
-        "{} Added {}@{}",
-        "✓".green().bold(),
-        library,
-        lib_info.version
-    );
+    for (library, _, version) in &added {
+        println!("{} Added {}@{}", "✓".green().bold(), library, version);
+        if args.optional {
+            status!(
+                "  {} Optional: install it with `kam sync --with {}`",
+                "i".cyan(),
+                library
+            );
+        }
+    }
+    if args.library.len() > 1 || !already_present.is_empty() {
+        println!(
+            "{} {} added, {} already present",
+            "Summary:".bold(),
+            added.len(),
+            already_present.len()
+        );
+    }
     Ok(())
 }
 
-/// Add a workspace member
-fn add_workspace_member(args: &AddArgs, project_path: &Path) -> Result<(), KamError> {
-    let member_path = args.library.as_deref().unwrap_or(".");
+/// Split a `gh:owner/repo[@rev]` (or `gl:`) library spec into a plain
+/// `repo[@rev]` spec and the repository's web URL. Other specs yield `None`.
+fn shorthand_library(spec: &str) -> Result<Option<(String, String)>, KamError> {
+    if !SHORTHAND_HOSTS
+        .iter()
+        .any(|(prefix, _)| spec.starts_with(prefix))
+    {
+        return Ok(None);
+    }
+    let Source::Git { url, rev, .. } = Source::parse(spec)? else {
+        return Ok(None);
+    };
+    let repo_url = url.trim_end_matches(".git").to_string();
+    let name = repo_url.rsplit('/').next().unwrap_or_default();
+    let library_spec = match rev {
+        Some(rev) => format!("{}@{}", name, rev),
+        None => name.to_string(),
+    };
+    Ok(Some((library_spec, repo_url)))
+}
 
-    println!(
-        "{} Adding workspace member: {}",
-        "→".cyan(),
-        member_path.bold()
+/// Normalize a `--git` argument (`<url>[#rev]`, optionally prefixed with
+/// `git+`) into the `git+<url>[#rev]` source spec stored in kam.toml
+fn git_source_spec(git: &str) -> Result<String, KamError> {
+    let spec = if git.starts_with("git+") {
+        git.to_string()
+    } else {
+        format!("git+{}", git)
+    };
+    Source::parse(&spec)?;
+    Ok(spec)
+}
+
+/// Clone a library from a `git+<url>[#rev]` source and install it into the
+/// cache under the id and versionCode of the `kam.toml` in the clone (only
+/// the clone is made with `dry_run`)
+fn fetch_git_library(
+    cache: &KamCache,
+    library: &str,
+    spec: &str,
+    dry_run: bool,
+) -> Result<(String, KamToml), KamError> {
+    status!("  {} Cloning {}", "→".cyan(), spec);
+    let module = KamModule::new(KamToml::default(), Some(Source::parse(spec)?));
+    let clone = module.fetch_to_temp()?;
+
+    let installed = (|| {
+        let kam_toml = KamToml::load_from_dir(&clone)?;
+        if kam_toml.prop.id != library {
+            return Err(KamError::InvalidConfig(format!(
+                "{} contains module '{}', not '{}'",
+                spec, kam_toml.prop.id, library
+            )));
+        }
+        if dry_run {
+            return Ok(kam_toml);
+        }
+        let module = KamModule::new(
+            kam_toml.clone(),
+            Some(Source::Local {
+                path: clone.clone(),
+                subdir: None,
+            }),
+        );
+        install_backend_into_cache(&module, cache)?;
+        Ok(kam_toml)
+    })();
+    let _ = fs::remove_dir_all(&clone);
+    let kam_toml = installed?;
+    if dry_run {
+        status!("  {} Would fetch from {}", "•".dimmed(), spec);
+        return Ok((kam_toml.prop.versionCode.to_string(), kam_toml));
+    }
+
+    status!(
+        "  {} Installed {} ({})",
+        "✓".green(),
+        kam_toml.prop.version,
+        kam_toml.prop.versionCode
     );
+    Ok((kam_toml.prop.versionCode.to_string(), kam_toml))
+}
+
+/// Add workspace members
+fn add_workspace_member(args: &AddArgs, project_path: &Path) -> Result<(), KamError> {
+    let default_member = [".".to_string()];
+    let member_paths = if args.library.is_empty() {
+        &default_member[..]
+    } else {
+        &args.library[..]
+    };
 
     // Load project kam.toml
     let mut kam_toml = KamToml::load_from_dir(project_path)?;
@@ -175,30 +436,135 @@ fn add_workspace_member(args: &AddArgs, project_path: &Path) -> Result<(), KamEr
     let workspace = kam_toml.kam.workspace.get_or_insert_with(Default::default);
     let members = workspace.members.get_or_insert_with(Vec::new);
 
-    // Check if already exists
-    if members.contains(&member_path.to_string()) {
-        println!(
-            "  {} Member '{}' already exists in workspace",
-            "!".yellow(),
-            member_path
+    let mut added = Vec::new();
+    for member_path in member_paths {
+        status!(
+            "{} Adding workspace member: {}",
+            "→".cyan(),
+            member_path.bold()
         );
+
+        // Check if already exists
+        if members.contains(member_path) {
+            println!(
+                "  {} Member '{}' already exists in workspace",
+                "!".yellow(),
+                member_path
+            );
+            continue;
+        }
+
+        // Add member
+        members.push(member_path.clone());
+        added.push(member_path);
+    }
+    if added.is_empty() {
         return Ok(());
     }
 
-    // Add member
-    members.push(member_path.to_string());
-
     // Save updated kam.toml
-    kam_toml.write_to_dir(project_path)?;
-    println!("  {} Updated kam.toml", "✓".green());
-    println!(
-        "{} Added workspace member: {}",
-        "✓".green().bold(),
-        member_path
-    );
+    kam_toml.write_workspace_members_to_dir(project_path)?;
+    status!("  {} Updated kam.toml", "✓".green());
+    for member_path in added {
+        println!(
+            "{} Added workspace member: {}",
+            "✓".green().bold(),
+            member_path
+        );
+    }
     Ok(())
 }
 
+/// Release channel accepted in place of a concrete version (`mylib@stable`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReleaseChannel {
+    /// What the repository marks as latest (`latest.json`, GitHub's latest release)
+    Latest,
+    /// Newest release including prereleases
+    LatestPrerelease,
+    /// Newest release that is not a prerelease
+    Stable,
+}
+
+impl ReleaseChannel {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "latest" => Some(ReleaseChannel::Latest),
+            "latest-prerelease" => Some(ReleaseChannel::LatestPrerelease),
+            "stable" => Some(ReleaseChannel::Stable),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReleaseChannel::Latest => "latest",
+            ReleaseChannel::LatestPrerelease => "latest-prerelease",
+            ReleaseChannel::Stable => "stable",
+        }
+    }
+
+    /// Whether a release with the given prerelease flag belongs to this channel
+    pub fn accepts(&self, prerelease: bool) -> bool {
+        !prerelease || *self == ReleaseChannel::LatestPrerelease
+    }
+}
+
+/// Semver-style prerelease versions carry a suffix: `1.2.0-beta.1`
+pub fn is_prerelease(version: &str) -> bool {
+    version.contains('-')
+}
+
+/// Select the index metadata for `version` from a module's index directory.
+///
+/// Concrete versions and `latest` read `<version>.json`; the `stable` and
+/// `latest-prerelease` channels pick the highest versionCode among the
+/// entries whose `prerelease` flag the channel accepts.
+fn select_index_metadata(
+    lib_index: &Path,
+    version: &str,
+) -> Result<Option<serde_json::Value>, KamError> {
+    let read = |path: &Path| -> Result<serde_json::Value, KamError> {
+        serde_json::from_str(&fs::read_to_string(path)?)
+            .map_err(|e| KamError::JsonError(e.to_string()))
+    };
+
+    match ReleaseChannel::parse(version) {
+        Some(channel) if channel != ReleaseChannel::Latest => {
+            let mut best: Option<serde_json::Value> = None;
+            for entry in fs::read_dir(lib_index)?.flatten() {
+                let path = entry.path();
+                if path.extension().and_then(|e| e.to_str()) != Some("json")
+                    || path.file_name().and_then(|n| n.to_str()) == Some("latest.json")
+                {
+                    continue;
+                }
+                let meta = read(&path)?;
+                let prerelease = meta
+                    .get("prerelease")
+                    .and_then(|p| p.as_bool())
+                    .unwrap_or(false);
+                if !channel.accepts(prerelease) {
+                    continue;
+                }
+                let code = |m: &serde_json::Value| m.get("versionCode").and_then(|c| c.as_i64());
+                if best.as_ref().is_none_or(|b| code(&meta) > code(b)) {
+                    best = Some(meta);
+                }
+            }
+            Ok(best)
+        }
+        _ => {
+            let metadata_path = lib_index.join(format!("{}.json", version));
+            if metadata_path.exists() {
+                Ok(Some(read(&metadata_path)?))
+            } else {
+                Ok(None)
+            }
+        }
+    }
+}
+
 /// Library information extracted from module
 #[derive(Debug)]
 #[allow(non_snake_case)]
@@ -208,7 +574,7 @@ struct LibraryInfo {
 }
 
 /// Compute index path based on module name (similar to cargo's index structure)
-fn compute_index_path(index_base: &Path, module_name: &str) -> PathBuf {
+pub fn compute_index_path(index_base: &Path, module_name: &str) -> PathBuf {
     let name_lower = module_name.to_lowercase();
     let chars: Vec<char> = name_lower.chars().collect();
 
@@ -228,14 +594,147 @@ fn compute_index_path(index_base: &Path, module_name: &str) -> PathBuf {
     }
 }
 
-/// Fetch library from repository
-fn fetch_library(
+/// Resolve the `latest` release of `id` to its concrete `(version,
+/// versionCode)` through the `latest.json` of its index entry.
+///
+/// The registry's own index is read first (`<registry>/index/...`, a
+/// directory or a URL; URLs are skipped offline), then the local cache
+/// index. Fails with [`KamError::LibraryNotFound`] when neither has a
+/// `latest.json` for `id`.
+pub(crate) fn resolve_latest(
+    cache: &KamCache,
+    registry: &str,
+    id: &str,
+) -> Result<(String, i64), KamError> {
+    let parse = |meta: serde_json::Value| -> Option<(String, i64)> {
+        let version = meta.get("version")?.as_str()?.to_string();
+        Some((version, meta.get("versionCode")?.as_i64()?))
+    };
+    let read = |index: &Path| {
+        fs::read_to_string(compute_index_path(index, id).join("latest.json"))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .and_then(parse)
+    };
+
+    let from_registry = registry_index_file(registry, id, "latest.json").and_then(parse);
+    from_registry
+        .or_else(|| read(&cache.root().join("index")))
+        .ok_or_else(|| {
+            KamError::LibraryNotFound(format!(
+                "no latest.json for {} in the index of {} or the local cache",
+                id, registry
+            ))
+        })
+}
+
+/// Resolve a release channel of `id` to a concrete version.
+///
+/// `latest` goes through [`resolve_latest`]. For `stable` and
+/// `latest-prerelease` the registry's index entries are searched with
+/// [`select_index_metadata`] when the registry is a directory; an HTTP index
+/// cannot be listed, so its `latest.json` is used when the channel accepts
+/// it. The local cache index is the fallback.
+pub(crate) fn resolve_channel(
+    cache: &KamCache,
+    registry: &str,
+    id: &str,
+    channel: ReleaseChannel,
+) -> Result<String, KamError> {
+    if channel == ReleaseChannel::Latest {
+        return Ok(resolve_latest(cache, registry, id)?.0);
+    }
+    let select = |index: &Path| -> Result<Option<serde_json::Value>, KamError> {
+        let lib_index = compute_index_path(index, id);
+        if !lib_index.is_dir() {
+            return Ok(None);
+        }
+        select_index_metadata(&lib_index, channel.as_str())
+    };
+    let version = |meta: &serde_json::Value| meta.get("version")?.as_str().map(str::to_string);
+
+    let remote = registry.starts_with("http://") || registry.starts_with("https://");
+    let from_registry = if remote {
+        registry_index_file(registry, id, "latest.json").filter(|meta| {
+            let prerelease = meta
+                .get("prerelease")
+                .and_then(|p| p.as_bool())
+                .unwrap_or_else(|| version(meta).is_some_and(|v| is_prerelease(&v)));
+            channel.accepts(prerelease)
+        })
+    } else {
+        select(&Path::new(registry).join("index"))?
+    };
+    let meta = match from_registry {
+        Some(meta) => Some(meta),
+        None => select(&cache.root().join("index"))?,
+    };
+    meta.as_ref().and_then(version).ok_or_else(|| {
+        KamError::LibraryNotFound(format!(
+            "no {} release of {} in the index of {} or the local cache",
+            channel.as_str(),
+            id,
+            registry
+        ))
+    })
+}
+
+/// Read `file` (e.g. `latest.json`) from the index entry of `id` in a
+/// registry's own index: `<registry>/index/...`, a directory or a URL. URLs
+/// are skipped offline.
+pub(crate) fn registry_index_file(
+    registry: &str,
+    id: &str,
+    file: &str,
+) -> Option<serde_json::Value> {
+    let remote = registry.starts_with("http://") || registry.starts_with("https://");
+    let content = if !remote {
+        let index = Path::new(registry).join("index");
+        fs::read_to_string(compute_index_path(&index, id).join(file)).ok()?
+    } else if crate::utils::is_offline() {
+        return None;
+    } else {
+        let relative = compute_index_path(Path::new(""), id);
+        let relative: Vec<_> = relative.iter().filter_map(|c| c.to_str()).collect();
+        let url = format!(
+            "{}/index/{}/{}",
+            registry.trim_end_matches('/'),
+            relative.join("/"),
+            file
+        );
+        crate::utils::http_get(&url)
+            .ok()
+            .filter(|resp| resp.status().is_success())?
+            .text()
+            .ok()?
+    };
+    serde_json::from_str(&content).ok()
+}
+
+/// Fetch library from repository, falling back to the project's default
+/// registry when no repository is given. With a `public_key` pinned in
+/// `[kam.trust]`, downloaded packages must carry a valid signature.
+///
+/// With `dry_run` the package is still downloaded (to a temporary
+/// directory) and checked to resolve its version, but nothing is installed
+/// into the cache or recorded in its index.
+pub(crate) fn fetch_library(
     cache: &KamCache,
     library: &str,
     version: &str,
     repo: Option<&str>,
+    registries: &Registries,
+    public_key: Option<&str>,
+    dry_run: bool,
 ) -> Result<(String, KamToml), KamError> {
-    println!("  {} Fetching {}@{}", "→".cyan(), library, version);
+    status!("  {} Fetching {}@{}", "→".cyan(), library, version);
+
+    // Offline, the cache is consulted first and the network never
+    let offline = crate::utils::is_offline();
+    if offline && let Some((cached_version, kam_toml)) = cached_library(cache, library, version) {
+        status!("  {} Using cached {}", "✓".green(), cached_version);
+        return Ok((cached_version, kam_toml));
+    }
 
     let mut actual_version = version.to_string();
 
@@ -252,17 +751,12 @@ fn fetch_library(
 
             if lib_index.exists() {
                 // Read library metadata
-                let metadata_path = lib_index.join(format!("{}.json", version));
-                if metadata_path.exists() {
-                    let metadata = fs::read_to_string(&metadata_path)?;
-                    let meta: serde_json::Value = serde_json::from_str(&metadata)
-                        .map_err(|e| KamError::JsonError(e.to_string()))?;
-
+                if let Some(meta) = select_index_metadata(&lib_index, version)? {
                     // Get actual version
-                    actual_version = if version == "latest" {
+                    actual_version = if ReleaseChannel::parse(version).is_some() {
                         meta.get("version")
                             .and_then(|v| v.as_str())
-                            .unwrap_or("latest")
+                            .unwrap_or(version)
                             .to_string()
                     } else {
                         version.to_string()
@@ -272,6 +766,13 @@ fn fetch_library(
                     if let Some(package_file) = meta.get("package").and_then(|p| p.as_str()) {
                         let source = repo_path.join("packages").join(package_file);
                         if source.exists() {
+                            let sha256 = verify_package_checksum(
+                                &source,
+                                meta.get("sha256").and_then(|s| s.as_str()),
+                            )?;
+                            crate::signing::verify_pinned(&source, library, public_key, || {
+                                fs::read_to_string(crate::signing::signature_path(&source)).ok()
+                            })?;
                             let temp_dir = tempfile::tempdir()?;
                             let temp_path = temp_dir.path();
 
@@ -280,14 +781,29 @@ fn fetch_library(
 
                             // Load kam.toml
                             let kam_toml = KamToml::load_from_dir(temp_path)?;
+                            if dry_run {
+                                status!(
+                                    "  {} Would fetch from local repo: {}",
+                                    "•".dimmed(),
+                                    source.display()
+                                );
+                                return Ok((actual_version.to_string(), kam_toml));
+                            }
 
                             // Install artifacts to cache
                             install_library_to_cache(temp_path, &cache)?;
 
                             // Update local index
-                            update_local_cache_index(&cache, library, &actual_version, &kam_toml, package_file)?;
-
-                            println!("  {} Fetched from local repo", "✓".green());
+                            update_local_cache_index(
+                                cache,
+                                library,
+                                &actual_version,
+                                &kam_toml,
+                                package_file,
+                                Some(&sha256),
+                            )?;
+
+                            status!("  {} Fetched from local repo", "✓".green());
                             return Ok((actual_version.to_string(), kam_toml));
                         }
                     }
@@ -296,15 +812,27 @@ fn fetch_library(
         }
     }
 
+    if offline {
+        return Err(KamError::OfflineUnavailable(format!(
+            "{}@{} is neither cached nor in a local repository",
+            library, version
+        )));
+    }
+
     // Try GitHub releases if repo URL is provided
     if let Some(repo_url) = repo {
         if repo_url.starts_with("https://github.com/") {
-            return fetch_from_github(cache, library, version, repo_url);
+            return fetch_from_github(cache, library, version, repo_url, public_key, dry_run);
         }
     }
 
-    // Try network sources
-    let source_base = repo.unwrap_or("https://github.com/MemDeco-WG/Kam-Index");
+    // Try network sources; release channels are resolved to a concrete
+    // version first, since package names carry the version
+    let source_base = repo.unwrap_or(registries.default_source());
+    if let Some(channel) = ReleaseChannel::parse(&actual_version) {
+        actual_version = resolve_channel(cache, source_base, library, channel)?;
+    }
+    let expected_sha256 = index_sha256(cache, source_base, library, &actual_version);
     let zip_name = format!("{}-{}.zip", library, actual_version);
     let candidates = vec![
         format!("{}/{}", source_base.trim_end_matches('/'), zip_name),
@@ -322,22 +850,33 @@ fn fetch_library(
     ];
 
     for url in candidates {
+        detail!("    {} Trying {}", "•".dimmed(), url);
         match Source::parse(&url) {
             Ok(src) => {
                 let temp_dir = tempfile::tempdir()?;
                 let temp_path = temp_dir.path();
+                let sha256;
 
                 // Fetch to temp
                 match src {
-                    Source::Url { url } => {
-                        let mut resp = reqwest::blocking::get(&url).map_err(|e| KamError::FetchFailed(format!("failed to download {}: {}", url, e)))?;
+                    Source::Url { url, .. } => {
+                        let resp = crate::utils::http_get(&url).map_err(|e| {
+                            KamError::FetchFailed(format!("failed to download {}: {}", url, e))
+                        })?;
                         if !resp.status().is_success() {
                             continue;
                         }
                         let mut data = Vec::new();
-                        resp.copy_to(&mut data).map_err(|e| KamError::FetchFailed(format!("read download body: {}", e)))?;
+                        let total = resp.content_length();
+                        crate::progress::download(resp, &mut data, library, total).map_err(
+                            |e| KamError::FetchFailed(format!("read download body: {}", e)),
+                        )?;
                         let file_path = temp_path.join("download.zip");
                         fs::write(&file_path, &data)?;
+                        sha256 = verify_package_checksum(&file_path, expected_sha256.as_deref())?;
+                        crate::signing::verify_pinned(&file_path, library, public_key, || {
+                            crate::signing::download_signature(&url)
+                        })?;
                         extract_package(&file_path, temp_path)?;
                     }
                     _ => continue,
@@ -345,14 +884,25 @@ fn fetch_library(
 
                 // Load kam.toml
                 let kam_toml = KamToml::load_from_dir(temp_path)?;
+                if dry_run {
+                    status!("  {} Would fetch from {}", "•".dimmed(), url);
+                    return Ok((actual_version.clone(), kam_toml));
+                }
 
                 // Install artifacts
                 install_library_to_cache(temp_path, &cache)?;
 
                 // Update index
-                update_local_cache_index(&cache, library, &actual_version, &kam_toml, &zip_name)?;
-
-                println!("  {} Fetched from network", "✓".green());
+                update_local_cache_index(
+                    &cache,
+                    library,
+                    &actual_version,
+                    &kam_toml,
+                    &zip_name,
+                    Some(&sha256),
+                )?;
+
+                status!("  {} Fetched from network", "✓".green());
                 return Ok((actual_version.clone(), kam_toml));
             }
             Err(_) => continue,
@@ -365,36 +915,74 @@ fn fetch_library(
     )))
 }
 
-/// Extract package archive (zip or tar.gz)
-fn extract_package(source: &Path, dest: &Path) -> Result<(), KamError> {
-    let ext = source.extension().and_then(|e| e.to_str());
-
-    match ext {
-        Some("zip") => {
-            let file = fs::File::open(source)?;
-            let mut archive =
-                zip::ZipArchive::new(file).map_err(|e| KamError::ExtractFailed(e.to_string()))?;
-            archive
-                .extract(dest)
-                .map_err(|e| KamError::ExtractFailed(e.to_string()))?;
-        }
-        Some("gz") => {
-            let file = fs::File::open(source)?;
-            let gz = flate2::read::GzDecoder::new(file);
-            let mut archive = tar::Archive::new(gz);
-            archive
-                .unpack(dest)
-                .map_err(|e| KamError::ExtractFailed(e.to_string()))?;
-        }
-        _ => {
-            return Err(KamError::UnsupportedFormat(format!(
-                "Unsupported package format: {:?}",
-                ext
-            )));
+/// A version of `library` already installed in the cache: the newest one a
+/// release channel accepts, or the one whose version (or versionCode) is
+/// `version`. Returns its version and `kam.toml`.
+fn cached_library(cache: &KamCache, library: &str, version: &str) -> Option<(String, KamToml)> {
+    let channel = ReleaseChannel::parse(version);
+    cache
+        .cached_versions(library)
+        .into_iter()
+        .rev()
+        .find_map(|code| {
+            let name = cache.cached_version_name(library, code)?;
+            let wanted = match channel {
+                Some(channel) => channel.accepts(is_prerelease(&name)),
+                None => name == version || code.to_string() == version,
+            };
+            if !wanted {
+                return None;
+            }
+            let kam_toml =
+                KamToml::load_from_dir(cache.lib_module_path(library, &code.to_string())).ok()?;
+            Some((name, kam_toml))
+        })
+}
+
+/// Check a downloaded package against the sha256 recorded in the index.
+///
+/// Returns the package's sha256. Packages indexed without a checksum are
+/// accepted as-is.
+pub fn verify_package_checksum(path: &Path, expected: Option<&str>) -> Result<String, KamError> {
+    let actual = crate::utils::sha256_file(path)?;
+    match expected {
+        Some(expected) if !expected.eq_ignore_ascii_case(&actual) => {
+            Err(KamError::ChecksumMismatch(format!(
+                "{}: index records sha256 {}, package has {}",
+                path.display(),
+                expected,
+                actual
+            )))
         }
+        _ => Ok(actual),
     }
+}
 
-    Ok(())
+/// The sha256 recorded for `library@version` by the registry's own index
+/// (see [`registry_index_file`]), or else by the local cache index
+fn index_sha256(cache: &KamCache, registry: &str, library: &str, version: &str) -> Option<String> {
+    registry_index_file(registry, library, &format!("{}.json", version))
+        .and_then(|meta| meta.get("sha256")?.as_str().map(str::to_string))
+        .or_else(|| cached_index_sha256(cache, library, version))
+}
+
+/// The sha256 the local cache index records for `library@version`
+fn cached_index_sha256(cache: &KamCache, library: &str, version: &str) -> Option<String> {
+    let lib_index = compute_index_path(&cache.root().join("index"), library);
+    if !lib_index.exists() {
+        return None;
+    }
+    select_index_metadata(&lib_index, version)
+        .ok()
+        .flatten()?
+        .get("sha256")?
+        .as_str()
+        .map(str::to_string)
+}
+
+/// Extract package archive (zip, tar.gz, tar.xz or tar.zst)
+fn extract_package(source: &Path, dest: &Path) -> Result<(), KamError> {
+    extract_archive(source, dest)
 }
 
 /// Fetch from GitHub releases
@@ -403,6 +991,8 @@ fn fetch_from_github(
     library: &str,
     version: &str,
     repo_url: &str,
+    public_key: Option<&str>,
+    dry_run: bool,
 ) -> Result<(String, KamToml), KamError> {
     // Parse GitHub repo from URL
     let parts: Vec<&str> = repo_url.trim_end_matches('/').split('/').collect();
@@ -416,35 +1006,46 @@ fn fetch_from_github(
     let owner = parts[3];
     let repo = parts[4];
 
-    // Construct release API URL
-    let api_url = if version == "latest" {
-        format!(
+    // Construct release API URL. GitHub's "latest" release never is a
+    // prerelease; the prerelease channel lists releases (newest first).
+    let channel = ReleaseChannel::parse(version);
+    let api_url = match channel {
+        Some(ReleaseChannel::Latest) | Some(ReleaseChannel::Stable) => format!(
             "https://api.github.com/repos/{}/{}/releases/latest",
             owner, repo
-        )
-    } else {
-        format!(
+        ),
+        Some(ReleaseChannel::LatestPrerelease) => format!(
+            "https://api.github.com/repos/{}/{}/releases?per_page=30",
+            owner, repo
+        ),
+        None => format!(
             "https://api.github.com/repos/{}/{}/releases/tags/{}",
             owner, repo, version
-        )
+        ),
     };
 
-    println!("  {} Fetching from GitHub: {}/{}", "→".cyan(), owner, repo);
+    status!("  {} Fetching from GitHub: {}/{}", "→".cyan(), owner, repo);
 
     // Make request
     let client = reqwest::blocking::Client::new();
-    let mut req = client
-        .get(&api_url)
-        .header("User-Agent", "kam-package-manager");
-
-    // Add auth token if available
-    if let Ok(token) = std::env::var("GITHUB_TOKEN") {
-        req = req.header("Authorization", format!("token {}", token));
-    }
+    let token = std::env::var("GITHUB_TOKEN").ok();
+    let request = || {
+        let req = client
+            .get(&api_url)
+            .header("User-Agent", "kam-package-manager");
+        // Add auth token if available
+        match &token {
+            Some(token) => req.header("Authorization", format!("token {}", token)),
+            None => req,
+        }
+    };
 
-    let response = req
-        .send()
-        .map_err(|e| KamError::FetchFailed(e.to_string()))?;
+    let response = crate::utils::send_with_retry(
+        request,
+        crate::utils::http_attempts(),
+        crate::utils::HTTP_BASE_DELAY,
+    )
+    .map_err(|e| KamError::FetchFailed(e.to_string()))?;
 
     if !response.status().is_success() {
         return Err(KamError::FetchFailed(format!(
@@ -456,53 +1057,106 @@ fn fetch_from_github(
     let release: serde_json::Value = response
         .json()
         .map_err(|e| KamError::JsonError(e.to_string()))?;
+    let release = match release {
+        serde_json::Value::Array(releases) => releases
+            .into_iter()
+            .find(|r| !r.get("draft").and_then(|d| d.as_bool()).unwrap_or(false))
+            .ok_or_else(|| {
+                KamError::LibraryNotFound(format!("No releases found for {}/{}", owner, repo))
+            })?,
+        single => single,
+    };
+    // Channels resolve to the release's tag
+    let version = match (channel, release.get("tag_name").and_then(|t| t.as_str())) {
+        (Some(_), Some(tag)) => tag.to_string(),
+        _ => version.to_string(),
+    };
+    let version = version.as_str();
 
     // Find asset matching library name
     if let Some(assets) = release.get("assets").and_then(|a| a.as_array()) {
         for asset in assets {
-            if let Some(name) = asset.get("name").and_then(|n| n.as_str()) {
-                if name.contains(library) && (name.ends_with(".zip") || name.ends_with(".tar.gz")) {
-                    if let Some(download_url) =
-                        asset.get("browser_download_url").and_then(|u| u.as_str())
-                    {
-                        // Download asset
-                        println!("  {} Downloading: {}", "→".cyan(), name);
-
-                        let response = client
-                            .get(download_url)
-                            .header("User-Agent", "kam-package-manager")
-                            .send()
-                            .map_err(|e| KamError::FetchFailed(e.to_string()))?;
-
-                        if response.status().is_success() {
-                            let bytes = response
-                                .bytes()
-                                .map_err(|e| KamError::FetchFailed(e.to_string()))?;
-
-                            // Save to temp and extract
-                            let temp_path = cache.root().join(name);
-                            fs::write(&temp_path, bytes)?;
+            if let Some(name) = asset.get("name").and_then(|n| n.as_str())
+                && name.contains(library)
+                && ArchiveFormat::from_name(name).is_some()
+                && let Some(download_url) =
+                    asset.get("browser_download_url").and_then(|u| u.as_str())
+            {
+                // Download asset
+                status!("  {} Downloading: {}", "→".cyan(), name);
+
+                let response = client
+                    .get(download_url)
+                    .header("User-Agent", "kam-package-manager")
+                    .send()
+                    .map_err(|e| KamError::FetchFailed(e.to_string()))?;
+
+                if response.status().is_success() {
+                    let mut bytes = Vec::new();
+                    let total = response.content_length();
+                    crate::progress::download(response, &mut bytes, name, total)
+                        .map_err(|e| KamError::FetchFailed(e.to_string()))?;
+
+                    // Save to temp and extract (outside the cache on
+                    // a dry run)
+                    let download_dir = tempfile::tempdir()?;
+                    let temp_path = if dry_run {
+                        download_dir.path().join(name)
+                    } else {
+                        cache.root().join(name)
+                    };
+                    fs::write(&temp_path, bytes)?;
+                    // GitHub records a digest for each asset
+                    let expected = asset
+                        .get("digest")
+                        .and_then(|d| d.as_str())
+                        .and_then(|d| d.strip_prefix("sha256:"))
+                        .map(str::to_string)
+                        .or_else(|| cached_index_sha256(cache, library, version));
+                    let checked = verify_package_checksum(&temp_path, expected.as_deref())
+                        .and_then(|sha256| {
+                            crate::signing::verify_pinned(&temp_path, library, public_key, || {
+                                crate::signing::download_signature(download_url)
+                            })?;
+                            Ok(sha256)
+                        });
+                    let sha256 = match checked {
+                        Ok(sha256) => sha256,
+                        Err(e) => {
+                            let _ = fs::remove_file(&temp_path);
+                            return Err(e);
+                        }
+                    };
 
-                            let temp_dir = tempfile::tempdir()?;
-                            let temp_extract_path = temp_dir.path();
-                            extract_package(&temp_path, temp_extract_path)?;
+                    let temp_dir = tempfile::tempdir()?;
+                    let temp_extract_path = temp_dir.path();
+                    extract_package(&temp_path, temp_extract_path)?;
 
-                            // Load kam.toml
-                            let kam_toml = KamToml::load_from_dir(temp_extract_path)?;
+                    // Load kam.toml
+                    let kam_toml = KamToml::load_from_dir(temp_extract_path)?;
+                    if dry_run {
+                        status!("  {} Would fetch from {}", "•".dimmed(), download_url);
+                        return Ok((version.to_string(), kam_toml));
+                    }
 
-                            // Install artifacts to cache
-                            install_library_to_cache(temp_extract_path, &cache)?;
+                    // Install artifacts to cache
+                    install_library_to_cache(temp_extract_path, &cache)?;
 
-                            // Update local index
-                            update_local_cache_index(&cache, library, &version, &kam_toml, name)?;
+                    // Update local index
+                    update_local_cache_index(
+                        cache,
+                        library,
+                        version,
+                        &kam_toml,
+                        name,
+                        Some(&sha256),
+                    )?;
 
-                            // Clean up temp file
-                            let _ = fs::remove_file(&temp_path);
+                    // Clean up temp file
+                    let _ = fs::remove_file(&temp_path);
 
-                            println!("  {} Downloaded and extracted", "✓".green());
-                            return Ok((version.to_string(), kam_toml));
-                        }
-                    }
+                    status!("  {} Downloaded and extracted", "✓".green());
+                    return Ok((version.to_string(), kam_toml));
                 }
             }
         }
@@ -524,10 +1178,7 @@ fn install_backend_into_cache(
 }
 
 /// Install library artifacts to cache (lib, lib64, bin)
-fn install_library_to_cache(
-    temp_path: &Path,
-    cache: &KamCache,
-) -> Result<(), KamError> {
+fn install_library_to_cache(temp_path: &Path, cache: &KamCache) -> Result<(), KamError> {
     // Copy lib to cache/lib
     let src_lib = temp_path.join("lib");
     if src_lib.exists() {
@@ -568,15 +1219,16 @@ fn copy_dir_all(src: &Path, dst: &Path) -> Result<(), KamError> {
 }
 
 /// Update local cache index for a published library
-fn update_local_cache_index(
+pub(crate) fn update_local_cache_index(
     cache: &KamCache,
     module_id: &str,
     version: &str,
     kam_toml: &KamToml,
     package_filename: &str,
+    sha256: Option<&str>,
 ) -> Result<(), KamError> {
-    use serde_json::json;
     use chrono;
+    use serde_json::json;
 
     // Create index directory structure based on module name
     let index_dir = cache.root().join("index");
@@ -584,18 +1236,29 @@ fn update_local_cache_index(
     fs::create_dir_all(&module_index_path)?;
 
     // Create metadata JSON for this version
-    let metadata = json!({
+    let mut metadata = json!({
         "id": module_id,
+        "name": kam_toml.prop.name.get("en").unwrap_or(&module_id.to_string()),
         "version": version,
         "versionCode": kam_toml.prop.versionCode,
         "author": kam_toml.prop.author,
         "description": kam_toml.prop.description.get("en").unwrap_or(&String::new()),
+        "keywords": kam_toml.mmrl.as_ref()
+            .and_then(|m| m.repo.as_ref())
+            .and_then(|r| r.keywords.as_ref())
+            .unwrap_or(&Vec::new()),
         "provides": kam_toml.kam.lib.as_ref()
             .and_then(|l| l.provides.as_ref())
             .unwrap_or(&Vec::new()),
+        "supported_arch": kam_toml.kam.supported_arch.as_ref().unwrap_or(&Vec::new()),
         "package": package_filename,
+        "prerelease": is_prerelease(&kam_toml.prop.version),
         "timestamp": chrono::Utc::now().to_rfc3339(),
     });
+    // Entries without a known checksum leave it out rather than record ""
+    if let Some(sha256) = sha256 {
+        metadata["sha256"] = json!(sha256);
+    }
 
     let metadata_file = module_index_path.join(format!("{}.json", version));
     let metadata_str =
@@ -609,12 +1272,15 @@ fn update_local_cache_index(
         let latest: serde_json::Value = serde_json::from_str(&latest_content)
             .map_err(|e| KamError::JsonError(e.to_string()))?;
 
-        // Simple version comparison (could be improved)
-        latest
-            .get("version")
-            .and_then(|v| v.as_str())
-            .map(|v| version > v)
-            .unwrap_or(true)
+        match latest.get("versionCode").and_then(|v| v.as_i64()) {
+            Some(latest_code) => kam_toml.prop.versionCode > latest_code,
+            // Older entries without versionCode: compare version strings
+            None => latest
+                .get("version")
+                .and_then(|v| v.as_str())
+                .map(|v| version > v)
+                .unwrap_or(true),
+        }
     } else {
         true
     };
@@ -625,3 +1291,132 @@ fn update_local_cache_index(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_latest() {
+        let cache_root = tempfile::tempdir().unwrap();
+        let registry = tempfile::tempdir().unwrap();
+        let cache = KamCache::with_root(cache_root.path()).unwrap();
+        let registry_path = registry.path().to_str().unwrap();
+        let write_latest = |index: &Path, version: &str, code: i64| {
+            let dir = compute_index_path(index, "mylib");
+            fs::create_dir_all(&dir).unwrap();
+            let meta = serde_json::json!({ "version": version, "versionCode": code });
+            fs::write(dir.join("latest.json"), meta.to_string()).unwrap();
+        };
+
+        assert!(matches!(
+            resolve_latest(&cache, registry_path, "mylib"),
+            Err(KamError::LibraryNotFound(_))
+        ));
+
+        // The cache index is the fallback; the registry's own index wins
+        write_latest(&cache.root().join("index"), "1.0.0", 100);
+        assert_eq!(
+            resolve_latest(&cache, registry_path, "mylib").unwrap(),
+            ("1.0.0".to_string(), 100)
+        );
+        write_latest(&registry.path().join("index"), "1.1.0", 110);
+        assert_eq!(
+            resolve_latest(&cache, registry_path, "mylib").unwrap(),
+            ("1.1.0".to_string(), 110)
+        );
+    }
+
+    #[test]
+    fn test_is_prerelease() {
+        assert!(is_prerelease("1.2.0-beta.1"));
+        assert!(is_prerelease("2.0.0-rc1"));
+        assert!(!is_prerelease("1.2.0"));
+        assert!(!is_prerelease("v3"));
+    }
+
+    #[test]
+    fn test_select_index_metadata() {
+        let registry = tempfile::tempdir().unwrap();
+        let lib_index = compute_index_path(&registry.path().join("index"), "mylib");
+        fs::create_dir_all(&lib_index).unwrap();
+        let write = |file: &str, version: &str, code: i64| {
+            let meta = serde_json::json!({
+                "version": version,
+                "versionCode": code,
+                "prerelease": is_prerelease(version),
+            });
+            fs::write(lib_index.join(file), meta.to_string()).unwrap();
+        };
+        write("1.0.0.json", "1.0.0", 100);
+        write("1.1.0.json", "1.1.0", 110);
+        write("1.2.0-beta.1.json", "1.2.0-beta.1", 115);
+        // latest.json is never a candidate of its own
+        write("latest.json", "9.9.9", 999);
+        let version = |v: &str| {
+            select_index_metadata(&lib_index, v)
+                .unwrap()
+                .map(|meta| meta["version"].as_str().unwrap().to_string())
+        };
+
+        assert_eq!(version("stable").as_deref(), Some("1.1.0"));
+        assert_eq!(
+            version("latest-prerelease").as_deref(),
+            Some("1.2.0-beta.1")
+        );
+        assert_eq!(version("latest").as_deref(), Some("9.9.9"));
+        assert_eq!(version("1.0.0").as_deref(), Some("1.0.0"));
+        assert_eq!(version("2.0.0"), None);
+
+        // Channels resolve through the registry's own index
+        let cache_root = tempfile::tempdir().unwrap();
+        let cache = KamCache::with_root(cache_root.path()).unwrap();
+        let registry_path = registry.path().to_str().unwrap();
+        let resolve = |registry: &str, channel| resolve_channel(&cache, registry, "mylib", channel);
+        assert_eq!(
+            resolve(registry_path, ReleaseChannel::Stable).unwrap(),
+            "1.1.0"
+        );
+        assert_eq!(
+            resolve(registry_path, ReleaseChannel::LatestPrerelease).unwrap(),
+            "1.2.0-beta.1"
+        );
+        let empty = tempfile::tempdir().unwrap();
+        assert!(matches!(
+            resolve(empty.path().to_str().unwrap(), ReleaseChannel::Stable),
+            Err(KamError::LibraryNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_fetch_rejects_tampered_package() {
+        let cache_root = tempfile::tempdir().unwrap();
+        let cache = KamCache::with_root(cache_root.path()).unwrap();
+        let index = compute_index_path(Path::new("/index"), "mylib");
+        let meta = serde_json::json!({
+            "version": "1.0.0",
+            "versionCode": 100,
+            "sha256": "0".repeat(64),
+        });
+        let registry = crate::utils::serve_files(vec![
+            (
+                format!("{}/1.0.0.json", index.display()),
+                meta.to_string().into_bytes(),
+            ),
+            ("/mylib-1.0.0.zip".to_string(), b"tampered".to_vec()),
+        ]);
+
+        // The registry's index is consulted before the first download
+        let result = fetch_library(
+            &cache,
+            "mylib",
+            "1.0.0",
+            Some(&registry),
+            &Registries::new(None),
+            None,
+            false,
+        );
+        assert!(matches!(result, Err(KamError::ChecksumMismatch(_))));
+        assert!(cache.cached_versions("mylib").is_empty());
+    }
+}
@@ -1,14 +1,17 @@
 mod args;
 mod build_all;
 mod build_project;
+mod installer;
 mod post_build;
 mod pre_build;
+mod verify;
 
 pub use args::BuildArgs;
 pub use build_all::run_build_all;
-pub use build_project::build_project;
+pub use build_project::{build_project, render_output_template};
 pub use post_build::handle_post_build_hook;
 pub use pre_build::handle_pre_build_hook;
+pub use verify::verify_archives;
 
 use crate::errors::kam::KamError;
 use std::path::Path;
@@ -10,7 +10,55 @@ pub struct BuildArgs {
     #[arg(long)]
     pub all: bool,
 
+    /// With --all, stop at the first member that fails to build
+    #[arg(long, requires = "all")]
+    pub fail_fast: bool,
+
     /// Output directory (default: dist)
     #[arg(short, long)]
     pub output: Option<String>,
+
+    /// Only produce the module zip (skip the source tar.gz)
+    #[arg(long, conflicts_with = "source_only")]
+    pub module_only: bool,
+
+    /// Only produce the source tar.gz (skip the module zip)
+    #[arg(long)]
+    pub source_only: bool,
+
+    /// Ship a trimmed runtime kam.toml in the module zip (no build hooks,
+    /// template variables or mmrl metadata)
+    #[arg(long)]
+    pub runtime_toml: bool,
+
+    /// Nest the contents of both archives under a single top-level
+    /// directory, e.g. `{{id}}-{{version}}` (placeholders as in
+    /// `kam.build.output_file`). Note that root managers expect module.prop
+    /// at the top of a module zip, so such a zip cannot be flashed as-is.
+    #[arg(long, value_name = "NAME")]
+    pub archive_root: Option<String>,
+
+    /// Don't package an installer: neither the project's META-INF nor the
+    /// generated Magisk installer stub (and customize.sh)
+    #[arg(long)]
+    pub no_installer: bool,
+
+    /// Don't write update.json next to the module zip
+    #[arg(long)]
+    pub no_update_json: bool,
+
+    /// Produce byte-identical archives for the same sources: entries are
+    /// sorted, timestamps set to SOURCE_DATE_EPOCH (1980-01-01 when unset)
+    /// and owners and permissions normalized
+    #[arg(long)]
+    pub reproducible: bool,
+
+    /// Re-open the produced archives and fail if they are malformed
+    #[arg(long)]
+    pub verify: bool,
+
+    /// Compare the built module zip against a published artifact and fail,
+    /// listing the differing entries, unless their sha256 match
+    #[arg(long, value_name = "PUBLISHED_ZIP", conflicts_with = "source_only")]
+    pub verify_reproducible: Option<String>,
 }
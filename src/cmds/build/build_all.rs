@@ -1,69 +1,115 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+use colored::Colorize;
 
 use super::args::BuildArgs;
 use super::build_project::build_project;
 use crate::errors::kam::KamError;
+use crate::status;
 use crate::types::kam_toml::KamToml;
 
-fn build_workspace_member(project_path: &Path, member: &str, args: &BuildArgs) {
+/// Build one workspace member from inside its directory. Returns the
+/// archives it produced, relative to the workspace root.
+fn build_workspace_member(
+    project_path: &Path,
+    member: &str,
+    args: &BuildArgs,
+) -> Result<Vec<PathBuf>, KamError> {
     let member_path = project_path.join(member);
-    println!(
-        "DEBUG: member_path {} exists: {}",
-        member_path.display(),
-        member_path.exists()
-    );
-    if !member_path.exists() {
-        println!("Warning: workspace member {} not found", member);
-        return;
-    }
-    if !member_path.join("kam.toml").exists() {
-        println!("Skipping {}: no kam.toml found", member);
-        return;
-    }
-    println!("Building workspace member: {}", member);
-    let original_cwd = match std::env::current_dir() {
-        Ok(cwd) => cwd,
-        Err(e) => {
-            println!("Failed to get current dir: {}", e);
-            return;
-        }
-    };
-    if let Err(e) = std::env::set_current_dir(&member_path) {
-        println!("Failed to change to {}: {}", member_path.display(), e);
-        return;
-    }
-    match KamToml::load_from_dir(".") {
-        Ok(kt) => {
-            if let Err(e) = build_project(std::path::Path::new("."), args, Some(kt)) {
-                println!("Failed to build {}: {}", member, e);
-            }
-        }
-        Err(e) => {
-            println!("Skipping {}: failed to load kam.toml: {}", member, e);
-        }
-    }
-    if let Err(e) = std::env::set_current_dir(original_cwd) {
-        println!("Failed to restore cwd: {}", e);
+    if !member_path.is_dir() {
+        return Err(KamError::InvalidConfig(format!(
+            "workspace member {} not found",
+            member_path.display()
+        )));
     }
+    status!("{} {}", "Building workspace member:".bold(), member);
+    let original_cwd = std::env::current_dir()?;
+    std::env::set_current_dir(&member_path)?;
+    let result =
+        KamToml::load_from_dir(".").and_then(|kt| build_project(Path::new("."), args, Some(kt)));
+    std::env::set_current_dir(original_cwd)?;
+    let artifacts = result?;
+    Ok(artifacts
+        .into_iter()
+        .map(|p| match p.strip_prefix(".") {
+            Ok(relative) => member_path.join(relative),
+            Err(_) => p,
+        })
+        .collect())
 }
 
+/// Build every member listed in `kam.workspace.members` of the root
+/// `kam.toml` (or the root project itself when it lists none).
+///
+/// Each member is built with its own `kam.toml`, so its archives go to its
+/// own `kam.build.target_dir`. A failing member doesn't stop the others
+/// unless `--fail-fast` is given; a summary of every member's archives or
+/// error is printed at the end.
 pub fn run_build_all(project_path: &Path, args: &BuildArgs) -> Result<(), KamError> {
     let root_kam_toml = KamToml::load_from_dir(project_path)?;
-    println!(
-        "DEBUG: root_kam_toml.kam.workspace: {:?}",
-        root_kam_toml.kam.workspace
-    );
     let workspace = root_kam_toml
         .kam
         .workspace
         .as_ref()
         .ok_or_else(|| KamError::InvalidConfig("No workspace section found".to_string()))?;
-    if let Some(members) = &workspace.members {
-        for member in members {
-            build_workspace_member(project_path, member, args);
-        }
-    } else {
+    let Some(members) = &workspace.members else {
         build_project(project_path, args, None)?;
+        return Ok(());
+    };
+
+    let mut results: Vec<(&String, Result<Vec<PathBuf>, KamError>)> = Vec::new();
+    for member in members {
+        let result = build_workspace_member(project_path, member, args);
+        let failed = result.is_err();
+        if let Err(e) = &result {
+            println!("  {} Failed to build {}: {}", "✗".red(), member, e);
+        }
+        results.push((member, result));
+        if failed && args.fail_fast {
+            break;
+        }
+        status!();
+    }
+
+    status!();
+    println!("{}", "Workspace build summary:".bold());
+    let width = members.iter().map(String::len).max().unwrap_or(0);
+    for (member, result) in &results {
+        match result {
+            Ok(artifacts) if artifacts.is_empty() => {
+                println!("  {} {:<width$}  (no archives)", "✓".green(), member)
+            }
+            Ok(artifacts) => {
+                for (i, artifact) in artifacts.iter().enumerate() {
+                    let name = if i == 0 { member.as_str() } else { "" };
+                    let mark = if i == 0 { "✓".green() } else { " ".normal() };
+                    println!("  {} {:<width$}  {}", mark, name, artifact.display());
+                }
+            }
+            Err(e) => {
+                // The full error was printed above; keep the summary to one line
+                let error = e.to_string();
+                let error = error.lines().next().unwrap_or_default();
+                println!("  {} {:<width$}  {}", "✗".red(), member, error.red())
+            }
+        }
+    }
+    for member in members.iter().skip(results.len()) {
+        println!(
+            "  {} {:<width$}  {}",
+            "-".dimmed(),
+            member,
+            "not built (--fail-fast)".dimmed()
+        );
+    }
+
+    let failed = results.iter().filter(|(_, r)| r.is_err()).count();
+    if failed > 0 {
+        return Err(KamError::BuildFailed(format!(
+            "{} of {} workspace member(s) failed to build",
+            failed,
+            members.len()
+        )));
     }
     Ok(())
 }
@@ -1,17 +1,60 @@
 use crate::types::kam_toml::enums::ModuleType;
+use chrono::{Datelike, Timelike};
 use colored::*;
 use glob::Pattern;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs::{self, File};
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 use tar::Builder as TarBuilder;
-use zip::{ZipWriter, write::FileOptions};
+use zip::{DateTime, ZipWriter, write::FileOptions};
 
 use super::args::BuildArgs;
+use super::installer::{UPDATE_BINARY, UPDATER_SCRIPT, render_customize_sh};
 use super::post_build::handle_post_build_hook;
 use super::pre_build::handle_pre_build_hook;
+use super::verify::{verify_archives, verify_reproducible};
 use crate::errors::kam::KamError;
 use crate::types::kam_toml::KamToml;
+use crate::{detail, status};
+
+/// Environment variable holding the time (seconds since the Unix epoch)
+/// given to every archive entry by `--reproducible`
+pub const SOURCE_DATE_EPOCH_ENV: &str = "SOURCE_DATE_EPOCH";
+
+/// 1980-01-01T00:00:00Z, the earliest time a zip entry can hold
+const ZIP_EPOCH: i64 = 315_532_800;
+
+/// Timestamp of the entries of a `--reproducible` build:
+/// `SOURCE_DATE_EPOCH` when set, otherwise [`ZIP_EPOCH`]
+fn reproducible_timestamp() -> Result<i64, KamError> {
+    match std::env::var(SOURCE_DATE_EPOCH_ENV) {
+        Ok(value) => value.trim().parse().map_err(|_| {
+            KamError::InvalidConfig(format!(
+                "{} must be a number of seconds since the Unix epoch, got '{}'",
+                SOURCE_DATE_EPOCH_ENV, value
+            ))
+        }),
+        Err(_) => Ok(ZIP_EPOCH),
+    }
+}
+
+/// `timestamp` as a zip entry time, clamped to the range zip can store
+fn zip_datetime(timestamp: i64) -> DateTime {
+    let Some(time) = chrono::DateTime::from_timestamp(timestamp.max(ZIP_EPOCH), 0) else {
+        return DateTime::default();
+    };
+    DateTime::from_date_and_time(
+        time.year().min(2107) as u16,
+        time.month() as u8,
+        time.day() as u8,
+        time.hour() as u8,
+        time.minute() as u8,
+        time.second() as u8,
+    )
+    .unwrap_or_default()
+}
 
 /// Check that library modules have proper architecture subdirectories in lib/
 fn check_library_structure(project_path: &Path) -> Result<(), KamError> {
@@ -72,17 +115,17 @@ pub fn determine_output_dir(
     Ok(output_dir)
 }
 
-/// Build a single project
+/// Build a single project. Returns the paths of the archives it produced.
 pub fn build_project(
     project_path: &Path,
     args: &BuildArgs,
     preloaded_kam_toml: Option<KamToml>,
-) -> Result<(), KamError> {
+) -> Result<Vec<PathBuf>, KamError> {
     // Use project path as-is
     let project_root = project_path.to_path_buf();
 
-    println!("{}", "Building module...".bold().cyan());
-    println!();
+    status!("{}", "Building module...".bold().cyan());
+    status!();
 
     // Load kam.toml
     let kam_toml = if let Some(kt) = preloaded_kam_toml {
@@ -93,7 +136,7 @@ pub fn build_project(
     let module_id = &kam_toml.prop.id;
     let version = &kam_toml.prop.version;
 
-    println!("  {} Module: {} v{}", "•".cyan(), module_id, version);
+    status!("  {} Module: {} v{}", "•".cyan(), module_id, version);
 
     // Check library structure for Library modules
     if kam_toml.kam.module_type == ModuleType::Library {
@@ -101,46 +144,88 @@ pub fn build_project(
     }
 
     let output_dir = determine_output_dir(&project_root, args, &kam_toml)?;
-    println!(
+    status!(
         "  {} Output: {}",
         "•".cyan(),
         output_dir.display().to_string().dimmed()
     );
-    println!();
+    status!();
 
     handle_pre_build_hook(&kam_toml, project_path)?;
 
-    // Package artifacts: produce two outputs
+    // Package artifacts: produce two outputs (unless narrowed by
+    // --module-only / --source-only)
     // 1) module zip: a module archive (zip) containing kam.toml and module sources (if present) + mmrl files
     // 2) source tar.gz: a source archive (tar.gz) containing kam.toml and full source tree (if present)
-    println!("{}", "Packaging artifacts...".bold());
+    status!("{}", "Packaging artifacts...".bold());
 
     let (effective_project_path, is_rendered_template) =
         prepare_effective_project(project_path, &kam_toml, module_id, &output_dir)?;
 
-    let basename = determine_basename(&kam_toml)?;
+    let basename = determine_basename(&kam_toml, project_path)?;
+    let archive_root = determine_archive_root(args, &kam_toml, project_path)?;
+    let timestamp = if args.reproducible {
+        Some(reproducible_timestamp()?)
+    } else {
+        None
+    };
 
-    create_module_zip_if_needed(
-        &kam_toml,
-        &output_dir,
-        &basename,
-        &effective_project_path,
-        project_path,
-        module_id,
-        is_rendered_template,
-    )?;
+    if !args.source_only {
+        create_module_zip_if_needed(
+            &kam_toml,
+            &output_dir,
+            &basename,
+            &effective_project_path,
+            project_path,
+            is_rendered_template,
+            args,
+            timestamp,
+        )?;
+    }
 
-    create_source_archive(
-        &kam_toml,
-        &output_dir,
-        &basename,
-        &effective_project_path,
-        &project_path,
-    )?;
+    if !args.source_only && !args.no_update_json {
+        write_update_json(&kam_toml, &output_dir, &basename, project_path)?;
+    }
+
+    if !args.module_only {
+        create_source_archive(
+            &kam_toml,
+            &output_dir,
+            &basename,
+            &effective_project_path,
+            archive_root.as_deref(),
+            timestamp,
+        )?;
+    }
+
+    if args.verify {
+        verify_archives(
+            &kam_toml,
+            project_path,
+            &output_dir,
+            &basename,
+            archive_root.as_deref(),
+        )?;
+    }
+
+    if let Some(published) = &args.verify_reproducible {
+        verify_reproducible(
+            &output_dir.join(format!("{}.zip", basename)),
+            Path::new(published),
+        )?;
+    }
 
     handle_post_build_hook(&kam_toml, project_path)?;
 
-    Ok(())
+    let artifacts = [
+        (!args.source_only).then(|| output_dir.join(format!("{}.zip", basename))),
+        (!args.module_only).then(|| output_dir.join(format!("{}.tar.gz", basename))),
+    ];
+    Ok(artifacts
+        .into_iter()
+        .flatten()
+        .filter(|p| p.is_file())
+        .collect())
 }
 
 pub fn prepare_effective_project(
@@ -158,7 +243,7 @@ pub fn prepare_effective_project(
     Ok((effective_project_path, is_rendered_template))
 }
 
-pub fn determine_basename(kam_toml: &KamToml) -> Result<String, KamError> {
+pub fn determine_basename(kam_toml: &KamToml, project_path: &Path) -> Result<String, KamError> {
     // Determine module output basename. Default is `{{id}}-{{versionCode}}` as requested.
     let default_basename = format!("{}-{}", kam_toml.prop.id, kam_toml.prop.versionCode);
 
@@ -172,7 +257,7 @@ pub fn determine_basename(kam_toml: &KamToml) -> Result<String, KamError> {
             if trimmed.is_empty() {
                 default_basename
             } else {
-                let rendered = render_output_template(trimmed, kam_toml);
+                let rendered = render_output_template(trimmed, kam_toml, project_path);
                 let p = std::path::Path::new(&rendered);
                 if p.extension().is_some() {
                     // Warn the user that extensions are not allowed in output_file
@@ -194,24 +279,163 @@ pub fn determine_basename(kam_toml: &KamToml) -> Result<String, KamError> {
     Ok(basename)
 }
 
-pub fn render_output_template(tpl: &str, kt: &KamToml) -> String {
+/// Top-level directory requested with `--archive-root`, with placeholders
+/// rendered. It must be a single, plain path component.
+pub fn determine_archive_root(
+    args: &BuildArgs,
+    kam_toml: &KamToml,
+    project_path: &Path,
+) -> Result<Option<String>, KamError> {
+    let Some(tpl) = args.archive_root.as_deref() else {
+        return Ok(None);
+    };
+    let root = render_output_template(tpl, kam_toml, project_path);
+    let root = root.trim_end_matches('/');
+    if root.is_empty() || root == "." || root == ".." || root.contains(['/', '\\']) {
+        return Err(KamError::InvalidFilename(format!(
+            "--archive-root must be a single directory name, got '{}'",
+            tpl
+        )));
+    }
+    Ok(Some(root.to_string()))
+}
+
+/// Path of an archive entry, nested under the archive root if there is one
+fn archive_entry(archive_root: Option<&str>, name: &str) -> String {
+    match archive_root {
+        Some(root) => format!("{}/{}", root, name),
+        None => name.to_string(),
+    }
+}
+
+/// Compression requested by `[mmrl.repo.options.archive].compression`
+/// (empty when unset)
+fn archive_compression_name(kam_toml: &KamToml) -> &str {
+    kam_toml
+        .mmrl
+        .as_ref()
+        .and_then(|m| m.repo.as_ref())
+        .and_then(|r| r.options.as_ref())
+        .and_then(|o| o.archive.as_ref())
+        .and_then(|a| a.compression.as_deref())
+        .unwrap_or_default()
+}
+
+/// Zip compression method for an archive compression name: "Store",
+/// "Deflate", "Bzip2" or "Zstd" (case-insensitive). An empty name means
+/// Deflate; `None` is returned for unknown names.
+pub fn compression_method(name: &str) -> Option<zip::CompressionMethod> {
+    match name.trim().to_ascii_lowercase().as_str() {
+        "" | "deflate" | "deflated" => Some(zip::CompressionMethod::Deflated),
+        "store" | "stored" => Some(zip::CompressionMethod::Stored),
+        "bzip2" => Some(zip::CompressionMethod::Bzip2),
+        "zstd" => Some(zip::CompressionMethod::Zstd),
+        _ => None,
+    }
+}
+
+/// Write `update.json` for the module zip `<basename>.zip` into `output_dir`.
+///
+/// `zipUrl` comes from `kam.build.zip_url` (with placeholders rendered) or
+/// defaults to the zip next to `prop.updateJson`. Nothing is written when no
+/// module zip was built.
+pub fn write_update_json(
+    kam_toml: &KamToml,
+    output_dir: &Path,
+    basename: &str,
+    project_path: &Path,
+) -> Result<(), KamError> {
+    let zip_name = format!("{}.zip", basename);
+    if !output_dir.join(&zip_name).is_file() {
+        return Ok(());
+    }
+    let configured = kam_toml
+        .kam
+        .build
+        .as_ref()
+        .and_then(|b| b.zip_url.as_deref())
+        .map(str::trim)
+        .filter(|u| !u.is_empty());
+    let zip_url = match configured {
+        Some(tpl) => render_output_template(tpl, kam_toml, project_path),
+        None => match kam_toml
+            .prop
+            .updateJson
+            .as_deref()
+            .and_then(|u| u.rsplit_once('/'))
+        {
+            Some((base, _)) if !base.is_empty() => format!("{}/{}", base, zip_name),
+            _ => zip_name,
+        },
+    };
+
+    let update_json = kam_toml.to_update_json(zip_url);
+    fs::write(
+        output_dir.join("update.json"),
+        update_json.to_json_string()?,
+    )?;
+    status!(
+        "  {} update.json {}",
+        "+".green(),
+        format!("(zipUrl: {})", update_json.zipUrl).dimmed()
+    );
+    Ok(())
+}
+
+/// Render the placeholders of an output name (`kam.build.output_file`,
+/// `kam.build.zip_url`, `--archive-root`): `{{id}}`, `{{version}}`,
+/// `{{versionCode}}` and `{{author}}` from `kam.toml`, the build time as
+/// `{{date}}` (UTC `YYYYMMDD`) and `{{datetime}}` (UTC `YYYYMMDD-HHMMSS`),
+/// and `{{gitsha}}`, the short hash of the HEAD commit of the repository
+/// containing `project_path` (empty outside a git repository).
+pub fn render_output_template(tpl: &str, kt: &KamToml, project_path: &Path) -> String {
+    render_output_template_at(tpl, kt, project_path, chrono::Utc::now())
+}
+
+fn render_output_template_at(
+    tpl: &str,
+    kt: &KamToml,
+    project_path: &Path,
+    now: chrono::DateTime<chrono::Utc>,
+) -> String {
     let mut s = tpl.to_string();
     s = s.replace("{{id}}", &kt.prop.id);
     s = s.replace("{{version}}", &kt.prop.version);
     s = s.replace("{{versionCode}}", &kt.prop.versionCode.to_string());
     s = s.replace("{{author}}", &kt.prop.author);
+    s = s.replace("{{date}}", &now.format("%Y%m%d").to_string());
+    s = s.replace("{{datetime}}", &now.format("%Y%m%d-%H%M%S").to_string());
+    if s.contains("{{gitsha}}") {
+        s = s.replace(
+            "{{gitsha}}",
+            &git_short_sha(project_path).unwrap_or_default(),
+        );
+    }
     s
 }
 
+/// Short hash of the HEAD commit of the git repository containing `path`
+fn git_short_sha(path: &Path) -> Option<String> {
+    let repo = git2::Repository::discover(path).ok()?;
+    let head = repo.head().ok()?.peel_to_commit().ok()?;
+    let short = head.as_object().short_id().ok()?;
+    short.as_str().map(str::to_string)
+}
+
 pub fn create_module_zip_if_needed(
     kam_toml: &KamToml,
     output_dir: &Path,
     basename: &str,
     effective_project_path: &Path,
     project_path: &Path,
-    module_id: &str,
     is_rendered_template: bool,
+    args: &BuildArgs,
+    timestamp: Option<i64>,
 ) -> Result<(), KamError> {
+    let module_id = kam_toml.prop.id.as_str();
+    let runtime_toml = args.runtime_toml;
+    let archive_root = determine_archive_root(args, kam_toml, project_path)?;
+    let archive_path = |name: &str| archive_entry(archive_root.as_deref(), name);
     let module_output_file = output_dir.join(format!("{}.zip", basename));
 
     // Only create a module zip when module_type == Kam. Other module types
@@ -223,27 +447,154 @@ pub fn create_module_zip_if_needed(
         && !is_rendered_template
         && effective_src_dir.exists()
     {
+        let compression_name = archive_compression_name(kam_toml);
+        let compression = compression_method(compression_name).unwrap_or_else(|| {
+            println!(
+                "  {} Unknown archive compression '{}', using Deflate (expected Store, Deflate, Bzip2 or Zstd)",
+                "!".yellow(),
+                compression_name
+            );
+            zip::CompressionMethod::Deflated
+        });
+
         // Create module zip archive
         let zip_file = File::create(&module_output_file)?;
         let mut zip = ZipWriter::new(zip_file);
-        let options: FileOptions<()> = FileOptions::default()
-            .compression_method(zip::CompressionMethod::Deflated)
+        let mut options: FileOptions<()> = FileOptions::default()
+            .compression_method(compression)
             .unix_permissions(0o755);
+        if let Some(timestamp) = timestamp {
+            options = options.last_modified_time(zip_datetime(timestamp));
+        }
+
+        if let Some(root) = &archive_root {
+            zip.add_directory(root.as_str(), options)?;
+        }
 
-        // Add kam.toml (from effective project path)
-        zip.start_file("kam.toml", options)?;
-        let kam_toml_content = fs::read_to_string(effective_project_path.join("kam.toml"))?;
+        // Add kam.toml (from effective project path), optionally trimmed to
+        // the fields needed at runtime
+        zip.start_file(archive_path("kam.toml"), options)?;
+        let kam_toml_content = if runtime_toml {
+            let effective = KamToml::load_from_dir(effective_project_path)?;
+            toml::to_string_pretty(&effective.to_runtime())?
+        } else {
+            fs::read_to_string(effective_project_path.join("kam.toml"))?
+        };
         zip.write_all(kam_toml_content.as_bytes())?;
-        println!("  {} {}", "+".green(), "kam.toml");
-
-        // Add source files (module dir: src/<module_id>)
-        // Since we checked effective_src_dir.exists(), we can add it directly
-        add_directory_to_zip(
-            &mut zip,
-            &effective_src_dir,
-            &format!("src/{}", module_id),
-            &effective_src_dir,
-        )?;
+        if runtime_toml {
+            status!("  {} kam.toml {}", "+".green(), "(runtime)".dimmed());
+        } else {
+            status!("  {} {}", "+".green(), "kam.toml");
+        }
+
+        // Collect module files up front so a module.prop shipped in
+        // src/<id> (or an overlay) takes the place of the generated one
+        let mut overlay_dirs: Vec<PathBuf> = Vec::new();
+        if let Some(overlays) = kam_toml.kam.build.as_ref().and_then(|b| b.overlay.as_ref()) {
+            for overlay in overlays {
+                let overlay_dir = effective_project_path.join(overlay);
+                if overlay_dir.is_dir() {
+                    overlay_dirs.push(overlay_dir);
+                } else {
+                    println!(
+                        "  {} Overlay directory not found: {}",
+                        "!".yellow(),
+                        overlay
+                    );
+                }
+            }
+        }
+        let kamignore = load_kamignore(effective_project_path)?;
+        let file_filter = BuildFileFilter::new(kam_toml)?;
+        let mut module_files = collect_module_files(&effective_src_dir, &overlay_dirs, &kamignore)?;
+        module_files.retain(|rel, _| !file_filter.excludes(&format!("src/{}/{}", module_id, rel)));
+
+        // Legacy module.prop for root managers that don't read kam.toml
+        if module_files.contains_key("module.prop") {
+            status!(
+                "  {} module.prop {}",
+                "•".cyan(),
+                format!("(shipped in src/{}, not generated)", module_id).dimmed()
+            );
+        } else {
+            zip.start_file(archive_path("module.prop"), options)?;
+            zip.write_all(kam_toml.to_module_prop().as_bytes())?;
+            status!("  {} module.prop {}", "+".green(), "(generated)".dimmed());
+        }
+
+        // Installer: the project's META-INF directory as is, with the
+        // standard Magisk installer stub filling in whatever it lacks, plus
+        // an optional customize.sh that checks the device against [kam]
+        let mut packaged: BTreeSet<String> = BTreeSet::new();
+        if !args.no_installer {
+            let meta_inf = effective_project_path.join("META-INF");
+            if meta_inf.is_dir() {
+                for (rel, path) in collect_module_files(&meta_inf, &[], &kamignore)? {
+                    let name = format!("META-INF/{}", rel);
+                    if file_filter.excludes(&name) {
+                        detail!("  {} {} {}", "-".dimmed(), name, "(excluded)".dimmed());
+                        continue;
+                    }
+                    zip.start_file(archive_path(&name), options)?;
+                    zip.write_all(&fs::read(&path)?)?;
+                    status!("  {} {}", "+".green(), name);
+                    packaged.insert(name);
+                }
+            }
+
+            let generate_installer = kam_toml
+                .kam
+                .build
+                .as_ref()
+                .and_then(|b| b.generate_installer)
+                .unwrap_or(false);
+            let mut installer_files = vec![
+                (
+                    "META-INF/com/google/android/update-binary",
+                    UPDATE_BINARY.to_string(),
+                ),
+                (
+                    "META-INF/com/google/android/updater-script",
+                    UPDATER_SCRIPT.to_string(),
+                ),
+            ];
+            if generate_installer {
+                installer_files.insert(0, ("customize.sh", render_customize_sh(kam_toml)));
+            }
+            for (name, content) in installer_files {
+                if !packaged.insert(name.to_string()) {
+                    continue;
+                }
+                zip.start_file(archive_path(name), options)?;
+                zip.write_all(content.as_bytes())?;
+                status!("  {} {} {}", "+".green(), name, "(generated)".dimmed());
+            }
+        }
+
+        // Add source files (module dir: src/<module_id>) merged with any
+        // overlay directories from `kam.build.overlay`
+        for (rel, path) in &module_files {
+            let name = format!("src/{}/{}", module_id, rel);
+            let zip_path = archive_path(&name);
+            zip.start_file(&zip_path, options)?;
+            let mut file = File::open(path)?;
+            let mut buffer = Vec::new();
+            file.read_to_end(&mut buffer)?;
+            zip.write_all(&buffer)?;
+            status!("  {} {}", "+".green(), zip_path.dimmed());
+            packaged.insert(name);
+        }
+
+        // Add project files matched by `kam.build.include` (service.sh,
+        // META-INF/**, ...) at the same path relative to the archive root
+        for rel in file_filter.included_files(effective_project_path, output_dir)? {
+            if !packaged.insert(rel.clone()) {
+                continue;
+            }
+            zip.start_file(archive_path(&rel), options)?;
+            zip.write_all(&fs::read(effective_project_path.join(&rel))?)?;
+            status!("  {} {} {}", "+".green(), rel, "(include)".dimmed());
+        }
 
         // Add other files if they exist
         // Include files referenced in kam.toml (mmrl.repo): readme, license, changelog
@@ -268,13 +619,16 @@ pub fn create_module_zip_if_needed(
 
                 for file_name in candidates {
                     let file_path = project_path.join(&file_name);
+                    if file_filter.excludes(&file_name) || packaged.contains(&file_name) {
+                        continue;
+                    }
                     if file_path.exists() {
-                        zip.start_file(&file_name, options)?;
+                        zip.start_file(archive_path(&file_name), options)?;
                         let mut file = File::open(&file_path)?;
                         let mut buffer = Vec::new();
                         file.read_to_end(&mut buffer)?;
                         zip.write_all(&buffer)?;
-                        println!("  {} {}", "+".green(), file_name);
+                        status!("  {} {}", "+".green(), file_name);
                     }
                 }
             }
@@ -282,14 +636,14 @@ pub fn create_module_zip_if_needed(
 
         zip.finish()?;
 
-        println!();
+        status!();
         println!(
             "{} Built module archive: {}",
             "✓".green().bold(),
             module_output_file.display().to_string().green()
         );
     } else {
-        println!(
+        status!(
             "  {} {}",
             "•".cyan(),
             "Module type is not 'kam' — skipping module zip, only creating source archive".dimmed()
@@ -298,12 +652,16 @@ pub fn create_module_zip_if_needed(
     Ok(())
 }
 
+/// Create the source tar.gz. With a `timestamp` (`--reproducible`) every
+/// entry gets that mtime, owner 0:0 and mode 0644 (0755 for directories and
+/// executables) instead of the metadata of the file on disk.
 pub fn create_source_archive(
     _kam_toml: &KamToml,
     output_dir: &Path,
     basename: &str,
     effective_project_path: &Path,
-    _project_path: &Path,
+    archive_root: Option<&str>,
+    timestamp: Option<i64>,
 ) -> Result<(), KamError> {
     // --- Create source tar.gz archive ---
     let source_filename = format!("{}.tar.gz", basename);
@@ -312,41 +670,33 @@ pub fn create_source_archive(
     let tar_gz = File::create(&source_output_file)?;
     let enc = flate2::write::GzEncoder::new(tar_gz, flate2::Compression::default());
     let mut tar = TarBuilder::new(enc);
-
-    // Compile exclude and include patterns
-    let exclude_patterns: Vec<Pattern> = if let Some(build) = _kam_toml.kam.build.as_ref() {
-        if let Some(excludes) = &build.exclude {
-            excludes
-                .iter()
-                .filter_map(|p| Pattern::new(p).ok())
-                .collect()
-        } else {
-            Vec::new()
-        }
-    } else {
-        Vec::new()
+    let archive_path = |name: &Path| match archive_root {
+        Some(root) => Path::new(root).join(name),
+        None => name.to_path_buf(),
     };
+    let mtime = timestamp.map(|t| t.max(0) as u64);
+    if let Some(root) = archive_root {
+        append_tar_entry(&mut tar, effective_project_path, Path::new(root), mtime)?;
+    }
 
-    let include_patterns: Vec<Pattern> = if let Some(build) = _kam_toml.kam.build.as_ref() {
-        if let Some(includes) = &build.include {
-            includes
-                .iter()
-                .filter_map(|p| Pattern::new(p).ok())
-                .collect()
-        } else {
-            Vec::new()
-        }
-    } else {
-        Vec::new()
-    };
+    let file_filter = BuildFileFilter::new(_kam_toml)?;
 
     // Use ignore::WalkBuilder to traverse all files, respecting .gitignore
+    // and pruning paths matched by .kamignore
+    let kamignore = load_kamignore(effective_project_path)?;
     let walker = ignore::WalkBuilder::new(effective_project_path)
         .git_ignore(true)
         .hidden(match _kam_toml.kam.module_type {
             ModuleType::Template => false, // include hidden files for templates
             _ => true,                     // ignore hidden files for other module types
         })
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .filter_entry(move |e| {
+            e.depth() == 0
+                || !kamignore
+                    .matched(e.path(), e.file_type().is_some_and(|t| t.is_dir()))
+                    .is_ignore()
+        })
         .build();
 
     for result in walker {
@@ -369,38 +719,27 @@ pub fn create_source_archive(
         }
 
         // Check custom exclude/include
-        let rel_str = rel_path.to_string_lossy();
-        let mut should_exclude = false;
-        for pat in &exclude_patterns {
-            if pat.matches(&rel_str) {
-                should_exclude = true;
-                break;
-            }
-        }
-        if should_exclude {
-            let mut should_include = false;
-            for pat in &include_patterns {
-                if pat.matches(&rel_str) {
-                    should_include = true;
-                    break;
-                }
-            }
-            if !should_include {
-                continue;
-            }
+        if file_filter.excludes(&slash_path(rel_path)) {
+            detail!(
+                "  {} {} {}",
+                "-".dimmed(),
+                rel_path.display(),
+                "(excluded)".dimmed()
+            );
+            continue;
         }
 
         if path.is_dir() {
             // Add directory to tar archive
-            tar.append_dir(rel_path, path)?;
-            println!(
+            append_tar_entry(&mut tar, path, &archive_path(rel_path), mtime)?;
+            status!(
                 "  {} {}/",
                 "+".green(),
                 rel_path.display().to_string().dimmed()
             );
         } else if path.is_file() {
-            tar.append_path_with_name(path, rel_path)?;
-            println!(
+            append_tar_entry(&mut tar, path, &archive_path(rel_path), mtime)?;
+            status!(
                 "  {} {}",
                 "+".green(),
                 rel_path.display().to_string().dimmed()
@@ -414,8 +753,13 @@ pub fn create_source_archive(
             for include in extra_includes {
                 let source_path = effective_project_path.join(&include.source);
                 if source_path.exists() && source_path.is_file() {
-                    tar.append_path_with_name(&source_path, &include.dest)?;
-                    println!("  {} {}", "+".green(), include.dest.dimmed());
+                    append_tar_entry(
+                        &mut tar,
+                        &source_path,
+                        &archive_path(Path::new(&include.dest)),
+                        mtime,
+                    )?;
+                    status!("  {} {}", "+".green(), include.dest.dimmed());
                 } else {
                     println!(
                         "  {} Extra include not found: {}",
@@ -438,67 +782,437 @@ pub fn create_source_archive(
     Ok(())
 }
 
-/// Add a directory to the zip archive recursively
-pub fn add_directory_to_zip<W: Write + std::io::Seek>(
-    zip: &mut ZipWriter<W>,
-    dir: &Path,
-    prefix: &str,
-    base: &Path,
+/// Add the file or directory `path` to `tar` as `name`, with normalized
+/// metadata and `mtime` when given
+fn append_tar_entry<W: Write>(
+    tar: &mut TarBuilder<W>,
+    path: &Path,
+    name: &Path,
+    mtime: Option<u64>,
 ) -> Result<(), KamError> {
-    let options: FileOptions<()> = FileOptions::default()
-        .compression_method(zip::CompressionMethod::Deflated)
-        .unix_permissions(0o755);
+    let Some(mtime) = mtime else {
+        if path.is_dir() {
+            tar.append_dir(name, path)?;
+        } else {
+            tar.append_path_with_name(path, name)?;
+        }
+        return Ok(());
+    };
+    let metadata = fs::metadata(path)?;
+    let mut header = tar::Header::new_gnu();
+    header.set_metadata_in_mode(&metadata, tar::HeaderMode::Deterministic);
+    header.set_mtime(mtime);
+    if metadata.is_dir() {
+        tar.append_data(&mut header, name, std::io::empty())?;
+    } else {
+        tar.append_data(&mut header, name, File::open(path)?)?;
+    }
+    Ok(())
+}
 
-    for entry in fs::read_dir(dir)? {
-        let entry = entry?;
-        let path = entry.path();
-        let name = path.strip_prefix(base).map_err(|e| {
-            KamError::StripPrefixFailed(format!("failed to strip prefix {}: {}", base.display(), e))
-        })?;
-        let zip_path = format!("{}/{}", prefix, name.display());
+/// The `kam.build.include` / `kam.build.exclude` globs, matched against
+/// `/`-separated paths relative to the project root
+pub struct BuildFileFilter {
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+}
 
-        if path.is_file() {
-            zip.start_file(&zip_path, options)?;
-            let mut file = File::open(&path)?;
-            let mut buffer = Vec::new();
-            file.read_to_end(&mut buffer)?;
-            zip.write_all(&buffer)?;
-            println!("  {} {}", "+".green(), zip_path.dimmed());
-        } else if path.is_dir() {
-            add_directory_to_zip(zip, &path, prefix, base)?;
+impl BuildFileFilter {
+    pub fn new(kam_toml: &KamToml) -> Result<Self, KamError> {
+        let compile = |globs: Option<&Vec<String>>, key: &str| {
+            globs
+                .into_iter()
+                .flatten()
+                .map(|glob| {
+                    Pattern::new(glob).map_err(|e| {
+                        KamError::InvalidConfig(format!(
+                            "kam.build.{}: invalid pattern '{}': {}",
+                            key, glob, e
+                        ))
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let build = kam_toml.kam.build.as_ref();
+        Ok(BuildFileFilter {
+            include: compile(build.and_then(|b| b.include.as_ref()), "include")?,
+            exclude: compile(build.and_then(|b| b.exclude.as_ref()), "exclude")?,
+        })
+    }
+
+    /// Whether `rel` matches an exclude pattern and no include pattern
+    pub fn excludes(&self, rel: &str) -> bool {
+        self.exclude.iter().any(|p| p.matches(rel)) && !self.include.iter().any(|p| p.matches(rel))
+    }
+
+    /// Files under `root` matching an include pattern, sorted. Hidden
+    /// directories (`.git`, `.kam_venv`, ...) and `output_dir` are skipped.
+    pub fn included_files(&self, root: &Path, output_dir: &Path) -> Result<Vec<String>, KamError> {
+        if self.include.is_empty() {
+            return Ok(Vec::new());
+        }
+        let output_dir = output_dir.canonicalize().ok();
+        let walker = walkdir::WalkDir::new(root)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|e| {
+                e.depth() == 0
+                    || !(e.file_type().is_dir()
+                        && (e.file_name().to_string_lossy().starts_with('.')
+                            || output_dir.is_some() && e.path().canonicalize().ok() == output_dir))
+            });
+        let mut files = Vec::new();
+        for entry in walker {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let rel = slash_path(
+                entry
+                    .path()
+                    .strip_prefix(root)
+                    .map_err(|e| KamError::StripPrefixFailed(format!("strip_prefix: {}", e)))?,
+            );
+            if self.include.iter().any(|p| p.matches(&rel)) && !self.excludes(&rel) {
+                files.push(rel);
+            }
         }
+        Ok(files)
     }
+}
 
-    Ok(())
+/// A relative path with `/` separators, as matched by build globs
+fn slash_path(rel: &Path) -> String {
+    rel.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Collect the files making up the module directory, keyed by their path
+/// relative to `src/<id>` (always `/`-separated).
+///
+/// Files from `src_dir` are collected first, then each overlay directory in
+/// order; a file present in several places resolves to the last overlay
+/// that provides it. The map is ordered so archive contents are stable.
+pub fn collect_module_files(
+    src_dir: &Path,
+    overlays: &[PathBuf],
+    kamignore: &Gitignore,
+) -> Result<BTreeMap<String, PathBuf>, KamError> {
+    let mut files = BTreeMap::new();
+    for dir in std::iter::once(src_dir).chain(overlays.iter().map(PathBuf::as_path)) {
+        // Ignored directories are pruned, so nothing below them is read
+        let walker = walkdir::WalkDir::new(dir)
+            .follow_links(true)
+            .into_iter()
+            .filter_entry(|e| {
+                e.depth() == 0
+                    || !kamignore
+                        .matched(e.path(), e.file_type().is_dir())
+                        .is_ignore()
+            });
+        for entry in walker {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let rel = entry.path().strip_prefix(dir).map_err(|e| {
+                KamError::StripPrefixFailed(format!(
+                    "failed to strip prefix {}: {}",
+                    dir.display(),
+                    e
+                ))
+            })?;
+            files.insert(slash_path(rel), entry.path().to_path_buf());
+        }
+    }
+    Ok(files)
 }
 
-/// Run a shell command
+/// Matcher for the gitignore-style `.kamignore` in the project root.
+///
+/// Patterns are relative to the project root and support negation
+/// (`!keep.me`); without a `.kamignore` nothing is ignored.
+pub fn load_kamignore(project_path: &Path) -> Result<Gitignore, KamError> {
+    let mut builder = GitignoreBuilder::new(project_path);
+    let path = project_path.join(".kamignore");
+    if path.is_file()
+        && let Some(e) = builder.add(&path)
+    {
+        return Err(KamError::InvalidConfig(format!(
+            "{}: {}",
+            path.display(),
+            e
+        )));
+    }
+    builder
+        .build()
+        .map_err(|e| KamError::InvalidConfig(format!("{}: {}", path.display(), e)))
+}
+
+/// Run a shell command, streaming its output to the terminal
 pub fn run_command(cmd: &str, working_dir: &Path) -> Result<(), KamError> {
     use std::process::Command;
 
-    let output = if cfg!(target_os = "windows") {
+    let status = if cfg!(target_os = "windows") {
         Command::new("cmd")
-            .args(&["/C", cmd])
+            .args(["/C", cmd])
             .current_dir(working_dir)
-            .output()
+            .status()
             .map_err(KamError::from)?
     } else {
         Command::new("sh")
-            .args(&["-c", cmd])
+            .args(["-c", cmd])
             .current_dir(working_dir)
-            .output()
+            .status()
             .map_err(KamError::from)?
     };
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-        return Err(KamError::CommandFailed(stderr));
+    if !status.success() {
+        return Err(KamError::CommandFailed(format!(
+            "`{}` exited with {}",
+            cmd, status
+        )));
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    if !stdout.trim().is_empty() {
-        println!("{}", stdout);
-    }
+    Ok(())
+}
 
+/// Run hook commands one after another, stopping at the first failure
+pub fn run_commands(commands: &[&str], working_dir: &Path) -> Result<(), KamError> {
+    for cmd in commands {
+        if commands.len() > 1 {
+            status!("{} {}", "$".dimmed(), cmd);
+        }
+        run_command(cmd, working_dir)?;
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overlay_precedence() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let write = |rel: &str, content: &str| {
+            let p = root.join(rel);
+            fs::create_dir_all(p.parent().unwrap()).unwrap();
+            fs::write(p, content).unwrap();
+        };
+        write("src/demo/module.prop", "src");
+        write("src/demo/system/etc/a.conf", "src");
+        write("overlay1/system/etc/a.conf", "overlay1");
+        write("overlay1/system/etc/b.conf", "overlay1");
+        write("overlay2/system/etc/a.conf", "overlay2");
+
+        let files = collect_module_files(
+            &root.join("src/demo"),
+            &[root.join("overlay1"), root.join("overlay2")],
+            &Gitignore::empty(),
+        )
+        .unwrap();
+
+        let read = |key: &str| fs::read_to_string(&files[key]).unwrap();
+        assert_eq!(
+            files.keys().collect::<Vec<_>>(),
+            vec!["module.prop", "system/etc/a.conf", "system/etc/b.conf"]
+        );
+        assert_eq!(read("module.prop"), "src");
+        assert_eq!(read("system/etc/a.conf"), "overlay2");
+        assert_eq!(read("system/etc/b.conf"), "overlay1");
+    }
+
+    #[test]
+    fn test_build_file_filter() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        for rel in [
+            "service.sh",
+            "notes.txt",
+            "META-INF/com/google/android/update-binary",
+            ".kam_venv/bin/tool.sh",
+            "dist/old.sh",
+        ] {
+            let p = root.join(rel);
+            fs::create_dir_all(p.parent().unwrap()).unwrap();
+            fs::write(p, rel).unwrap();
+        }
+        let mut kam_toml = KamToml::default();
+        let build = kam_toml.kam.build.get_or_insert_with(Default::default);
+        build.include = Some(vec!["*.sh".to_string(), "META-INF/**".to_string()]);
+        build.exclude = Some(vec!["**/*.bak".to_string(), "*.txt".to_string()]);
+
+        let filter = BuildFileFilter::new(&kam_toml).unwrap();
+        assert_eq!(
+            filter.included_files(root, &root.join("dist")).unwrap(),
+            vec!["META-INF/com/google/android/update-binary", "service.sh"]
+        );
+        assert!(filter.excludes("src/demo/system/a.bak"));
+        assert!(filter.excludes("notes.txt"));
+        assert!(!filter.excludes("README.md"));
+        // Includes take precedence over excludes
+        assert!(!filter.excludes("META-INF/notes.bak"));
+
+        kam_toml.kam.build.as_mut().unwrap().include = Some(vec!["[".to_string()]);
+        assert!(BuildFileFilter::new(&kam_toml).is_err());
+    }
+
+    #[test]
+    fn test_compression_method() {
+        use zip::CompressionMethod;
+        assert_eq!(compression_method(""), Some(CompressionMethod::Deflated));
+        assert_eq!(compression_method("Store"), Some(CompressionMethod::Stored));
+        assert_eq!(
+            compression_method("Deflate"),
+            Some(CompressionMethod::Deflated)
+        );
+        assert_eq!(compression_method("bzip2"), Some(CompressionMethod::Bzip2));
+        assert_eq!(compression_method("Zstd"), Some(CompressionMethod::Zstd));
+        assert_eq!(compression_method("lzma"), None);
+    }
+
+    #[test]
+    fn test_kamignore() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        for rel in [
+            "src/demo/service.sh",
+            "src/demo/service.sh~",
+            "src/demo/keep.bak",
+            "src/demo/old.bak",
+            "src/demo/fixtures/big.bin",
+        ] {
+            let p = root.join(rel);
+            fs::create_dir_all(p.parent().unwrap()).unwrap();
+            fs::write(p, rel).unwrap();
+        }
+        fs::write(
+            root.join(".kamignore"),
+            "*~\n*.bak\n!keep.bak\nsrc/demo/fixtures/\n",
+        )
+        .unwrap();
+
+        let kamignore = load_kamignore(root).unwrap();
+        let files = collect_module_files(&root.join("src/demo"), &[], &kamignore).unwrap();
+        assert_eq!(
+            files.keys().collect::<Vec<_>>(),
+            vec!["keep.bak", "service.sh"]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_module_zip_keeps_executable_mode() {
+        use clap::Parser;
+        use std::os::unix::fs::PermissionsExt;
+
+        #[derive(Parser)]
+        struct Cli {
+            #[command(flatten)]
+            build: BuildArgs,
+        }
+
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let mut kam_toml = KamToml::default();
+        kam_toml.prop.id = "demo".to_string();
+        kam_toml.write_to_dir(root).unwrap();
+        fs::create_dir_all(root.join("src/demo")).unwrap();
+        fs::write(root.join("src/demo/service.sh"), "#!/system/bin/sh\n").unwrap();
+
+        let args = Cli::parse_from(["kam", "--module-only", "--no-update-json"]).build;
+        let artifacts = build_project(root, &args, None).unwrap();
+        let out = root.join("out");
+        crate::types::modules::extract_archive(&artifacts[0], &out).unwrap();
+
+        let mode = fs::metadata(out.join("src/demo/service.sh"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o755);
+    }
+
+    #[test]
+    fn test_reproducible_build() {
+        use clap::Parser;
+
+        #[derive(Parser)]
+        struct Cli {
+            #[command(flatten)]
+            build: BuildArgs,
+        }
+
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().join("demo");
+        let mut kam_toml = KamToml::default();
+        kam_toml.prop.id = "demo".to_string();
+        let build = kam_toml.kam.build.get_or_insert_with(Default::default);
+        build.target_dir = Some(tmp.path().join("dist").display().to_string());
+        fs::create_dir_all(root.join("src/demo/system/bin")).unwrap();
+        kam_toml.write_to_dir(&root).unwrap();
+        fs::write(root.join("src/demo/service.sh"), "#!/system/bin/sh\n").unwrap();
+        fs::write(root.join("src/demo/system/bin/tool"), "tool").unwrap();
+
+        let args = Cli::parse_from(["kam", "--reproducible", "--no-update-json"]).build;
+        let build_hashes = || {
+            build_project(&root, &args, None)
+                .unwrap()
+                .iter()
+                .map(|p| crate::utils::sha256_file(p).unwrap())
+                .collect::<Vec<_>>()
+        };
+        let first = build_hashes();
+        // Sources touched since the first build still give the same bytes
+        let touched = std::time::SystemTime::now() + std::time::Duration::from_secs(3600);
+        File::options()
+            .write(true)
+            .open(root.join("src/demo/service.sh"))
+            .unwrap()
+            .set_modified(touched)
+            .unwrap();
+        let second = build_hashes();
+        assert_eq!(first.len(), 2);
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_output_template_placeholders() {
+        use chrono::TimeZone;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let mut kam_toml = KamToml::default();
+        kam_toml.prop.id = "demo".to_string();
+        kam_toml.prop.version = "1.2.0".to_string();
+        kam_toml.prop.versionCode = 120;
+        let now = chrono::Utc.with_ymd_and_hms(2024, 3, 5, 7, 8, 9).unwrap();
+        let render = |tpl: &str, path: &Path| render_output_template_at(tpl, &kam_toml, path, now);
+
+        assert_eq!(
+            render("{{id}}-{{versionCode}}-{{date}}", tmp.path()),
+            "demo-120-20240305"
+        );
+        assert_eq!(
+            render("{{id}}-{{datetime}}", tmp.path()),
+            "demo-20240305-070809"
+        );
+        // Outside a git repository the hash is empty
+        assert_eq!(render("{{id}}-{{gitsha}}", tmp.path()), "demo-");
+
+        let repo = git2::Repository::init(tmp.path()).unwrap();
+        let sig = git2::Signature::now("kam", "kam@example.com").unwrap();
+        let tree_id = repo.index().unwrap().write_tree().unwrap();
+        let tree = repo.find_tree(tree_id).unwrap();
+        let commit = repo
+            .commit(Some("HEAD"), &sig, &sig, "init", &tree, &[])
+            .unwrap();
+        fs::create_dir_all(tmp.path().join("sub")).unwrap();
+        assert_eq!(
+            render("{{gitsha}}", &tmp.path().join("sub")),
+            commit.to_string()[..7]
+        );
+    }
+}
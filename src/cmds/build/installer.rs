@@ -0,0 +1,147 @@
+use crate::types::kam_toml::KamToml;
+use crate::types::kam_toml::enums::SupportedArch;
+
+/// `META-INF/com/google/android/update-binary`: the standard Magisk module
+/// installer shim, which sources `util_functions.sh` and runs `customize.sh`
+pub const UPDATE_BINARY: &str = r#"#!/sbin/sh
+
+#################
+# Initialization
+#################
+
+umask 022
+
+# echo before loading util_functions
+ui_print() { echo "$1"; }
+
+require_new_magisk() {
+  ui_print "*******************************"
+  ui_print " Please install Magisk v20.4+! "
+  ui_print "*******************************"
+  exit 1
+}
+
+#########################
+# Load util_functions.sh
+#########################
+
+OUTFD=$2
+ZIPFILE=$3
+
+mount /data 2>/dev/null
+
+[ -f /data/adb/magisk/util_functions.sh ] || require_new_magisk
+. /data/adb/magisk/util_functions.sh
+[ $MAGISK_VER_CODE -lt 20400 ] && require_new_magisk
+
+install_module
+exit 0
+"#;
+
+/// `META-INF/com/google/android/updater-script`
+pub const UPDATER_SCRIPT: &str = "#MAGISK\n";
+
+/// Render a `customize.sh` that aborts the installation when the device does
+/// not match the module's `min_api`/`max_api`/`supported_arch`/`conflicts`.
+///
+/// An API bound of 0 means "no bound". `$API` and `$ARCH` are provided by
+/// the root manager's installer environment.
+pub fn render_customize_sh(kam_toml: &KamToml) -> String {
+    let id = &kam_toml.prop.id;
+    let kam = &kam_toml.kam;
+    let mut script = format!(
+        "# Generated by kam build from kam.toml: checks that the device can run {}\n",
+        id
+    );
+
+    let min_api = kam.min_api.unwrap_or(0);
+    let max_api = kam.max_api.unwrap_or(0);
+    if min_api > 0 {
+        script.push_str(&format!(
+            "\n[ \"$API\" -lt {min} ] && abort \"! {id} requires Android API {min} or newer (device: $API)\"\n",
+            min = min_api,
+            id = id
+        ));
+    }
+    if max_api > 0 {
+        script.push_str(&format!(
+            "\n[ \"$API\" -gt {max} ] && abort \"! {id} supports Android API {max} or older (device: $API)\"\n",
+            max = max_api,
+            id = id
+        ));
+    }
+
+    let arches: Vec<&str> = kam
+        .supported_arch
+        .iter()
+        .flatten()
+        .map(manager_arch)
+        .collect();
+    if !arches.is_empty() {
+        script.push_str(&format!(
+            "\ncase \"$ARCH\" in\n  {})\n    ;;\n  *)\n    abort \"! {} does not support $ARCH (supported: {})\"\n    ;;\nesac\n",
+            arches.join("|"),
+            id,
+            arches.join(" ")
+        ));
+    }
+
+    let conflicts: Vec<String> = kam
+        .conflicts
+        .iter()
+        .flatten()
+        .map(|c| sh_quote(c))
+        .collect();
+    if !conflicts.is_empty() {
+        script.push_str(&format!(
+            "\nfor conflict in {}; do\n  if [ -d \"/data/adb/modules/$conflict\" ] && [ ! -f \"/data/adb/modules/$conflict/remove\" ]; then\n    abort \"! {} conflicts with installed module $conflict\"\n  fi\ndone\n",
+            conflicts.join(" "),
+            id
+        ));
+    }
+
+    script
+}
+
+/// Architecture name as reported in the installer's `$ARCH`
+fn manager_arch(arch: &SupportedArch) -> &str {
+    match arch {
+        SupportedArch::Arm => "arm",
+        SupportedArch::Arm64 => "arm64",
+        SupportedArch::X86 => "x86",
+        SupportedArch::X86_64 => "x64",
+        SupportedArch::Other(s) => s,
+    }
+}
+
+/// Single-quote a value for POSIX sh
+fn sh_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_customize_sh() {
+        let mut kam_toml = KamToml::default();
+        kam_toml.prop.id = "demo".to_string();
+        kam_toml.kam.min_api = Some(26);
+        kam_toml.kam.max_api = Some(0);
+        kam_toml.kam.supported_arch = Some(vec![SupportedArch::Arm64, SupportedArch::X86_64]);
+        kam_toml.kam.conflicts = Some(vec!["other".to_string()]);
+
+        let script = render_customize_sh(&kam_toml);
+        assert!(script.contains("[ \"$API\" -lt 26 ]"));
+        assert!(!script.contains("-gt"));
+        assert!(script.contains("  arm64|x64)"));
+        assert!(script.contains("for conflict in 'other'; do"));
+
+        kam_toml.kam.min_api = None;
+        kam_toml.kam.supported_arch = None;
+        kam_toml.kam.conflicts = None;
+        let script = render_customize_sh(&kam_toml);
+        assert_eq!(script.lines().count(), 1);
+    }
+}
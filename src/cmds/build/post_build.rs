@@ -2,17 +2,19 @@ use std::path::Path;
 
 use colored::*;
 
-use super::build_project::run_command;
+use super::build_project::run_commands;
 use crate::errors::kam::KamError;
+use crate::status;
 use crate::types::kam_toml::KamToml;
 
 pub fn handle_post_build_hook(kam_toml: &KamToml, project_path: &Path) -> Result<(), KamError> {
     // Run post-build hook
     if let Some(build_config) = &kam_toml.kam.build {
-        if let Some(post_build) = &build_config.post_build {
-            println!();
-            println!("{}", "Running post-build hook...".yellow());
-            run_command(post_build, project_path)?;
+        let commands = build_config.post_build_commands();
+        if !commands.is_empty() {
+            status!();
+            status!("{}", "Running post-build hook...".yellow());
+            run_commands(&commands, project_path)?;
         }
     }
     Ok(())
@@ -0,0 +1,294 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use colored::*;
+use flate2::read::GzDecoder;
+
+use crate::errors::kam::KamError;
+use crate::status;
+use crate::types::kam_toml::KamToml;
+use crate::types::modules::safe_entry_path;
+
+/// Re-open the archives produced by a build and check that they are sane:
+/// no path-traversal entries, a parseable `kam.toml` matching the project's
+/// id/version and, for the module zip, the `mmrl.repo` files it declares
+/// (those that exist in the project; `license` may also be an SPDX id).
+///
+/// With an `archive_root`, every entry must be nested under it and the checks
+/// apply to the paths below it.
+pub fn verify_archives(
+    kam_toml: &KamToml,
+    project_path: &Path,
+    output_dir: &Path,
+    basename: &str,
+    archive_root: Option<&str>,
+) -> Result<(), KamError> {
+    status!("{}", "Verifying artifacts...".bold());
+    let mut problems = Vec::new();
+
+    let module_zip = output_dir.join(format!("{}.zip", basename));
+    if module_zip.exists() {
+        let entries = read_zip(&module_zip, archive_root)?;
+        let entries = strip_root(&module_zip, entries, archive_root, &mut problems);
+        problems.extend(check_entries(&module_zip, kam_toml, &entries));
+        for declared in declared_repo_files(kam_toml) {
+            if project_path.join(&declared).is_file()
+                && !entries.iter().any(|(name, _)| *name == declared)
+            {
+                problems.push(format!(
+                    "{}: missing declared file '{}'",
+                    module_zip.display(),
+                    declared
+                ));
+            }
+        }
+    }
+
+    let source_archive = output_dir.join(format!("{}.tar.gz", basename));
+    if source_archive.exists() {
+        let entries = read_tar_gz(&source_archive, archive_root)?;
+        let entries = strip_root(&source_archive, entries, archive_root, &mut problems);
+        problems.extend(check_entries(&source_archive, kam_toml, &entries));
+    }
+
+    if !problems.is_empty() {
+        for problem in &problems {
+            println!("  {} {}", "✗".red(), problem);
+        }
+        return Err(KamError::VerificationFailed(problems.join("; ")));
+    }
+
+    println!("  {} Archives verified", "✓".green());
+    status!();
+    Ok(())
+}
+
+/// Compare a freshly built module zip against a previously published one.
+///
+/// Passes when both files have the same sha256. Otherwise the entries are
+/// compared by name, size and CRC-32 and every added, missing or differing
+/// entry is reported; if all entries match, the archives only differ in
+/// metadata such as timestamps.
+pub fn verify_reproducible(built: &Path, published: &Path) -> Result<(), KamError> {
+    status!("{}", "Verifying reproducibility...".bold());
+    if !built.is_file() {
+        return Err(KamError::VerificationFailed(format!(
+            "no module zip was built ({} not found); only 'kam' modules produce one",
+            built.display()
+        )));
+    }
+    if !published.is_file() {
+        return Err(KamError::VerificationFailed(format!(
+            "published artifact not found: {}",
+            published.display()
+        )));
+    }
+
+    let built_sha = crate::utils::sha256_file(built)?;
+    let published_sha = crate::utils::sha256_file(published)?;
+    println!("  {} built:     {}", "•".cyan(), built_sha.dimmed());
+    println!("  {} published: {}", "•".cyan(), published_sha.dimmed());
+    if built_sha == published_sha {
+        println!("  {} Build is reproducible", "✓".green());
+        status!();
+        return Ok(());
+    }
+
+    let built_entries = zip_digests(built)?;
+    let published_entries = zip_digests(published)?;
+    let mut differences = Vec::new();
+    for (name, digest) in &published_entries {
+        match built_entries.get(name) {
+            None => differences.push(format!("only in published: {}", name)),
+            Some(d) if d != digest => differences.push(format!(
+                "differs: {} (built {} bytes, crc {:08x}; published {} bytes, crc {:08x})",
+                name, d.0, d.1, digest.0, digest.1
+            )),
+            Some(_) => {}
+        }
+    }
+    for name in built_entries.keys() {
+        if !published_entries.contains_key(name) {
+            differences.push(format!("only in built: {}", name));
+        }
+    }
+    if differences.is_empty() {
+        differences.push(
+            "all entries match; the archives differ only in metadata (timestamps, order or permissions)"
+                .to_string(),
+        );
+    }
+    for line in &differences {
+        println!("  {} {}", "✗".red(), line);
+    }
+    Err(KamError::VerificationFailed(format!(
+        "{} does not reproduce {}: {}",
+        built.display(),
+        published.display(),
+        differences.join("; ")
+    )))
+}
+
+/// `(size, crc32)` of every file entry in a zip, keyed by name
+fn zip_digests(path: &Path) -> Result<BTreeMap<String, (u64, u32)>, KamError> {
+    let mut archive = zip::ZipArchive::new(File::open(path)?)?;
+    let mut digests = BTreeMap::new();
+    for i in 0..archive.len() {
+        let file = archive.by_index(i)?;
+        if !file.is_dir() {
+            digests.insert(file.name().to_string(), (file.size(), file.crc32()));
+        }
+    }
+    Ok(digests)
+}
+
+/// Archive entries as `(path, contents)`; contents are only kept for
+/// `kam.toml` to avoid holding the whole archive in memory
+type Entries = Vec<(String, Option<Vec<u8>>)>;
+
+/// Archive path of the top-level `kam.toml`
+fn kam_toml_entry(archive_root: Option<&str>) -> String {
+    match archive_root {
+        Some(root) => format!("{}/kam.toml", root),
+        None => "kam.toml".to_string(),
+    }
+}
+
+fn read_zip(path: &Path, archive_root: Option<&str>) -> Result<Entries, KamError> {
+    let kam_toml_name = kam_toml_entry(archive_root);
+    let mut archive = zip::ZipArchive::new(File::open(path)?)?;
+    let mut entries = Vec::new();
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        let name = file.name().to_string();
+        let content = if name == kam_toml_name {
+            let mut buf = Vec::new();
+            file.read_to_end(&mut buf)?;
+            Some(buf)
+        } else {
+            None
+        };
+        entries.push((name, content));
+    }
+    Ok(entries)
+}
+
+fn read_tar_gz(path: &Path, archive_root: Option<&str>) -> Result<Entries, KamError> {
+    let kam_toml_name = kam_toml_entry(archive_root);
+    let mut archive = tar::Archive::new(GzDecoder::new(File::open(path)?));
+    let mut entries = Vec::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().to_string();
+        let content = if name == kam_toml_name {
+            let mut buf = Vec::new();
+            entry.read_to_end(&mut buf)?;
+            Some(buf)
+        } else {
+            None
+        };
+        entries.push((name, content));
+    }
+    Ok(entries)
+}
+
+/// Drop the archive root from entry paths, reporting entries outside of it
+fn strip_root(
+    archive: &Path,
+    entries: Entries,
+    archive_root: Option<&str>,
+    problems: &mut Vec<String>,
+) -> Entries {
+    let Some(root) = archive_root else {
+        return entries;
+    };
+    let prefix = format!("{}/", root);
+    entries
+        .into_iter()
+        .filter_map(|(name, content)| {
+            if name == root || name == prefix {
+                return None;
+            }
+            match name.strip_prefix(&prefix) {
+                Some(rest) => Some((rest.to_string(), content)),
+                None => {
+                    problems.push(format!(
+                        "{}: entry '{}' is outside the archive root '{}'",
+                        archive.display(),
+                        name,
+                        root
+                    ));
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Problems common to both archive kinds
+fn check_entries(archive: &Path, kam_toml: &KamToml, entries: &Entries) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    for (name, _) in entries {
+        // The same rules extraction applies
+        if safe_entry_path(Path::new(""), name).is_err() {
+            problems.push(format!(
+                "{}: unsafe entry path '{}'",
+                archive.display(),
+                name
+            ));
+        }
+    }
+
+    match entries.iter().find_map(|(name, content)| {
+        (name == "kam.toml").then_some(content.as_deref().unwrap_or_default())
+    }) {
+        None => problems.push(format!("{}: missing kam.toml", archive.display())),
+        Some(content) => {
+            match std::str::from_utf8(content)
+                .map_err(|e| e.to_string())
+                .and_then(|s| toml::from_str::<KamToml>(s).map_err(|e| e.to_string()))
+            {
+                Err(e) => problems.push(format!(
+                    "{}: kam.toml does not parse: {}",
+                    archive.display(),
+                    e
+                )),
+                Ok(packed) => {
+                    if packed.prop.id != kam_toml.prop.id
+                        || packed.prop.version != kam_toml.prop.version
+                        || packed.prop.versionCode != kam_toml.prop.versionCode
+                    {
+                        problems.push(format!(
+                            "{}: kam.toml is for {} {} ({}), expected {} {} ({})",
+                            archive.display(),
+                            packed.prop.id,
+                            packed.prop.version,
+                            packed.prop.versionCode,
+                            kam_toml.prop.id,
+                            kam_toml.prop.version,
+                            kam_toml.prop.versionCode
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    problems
+}
+
+/// Files referenced by `[mmrl.repo]` (readme, license, changelog)
+fn declared_repo_files(kam_toml: &KamToml) -> Vec<String> {
+    let Some(repo) = kam_toml.mmrl.as_ref().and_then(|m| m.repo.as_ref()) else {
+        return Vec::new();
+    };
+    [&repo.readme, &repo.license, &repo.changelog]
+        .into_iter()
+        .flatten()
+        .filter(|f| !f.trim().is_empty())
+        .cloned()
+        .collect()
+}
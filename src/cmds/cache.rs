@@ -1,17 +1,34 @@
-use crate::cache::KamCache;
+use crate::cache::{KamCache, VersionResolver};
 use crate::errors::KamError;
+use crate::status;
+use crate::types::kam_lock::KamLock;
+use crate::types::kam_toml::KamToml;
+use crate::types::kam_toml::sections::Dependency;
+use crate::types::modules::{ArchiveFormat, KamModule, extract_archive_as};
+use crate::types::source::Source;
 /// # Kam Cache Command
 ///
 /// Manage the global Kam cache.
 ///
 /// ## Subcommands
 ///
-/// - `info` - Show cache information and statistics
+/// - `info [--json]` - Show cache information and statistics
 /// - `clear` - Clear all cache
 /// - `clear-dir <dir>` - Clear specific directory (bin, lib, log, profile)
 /// - `path` - Show cache root path
+/// - `lock-status` - Show locks held in the cache
+/// - `gc [PATH...]` - Remove cached modules no project references
+/// - `pin <ID@VERSION>` / `unpin <ID@VERSION>` - Keep a cached module from
+///   being removed by `gc` and `clear-dir lib`
+/// - `add <SPEC>` - Install a module archive, directory, URL or git repo
+/// - `move <DIR>` - Relocate the cache to another directory
+/// - `export <FILE>` / `import <FILE>` - Carry the cached modules (`lib/`,
+///   `bin/`, `index/`) to another machine as a `.tar.gz`
 use clap::{Args, Subcommand};
 use colored::Colorize;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
 
 /// Arguments for the cache command
 #[derive(Args, Debug)]
@@ -24,7 +41,11 @@ pub struct CacheArgs {
 #[derive(Subcommand, Debug)]
 pub enum CacheCommands {
     /// Show cache information and statistics
-    Info,
+    Info {
+        /// Print the information as JSON
+        #[arg(long)]
+        json: bool,
+    },
 
     /// Clear all cache
     Clear {
@@ -45,6 +66,86 @@ pub enum CacheCommands {
 
     /// Show the cache root path
     Path,
+
+    /// Show locks held in the cache (pid, acquired time)
+    LockStatus {
+        /// Remove locks whose owning process is no longer running
+        #[arg(long)]
+        force_unlock: bool,
+    },
+
+    /// Remove cached library modules not referenced by the given projects
+    Gc {
+        /// Project directories whose dependencies are kept
+        #[arg(default_value = ".")]
+        paths: Vec<PathBuf>,
+
+        /// Only report what would be removed
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Also keep the newest N cached versions of each module
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        keep_latest: usize,
+    },
+
+    /// Keep a cached module regardless of references (`gc` and
+    /// `clear-dir lib` skip it)
+    Pin {
+        /// Module to pin as `<id>@<versionCode>` or `<id>@<version>`
+        spec: String,
+    },
+
+    /// Remove the pin of a cached module
+    Unpin {
+        /// Module to unpin as `<id>@<versionCode>` or `<id>@<version>`
+        spec: String,
+    },
+
+    /// Check cached modules for broken or incomplete installs
+    Verify {
+        /// Remove broken entries so the next sync fetches them again
+        #[arg(long)]
+        repair: bool,
+    },
+
+    /// Install a module into the cache without adding it to a project
+    Add {
+        /// Module archive, directory, URL or git source (`git+<url>#<rev>`)
+        spec: String,
+
+        /// Replace the cached module if it is already there
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Move the cache to another directory (then point KAM_CACHE_ROOT at it)
+    Move {
+        /// New cache root (absolute path)
+        dir: PathBuf,
+
+        /// Move into a non-empty directory, replacing cache directories there
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Bundle the cached modules (`lib/`, `bin/` and `index/`) into a
+    /// `.tar.gz`, e.g. to carry them to an offline device
+    Export {
+        /// Archive to write
+        file: PathBuf,
+    },
+
+    /// Unpack an archive written by `kam cache export` into the cache,
+    /// keeping the modules already there
+    Import {
+        /// Archive to read
+        file: PathBuf,
+
+        /// Replace cached modules and binaries the archive also contains
+        #[arg(short, long)]
+        force: bool,
+    },
 }
 
 /// Run the cache command
@@ -53,23 +154,351 @@ pub enum CacheCommands {
 ///
 /// ```bash
 /// kam cache info
+/// kam cache info --json
 /// kam cache clear --yes
 /// kam cache clear-dir log
 /// kam cache path
+/// kam cache lock-status --force-unlock
+/// kam cache gc ~/modules/a ~/modules/b --keep-latest 1 --dry-run
+/// kam cache pin core-lib@1000
+/// kam cache unpin core-lib@1.0.0
+/// kam cache verify --repair
+/// kam cache add ./dist/mylib-100.zip
+/// kam cache move /mnt/data/kam
+/// kam cache export kam-cache.tar.gz
+/// kam cache import kam-cache.tar.gz
 /// ```
 pub fn run(args: CacheArgs) -> Result<(), KamError> {
     match args.command {
-        CacheCommands::Info => show_info(),
+        CacheCommands::Info { json } => show_info(json),
         CacheCommands::Clear { yes } => clear_cache(yes),
         CacheCommands::ClearDir { dir, yes } => clear_dir(&dir, yes),
         CacheCommands::Path => show_path(),
+        CacheCommands::LockStatus { force_unlock } => lock_status(force_unlock),
+        CacheCommands::Gc {
+            paths,
+            dry_run,
+            keep_latest,
+        } => gc(&paths, dry_run, keep_latest),
+        CacheCommands::Pin { spec } => pin(&spec, true),
+        CacheCommands::Unpin { spec } => pin(&spec, false),
+        CacheCommands::Verify { repair } => verify(repair),
+        CacheCommands::Add { spec, force } => add(&spec, force),
+        CacheCommands::Move { dir, force } => move_cache(&dir, force),
+        CacheCommands::Export { file } => export(&file),
+        CacheCommands::Import { file, force } => import(&file, force),
+    }
+}
+
+/// Cache directories `kam cache export` bundles; logs and extracted
+/// templates stay behind
+const EXPORT_DIRS: &[&str] = &["lib", "bin", "index"];
+
+/// Write the cache's modules, binaries and index to the `.tar.gz` `file`
+fn export(file: &Path) -> Result<(), KamError> {
+    let cache = KamCache::new()?;
+    let modules = export_to(&cache, file)?;
+    println!(
+        "{} Exported {} module(s) to {}",
+        "✓".green(),
+        modules,
+        file.display()
+    );
+    Ok(())
+}
+
+/// Write [`EXPORT_DIRS`] of `cache` to `file`; returns the number of
+/// modules under `lib/`
+fn export_to(cache: &KamCache, file: &Path) -> Result<usize, KamError> {
+    let _guard = cache.lock()?;
+    let encoder =
+        flate2::write::GzEncoder::new(std::fs::File::create(file)?, flate2::Compression::default());
+    let mut builder = tar::Builder::new(encoder);
+    // Binaries may be links into lib/; keep them links
+    builder.follow_symlinks(false);
+    for name in EXPORT_DIRS {
+        let dir = cache.root().join(name);
+        if dir.is_dir() {
+            builder.append_dir_all(name, &dir)?;
+        }
     }
+    builder.into_inner()?.finish()?;
+    Ok(cache.installed_module_count())
+}
+
+/// What [`import_from`] did
+#[derive(Debug, Default)]
+struct ImportReport {
+    /// `lib/` entries installed
+    imported: Vec<String>,
+    /// `lib/` entries already cached and kept
+    skipped: Vec<String>,
+}
+
+/// Merge the `.tar.gz` `file` written by `kam cache export` into the cache
+fn import(file: &Path, force: bool) -> Result<(), KamError> {
+    let cache = KamCache::new()?;
+    cache.ensure_dirs()?;
+    status!("{} Importing {}", "→".cyan(), file.display());
+    let report = import_from(&cache, file, force)?;
+    for name in &report.imported {
+        status!("  {} {}", "+".green(), name);
+    }
+    println!(
+        "{} Imported {} module(s) into {}",
+        "✓".green(),
+        report.imported.len(),
+        cache.root().display()
+    );
+    if !report.skipped.is_empty() {
+        status!(
+            "  {} Kept {} module(s) already in the cache; use --force to replace them",
+            "i".cyan(),
+            report.skipped.len()
+        );
+    }
+    Ok(())
+}
+
+/// Unpack `file` next to the cache, check that every module in it loads,
+/// then move the modules and binaries into place and record the modules in
+/// the cache index. Entries already in the cache are kept unless `force`.
+/// Nothing is changed when a module of the archive is invalid.
+fn import_from(cache: &KamCache, file: &Path, force: bool) -> Result<ImportReport, KamError> {
+    let _guard = cache.lock()?;
+    // Staged inside the cache root so entries are moved by a rename
+    let staging = tempfile::tempdir_in(cache.root())?;
+    // Entries escaping the staging directory are refused like in any
+    // downloaded archive
+    extract_archive_as(file, ArchiveFormat::TarGz, staging.path())?;
+
+    let staged_lib = staging.path().join("lib");
+    let mut modules = Vec::new();
+    let mut invalid = Vec::new();
+    if staged_lib.is_dir() {
+        for entry in std::fs::read_dir(&staged_lib)?.flatten() {
+            let path = entry.path();
+            if !path.is_dir() {
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !path.join("kam.toml").is_file() {
+                invalid.push(format!("{}: missing kam.toml", name));
+                continue;
+            }
+            match KamToml::load_from_dir(&path) {
+                Ok(kam_toml) => modules.push((name, path, kam_toml)),
+                Err(e) => invalid.push(format!("{}: {}", name, e)),
+            }
+        }
+    }
+    if !invalid.is_empty() {
+        return Err(KamError::InvalidModuleStructure(format!(
+            "{} contains invalid modules: {}",
+            file.display(),
+            invalid.join("; ")
+        )));
+    }
+    modules.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut report = ImportReport::default();
+    let staged_index = staging.path().join("index");
+    for (name, path, kam_toml) in modules {
+        let code = kam_toml.prop.versionCode;
+        let dest = cache.lib_module_path(&kam_toml.prop.id, &code.to_string());
+        if dest.exists() {
+            if !force {
+                report.skipped.push(name);
+                continue;
+            }
+            std::fs::remove_dir_all(&dest)?;
+        }
+        std::fs::rename(&path, &dest)?;
+        if let Some((version, meta)) = archived_index_entry(&staged_index, &kam_toml.prop.id, code)
+        {
+            crate::cmds::add::update_local_cache_index(
+                cache,
+                &kam_toml.prop.id,
+                &version,
+                &kam_toml,
+                meta.get("package")
+                    .and_then(|p| p.as_str())
+                    .unwrap_or_default(),
+                meta.get("sha256").and_then(|s| s.as_str()),
+            )?;
+        }
+        report.imported.push(name);
+    }
+
+    let staged_bin = staging.path().join("bin");
+    if staged_bin.is_dir() {
+        std::fs::create_dir_all(cache.bin_dir())?;
+        for entry in std::fs::read_dir(&staged_bin)?.flatten() {
+            let dest = cache.bin_dir().join(entry.file_name());
+            if dest.symlink_metadata().is_ok() {
+                if !force {
+                    continue;
+                }
+                if dest.is_dir() && !dest.is_symlink() {
+                    std::fs::remove_dir_all(&dest)?;
+                } else {
+                    std::fs::remove_file(&dest)?;
+                }
+            }
+            std::fs::rename(entry.path(), &dest)?;
+        }
+    }
+    Ok(report)
+}
+
+/// The index entry of `id` at `version_code` in the exported `index` dir:
+/// the version it is recorded under and its metadata
+fn archived_index_entry(
+    index: &Path,
+    id: &str,
+    version_code: i64,
+) -> Option<(String, serde_json::Value)> {
+    let dir = crate::cmds::add::compute_index_path(index, id);
+    std::fs::read_dir(dir).ok()?.flatten().find_map(|entry| {
+        let path = entry.path();
+        let version = path.file_stem()?.to_str()?.to_string();
+        if version == "latest" || path.extension()? != "json" {
+            return None;
+        }
+        let meta: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).ok()?).ok()?;
+        (meta.get("versionCode")?.as_i64()? == version_code).then_some((version, meta))
+    })
+}
+
+/// Install the module `spec` points at under its `<id>-<versionCode>` in
+/// the cache, reading the id and version code from its `kam.toml`
+fn add(spec: &str, force: bool) -> Result<(), KamError> {
+    let cache = KamCache::new()?;
+    cache.ensure_dirs()?;
+    let source = Source::parse(spec)?;
+    status!("{} Fetching {}", "→".cyan(), spec);
+    let fetched = KamModule::new(KamToml::default(), Some(source)).fetch_to_temp()?;
+    let result = install_fetched(&cache, spec, &fetched, force);
+    let _ = std::fs::remove_dir_all(&fetched);
+    let (kam_toml, path) = result?;
+    println!(
+        "{} Cached {}@{} at {}",
+        "✓".green(),
+        kam_toml.prop.id,
+        kam_toml.prop.versionCode,
+        path.display()
+    );
+    Ok(())
+}
+
+/// Install a module fetched into `fetched` (its root, or the only directory
+/// in it) into the cache
+fn install_fetched(
+    cache: &KamCache,
+    spec: &str,
+    fetched: &Path,
+    force: bool,
+) -> Result<(KamToml, PathBuf), KamError> {
+    let mut root = fetched.to_path_buf();
+    if !root.join("kam.toml").is_file() {
+        let dirs: Vec<PathBuf> = std::fs::read_dir(fetched)?
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.is_dir())
+            .collect();
+        if let [only] = dirs.as_slice() {
+            root = only.clone();
+        }
+    }
+    if !root.join("kam.toml").is_file() {
+        return Err(KamError::InvalidConfig(format!("{} has no kam.toml", spec)));
+    }
+    let kam_toml = KamToml::load_from_dir(&root)?;
+    let version = kam_toml.prop.versionCode.to_string();
+    let path = cache.lib_module_path(&kam_toml.prop.id, &version);
+    if path.exists() && !force {
+        return Err(KamError::AlreadyCached(format!(
+            "{}@{} is already at {}; use --force to replace it",
+            kam_toml.prop.id,
+            version,
+            path.display()
+        )));
+    }
+
+    let module = KamModule::new(
+        kam_toml.clone(),
+        Some(Source::Local {
+            path: root,
+            subdir: None,
+        }),
+    );
+    let installed = module.install_into_cache(cache)?;
+    std::fs::write(
+        installed.join(".synced"),
+        format!("Synced: {} @ {} ({})", kam_toml.prop.id, version, spec),
+    )?;
+    Ok((kam_toml, installed))
+}
+
+/// Machine-readable output of `kam cache info --json`
+#[derive(Serialize)]
+struct CacheInfo {
+    root: PathBuf,
+    total_size: u64,
+    file_count: usize,
+    /// Library modules installed under `lib/`
+    modules: usize,
+    /// Pinned `lib/` entries (`kam cache pin`)
+    pinned: Vec<String>,
+    directories: BTreeMap<&'static str, CacheDirInfo>,
+}
+
+#[derive(Serialize)]
+struct CacheDirInfo {
+    path: PathBuf,
+    size: u64,
+    file_count: usize,
 }
 
 /// Show cache information
-fn show_info() -> Result<(), KamError> {
+fn show_info(json: bool) -> Result<(), KamError> {
     let cache = KamCache::new()?;
 
+    if json {
+        let mut directories = BTreeMap::new();
+        for (name, path) in [
+            ("bin", cache.bin_dir()),
+            ("lib", cache.lib_dir()),
+            ("log", cache.log_dir()),
+            ("profile", cache.profile_dir()),
+            ("tmpl", cache.tmpl_dir()),
+        ] {
+            let stats = cache.dir_stats(&path)?;
+            directories.insert(
+                name,
+                CacheDirInfo {
+                    path,
+                    size: stats.total_size,
+                    file_count: stats.file_count,
+                },
+            );
+        }
+        let stats = cache.stats()?;
+        let info = CacheInfo {
+            root: cache.root().to_path_buf(),
+            total_size: stats.total_size,
+            file_count: stats.file_count,
+            modules: cache.installed_module_count(),
+            pinned: cache.pins()?.into_iter().collect(),
+            directories,
+        };
+        let out =
+            serde_json::to_string_pretty(&info).map_err(|e| KamError::JsonError(e.to_string()))?;
+        println!("{}", out);
+        return Ok(());
+    }
+
     println!("{}", "Kam Cache Information".bold().cyan());
     println!();
     println!("  {}: {}", "Root".bold(), cache.root().display());
@@ -98,6 +527,19 @@ fn show_info() -> Result<(), KamError> {
         format!("{}", stats.file_count).green()
     );
 
+    let pins = cache.pins()?;
+    if !pins.is_empty() {
+        println!();
+        println!("{}", "Pinned:".bold());
+        for name in &pins {
+            if cache.lib_dir().join(name).is_dir() {
+                println!("  {}", name);
+            } else {
+                println!("  {} {}", name, "(not in the cache)".dimmed());
+            }
+        }
+    }
+
     Ok(())
 }
 
@@ -176,6 +618,12 @@ fn clear_dir(dir: &str, skip_confirm: bool) -> Result<(), KamError> {
             .green()
             .bold()
     );
+    if dir == "lib" {
+        let pinned = cache.pins()?;
+        if !pinned.is_empty() {
+            status!("  Kept {} pinned module(s)", pinned.len());
+        }
+    }
 
     Ok(())
 }
@@ -186,3 +634,452 @@ fn show_path() -> Result<(), KamError> {
     println!("{}", cache.root().display());
     Ok(())
 }
+
+/// Move the cache to `dir` and explain how to point kam at it
+fn move_cache(dir: &Path, force: bool) -> Result<(), KamError> {
+    let cache = KamCache::new()?;
+    status!(
+        "{} Moving cache {} -> {}",
+        "→".cyan(),
+        cache.root().display(),
+        dir.display()
+    );
+    let moved = cache.move_to(dir, force)?;
+    if moved.is_empty() {
+        println!("  {} The cache was empty; nothing to move", "•".dimmed());
+    } else {
+        println!("  {} Moved {}", "✓".green(), moved.join(", "));
+    }
+
+    println!();
+    println!("Point kam at the new location by setting KAM_CACHE_ROOT:");
+    if cfg!(windows) {
+        println!("  setx KAM_CACHE_ROOT \"{}\"", dir.display());
+    } else {
+        println!("  export KAM_CACHE_ROOT=\"{}\"", dir.display());
+    }
+    println!(
+        "{} Virtual environments link into the old cache; run `kam venv relocate` in each project",
+        "!".yellow()
+    );
+    Ok(())
+}
+
+/// Report locks held in the cache, optionally clearing abandoned ones
+fn lock_status(force_unlock: bool) -> Result<(), KamError> {
+    let cache = KamCache::new()?;
+    let locks = cache.list_locks()?;
+
+    if locks.is_empty() {
+        println!("{}", "No locks held".green());
+        return Ok(());
+    }
+
+    let mut cleared = 0;
+    for lock in &locks {
+        let pid = lock
+            .pid
+            .map(|p| p.to_string())
+            .unwrap_or_else(|| "?".to_string());
+        let state = match lock.owner_alive() {
+            Some(true) => "held".green(),
+            Some(false) => "abandoned".red(),
+            None => "unknown".yellow(),
+        };
+        println!(
+            "  {} [{}] pid {} since {}{}",
+            lock.name.bold(),
+            state,
+            pid,
+            lock.acquired.as_deref().unwrap_or("?"),
+            lock.command
+                .as_deref()
+                .map(|c| format!(" ({})", c))
+                .unwrap_or_default()
+        );
+
+        if !force_unlock {
+            continue;
+        }
+        match lock.owner_alive() {
+            Some(false) => {
+                std::fs::remove_file(&lock.path)?;
+                println!("    {} removed {}", "✓".green(), lock.path.display());
+                cleared += 1;
+            }
+            Some(true) => {
+                println!("    {} owner is still running; not removed", "!".yellow());
+            }
+            None => {
+                println!(
+                    "    {} cannot check whether pid {} is running; remove {} manually if it is not",
+                    "!".yellow(),
+                    pid,
+                    lock.path.display()
+                );
+            }
+        }
+    }
+
+    if force_unlock {
+        println!(
+            "{}",
+            format!("✓ Cleared {} lock(s)", cleared).green().bold()
+        );
+    } else if locks.iter().any(|l| l.owner_alive() == Some(false)) {
+        status!(
+            "{}",
+            "Run 'kam cache lock-status --force-unlock' to clear abandoned locks".yellow()
+        );
+    }
+
+    Ok(())
+}
+
+/// Remove cached library modules none of `paths` depends on (see
+/// [`referenced_modules`])
+fn gc(paths: &[PathBuf], dry_run: bool, keep_latest: usize) -> Result<(), KamError> {
+    let cache = KamCache::new()?;
+
+    let mut referenced: HashSet<String> = HashSet::new();
+    for path in paths {
+        referenced.extend(referenced_modules(&cache, path)?);
+    }
+
+    let report = cache.gc(&referenced, keep_latest, dry_run)?;
+    if report.removed.is_empty() {
+        println!("{}", "Nothing to remove".green());
+        return Ok(());
+    }
+
+    for name in &report.pinned {
+        status!("  {} {}", "Kept (pinned)".dimmed(), name);
+    }
+    let verb = if dry_run { "Would remove" } else { "Removed" };
+    for name in &report.removed {
+        println!("  {} {}", verb.yellow(), name);
+    }
+    let summary = format!(
+        "{} {} module(s), {} {}",
+        if dry_run {
+            "Would remove"
+        } else {
+            "✓ Removed"
+        },
+        report.removed.len(),
+        if dry_run { "freeing" } else { "freed" },
+        report.freed.format_size()
+    );
+    println!("{}", summary.green().bold());
+
+    Ok(())
+}
+
+/// `<id>-<version>` names of the cached modules the project at `path`
+/// references: its runtime and dev dependencies (resolved the same way
+/// `kam sync` does, with every feature and optional dependency enabled, since
+/// a later sync may turn them on), the dependencies features of those
+/// modules gate, and every package pinned by its `kam.lock`.
+fn referenced_modules(cache: &KamCache, path: &Path) -> Result<HashSet<String>, KamError> {
+    let resolver = VersionResolver::new(cache);
+    let kam_toml = KamToml::load_from_dir(path)?;
+    let section = kam_toml.kam.dependency.clone().unwrap_or_default();
+    let features: Vec<String> = section
+        .declared_features()
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+    let optional: Vec<String> = section
+        .optional_ids()
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+    let resolved = kam_toml.resolve_dependencies_with_features(&features, &optional)?;
+
+    let mut referenced = HashSet::new();
+    let mut pending: Vec<Dependency> = ["kam", "dev"]
+        .iter()
+        .filter_map(|g| resolved.get(g))
+        .flat_map(|group| group.dependencies.iter().cloned())
+        .collect();
+    while let Some(dep) = pending.pop() {
+        let version = resolver
+            .resolve(&dep.id, dep.versionCode.as_ref())
+            .unwrap_or(0);
+        if !referenced.insert(format!("{}-{}", dep.id, version)) {
+            continue;
+        }
+        // Features requested of the module gate more of its dependencies
+        let module_toml = cache
+            .lib_module_path(&dep.id, &version.to_string())
+            .join("kam.toml");
+        if dep.requested_features().is_empty() || !module_toml.exists() {
+            continue;
+        }
+        let gated = KamToml::load_from_file(&module_toml)?
+            .kam
+            .dependency
+            .unwrap_or_default()
+            .feature_dependencies(&dep.id, dep.requested_features())?;
+        pending.extend(gated);
+    }
+
+    let lock_path = path.join("kam.lock");
+    if lock_path.exists() {
+        for package in KamLock::load_from_path(&lock_path)?.packages {
+            referenced.insert(format!("{}-{}", package.name, package.version));
+        }
+    }
+    Ok(referenced)
+}
+
+/// Pin (or unpin) the cached module `spec`, `<id>@<versionCode>` or
+/// `<id>@<version>`
+fn pin(spec: &str, pin: bool) -> Result<(), KamError> {
+    let cache = KamCache::new()?;
+    let (id, version) = spec
+        .rsplit_once('@')
+        .filter(|(id, version)| !id.is_empty() && !version.is_empty())
+        .ok_or_else(|| {
+            KamError::ParseSourceFailed(format!("expected <id>@<version>, got '{}'", spec))
+        })?;
+    let version_code = match version.parse::<i64>() {
+        Ok(code) => code,
+        Err(_) => cache
+            .cached_versions(id)
+            .into_iter()
+            .find(|code| cache.cached_version_name(id, *code).as_deref() == Some(version))
+            .ok_or_else(|| {
+                KamError::LibraryNotFound(format!("{}@{} is not in the cache", id, version))
+            })?,
+    };
+    let name = format!("{}-{}", id, version_code);
+
+    if pin {
+        if cache.pin(id, version_code)? {
+            println!("{} Pinned {}", "✓".green(), name.bold());
+        } else {
+            println!("{} is already pinned", name.bold());
+        }
+    } else if cache.unpin(id, version_code)? {
+        println!("{} Unpinned {}", "✓".green(), name.bold());
+    } else {
+        println!("{} is not pinned", name.bold());
+    }
+    Ok(())
+}
+
+/// Check the modules installed in the cache, optionally removing broken ones
+fn verify(repair: bool) -> Result<(), KamError> {
+    let cache = KamCache::new()?;
+    let report = cache.verify(repair)?;
+
+    for (name, reason) in &report.broken {
+        let status = if report.repaired.contains(name) {
+            "removed".yellow()
+        } else {
+            "broken".red()
+        };
+        println!("  {} {}: {}", status, name, reason);
+    }
+
+    println!(
+        "{} healthy, {} broken, {} repaired",
+        report.healthy.len().to_string().green(),
+        report.broken.len().to_string().red(),
+        report.repaired.len()
+    );
+    if !repair && !report.broken.is_empty() {
+        status!(
+            "{} Run `kam cache verify --repair` to remove broken entries",
+            "Hint:".dimmed()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A cache at `root` holding `mylib` at version code `code`, indexed
+    fn cache_with_module(root: &Path, code: i64) -> KamCache {
+        let cache = KamCache::with_root(root).unwrap();
+        let mut kam_toml = KamToml::default();
+        kam_toml.prop.id = "mylib".to_string();
+        kam_toml.prop.version = format!("1.0.{}", code);
+        kam_toml.prop.versionCode = code;
+        let dir = cache.lib_module_path("mylib", &code.to_string());
+        std::fs::create_dir_all(&dir).unwrap();
+        kam_toml.write_to_dir(&dir).unwrap();
+        crate::cmds::add::update_local_cache_index(
+            &cache,
+            "mylib",
+            &kam_toml.prop.version,
+            &kam_toml,
+            &format!("mylib-{}.zip", code),
+            Some("abc"),
+        )
+        .unwrap();
+        std::fs::create_dir_all(cache.bin_dir()).unwrap();
+        std::fs::write(cache.bin_dir().join("mytool"), code.to_string()).unwrap();
+        std::fs::create_dir_all(cache.log_dir()).unwrap();
+        std::fs::write(cache.log_dir().join("kam.log"), "log").unwrap();
+        cache
+    }
+
+    #[test]
+    fn test_export_import_merges_into_cache() {
+        let source_root = tempfile::tempdir().unwrap();
+        let source = cache_with_module(source_root.path(), 100);
+        let archive = source_root.path().join("cache.tar.gz");
+        assert_eq!(export_to(&source, &archive).unwrap(), 1);
+
+        // A different version already cached is kept alongside
+        let target_root = tempfile::tempdir().unwrap();
+        let target = cache_with_module(target_root.path(), 90);
+        std::fs::remove_file(target.log_dir().join("kam.log")).unwrap();
+        let report = import_from(&target, &archive, false).unwrap();
+        assert_eq!(report.imported, vec!["mylib-100"]);
+        assert_eq!(target.cached_versions("mylib"), vec![90, 100]);
+        assert!(!target.log_dir().join("kam.log").exists());
+        // Existing binaries are not clobbered
+        assert_eq!(
+            std::fs::read_to_string(target.bin_dir().join("mytool")).unwrap(),
+            "90"
+        );
+        let index = crate::cmds::add::compute_index_path(&target.root().join("index"), "mylib");
+        let latest: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(index.join("latest.json")).unwrap())
+                .unwrap();
+        assert_eq!(latest["versionCode"], 100);
+        assert_eq!(latest["sha256"], "abc");
+
+        let report = import_from(&target, &archive, false).unwrap();
+        assert!(report.imported.is_empty());
+        assert_eq!(report.skipped, vec!["mylib-100"]);
+        let report = import_from(&target, &archive, true).unwrap();
+        assert_eq!(report.imported, vec!["mylib-100"]);
+        assert_eq!(
+            std::fs::read_to_string(target.bin_dir().join("mytool")).unwrap(),
+            "100"
+        );
+    }
+
+    #[test]
+    fn test_import_rejects_escaping_entries() {
+        let root = tempfile::tempdir().unwrap();
+        let cache = KamCache::with_root(root.path().join("cache")).unwrap();
+        let archive = root.path().join("evil.tar.gz");
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+            std::fs::File::create(&archive).unwrap(),
+            flate2::Compression::default(),
+        ));
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        builder
+            .append_link(&mut header, "lib/mylib-1/link", "../../../../outside")
+            .unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+
+        assert!(matches!(
+            import_from(&cache, &archive, false),
+            Err(KamError::UnsafeArchivePath(_))
+        ));
+        assert!(cache.cached_versions("mylib").is_empty());
+    }
+
+    #[test]
+    fn test_import_omits_missing_checksum() {
+        let source_root = tempfile::tempdir().unwrap();
+        let source = cache_with_module(source_root.path(), 100);
+        let index = crate::cmds::add::compute_index_path(&source.root().join("index"), "mylib");
+        let entry = index.join("1.0.100.json");
+        let mut meta: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&entry).unwrap()).unwrap();
+        meta.as_object_mut().unwrap().remove("sha256");
+        std::fs::write(&entry, meta.to_string()).unwrap();
+        let archive = source_root.path().join("cache.tar.gz");
+        export_to(&source, &archive).unwrap();
+
+        let target_root = tempfile::tempdir().unwrap();
+        let target = KamCache::with_root(target_root.path()).unwrap();
+        target.ensure_dirs().unwrap();
+        import_from(&target, &archive, false).unwrap();
+        let index = crate::cmds::add::compute_index_path(&target.root().join("index"), "mylib");
+        let imported: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(index.join("1.0.100.json")).unwrap())
+                .unwrap();
+        assert_eq!(imported["versionCode"], 100);
+        assert!(imported.get("sha256").is_none());
+    }
+
+    #[test]
+    fn test_gc_keeps_feature_and_optional_dependencies() {
+        use crate::types::kam_toml::sections::{DependencySection, VersionSpec};
+
+        let root = tempfile::tempdir().unwrap();
+        let cache = KamCache::with_root(root.path()).unwrap();
+        let dep = |id: &str| Dependency {
+            versionCode: Some(VersionSpec::Exact(1)),
+            ..Dependency::new(id)
+        };
+        let install = |id: &str, code: i64, section: Option<DependencySection>| {
+            let mut kam_toml = KamToml::default();
+            kam_toml.prop.id = id.to_string();
+            kam_toml.prop.versionCode = code;
+            kam_toml.kam.dependency = section;
+            let dir = cache.lib_module_path(id, &code.to_string());
+            std::fs::create_dir_all(&dir).unwrap();
+            kam_toml.write_to_dir(&dir).unwrap();
+        };
+        // `a` gates `d` behind its `net` feature
+        install(
+            "a",
+            1,
+            Some(DependencySection {
+                features: Some(BTreeMap::from([("net".to_string(), vec![dep("d")])])),
+                ..Default::default()
+            }),
+        );
+        for (id, code) in [("a", 0), ("b", 1), ("c", 1), ("d", 1), ("e", 1), ("e", 2)] {
+            install(id, code, None);
+        }
+
+        // The project uses `a[net]`, optional `b` and `c` behind a feature
+        let project = tempfile::tempdir().unwrap();
+        let mut kam_toml = KamToml::default();
+        kam_toml.prop.id = "app".to_string();
+        kam_toml.kam.dependency = Some(DependencySection {
+            kam: Some(vec![
+                Dependency {
+                    features: Some(vec!["net".to_string()]),
+                    ..dep("a")
+                },
+                Dependency {
+                    optional: true,
+                    ..dep("b")
+                },
+            ]),
+            features: Some(BTreeMap::from([("extra".to_string(), vec![dep("c")])])),
+            ..Default::default()
+        });
+        kam_toml.write_to_dir(project.path()).unwrap();
+
+        let referenced = referenced_modules(&cache, project.path()).unwrap();
+        let mut names: Vec<&str> = referenced.iter().map(String::as_str).collect();
+        names.sort();
+        assert_eq!(names, ["a-1", "b-1", "c-1", "d-1"]);
+
+        // A dry run only reports; the newest version of each id may be kept
+        let report = cache.gc(&referenced, 1, true).unwrap();
+        assert_eq!(report.removed, ["a-0", "e-1"]);
+        assert!(cache.lib_module_path("e", "1").exists());
+
+        let report = cache.gc(&referenced, 0, false).unwrap();
+        assert_eq!(report.removed, ["a-0", "e-1", "e-2"]);
+        assert!(!cache.lib_module_path("e", "2").exists());
+        assert!(cache.lib_module_path("d", "1").exists());
+    }
+}
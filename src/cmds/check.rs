@@ -9,7 +9,12 @@ use std::fs;
 use std::path::Path;
 use toml;
 
+use crate::cache::KamCache;
 use crate::errors::KamError;
+use crate::template::TemplateManager;
+use crate::types::kam_toml::KamToml;
+use crate::types::kam_toml::enums::ModuleType;
+use crate::types::root_manager;
 
 /// Arguments for the check command
 #[derive(Args, Debug)]
@@ -17,6 +22,13 @@ pub struct CheckArgs {
     /// Automatically fix issues where possible
     #[arg(long)]
     fix: bool,
+    /// Fail if several resolved dependencies provide the same name
+    #[arg(long)]
+    duplicate_provides: bool,
+    /// Fail when a root manager on this device is older than
+    /// mmrl.repo.manager.<name>.min (only a warning otherwise)
+    #[arg(long)]
+    strict: bool,
     /// Specific files to check (if not specified, check all non-hidden files)
     #[arg()]
     files: Vec<String>,
@@ -73,6 +85,53 @@ pub fn run(args: CheckArgs) -> Result<(), KamError> {
         }
     }
 
+    // Project-level rules on kam.toml
+    let project_toml = Path::new("kam.toml");
+    // Legacy keys are migrated first, so --fix lets the rules below load it
+    if project_toml.is_file() {
+        let res = check_legacy_keys(project_toml, args.fix)?;
+        if !res.issues.is_empty() {
+            results.push(res);
+        }
+    }
+    let (semantic_errors, semantic_warnings) = if project_toml.is_file() {
+        check_semantics(project_toml)
+    } else {
+        (Vec::new(), Vec::new())
+    };
+    // Dependency rules need a kam.toml whose dependency section resolves
+    if project_toml.is_file() && semantic_errors.is_empty() {
+        let res = check_dependencies(project_toml)?;
+        if !res.issues.is_empty() {
+            results.push(res);
+        }
+    }
+    let layout_mismatches = if project_toml.is_file() {
+        check_layout(project_toml)
+    } else {
+        Vec::new()
+    };
+    let provide_errors = if project_toml.is_file() {
+        check_provides(project_toml)
+    } else {
+        Vec::new()
+    };
+    let media_warnings = if project_toml.is_file() {
+        check_media(project_toml)
+    } else {
+        Vec::new()
+    };
+    let placeholder_warnings = if project_toml.is_file() {
+        check_template_placeholders(project_toml)
+    } else {
+        Vec::new()
+    };
+    let duplicates = if args.duplicate_provides {
+        check_duplicate_provides(project_toml)?
+    } else {
+        Vec::new()
+    };
+
     let total_issues: usize = results.iter().map(|r| r.issues.len()).sum();
     let total_fixed: usize = results.iter().map(|r| r.fixed_count).sum();
     let remaining_issues = total_issues - total_fixed;
@@ -110,9 +169,261 @@ pub fn run(args: CheckArgs) -> Result<(), KamError> {
         }
     }
 
+    if !media_warnings.is_empty() {
+        println!();
+        println!("{} Display assets:", "!".yellow());
+        for line in &media_warnings {
+            println!("  - {}", line);
+        }
+    }
+
+    if !placeholder_warnings.is_empty() {
+        println!();
+        println!(
+            "{} Template placeholders (kam init only replaces plain {{{{name}}}} placeholders; Tera syntax is copied verbatim):",
+            "!".yellow()
+        );
+        for line in &placeholder_warnings {
+            println!("  - {}", line);
+        }
+    }
+
+    if !semantic_warnings.is_empty() {
+        println!();
+        println!("{} kam.toml warnings:", "!".yellow());
+        for line in &semantic_warnings {
+            println!("  - {}", line);
+        }
+    }
+
+    if !semantic_errors.is_empty() {
+        println!();
+        println!("{} kam.toml errors:", "✗".red());
+        for line in &semantic_errors {
+            println!("  - {}", line);
+        }
+        return Err(KamError::InvalidKamToml(semantic_errors.join("; ")));
+    }
+
+    if !layout_mismatches.is_empty() {
+        println!();
+        println!(
+            "{} module_type does not match the project layout:",
+            "✗".red()
+        );
+        for line in &layout_mismatches {
+            println!("  - {}", line);
+        }
+        return Err(KamError::InvalidModuleStructure(
+            layout_mismatches.join("; "),
+        ));
+    }
+
+    if !provide_errors.is_empty() {
+        println!();
+        println!("{} Library provides:", "✗".red());
+        for line in &provide_errors {
+            println!("  - {}", line);
+        }
+        return Err(KamError::InvalidModuleStructure(provide_errors.join("; ")));
+    }
+
+    if !duplicates.is_empty() {
+        println!();
+        println!("{} Duplicate provides:", "✗".red());
+        for line in &duplicates {
+            println!("  - {}", line);
+        }
+        return Err(KamError::DuplicateProvides(duplicates.join("; ")));
+    }
+
+    if let Ok(kam_toml) = KamToml::load_from_file(project_toml) {
+        check_root_manager(&kam_toml, args.strict)?;
+    }
+
+    Ok(())
+}
+
+/// Compare the root managers installed on this device with the
+/// `mmrl.repo.manager.<name>.min` versions of `kam_toml`: one that is too old
+/// is a warning, or an error with `strict`. Off-device nothing is checked.
+pub(crate) fn check_root_manager(kam_toml: &KamToml, strict: bool) -> Result<(), KamError> {
+    if !root_manager::requires_min_version(kam_toml) {
+        return Ok(());
+    }
+    let Some(installed) = root_manager::detect() else {
+        println!(
+            "  {} Not on an Android device; root manager versions are not checked",
+            "•".cyan()
+        );
+        return Ok(());
+    };
+    let problems = root_manager::version_problems(kam_toml, &installed);
+    if problems.is_empty() {
+        return Ok(());
+    }
+    println!();
+    if strict {
+        println!("{} Root manager:", "✗".red());
+    } else {
+        println!("{} Root manager:", "!".yellow());
+    }
+    for line in &problems {
+        println!("  - {}", line);
+    }
+    if strict {
+        return Err(KamError::ManagerTooOld(problems.join("; ")));
+    }
     Ok(())
 }
 
+/// Report the legacy keys of kam.toml that `kam migrate` rewrites; with
+/// `fix`, rewrite them
+fn check_legacy_keys(path: &Path, fix: bool) -> Result<CheckResult, KamError> {
+    let content = fs::read_to_string(path)?;
+    // Syntax errors are reported by the per-file checks
+    let Ok((migrated, changes)) = KamToml::migrate_str(&content) else {
+        return Ok(CheckResult {
+            file: path.display().to_string(),
+            issues: Vec::new(),
+            fixed_count: 0,
+        });
+    };
+    if fix && !changes.is_empty() {
+        fs::write(path, migrated)?;
+    }
+    Ok(CheckResult {
+        file: path.display().to_string(),
+        fixed_count: if fix { changes.len() } else { 0 },
+        issues: changes
+            .into_iter()
+            .map(|change| format!("Legacy key: {}", change))
+            .collect(),
+    })
+}
+
+/// Find names provided by more than one module among the project and its
+/// resolved (runtime and dev) dependencies
+fn check_duplicate_provides(path: &Path) -> Result<Vec<String>, KamError> {
+    if !path.is_file() {
+        println!(
+            "{} No kam.toml found, skipping provides check",
+            "!".yellow()
+        );
+        return Ok(Vec::new());
+    }
+    let kam_toml = KamToml::load_from_file(path)?;
+    let cache = KamCache::new()?;
+    Ok(kam_toml
+        .duplicate_provides(&cache, &["kam", "dev"])?
+        .into_iter()
+        .map(|(name, ids)| format!("'{}' is provided by {}", name, ids.join(", ")))
+        .collect())
+}
+
+/// Check that the declared `module_type` matches the project layout
+fn check_layout(path: &Path) -> Vec<String> {
+    // Syntax errors are reported by the per-file checks
+    let Ok(kam_toml) = KamToml::load_from_file(path) else {
+        return Vec::new();
+    };
+    let project_dir = path.parent().unwrap_or(Path::new("."));
+    kam_toml.layout_mismatches(project_dir)
+}
+
+/// Check that the module's own `kam.lib.provides` names are unique and
+/// their paths exist
+fn check_provides(path: &Path) -> Vec<String> {
+    // Syntax errors are reported by the per-file checks
+    let Ok(kam_toml) = KamToml::load_from_file(path) else {
+        return Vec::new();
+    };
+    let project_dir = path.parent().unwrap_or(Path::new("."));
+    kam_toml.provide_errors(project_dir)
+}
+
+/// Check the icon/cover/screenshot entries of `[mmrl.repo]`
+fn check_media(path: &Path) -> Vec<String> {
+    // Syntax errors are reported by the per-file checks
+    let Ok(kam_toml) = KamToml::load_from_file(path) else {
+        return Vec::new();
+    };
+    let project_dir = path.parent().unwrap_or(Path::new("."));
+    kam_toml.media_warnings(project_dir)
+}
+
+/// Validate kam.toml beyond its syntax. Returns `(errors, warnings)`:
+/// errors cover the schema, `prop.id`/`prop.version`, dependency sources and
+/// include cycles; warnings cover missing readme/license/changelog files and
+/// unknown or inconsistent architectures
+fn check_semantics(path: &Path) -> (Vec<String>, Vec<String>) {
+    let kam_toml = match KamToml::load_from_file(path) {
+        Ok(kam_toml) => kam_toml,
+        Err(e) => return (vec![e.to_string()], Vec::new()),
+    };
+    let project_dir = path.parent().unwrap_or(Path::new("."));
+    let mut warnings = kam_toml.missing_repo_files(project_dir);
+    warnings.extend(kam_toml.arch_warnings());
+    (kam_toml.semantic_errors(), warnings)
+}
+
+/// For template modules, find Tera syntax under `src/` that `kam init` would
+/// copy unrendered: it only substitutes plain `{{name}}` placeholders
+fn check_template_placeholders(path: &Path) -> Vec<String> {
+    // Syntax errors are reported by the per-file checks
+    let Ok(kam_toml) = KamToml::load_from_file(path) else {
+        return Vec::new();
+    };
+    if kam_toml.kam.module_type != ModuleType::Template {
+        return Vec::new();
+    }
+    let project_dir = path.parent().unwrap_or(Path::new("."));
+    let mut warnings = Vec::new();
+    for entry in WalkBuilder::new(project_dir.join("src"))
+        .hidden(false)
+        .build()
+        .flatten()
+    {
+        let Ok(content) = fs::read_to_string(entry.path()) else {
+            continue;
+        };
+        let rel = entry
+            .path()
+            .strip_prefix(project_dir)
+            .unwrap_or(entry.path());
+        for (line, construct) in TemplateManager::tera_constructs(&content) {
+            warnings.push(format!("{}:{}: {}", rel.display(), line, construct));
+        }
+    }
+    warnings
+}
+
+/// Check the project's dependencies against the cached modules: peer
+/// dependencies must be provided by the project and requested features must
+/// be declared by the target module
+fn check_dependencies(path: &Path) -> Result<CheckResult, KamError> {
+    let mut issues = Vec::new();
+    // Syntax errors are reported by the per-file checks
+    if let Ok(kam_toml) = KamToml::load_from_file(path) {
+        let cache = KamCache::new()?;
+        for peer in kam_toml.unsatisfied_peers(&cache)? {
+            issues.push(format!("Unsatisfied peer dependency: {}", peer));
+        }
+        for (id, feature) in kam_toml.unknown_requested_features(&cache)? {
+            issues.push(format!(
+                "Dependency '{}' does not declare requested feature '{}'",
+                id, feature
+            ));
+        }
+    }
+
+    Ok(CheckResult {
+        file: path.display().to_string(),
+        issues,
+        fixed_count: 0,
+    })
+}
+
 /// Check a single file
 fn check_file(path: &Path, fix: bool) -> Result<CheckResult, KamError> {
     let mut issues = Vec::new();
@@ -1,4 +1,5 @@
 use crate::errors::KamError;
+use chrono::Utc;
 use clap::{Args, Subcommand};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
@@ -6,7 +7,6 @@ use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 use walkdir::WalkDir;
-use chrono::Utc;
 
 /// Arguments for the dev command
 #[derive(Args, Debug)]
@@ -32,6 +32,9 @@ pub struct CollectArgs {
     /// Output file
     #[arg(short, long, default_value = "json/modules_index.json")]
     output: String,
+    /// Additional index tree to merge into the output (repeatable)
+    #[arg(long = "merge", value_name = "INDEX_PATH")]
+    merge: Vec<String>,
 }
 
 #[derive(Args, Debug)]
@@ -50,6 +53,10 @@ pub struct SyncArgs {
     /// Path to the index directory
     #[arg(short, long)]
     output: String,
+    /// Directory holding the packages, looked up by the file name of each
+    /// zipUrl; packages not found there are downloaded to compute `cksum`
+    #[arg(long, value_name = "DIR")]
+    packages: Option<String>,
 }
 
 /// Run the dev command
@@ -69,7 +76,8 @@ fn collect(args: CollectArgs) -> Result<(), KamError> {
     // Read repo metadata from config.json
     let metadata: RepoMetadata = if config_path.exists() {
         let content = fs::read_to_string(&config_path)?;
-        serde_json::from_str(&content).map_err(|e| KamError::JsonError(format!("Failed to parse config.json: {}", e)))?
+        serde_json::from_str(&content)
+            .map_err(|e| KamError::JsonError(format!("Failed to parse config.json: {}", e)))?
     } else {
         // Default metadata if config.json doesn't exist
         RepoMetadata {
@@ -84,23 +92,31 @@ fn collect(args: CollectArgs) -> Result<(), KamError> {
     };
 
     let mut modules_map: HashMap<String, Vec<IndexEntry>> = HashMap::new();
+    // Tree each id/version was first collected from, to report conflicts
+    let mut origin: HashMap<(String, String), String> = HashMap::new();
+    let mut conflicts = 0;
 
-    for entry in WalkDir::new(&index_path).into_iter().filter_map(|e| e.ok()) {
-        if entry.file_type().is_file() {
-            let path = entry.path();
-            if let Some(parent) = path.parent() {
-                if parent.starts_with(&index_path) {
-                    let content = fs::read_to_string(path)?;
-                    for line in content.lines() {
-                        if let Ok(entry) = serde_json::from_str::<IndexEntry>(line) {
-                            modules_map
-                                .entry(entry.name.clone())
-                                .or_insert_with(Vec::new)
-                                .push(entry);
-                        }
-                    }
+    let merged = args.merge.iter().map(Path::new);
+    for tree in std::iter::once(index_path.as_path()).chain(merged) {
+        for entry in read_index_tree(tree)? {
+            let key = (entry.name.clone(), entry.version_key());
+            let versions = modules_map.entry(entry.name.clone()).or_default();
+            // Same version listed more than once: keep the first one
+            if let Some(existing) = versions.iter().find(|e| e.version_key() == key.1) {
+                if *existing != entry {
+                    conflicts += 1;
+                    println!(
+                        "Conflicting metadata for {}@{}: {} differs from {}",
+                        key.0,
+                        key.1,
+                        tree.display(),
+                        origin[&key]
+                    );
                 }
+                continue;
             }
+            origin.insert(key, tree.display().to_string());
+            versions.push(entry);
         }
     }
 
@@ -162,9 +178,61 @@ fn collect(args: CollectArgs) -> Result<(), KamError> {
     let json = serde_json::to_string_pretty(&modules_json)?;
     fs::write(&args.output, json)?;
     println!("Collected {} modules to {}", len, args.output);
+    if conflicts > 0 {
+        println!(
+            "{} conflicting entries were skipped (first tree wins)",
+            conflicts
+        );
+    }
     Ok(())
 }
 
+/// sha256 of the package behind `zip_url`: the file of the same name in
+/// `packages_dir`, the local file the URL names, or else the downloaded file
+fn package_cksum(zip_url: &str, packages_dir: Option<&Path>) -> Result<String, KamError> {
+    let file_name = zip_url.rsplit('/').next().unwrap_or(zip_url);
+    if let Some(local) = packages_dir
+        .map(|dir| dir.join(file_name))
+        .filter(|p| p.is_file())
+    {
+        return Ok(crate::utils::sha256_file(&local)?);
+    }
+    let local = Path::new(zip_url.strip_prefix("file://").unwrap_or(zip_url));
+    if local.is_file() {
+        return Ok(crate::utils::sha256_file(local)?);
+    }
+
+    let response = crate::utils::http_get(zip_url)
+        .map_err(|e| KamError::FetchFailed(format!("failed to download {}: {}", zip_url, e)))?;
+    if !response.status().is_success() {
+        return Err(KamError::FetchFailed(format!(
+            "download failed: {} -> {}",
+            zip_url,
+            response.status()
+        )));
+    }
+    let bytes = response
+        .bytes()
+        .map_err(|e| KamError::FetchFailed(format!("read download body: {}", e)))?;
+    Ok(format!("{:x}", Sha256::digest(&bytes)))
+}
+
+/// Read every index entry (one JSON object per line) below `index_path`
+fn read_index_tree(index_path: &Path) -> Result<Vec<IndexEntry>, KamError> {
+    let mut entries = Vec::new();
+    for entry in WalkDir::new(index_path).into_iter().filter_map(|e| e.ok()) {
+        if entry.file_type().is_file() {
+            let content = fs::read_to_string(entry.path())?;
+            for line in content.lines() {
+                if let Ok(entry) = serde_json::from_str::<IndexEntry>(line) {
+                    entries.push(entry);
+                }
+            }
+        }
+    }
+    Ok(entries)
+}
+
 fn mkindex(args: MkindexArgs) -> Result<(), KamError> {
     let index_path = Path::new(&args.index_path);
     if args.ensure {
@@ -202,9 +270,7 @@ fn sync(args: SyncArgs) -> Result<(), KamError> {
 
         let mut content = String::new();
         for version in module.versions {
-            let mut hasher = Sha256::new();
-            hasher.update(version.zipUrl.as_bytes());
-            let cksum = format!("{:x}", hasher.finalize());
+            let cksum = package_cksum(&version.zipUrl, args.packages.as_deref().map(Path::new))?;
 
             let entry = IndexEntry {
                 name: module.id.clone(),
@@ -313,7 +379,7 @@ struct Version {
     size: Option<u64>,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 struct Features {
     service: Option<bool>,
     post_fs_data: Option<bool>,
@@ -327,7 +393,7 @@ struct Features {
     modconf: Option<bool>,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 #[allow(non_snake_case)]
 struct Track {
     #[serde(rename = "type")]
@@ -337,7 +403,7 @@ struct Track {
     antifeatures: Option<Vec<String>>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, PartialEq)]
 #[allow(non_snake_case)]
 struct IndexEntry {
     name: String,
@@ -364,3 +430,13 @@ struct IndexEntry {
     cksum: String,
     yanked: bool,
 }
+
+impl IndexEntry {
+    /// Identifies a version of a module: its versionCode, or the version
+    /// string for entries without one
+    fn version_key(&self) -> String {
+        self.versionCode
+            .map(|c| c.to_string())
+            .unwrap_or_else(|| self.vers.clone())
+    }
+}
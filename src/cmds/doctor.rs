@@ -0,0 +1,273 @@
+use crate::cache::KamCache;
+use crate::errors::KamError;
+use crate::template::TemplateManager;
+use crate::types::kam_toml::KamToml;
+use crate::types::kam_toml::sections::Registries;
+/// # Kam Doctor Command
+///
+/// Diagnose the environment kam runs in and print one PASS/WARN/FAIL line
+/// per check:
+///
+/// - the cache root can be determined (`HOME`/`USERPROFILE` or
+///   `KAM_CACHE_ROOT`) and is writable
+/// - the built-in templates are extracted under the cache's `tmpl/`
+/// - git `user.name` and `user.email` are configured (used by `kam init`)
+/// - the default registry is reachable
+/// - `sh` (`cmd` on Windows) is available to run build hooks
+///
+/// Only failures make the command exit non-zero.
+///
+/// ## Example
+///
+/// ```bash
+/// kam doctor
+/// kam --offline doctor
+/// ```
+use clap::Args;
+use colored::Colorize;
+use std::path::Path;
+use std::time::Duration;
+
+/// How long the registry reachability check waits
+const REGISTRY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Arguments for the doctor command
+#[derive(Args, Debug)]
+pub struct DoctorArgs {
+    /// Project whose registry is checked (default: current directory; the
+    /// default registry is used outside a project)
+    #[arg(default_value = ".")]
+    pub path: String,
+}
+
+/// Outcome of one check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+/// A check's outcome with what was found (and how to fix it)
+struct Check {
+    status: Status,
+    name: &'static str,
+    detail: String,
+}
+
+impl Check {
+    fn new(status: Status, name: &'static str, detail: impl Into<String>) -> Check {
+        Check {
+            status,
+            name,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Run the doctor command
+pub fn run(args: DoctorArgs) -> Result<(), KamError> {
+    let mut checks = Vec::new();
+    let cache = match KamCache::new() {
+        Ok(cache) => {
+            checks.push(check_cache_root(&cache));
+            Some(cache)
+        }
+        Err(e) => {
+            checks.push(Check::new(
+                Status::Fail,
+                "Cache root",
+                format!("{}; set HOME (USERPROFILE on Windows) or KAM_CACHE_ROOT", e),
+            ));
+            None
+        }
+    };
+    if let Some(cache) = &cache {
+        checks.push(check_templates(cache));
+    }
+    checks.push(check_git_identity());
+    checks.push(check_registry(Path::new(&args.path)));
+    checks.push(check_shell());
+
+    let width = checks.iter().map(|c| c.name.len()).max().unwrap_or(0);
+    for check in &checks {
+        let status = match check.status {
+            Status::Pass => "PASS".green().bold(),
+            Status::Warn => "WARN".yellow().bold(),
+            Status::Fail => "FAIL".red().bold(),
+        };
+        println!("{}  {:<width$}  {}", status, check.name, check.detail);
+    }
+
+    let count = |status: Status| checks.iter().filter(|c| c.status == status).count();
+    let (warnings, failures) = (count(Status::Warn), count(Status::Fail));
+    println!();
+    println!(
+        "{} passed, {} warning(s), {} failure(s)",
+        count(Status::Pass),
+        warnings,
+        failures
+    );
+    if failures > 0 {
+        let failed: Vec<&str> = checks
+            .iter()
+            .filter(|c| c.status == Status::Fail)
+            .map(|c| c.name)
+            .collect();
+        return Err(KamError::DoctorFailed(failed.join(", ")));
+    }
+    Ok(())
+}
+
+/// The cache root exists (or can be created) and files can be written there
+fn check_cache_root(cache: &KamCache) -> Check {
+    let root = cache.root();
+    let writable = std::fs::create_dir_all(root).and_then(|_| tempfile::tempfile_in(root));
+    match writable {
+        Ok(_) => Check::new(Status::Pass, "Cache root", root.display().to_string()),
+        Err(e) => Check::new(
+            Status::Fail,
+            "Cache root",
+            format!(
+                "{} is not writable: {}; fix its permissions or set KAM_CACHE_ROOT",
+                root.display(),
+                e
+            ),
+        ),
+    }
+}
+
+/// The built-in templates are extracted into the cache
+fn check_templates(cache: &KamCache) -> Check {
+    let missing: Vec<String> = TemplateManager::list_builtin_templates()
+        .into_iter()
+        .filter(|t| !cache.tmpl_dir().join(format!("{}.tar.gz", t)).is_file())
+        .collect();
+    if missing.is_empty() {
+        Check::new(
+            Status::Pass,
+            "Templates",
+            format!("built-in templates in {}", cache.tmpl_dir().display()),
+        )
+    } else {
+        Check::new(
+            Status::Warn,
+            "Templates",
+            format!(
+                "not extracted yet: {} (done on first `kam init` that uses them)",
+                missing.join(", ")
+            ),
+        )
+    }
+}
+
+/// git `user.name` and `user.email` are set, so `kam init` can fill in the
+/// author
+fn check_git_identity() -> Check {
+    let config = git2::Config::open_default().ok();
+    let get = |key: &str| {
+        config
+            .as_ref()
+            .and_then(|c| c.get_string(key).ok())
+            .filter(|v| !v.trim().is_empty())
+    };
+    match (get("user.name"), get("user.email")) {
+        (Some(name), Some(email)) => Check::new(
+            Status::Pass,
+            "Git identity",
+            format!("{} <{}>", name, email),
+        ),
+        (name, email) => {
+            let missing: Vec<&str> = [("user.name", name), ("user.email", email)]
+                .into_iter()
+                .filter(|(_, v)| v.is_none())
+                .map(|(key, _)| key)
+                .collect();
+            Check::new(
+                Status::Warn,
+                "Git identity",
+                format!(
+                    "{} not set; `kam init` falls back to a placeholder author \
+                     (git config --global {} ...)",
+                    missing.join(" and "),
+                    missing[0]
+                ),
+            )
+        }
+    }
+}
+
+/// The project's default registry (or the built-in index) answers
+fn check_registry(project: &Path) -> Check {
+    let registries = match KamToml::load_from_dir(project) {
+        Ok(kam_toml) => kam_toml.registries(),
+        Err(_) => Registries::new(None),
+    };
+    let url = registries.default_source();
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return if Path::new(url).is_dir() {
+            Check::new(Status::Pass, "Registry", format!("{} (local)", url))
+        } else {
+            Check::new(
+                Status::Fail,
+                "Registry",
+                format!("{} is neither a URL nor an existing directory", url),
+            )
+        };
+    }
+    if crate::utils::is_offline() {
+        return Check::new(
+            Status::Warn,
+            "Registry",
+            format!("{} not checked (offline)", url),
+        );
+    }
+    let client = reqwest::blocking::Client::builder()
+        .timeout(REGISTRY_TIMEOUT)
+        .build();
+    match client.and_then(|c| c.get(url).send()) {
+        Ok(resp) if resp.status().is_success() => {
+            Check::new(Status::Pass, "Registry", format!("{} reachable", url))
+        }
+        Ok(resp) => Check::new(
+            Status::Warn,
+            "Registry",
+            format!("{} answered HTTP {}", url, resp.status()),
+        ),
+        Err(e) => Check::new(
+            Status::Fail,
+            "Registry",
+            format!(
+                "{} unreachable: {}; check your network or proxy, or use --offline",
+                url, e
+            ),
+        ),
+    }
+}
+
+/// The shell build hooks run in is available
+fn check_shell() -> Check {
+    let (shell, args): (&str, &[&str]) = if cfg!(target_os = "windows") {
+        ("cmd", &["/C", "exit 0"])
+    } else {
+        ("sh", &["-c", "exit 0"])
+    };
+    match std::process::Command::new(shell).args(args).status() {
+        Ok(status) if status.success() => {
+            Check::new(Status::Pass, "Hook shell", format!("{} available", shell))
+        }
+        Ok(status) => Check::new(
+            Status::Fail,
+            "Hook shell",
+            format!("{} exited with {}", shell, status),
+        ),
+        Err(e) => Check::new(
+            Status::Fail,
+            "Hook shell",
+            format!(
+                "{} not found ({}); build hooks (pre_build/post_build) cannot run",
+                shell, e
+            ),
+        ),
+    }
+}
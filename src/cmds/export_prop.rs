@@ -0,0 +1,49 @@
+use crate::errors::KamError;
+use crate::types::kam_toml::KamToml;
+/// # Kam Export-Prop Command
+///
+/// Write the flat Magisk `module.prop` for a project from its `kam.toml`,
+/// without running a build. Useful for CI steps that only need the prop file.
+///
+/// ## Example
+///
+/// ```bash
+/// kam export-prop
+/// kam export-prop --output dist/module.prop
+/// kam export-prop --output -   # print to stdout
+/// ```
+use clap::Args;
+use colored::Colorize;
+use std::path::Path;
+
+/// Arguments for the export-prop command
+#[derive(Args, Debug)]
+pub struct ExportPropArgs {
+    /// Path to the project (default: current directory)
+    #[arg(default_value = ".")]
+    pub path: String,
+
+    /// Output file, relative to the project; `-` prints to stdout
+    #[arg(short, long, default_value = "module.prop")]
+    pub output: String,
+}
+
+/// Run the export-prop command
+pub fn run(args: ExportPropArgs) -> Result<(), KamError> {
+    let project_path = Path::new(&args.path);
+    let kam_toml = KamToml::load_from_dir(project_path)?;
+    let module_prop = kam_toml.to_module_prop();
+
+    if args.output == "-" {
+        print!("{}", module_prop);
+        return Ok(());
+    }
+
+    let output = project_path.join(&args.output);
+    if let Some(parent) = output.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&output, module_prop)?;
+    println!("{} Wrote {}", "✓".green(), output.display());
+    Ok(())
+}
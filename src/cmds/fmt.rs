@@ -0,0 +1,52 @@
+use crate::errors::KamError;
+use crate::types::kam_toml::KamToml;
+/// # Kam Fmt Command
+///
+/// Rewrite a project's `kam.toml` in the canonical style (see
+/// [`KamToml::format_str`]): `[prop]`, `[mmrl]` and `[kam]` in that order,
+/// unindented `key = value` lines and sorted language keys. Comments are
+/// kept. `--check` only reports whether the file is formatted and fails if
+/// it is not, for CI.
+///
+/// ## Example
+///
+/// ```bash
+/// kam fmt
+/// kam fmt --check
+/// ```
+use clap::Args;
+use colored::Colorize;
+use std::path::Path;
+
+/// Arguments for the fmt command
+#[derive(Args, Debug)]
+pub struct FmtArgs {
+    /// Path to the project (default: current directory)
+    #[arg(default_value = ".")]
+    pub path: String,
+
+    /// Don't write anything; fail if kam.toml is not formatted
+    #[arg(long)]
+    pub check: bool,
+}
+
+/// Run the fmt command
+pub fn run(args: FmtArgs) -> Result<(), KamError> {
+    let path = Path::new(&args.path).join("kam.toml");
+    let content = std::fs::read_to_string(&path)?;
+    let formatted = KamToml::format_str(&content)?;
+    if formatted == content {
+        println!("{} {} is formatted", "✓".green(), path.display());
+        return Ok(());
+    }
+    if args.check {
+        println!("{} {} is not formatted", "✗".red(), path.display());
+        return Err(KamError::NotFormatted(format!(
+            "{}; run `kam fmt` to format it",
+            path.display()
+        )));
+    }
+    std::fs::write(&path, formatted)?;
+    println!("{} Formatted {}", "✓".green(), path.display());
+    Ok(())
+}
@@ -0,0 +1,112 @@
+use crate::cache::KamCache;
+use crate::cmds::add::compute_index_path;
+use crate::errors::KamError;
+use crate::types::kam_toml::KamToml;
+/// # Kam Info Command
+///
+/// Show information about a module.
+///
+/// By default the module's entry in the local index (`<cache>/index`) is
+/// shown. With `--cached`, the versions already extracted into the cache's
+/// `lib/` directory are listed instead, which tells whether `kam sync` will
+/// need the network.
+///
+/// ## Example
+///
+/// ```bash
+/// kam info core-lib
+/// kam info core-lib --cached
+/// ```
+use clap::Args;
+use colored::Colorize;
+use std::fs;
+
+/// Arguments for the info command
+#[derive(Args, Debug)]
+pub struct InfoArgs {
+    /// Module id
+    pub id: String,
+
+    /// List the versions present in the local cache
+    #[arg(long)]
+    pub cached: bool,
+}
+
+/// Run the info command
+pub fn run(args: InfoArgs) -> Result<(), KamError> {
+    let cache = KamCache::new()?;
+    if args.cached {
+        show_cached(&cache, &args.id)
+    } else {
+        show_index(&cache, &args.id)
+    }
+}
+
+/// List the cached `<id>-<versionCode>` directories of a module
+fn show_cached(cache: &KamCache, id: &str) -> Result<(), KamError> {
+    let versions = cache.cached_versions(id);
+    if versions.is_empty() {
+        println!(
+            "{} No cached versions of '{}' in {}",
+            "i".cyan(),
+            id,
+            cache.lib_dir().display()
+        );
+        return Ok(());
+    }
+
+    println!("{} {}:", "Cached versions of".bold(), id.bold());
+    for code in versions {
+        let version_code = code.to_string();
+        let stats = cache.module_stats(id, &version_code)?;
+        let module_toml = cache.lib_module_path(id, &version_code).join("kam.toml");
+        let (version, status) = if !module_toml.exists() {
+            ("?".to_string(), "missing kam.toml".yellow())
+        } else {
+            match KamToml::load_from_file(&module_toml) {
+                Ok(kt) if kt.prop.id == id => (kt.prop.version, "kam.toml ok".green()),
+                Ok(kt) => (
+                    kt.prop.version,
+                    format!("kam.toml declares id '{}'", kt.prop.id).red(),
+                ),
+                Err(_) => ("?".to_string(), "invalid kam.toml".red()),
+            }
+        };
+        println!(
+            "  {:<16} {:<12} {:>10}  {}",
+            version_code,
+            version,
+            stats.format_size(),
+            status
+        );
+    }
+    Ok(())
+}
+
+/// Show the module's latest entry from the local index
+fn show_index(cache: &KamCache, id: &str) -> Result<(), KamError> {
+    let latest = compute_index_path(&cache.root().join("index"), id).join("latest.json");
+    if !latest.exists() {
+        println!(
+            "{} '{}' is not in the local index; run with --cached to list cached versions",
+            "i".cyan(),
+            id
+        );
+        return Ok(());
+    }
+
+    let content = fs::read_to_string(&latest)?;
+    let entry: serde_json::Value =
+        serde_json::from_str(&content).map_err(|e| KamError::JsonError(e.to_string()))?;
+    println!("{}", id.bold());
+    for key in ["version", "versionCode", "author", "description", "package"] {
+        if let Some(value) = entry.get(key).filter(|v| !v.is_null()) {
+            let value = value
+                .as_str()
+                .map(str::to_string)
+                .unwrap_or_else(|| value.to_string());
+            println!("  {}: {}", key.bold(), value);
+        }
+    }
+    Ok(())
+}
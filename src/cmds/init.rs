@@ -1,11 +1,13 @@
 use std::collections::BTreeMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::errors::KamError;
 use crate::types::kam_toml::enums::ModuleType;
+use crate::types::kam_toml::sections::validate_id;
 use crate::types::modules::KamToml;
 
 pub mod args;
+pub mod git;
 pub mod impl_mod;
 pub mod kam;
 pub mod post_init;
@@ -107,19 +109,47 @@ fn generate_update_json_url(remote_url: &str, id: &str, default_branch: &str) ->
     )
 }
 
+/// Resolve the project directory for the `PATH` argument of `kam init`
+fn resolve_project_path(name: &str, current_dir: &Path) -> PathBuf {
+    if name.starts_with('/') || name.starts_with('\\') || name.contains(':') {
+        PathBuf::from(name)
+    } else {
+        current_dir.join(name)
+    }
+}
+
+/// Derive the module id: an explicit `--id` wins, otherwise the basename of
+/// the project directory (so `kam init .` inside `my-module/` yields
+/// `my-module`). The result is checked with `validate_id`.
+fn derive_id(explicit_id: Option<&str>, project_path: &Path) -> Result<String, KamError> {
+    let id = match explicit_id {
+        Some(id) => id.to_string(),
+        None => {
+            // Resolve `.`/`..` through the filesystem when the directory
+            // exists; `file_name` already ignores a trailing `.`.
+            let resolved = project_path
+                .canonicalize()
+                .unwrap_or_else(|_| project_path.to_path_buf());
+            resolved
+                .file_name()
+                .and_then(|n| n.to_str())
+                .map(|n| n.to_string())
+                .ok_or_else(|| {
+                    KamError::InvalidDirectory(format!(
+                        "cannot derive a module id from '{}'; pass --id",
+                        project_path.display()
+                    ))
+                })?
+        }
+    };
+    validate_id(&id)?;
+    Ok(id)
+}
+
 /// Run the init command
 pub fn run(args: InitArgs) -> Result<(), KamError> {
     let current_dir = std::env::current_dir()?;
-    let project_name = &args.name;
-    let project_path: PathBuf = if project_name.starts_with('/')
-        || project_name.starts_with('\\')
-        || project_name.contains(':')
-    {
-        PathBuf::from(project_name)
-    } else {
-        current_dir.join(project_name)
-    };
-    let name = args.project_name.as_deref().unwrap_or("My Module");
+    let project_path = resolve_project_path(&args.name, &current_dir);
     let path = project_path.as_path();
 
     // Ensure cache is initialized early so templates and builtins are available.
@@ -164,6 +194,14 @@ pub fn run(args: InitArgs) -> Result<(), KamError> {
         (ModuleType::Repo, "repo_template".to_string())
     } else if args.venv {
         (ModuleType::Template, "venv_template".to_string())
+    } else if let Some(from) = &args.from {
+        if !tmpl_mod::is_external_template(from) {
+            return Err(KamError::TemplateNotFound(format!(
+                "'{}' is neither an http(s) URL nor an existing path",
+                from
+            )));
+        }
+        (ModuleType::Kam, from.clone())
     } else if let Some(impl_name) = &args.r#impl {
         (ModuleType::Kam, impl_name.clone())
     } else {
@@ -175,7 +213,7 @@ pub fn run(args: InitArgs) -> Result<(), KamError> {
 
     let version = args.version.as_deref().unwrap_or("1.0.0");
 
-    // Add project_name and description to template_vars
+    // Add project_name (display name, default "My Module") and description to template_vars
     let project_name = args.project_name.as_deref().unwrap_or("My Module");
     let description = args.description.as_deref().unwrap_or(&match module_type {
         ModuleType::Kam => "A kam module",
@@ -196,18 +234,7 @@ pub fn run(args: InitArgs) -> Result<(), KamError> {
     let default_author = format!("{} ({})", git_author, git_email);
     let author = args.author.as_deref().unwrap_or(&default_author);
 
-    // Determine ID from the project path's basename
-    let id = if args.name == "." {
-        std::env::current_dir()
-            .unwrap()
-            .file_name()
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .to_string()
-    } else {
-        args.name.clone()
-    };
+    let id = derive_id(args.id.as_deref(), path)?;
 
     let update_json = if args.update_json.is_some() {
         Some(args.update_json.as_deref().unwrap().to_string())
@@ -219,82 +246,82 @@ pub fn run(args: InitArgs) -> Result<(), KamError> {
         ))
     };
 
-// Create name and description maps with multiple languages
-let mut name_map = BTreeMap::new();
-name_map.insert("en".to_string(), id.clone()); // Use ID for all languages
-name_map.insert("zh-CN".to_string(), id.clone());
-name_map.insert("zh-TW".to_string(), id.clone());
-name_map.insert("ja".to_string(), id.clone());
-name_map.insert("ko".to_string(), id.clone());
-
-let mut description_map = BTreeMap::new();
-description_map.insert("en".to_string(), description.to_string());
-description_map.insert(
-    "zh-CN".to_string(),
-    format!(
-        "一个{}模块",
-        match module_type {
-            ModuleType::Kam => "kam",
-            ModuleType::Library => "库",
-            ModuleType::Template => "模板",
-            ModuleType::Repo => "仓库",
-        }
-    ),
-);
-description_map.insert(
-    "zh-TW".to_string(),
-    format!(
-        "一個{}模組",
-        match module_type {
-            ModuleType::Kam => "kam",
-            ModuleType::Library => "庫",
-            ModuleType::Template => "模板",
-            ModuleType::Repo => "倉庫",
-        }
-    ),
-);
-description_map.insert(
-    "ja".to_string(),
-    format!(
-        "{}モジュール",
-        match module_type {
-            ModuleType::Kam => "kam",
-            ModuleType::Library => "ライブラリ",
-            ModuleType::Template => "テンプレート",
-            ModuleType::Repo => "リポジトリ",
-        }
-    ),
-);
-description_map.insert(
-    "ko".to_string(),
-    format!(
-        "{} 모듈",
-        match module_type {
-            ModuleType::Kam => "kam",
-            ModuleType::Library => "라이브러리",
-            ModuleType::Template => "템플릿",
-            ModuleType::Repo => "저장소",
-        }
-    ),
-);
-
-// Create KamToml
-let mut kt = KamToml::new_with_current_timestamp(
-    id.clone(),
-    name_map.clone(),
-    version.to_string(),
-    author.to_string(),
-    description_map.clone(),
-    update_json.clone(),
-    None,
-);
-
-// For repo modules, initialize mmrl.repo with repository template variable
-if module_type == ModuleType::Repo {
-    let mmrl = kt.mmrl.get_or_insert_with(Default::default);
-    let repo = mmrl.repo.get_or_insert_with(Default::default);
-    repo.repository = Some("{{repository}}".to_string());
-}
+    // Create name and description maps with multiple languages
+    let mut name_map = BTreeMap::new();
+    name_map.insert("en".to_string(), id.clone()); // Use ID for all languages
+    name_map.insert("zh-CN".to_string(), id.clone());
+    name_map.insert("zh-TW".to_string(), id.clone());
+    name_map.insert("ja".to_string(), id.clone());
+    name_map.insert("ko".to_string(), id.clone());
+
+    let mut description_map = BTreeMap::new();
+    description_map.insert("en".to_string(), description.to_string());
+    description_map.insert(
+        "zh-CN".to_string(),
+        format!(
+            "一个{}模块",
+            match module_type {
+                ModuleType::Kam => "kam",
+                ModuleType::Library => "库",
+                ModuleType::Template => "模板",
+                ModuleType::Repo => "仓库",
+            }
+        ),
+    );
+    description_map.insert(
+        "zh-TW".to_string(),
+        format!(
+            "一個{}模組",
+            match module_type {
+                ModuleType::Kam => "kam",
+                ModuleType::Library => "庫",
+                ModuleType::Template => "模板",
+                ModuleType::Repo => "倉庫",
+            }
+        ),
+    );
+    description_map.insert(
+        "ja".to_string(),
+        format!(
+            "{}モジュール",
+            match module_type {
+                ModuleType::Kam => "kam",
+                ModuleType::Library => "ライブラリ",
+                ModuleType::Template => "テンプレート",
+                ModuleType::Repo => "リポジトリ",
+            }
+        ),
+    );
+    description_map.insert(
+        "ko".to_string(),
+        format!(
+            "{} 모듈",
+            match module_type {
+                ModuleType::Kam => "kam",
+                ModuleType::Library => "라이브러리",
+                ModuleType::Template => "템플릿",
+                ModuleType::Repo => "저장소",
+            }
+        ),
+    );
+
+    // Create KamToml
+    let mut kt = KamToml::new_with_current_timestamp(
+        id.clone(),
+        name_map.clone(),
+        version.to_string(),
+        author.to_string(),
+        description_map.clone(),
+        update_json.clone(),
+        None,
+    );
+
+    // For repo modules, initialize mmrl.repo with repository template variable
+    if module_type == ModuleType::Repo {
+        let mmrl = kt.mmrl.get_or_insert_with(Default::default);
+        let repo = mmrl.repo.get_or_insert_with(Default::default);
+        repo.repository = Some("{{repository}}".to_string());
+    }
 
     // Initialize using template
     tmpl_mod::init_template(
@@ -316,7 +343,7 @@ if module_type == ModuleType::Repo {
         &args,
         &mut template_vars,
         &id,
-        &name,
+        project_name,
         &version,
         &author,
         &description,
@@ -324,3 +351,35 @@ if module_type == ModuleType::Repo {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_derive_id_from_dot() {
+        let tmp = tempfile::tempdir().unwrap();
+        let module_dir = tmp.path().join("my-module");
+        std::fs::create_dir(&module_dir).unwrap();
+
+        let path = resolve_project_path(".", &module_dir);
+        assert_eq!(derive_id(None, &path).unwrap(), "my-module");
+    }
+
+    #[test]
+    fn test_derive_id_from_absolute_path() {
+        let tmp = tempfile::tempdir().unwrap();
+        let target = tmp.path().join("abs_module");
+        let path = resolve_project_path(target.to_str().unwrap(), Path::new("/elsewhere"));
+        assert_eq!(path, target);
+        assert_eq!(derive_id(None, &path).unwrap(), "abs_module");
+    }
+
+    #[test]
+    fn test_derive_id_explicit_wins() {
+        let path = resolve_project_path("some-dir", Path::new("/work"));
+        assert_eq!(derive_id(Some("custom.id"), &path).unwrap(), "custom.id");
+        assert!(derive_id(Some("1bad"), &path).is_err());
+        assert!(derive_id(None, &resolve_project_path("bad id", Path::new("/work"))).is_err());
+    }
+}
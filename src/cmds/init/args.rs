@@ -39,6 +39,16 @@ pub struct InitArgs {
     #[arg(long)]
     pub r#impl: Option<String>,
 
+    /// Scaffold from a template archive (zip or tar.gz) at a local path or
+    /// http(s) URL; the variables its kam.toml declares are set with
+    /// --var key=value or prompted for
+    #[arg(
+        long,
+        value_name = "URL|PATH",
+        conflicts_with_all = ["impl", "kam", "lib", "tmpl", "repo", "venv"]
+    )]
+    pub from: Option<String>,
+
     /// Create META-INF folder for traditional Magisk modules
     #[arg(long)]
     pub meta_inf: bool,
@@ -47,6 +57,10 @@ pub struct InitArgs {
     #[arg(long)]
     pub web_root: bool,
 
+    /// Initialize a git repository and write a .gitignore (dist/, .kam_venv/, .env)
+    #[arg(long)]
+    pub git: bool,
+
     /// Template variables in key=value format
     #[arg(long)]
     pub var: Vec<String>,
@@ -0,0 +1,119 @@
+use std::path::Path;
+
+use crate::cmds::init::status::{StatusType, print_status};
+use crate::errors::KamError;
+use crate::status;
+
+/// Entries `kam init --git` makes sure the project's `.gitignore` contains
+pub const GITIGNORE_ENTRIES: &[&str] = &["dist/", ".kam_venv/", ".env"];
+
+/// Initialize a git repository in `path` (unless it already is inside one)
+/// and add the build output, the venv and `.env` to its `.gitignore`.
+///
+/// An existing `.gitignore` is never replaced: missing entries are appended.
+/// When `path` is inside an existing repository the user is asked first
+/// (`KAM_NONINTERACTIVE` answers yes).
+pub fn init_repository(path: &Path) -> Result<(), KamError> {
+    match git2::Repository::discover(path) {
+        Ok(repo) => {
+            let root = repo
+                .workdir()
+                .map(|w| w.display().to_string())
+                .unwrap_or_else(|| repo.path().display().to_string());
+            status!("Already inside git repository {}, skipping git init", root);
+            if !confirm("Add kam entries to .gitignore?")? {
+                return Ok(());
+            }
+        }
+        Err(_) => {
+            git2::Repository::init(path)?;
+            print_status(StatusType::Add, ".git/", true);
+        }
+    }
+    write_gitignore(path)
+}
+
+/// Create `.gitignore` or append the entries it lacks
+fn write_gitignore(path: &Path) -> Result<(), KamError> {
+    let gitignore = path.join(".gitignore");
+    let existing = match std::fs::read_to_string(&gitignore) {
+        Ok(content) => Some(content),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e.into()),
+    };
+    match merge_gitignore(existing.as_deref().unwrap_or("")) {
+        Some(content) => {
+            std::fs::write(&gitignore, content)?;
+            let status = if existing.is_some() {
+                StatusType::Update
+            } else {
+                StatusType::Add
+            };
+            print_status(status, ".gitignore", false);
+        }
+        None => print_status(StatusType::Skip, ".gitignore", false),
+    }
+    Ok(())
+}
+
+/// `existing` with the missing [`GITIGNORE_ENTRIES`] appended, or `None`
+/// when it already ignores all of them. `dist`, `/dist` and `/dist/` count
+/// as the same entry.
+fn merge_gitignore(existing: &str) -> Option<String> {
+    let normalize = |line: &str| line.trim().trim_matches('/').to_string();
+    let present: Vec<String> = existing.lines().map(normalize).collect();
+    let missing: Vec<&str> = GITIGNORE_ENTRIES
+        .iter()
+        .copied()
+        .filter(|entry| !present.contains(&normalize(entry)))
+        .collect();
+    if missing.is_empty() {
+        return None;
+    }
+
+    let mut content = existing.to_string();
+    if !content.is_empty() {
+        if !content.ends_with('\n') {
+            content.push('\n');
+        }
+        content.push('\n');
+    }
+    content.push_str("# kam\n");
+    for entry in missing {
+        content.push_str(entry);
+        content.push('\n');
+    }
+    Some(content)
+}
+
+/// Ask a yes/no question, defaulting to yes
+fn confirm(question: &str) -> Result<bool, KamError> {
+    if std::env::var("KAM_NONINTERACTIVE").is_ok() {
+        return Ok(true);
+    }
+    use std::io::{Write, stdin, stdout};
+    print!("{} [Y/n] ", question);
+    let _ = stdout().flush();
+    let mut input = String::new();
+    stdin().read_line(&mut input)?;
+    let answer = input.trim().to_ascii_lowercase();
+    Ok(answer.is_empty() || answer == "y" || answer == "yes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_gitignore() {
+        assert_eq!(
+            merge_gitignore("").unwrap(),
+            "# kam\ndist/\n.kam_venv/\n.env\n"
+        );
+        assert_eq!(
+            merge_gitignore("target\n/dist").unwrap(),
+            "target\n/dist\n\n# kam\n.kam_venv/\n.env\n"
+        );
+        assert_eq!(merge_gitignore("dist\n.kam_venv\n.env\n"), None);
+    }
+}
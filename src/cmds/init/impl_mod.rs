@@ -49,13 +49,17 @@ pub fn init_impl(
 
     // Load template variables and insert defaults (refactored to helper to avoid deep nesting)
     let template_kam_path = template_path.join("kam.toml");
+    // Variables declared by the template, listed when rendering fails
+    let mut declared_vars: Vec<String> = Vec::new();
     if template_kam_path.exists() {
         fn merge_template_defaults(
             kt_path: &std::path::Path,
             template_vars: &mut HashMap<String, String>,
+            declared: &mut Vec<String>,
         ) -> Result<(), KamError> {
             let kt_template = KamToml::load_from_file(kt_path)?;
             if let Some(tmpl) = &kt_template.kam.tmpl {
+                declared.extend(tmpl.variables.keys().cloned());
                 for (var_name, var_def) in &tmpl.variables {
                     if template_vars.contains_key(var_name.as_str()) {
                         continue;
@@ -86,7 +90,7 @@ pub fn init_impl(
             Ok(())
         }
 
-        merge_template_defaults(&template_kam_path, template_vars)?;
+        merge_template_defaults(&template_kam_path, template_vars, &mut declared_vars)?;
     }
 
     let name_map_btree: BTreeMap<_, _> = name_map.into_iter().collect();
@@ -141,7 +145,9 @@ pub fn init_impl(
             context.insert(k, v);
         }
         let mut tera = Tera::default();
-        content = tera.render_str(&content, &context).map_err(|e| KamError::TemplateRenderError(e.to_string()))?;
+        content = tera
+            .render_str(&content, &context)
+            .map_err(|e| render_error("kam.toml", &e, &declared_vars))?;
         std::fs::write(&kam_toml_path, content)?;
     }
 
@@ -153,7 +159,9 @@ pub fn init_impl(
             context.insert(k, v);
         }
         let mut tera = Tera::default();
-        let src_dir_replaced = tera.render_str(src_dir_placeholder, &context).map_err(|e| KamError::TemplateRenderError(e.to_string()))?;
+        let src_dir_replaced = tera
+            .render_str(src_dir_placeholder, &context)
+            .map_err(|e| render_error("src/{{id}}", &e, &declared_vars))?;
         let src_temp = template_path.join("src").join(&src_dir_replaced);
 
         if src_temp.exists() {
@@ -165,9 +173,14 @@ pub fn init_impl(
                 let entry = entry?;
                 let filename = entry.file_name();
                 let file_name_str = filename.to_string_lossy().to_string();
-                let replaced_name = tera.render_str(&file_name_str, &context).map_err(|e| KamError::TemplateRenderError(e.to_string()))?;
+                let template_rel = format!("src/{}/{}", src_dir_replaced, file_name_str);
+                let replaced_name = tera.render_str(&file_name_str, &context).map_err(|e| {
+                    render_error(&format!("{} (file name)", template_rel), &e, &declared_vars)
+                })?;
                 let mut content = std::fs::read_to_string(entry.path())?;
-                content = tera.render_str(&content, &context).map_err(|e| KamError::TemplateRenderError(e.to_string()))?;
+                content = tera
+                    .render_str(&content, &context)
+                    .map_err(|e| render_error(&template_rel, &e, &declared_vars))?;
                 let dest_file = src_dir.join(&replaced_name);
                 let file_rel = format!("src/{}/{}", id, replaced_name);
                 print_status(StatusType::Add, &file_rel, false);
@@ -184,3 +197,57 @@ pub fn init_impl(
 
     Ok(())
 }
+
+/// Describe a Tera failure for `file` (relative to the template root): the
+/// whole error chain, which carries the line for syntax errors, plus the
+/// undefined variable and the variables the template declares.
+fn render_error(file: &str, err: &tera::Error, declared: &[String]) -> KamError {
+    let mut messages = vec![err.to_string()];
+    let mut source = std::error::Error::source(err);
+    while let Some(e) = source {
+        messages.push(e.to_string());
+        source = e.source();
+    }
+    let mut message = format!("{}: {}", file, messages.join(": "));
+
+    let undefined = messages.iter().find_map(|m| {
+        let rest = m.strip_prefix("Variable `")?;
+        rest.split_once("` not found")
+            .map(|(name, _)| name.to_string())
+    });
+    if let Some(name) = undefined {
+        let declared = if declared.is_empty() {
+            "none".to_string()
+        } else {
+            declared.join(", ")
+        };
+        message.push_str(&format!(
+            "; undefined variable '{}' (declared template variables: {})",
+            name, declared
+        ));
+    }
+    KamError::TemplateRenderError(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_error_names_undefined_variable() {
+        let err = Tera::default()
+            .render_str("hello {{ bar }}", &Context::new())
+            .unwrap_err();
+        let declared = vec!["foo".to_string()];
+        let message = render_error("src/demo/a.sh", &err, &declared).to_string();
+        assert!(message.contains("src/demo/a.sh: "));
+        assert!(message.contains("undefined variable 'bar' (declared template variables: foo)"));
+
+        let err = Tera::default()
+            .render_str("{{ bar ", &Context::new())
+            .unwrap_err();
+        let message = render_error("kam.toml", &err, &[]).to_string();
+        assert!(message.contains("kam.toml: "));
+        assert!(!message.contains("undefined variable"));
+    }
+}
@@ -50,6 +50,10 @@ pub fn post_process(
         print_status(StatusType::Add, &web_root_rel, true);
     }
 
+    if args.git {
+        crate::cmds::init::git::init_repository(path)?;
+    }
+
     println!("Initialized Kam project in {}", path.display());
 
     Ok(())
@@ -1,5 +1,7 @@
 use colored::{Color, Colorize};
 
+use crate::status;
+
 /// Status types for file operations
 pub enum StatusType {
     /// Adding a new file or directory
@@ -21,25 +23,25 @@ pub fn print_status(status: StatusType, rel: &str, is_dir: bool) {
     match status {
         StatusType::Add => {
             let color = if is_dir { Color::Blue } else { Color::Green };
-            println!("{}", format!("+ {}", rel).color(color));
+            status!("{}", format!("+ {}", rel).color(color));
         }
         StatusType::Update => {
-            println!("{}", format!("~ {}", rel).color(Color::Yellow));
+            status!("{}", format!("~ {}", rel).color(Color::Yellow));
         }
         StatusType::Skip => {
-            println!("{}", format!("~ {}", rel).color(Color::Yellow));
+            status!("{}", format!("~ {}", rel).color(Color::Yellow));
         }
         StatusType::Copy(from, to) => {
-            println!("{}", format!("{} -> {}", from, to).color(Color::Cyan));
+            status!("{}", format!("{} -> {}", from, to).color(Color::Cyan));
         }
         StatusType::Symlink(target, link) => {
-            println!(
+            status!(
                 "{}",
                 format!("{} --> {}", link, target).color(Color::Magenta)
             );
         }
         StatusType::Delete => {
-            println!("{}", format!("- {}", rel).color(Color::Red));
+            status!("{}", format!("- {}", rel).color(Color::Red));
         }
     }
 }
@@ -1,40 +1,185 @@
 use crate::cmds::init::status::{StatusType, print_status};
 use crate::types::kam_toml::KamToml;
 use crate::types::kam_toml::enums::ModuleType;
-use crate::types::kam_toml::sections::TmplSection;
+use crate::types::kam_toml::sections::{TmplSection, VariableDefinition};
 use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
 use crate::cache::KamCache;
 use crate::errors::KamError;
-use flate2;
-use tar;
+use crate::types::modules::{ArchiveFormat, extract_archive_as};
 use tempfile::TempDir;
 use walkdir;
-use zip;
 
-// Helper to extract a zip or tar.gz file into a TempDir and return the template folder path
+// Helper to extract a template archive into a TempDir and return the template folder path.
+// Files without a recognized extension (e.g. downloads) are treated as tar.gz.
 pub fn extract_archive_to_temp(archive_path: &Path) -> Result<(TempDir, PathBuf), KamError> {
     let temp_dir = TempDir::new()?;
-    let file = std::fs::File::open(archive_path)?;
-    if archive_path.extension().and_then(|s| s.to_str()) == Some("zip") {
-        let mut archive = zip::ZipArchive::new(file)?;
-        archive.extract(temp_dir.path())?;
-    } else {
-        let gz_decoder = flate2::read::GzDecoder::new(file);
-        let mut archive = tar::Archive::new(gz_decoder);
-        archive.unpack(temp_dir.path())?;
+    let format =
+        ArchiveFormat::from_name(&archive_path.to_string_lossy()).unwrap_or(ArchiveFormat::TarGz);
+    extract_archive_as(archive_path, format, temp_dir.path())?;
+    let template_path = template_root(temp_dir.path());
+    Ok((temp_dir, template_path))
+}
+
+/// The folder of an extracted template: the extraction directory itself, or
+/// its only subdirectory when the archive wraps the template in one folder
+/// (as GitHub source archives do)
+fn template_root(dir: &Path) -> PathBuf {
+    if dir.join("kam.toml").is_file() {
+        return dir.to_path_buf();
     }
-    let template_path = temp_dir.path().to_path_buf();
+    let entries: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| entries.flatten().map(|e| e.path()).collect())
+        .unwrap_or_default();
+    match entries.as_slice() {
+        [only] if only.join("kam.toml").is_file() => only.clone(),
+        _ => dir.to_path_buf(),
+    }
+}
+
+/// Whether `template` names a template archive (an http(s) URL or a local
+/// path) rather than a built-in template
+pub fn is_external_template(template: &str) -> bool {
+    template.starts_with("http://")
+        || template.starts_with("https://")
+        || Path::new(template).exists()
+}
+
+/// Download or extract the template named by `template_key` into a temporary
+/// directory and return it with the template folder.
+///
+/// `template_key` is an http(s) URL or a local path to a zip/tar.gz archive
+/// (or an unpacked template folder), or else the name of a built-in
+/// template.
+pub fn prepare_template(template_key: &str) -> Result<(TempDir, PathBuf), KamError> {
+    // Normalize template_key into an asset/base name we use, e.g.
+    // input: "tmpl" | "template" | "tmpl_template" -> base "tmpl_template"
+    let normalized_key = match template_key {
+        "tmpl" | "template" => "tmpl_template",
+        _ => template_key,
+    };
+
+    // If template_key is a URL, try downloading
+    if template_key.starts_with("http://") || template_key.starts_with("https://") {
+        let resp = crate::utils::http_get(template_key)?;
+        if !resp.status().is_success() {
+            return Err(KamError::FetchFailed(format!(
+                "Failed to download template {}: HTTP {}",
+                template_key,
+                resp.status()
+            )));
+        }
+        // Keep the archive's extension so its format is detected
+        let name = template_key
+            .split(['?', '#'])
+            .next()
+            .and_then(|url| url.rsplit('/').next())
+            .unwrap_or_default();
+        let mut tmp = tempfile::Builder::new()
+            .prefix("kam-template-")
+            .suffix(&format!("-{}", name))
+            .tempfile()?;
+        let total = resp.content_length();
+        crate::progress::download(resp, tmp.as_file_mut(), template_key, total)?;
+        return extract_archive_to_temp(tmp.path());
+    }
+
+    let local = Path::new(template_key);
+    if local.is_dir() {
+        return Ok((TempDir::new()?, local.to_path_buf()));
+    }
+    if local.is_file() {
+        return extract_archive_to_temp(local);
+    }
+
+    // Ensure the template is available in cache (only for built-ins)
+    crate::template::TemplateManager::ensure_template(normalized_key)?;
+
+    // Extract from cache
+    let cache = KamCache::new()?;
+    let cache_path = cache.tmpl_dir().join(format!("{}.tar.gz", normalized_key));
+    let (temp_dir, template_path) = extract_archive_to_temp(&cache_path)?;
+
     Ok((temp_dir, template_path))
 }
 
+/// Variables declared in `[kam.tmpl.variables]` of the template's kam.toml
+fn template_variables(
+    template_path: &Path,
+) -> Result<BTreeMap<String, VariableDefinition>, KamError> {
+    if !template_path.join("kam.toml").is_file() {
+        return Ok(BTreeMap::new());
+    }
+    let kt = KamToml::load_from_dir(template_path)?;
+    Ok(kt.kam.tmpl.map(|tmpl| tmpl.variables).unwrap_or_default())
+}
+
+/// Prompt for the value of required variable `name` until a valid one is
+/// entered. The template's `note` is the prompt and its `help` is shown
+/// above it; `choices` are listed as a numbered menu and can be picked by
+/// number or typed out.
+fn prompt_variable(name: &str, def: &VariableDefinition) -> Result<String, KamError> {
+    use std::io::{Write, stdin, stdout};
+
+    if let Some(help) = &def.help {
+        println!("{}", help);
+    }
+    let choices = def.choices.as_deref().unwrap_or_default();
+    for (i, choice) in choices.iter().enumerate() {
+        println!("  {}) {}", i + 1, choice);
+    }
+    loop {
+        // If the template provides a human-friendly note, show it as the
+        // prompt; otherwise fall back to a generic prompt.
+        if let Some(n) = &def.note {
+            print!("{} ", n);
+        } else if !choices.is_empty() {
+            print!(
+                "Choose a value for required template variable '{}' [1-{}]: ",
+                name,
+                choices.len()
+            );
+        } else {
+            print!(
+                "Enter value for required template variable '{}' (type: {}): ",
+                name, def.var_type
+            );
+        }
+        let _ = stdout().flush();
+        let mut input = String::new();
+        let read = stdin().read_line(&mut input)?;
+        let val = input.trim();
+        if val.is_empty() {
+            let reason = match &def.note {
+                Some(n) => format!("Required template variable '{}' not provided: {}", name, n),
+                None => format!("Required template variable '{}' not provided", name),
+            };
+            return Err(KamError::TemplateVarRequired(reason));
+        }
+        let val = match val.parse::<usize>() {
+            Ok(i) if (1..=choices.len()).contains(&i) => choices[i - 1].as_str(),
+            _ => val,
+        };
+        match def.validate(name, val) {
+            Ok(value) => return Ok(value),
+            // Out of input: don't prompt forever
+            Err(e) if read == 0 || !input.ends_with('\n') => return Err(e),
+            Err(e) => println!("{}", e),
+        }
+    }
+}
+
 /// Initialize a template project.
 ///
 /// `impl_template` is an optional template selector. If provided, we will
 /// search `cache/tmpl/<impl_template>.zip` first, then try embedded built-in
 /// templates, then local repo (KAM_LOCAL_REPO), and finally try a direct URL
 /// `impl_template` looks like one.
+///
+/// A template given as a URL or local archive (`kam init --from`) declares
+/// its variables in its own kam.toml; `vars` then supplies their values as
+/// `key=value`, and required ones without a value are prompted for.
 pub fn init_template(
     path: &Path,
     id: &str,
@@ -48,8 +193,22 @@ pub fn init_template(
     module_type: ModuleType,
     update_json: Option<String>,
 ) -> Result<(), KamError> {
+    // Determine which template to use
+    let template_key = impl_template.as_deref().unwrap_or("tmpl");
+    let (_temp_dir, template_path) = prepare_template(template_key)?;
+
     // Parse template variable definitions from CLI args and template kam.toml
     let mut variables = crate::template::TemplateManager::parse_template_variables(vars)?;
+    let mut provided = std::collections::HashMap::new();
+    if is_external_template(template_key) {
+        let values = crate::template::TemplateManager::parse_template_vars(vars)?;
+        for (k, def) in template_variables(&template_path)? {
+            if let Some(value) = values.get(&k) {
+                provided.insert(k.clone(), value.clone());
+            }
+            variables.insert(k, def);
+        }
+    }
 
     // Protect core project parameters from being overridden by template variables.
     // These are provided via CLI flags or inferred (id/name/version/author) and
@@ -74,13 +233,23 @@ pub fn init_template(
     // For variables marked `required` with no default, prompt the user interactively.
     // For others, use the default when provided. If non-interactive mode is set,
     // fail on missing required variables.
+    // Values must match the variable's `choices` and `var_type`; invalid
+    // given values fail, and prompts ask again until the input is valid.
+    let non_interactive = std::env::var("KAM_NONINTERACTIVE").is_ok();
     for (k, def) in &variables {
-        if let Some(d) = &def.default {
-            runtime_values.insert(k.to_string(), d.clone());
+        if let Some(value) = provided.get(k) {
+            runtime_values.insert(k.to_string(), def.validate(k, value)?);
+        } else if let Some(d) = &def.default {
+            let value = if d.is_empty() {
+                d.clone()
+            } else {
+                def.validate(k, d)?
+            };
+            runtime_values.insert(k.to_string(), value);
         } else if def.required {
             // If non-interactive, surface an error that includes the template-provided
             // note when available to guide the user how to supply the missing value.
-            if std::env::var("KAM_NONINTERACTIVE").is_ok() {
+            if non_interactive {
                 if let Some(n) = &def.note {
                     return Err(KamError::TemplateVarRequired(format!(
                         "Required template variable '{}' not provided (non-interactive): {}",
@@ -92,34 +261,7 @@ pub fn init_template(
                     k
                 )));
             }
-
-            // Prompt user for required value. If the template provides a human-friendly
-            // note, show it as the prompt; otherwise fall back to a generic prompt.
-            use std::io::{Write, stdin, stdout};
-            let mut input = String::new();
-            if let Some(n) = &def.note {
-                print!("{} ", n);
-            } else {
-                print!(
-                    "Enter value for required template variable '{}' (type: {}): ",
-                    k, def.var_type
-                );
-            }
-            let _ = stdout().flush();
-            stdin().read_line(&mut input)?;
-            let val = input.trim().to_string();
-            if val.is_empty() {
-                if let Some(n) = &def.note {
-                    return Err(KamError::TemplateVarRequired(format!(
-                        "Required template variable '{}' not provided: {}",
-                        k, n
-                    )));
-                }
-                return Err(KamError::TemplateVarRequired(format!(
-                    "Required template variable '{}' not provided",
-                    k
-                )));
-            }
+            let val = prompt_variable(k, def)?;
             runtime_values.insert(k.to_string(), val);
         }
     }
@@ -146,49 +288,6 @@ pub fn init_template(
     print_status(StatusType::Add, &kam_toml_rel, false);
     kt.write_to_dir(path)?;
 
-    // Determine which template to use
-    let template_key = impl_template.as_deref().unwrap_or("tmpl");
-
-    // Ensure cache exists and try to find template in cache/tmpl
-    // Refactored: determine and prepare the template zip (built-in / url only)
-    fn prepare_template(template_key: &str) -> Result<(TempDir, PathBuf), KamError> {
-        // Normalize template_key into an asset/base name we use, e.g.
-        // input: "tmpl" | "template" | "tmpl_template" -> base "tmpl_template"
-        let normalized_key = match template_key {
-            "tmpl" | "template" => "tmpl_template",
-            _ => template_key,
-        };
-
-        // If template_key is a URL, try downloading
-        if template_key.starts_with("http://") || template_key.starts_with("https://") {
-            let resp = reqwest::blocking::get(template_key)?;
-            if resp.status().is_success() {
-                let bytes = resp.bytes()?;
-                let tmp = tempfile::NamedTempFile::new()?;
-                std::fs::write(tmp.path(), &bytes)?;
-                let (temp_dir, template_path) = extract_archive_to_temp(tmp.path())?;
-                // Optionally save to cache, but for now just return
-                return Ok((temp_dir, template_path));
-            } else {
-                return Err(KamError::FetchFailed(
-                    "Failed to download template".to_string(),
-                ));
-            }
-        }
-
-        // Ensure the template is available in cache (only for built-ins)
-        crate::template::TemplateManager::ensure_template(normalized_key)?;
-
-        // Extract from cache
-        let cache = KamCache::new()?;
-        let cache_path = cache.tmpl_dir().join(format!("{}.tar.gz", normalized_key));
-        let (temp_dir, template_path) = extract_archive_to_temp(&cache_path)?;
-
-        Ok((temp_dir, template_path))
-    }
-
-    let (_temp_dir, template_path) = prepare_template(template_key)?;
-
     // Copy template files recursively from `src/` (and support placeholders in
     // both file/directory names and file contents). Placeholders like
     // `{{id}}` will be replaced by the confirmed project `id` from above.
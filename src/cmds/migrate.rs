@@ -0,0 +1,61 @@
+use crate::errors::KamError;
+use crate::types::kam_toml::KamToml;
+/// # Kam Migrate Command
+///
+/// Rewrite legacy keys of a project's `kam.toml` to the current schema
+/// (`module_type = "Normal"`, the `kam.dependency.normal` group), keeping
+/// its comments. Running it on an up-to-date file changes nothing.
+///
+/// ## Example
+///
+/// ```bash
+/// kam migrate
+/// kam migrate --dry-run
+/// ```
+use clap::Args;
+use colored::Colorize;
+use std::path::Path;
+
+/// Arguments for the migrate command
+#[derive(Args, Debug)]
+pub struct MigrateArgs {
+    /// Path to the project (default: current directory)
+    #[arg(default_value = ".")]
+    pub path: String,
+
+    /// Only report what would change
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+/// Run the migrate command
+pub fn run(args: MigrateArgs) -> Result<(), KamError> {
+    let path = Path::new(&args.path).join("kam.toml");
+    // Read as text: a legacy file may not load as a KamToml at all
+    let content = std::fs::read_to_string(&path)?;
+    let (migrated, changes) = KamToml::migrate_str(&content)?;
+    if changes.is_empty() {
+        println!("{} {} is up to date", "✓".green(), path.display());
+        return Ok(());
+    }
+    for change in &changes {
+        println!("  {} {}", "•".cyan(), change);
+    }
+    if args.dry_run {
+        println!(
+            "{} {} change(s) would be made to {}",
+            "!".yellow(),
+            changes.len(),
+            path.display()
+        );
+        return Ok(());
+    }
+    std::fs::write(&path, migrated)?;
+    println!(
+        "{} Migrated {} ({} change(s))",
+        "✓".green(),
+        path.display(),
+        changes.len()
+    );
+    Ok(())
+}
@@ -1,13 +1,16 @@
 use crate::errors::KamError;
+use crate::signing::{SIGN_KEY_ENV, SigningKey};
+use crate::status;
 use crate::types::kam_toml::KamToml;
 use crate::types::kam_toml::enums::ModuleType;
+use crate::types::modules::ArchiveFormat;
 use chrono;
 use clap::Args;
 use colored::Colorize;
-use flate2::read::GzDecoder;
 use git2::Repository;
 use regex::Regex;
 use serde_json::json;
+use sha2::{Digest, Sha256};
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -33,6 +36,64 @@ pub struct PublishArgs {
     /// Output directory to place the built package before publishing
     #[arg(long)]
     pub output: Option<String>,
+
+    /// Re-apply the index update up to N times (default 3) when another
+    /// publisher changed the index concurrently
+    #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "3")]
+    pub retry_on_conflict: Option<u32>,
+
+    /// Create a GitHub release with the package before submitting the module
+    /// issue, and use the uploaded asset's download URL as `zipUrl`
+    #[arg(long)]
+    pub release: bool,
+
+    /// Reuse an existing GitHub release for this version, replacing its
+    /// assets, instead of failing
+    #[arg(long, requires = "release")]
+    pub force: bool,
+
+    /// Retry a failed package upload up to N times (default: $KAM_HTTP_RETRIES
+    /// attempts in total)
+    #[arg(long, value_name = "N")]
+    pub retry: Option<u32>,
+
+    /// Sign the package with an ed25519 key and upload the detached
+    /// signature (`<package>.sig`) alongside it
+    #[arg(long)]
+    pub sign: bool,
+
+    /// Signing key file (PKCS#8 PEM); defaults to $KAM_SIGN_KEY
+    #[arg(long, value_name = "PATH", requires = "sign")]
+    pub key: Option<String>,
+
+    /// Changelog whose section for this version becomes the release notes
+    /// (default: mmrl.repo.changelog_file, or mmrl.repo.changelog when it is
+    /// a path)
+    #[arg(long, value_name = "PATH")]
+    pub changelog_file: Option<String>,
+
+    /// Only update the index of the `--repo` module repository, without
+    /// copying the package into it (for packages hosted elsewhere)
+    #[arg(long, requires = "repo")]
+    pub index_only: bool,
+
+    /// External download URL of the package, recorded as `zipUrl` in the
+    /// index (default with --index-only: kam.build.zip_url)
+    #[arg(long, value_name = "URL")]
+    pub zip_url: Option<String>,
+}
+
+/// A built package as recorded in the repository index
+struct IndexedPackage<'a> {
+    filename: &'a str,
+    sha256: &'a str,
+    /// Size of the package in bytes
+    size: u64,
+    /// Fingerprint of the key the package is signed with
+    signing_key: Option<&'a str>,
+    /// Where the package is downloaded from when it is not in the
+    /// repository's `packages/`
+    zip_url: Option<&'a str>,
 }
 
 /// Run the publish command
@@ -44,7 +105,7 @@ pub struct PublishArgs {
 pub fn run(args: PublishArgs) -> Result<(), KamError> {
     let project_path = Path::new(&args.path);
 
-    println!("{} Publishing module...", "→".cyan());
+    status!("{} Publishing module...", "→".cyan());
 
     // Load kam.toml to determine module id/version
     let kam_toml = KamToml::load_from_dir(&project_path)?;
@@ -53,6 +114,39 @@ pub fn run(args: PublishArgs) -> Result<(), KamError> {
     let version_code = kam_toml.prop.versionCode;
     let version = version_code.to_string();
     let module_type = &kam_toml.kam.module_type;
+    let conflict_retries = args.retry_on_conflict.unwrap_or(0);
+    // Manager minimums are published as is, so they must be version codes
+    let manager_errors = kam_toml
+        .mmrl
+        .as_ref()
+        .and_then(|m| m.repo.as_ref())
+        .and_then(|r| r.manager.as_ref())
+        .map(|manager| manager.validate())
+        .unwrap_or_default();
+    if !manager_errors.is_empty() {
+        return Err(KamError::InvalidKamToml(manager_errors.join("; ")));
+    }
+    // A library with ambiguous or dangling provides must not reach an index
+    let provide_errors = kam_toml.provide_errors(project_path);
+    if !provide_errors.is_empty() {
+        return Err(KamError::InvalidModuleStructure(provide_errors.join("; ")));
+    }
+    let notes = release_notes(&kam_toml, project_path, args.changelog_file.as_deref())?;
+    let zip_url = external_zip_url(&args, &kam_toml, project_path)?;
+
+    // Load the signing key before building so a bad key fails early
+    let signing_key = if args.sign {
+        let key_path = args
+            .key
+            .clone()
+            .or_else(|| std::env::var(SIGN_KEY_ENV).ok())
+            .ok_or_else(|| {
+                KamError::SigningKey(format!("no key given; use --key or set {}", SIGN_KEY_ENV))
+            })?;
+        Some(SigningKey::load(Path::new(&key_path))?)
+    } else {
+        None
+    };
 
     // Determine output directory to build into
     let output_dir: PathBuf = args
@@ -66,10 +160,21 @@ pub fn run(args: PublishArgs) -> Result<(), KamError> {
 
     // Build package by invoking the existing build logic
     // We call the build command implementation directly to avoid duplicating logic.
+    // Kam modules are published as their module zip, so skip the source archive.
     let build_args = crate::cmds::build::BuildArgs {
         path: args.path.clone(),
         all: false,
+        fail_fast: false,
         output: Some(output_dir.to_string_lossy().to_string()),
+        module_only: module_type == &ModuleType::Kam,
+        source_only: false,
+        runtime_toml: false,
+        archive_root: None,
+        no_installer: false,
+        no_update_json: true,
+        reproducible: false,
+        verify: false,
+        verify_reproducible: None,
     };
 
     crate::cmds::build::run(build_args)?;
@@ -80,23 +185,13 @@ pub fn run(args: PublishArgs) -> Result<(), KamError> {
     let package_path = if candidate.exists() {
         candidate
     } else {
-        // Fallback: pick the first zip file in the output dir
+        // Fallback: pick the first archive in the output dir
         let mut found: Option<PathBuf> = None;
         for entry in fs::read_dir(&output_dir)? {
             let p = entry?.path();
-            if p.is_file() {
-                if let Some(ext) = p.extension() {
-                    if ext == "zip"
-                        || p.file_name()
-                            .unwrap()
-                            .to_str()
-                            .unwrap()
-                            .ends_with(".tar.gz")
-                    {
-                        found = Some(p);
-                        break;
-                    }
-                }
+            if p.is_file() && ArchiveFormat::from_name(&p.to_string_lossy()).is_some() {
+                found = Some(p);
+                break;
             }
         }
         found.ok_or_else(|| {
@@ -105,9 +200,25 @@ pub fn run(args: PublishArgs) -> Result<(), KamError> {
     };
 
     println!("  {} Package: {}", "✓".green(), package_path.display());
+    let package_sha256 = crate::utils::sha256_file(&package_path)?;
+    let package_size = fs::metadata(&package_path)?.len();
+    let signature_path = match &signing_key {
+        Some(key) => {
+            let path = key.sign_file(&package_path)?;
+            status!(
+                "  {} Signed with key {} (public key: {})",
+                "✓".green(),
+                key.fingerprint(),
+                key.public_key()
+            );
+            Some(path)
+        }
+        None => None,
+    };
+    let signing_fingerprint = signing_key.as_ref().map(SigningKey::fingerprint);
 
     if args.dry_run {
-        println!("  {} Dry-run: skipping upload", "•".yellow());
+        status!("  {} Dry-run: skipping upload", "•".yellow());
         return Ok(());
     }
 
@@ -129,7 +240,7 @@ pub fn run(args: PublishArgs) -> Result<(), KamError> {
             if let Some(r) = repo_from_kam {
                 r
             } else {
-                println!(
+                status!(
                     "  {} No repository provided; package is available at: {}",
                     "i".cyan(),
                     package_path.display()
@@ -157,10 +268,36 @@ pub fn run(args: PublishArgs) -> Result<(), KamError> {
             if let Some(kt) = maybe_toml {
                 if kt.kam.module_type == ModuleType::Repo {
                     // Update repo index with metadata
-                    let package_filename = package_path.file_name().ok_or_else(|| {
-                        KamError::InvalidFilename("invalid package filename".to_string())
-                    })?.to_string_lossy().to_string();
-                    update_repo_index(&dest, &module_id, &version, &kam_toml, &package_filename)?;
+                    let package_filename = package_path
+                        .file_name()
+                        .ok_or_else(|| {
+                            KamError::InvalidFilename("invalid package filename".to_string())
+                        })?
+                        .to_string_lossy()
+                        .to_string();
+                    update_repo_index(
+                        &dest,
+                        &module_id,
+                        &version,
+                        &kam_toml,
+                        &IndexedPackage {
+                            filename: &package_filename,
+                            sha256: &package_sha256,
+                            size: package_size,
+                            signing_key: signing_fingerprint.as_deref(),
+                            zip_url: zip_url.as_deref(),
+                        },
+                        conflict_retries,
+                    )?;
+
+                    if args.index_only {
+                        println!(
+                            "  {} Published metadata to module repo index (zipUrl: {})",
+                            "✓".green(),
+                            zip_url.as_deref().unwrap_or_default()
+                        );
+                        return Ok(());
+                    }
 
                     // Copy package to repo/packages directory
                     let packages_dir = dest.join("packages");
@@ -170,6 +307,7 @@ pub fn run(args: PublishArgs) -> Result<(), KamError> {
                             KamError::InvalidFilename("invalid package filename".to_string())
                         })?);
                     fs::copy(&package_path, &dest_package)?;
+                    copy_signature(signature_path.as_deref(), &packages_dir)?;
                     println!(
                         "  {} Published package to module repo: {}",
                         "✓".green(),
@@ -186,11 +324,19 @@ pub fn run(args: PublishArgs) -> Result<(), KamError> {
                 }
             }
 
+            if args.index_only {
+                return Err(KamError::InvalidConfig(format!(
+                    "--index-only needs a module repository (module_type = \"repo\"), but {} is not one",
+                    dest.display()
+                )));
+            }
+
             // Fallback: plain directory copy
             let dest_file = dest.join(package_path.file_name().ok_or_else(|| {
                 KamError::InvalidFilename("invalid package filename".to_string())
             })?);
             fs::copy(&package_path, &dest_file)?;
+            copy_signature(signature_path.as_deref(), &dest)?;
             println!(
                 "  {} Published to local repository: {}",
                 "✓".green(),
@@ -199,6 +345,13 @@ pub fn run(args: PublishArgs) -> Result<(), KamError> {
             return Ok(());
         }
 
+        if args.index_only {
+            return Err(KamError::InvalidConfig(format!(
+                "--index-only needs a local module repository, not {}",
+                repo
+            )));
+        }
+
         // Otherwise try HTTP upload (simple PUT)
         // If the repo is an HTTP(S) URL, append the package filename so we don't overwrite the repository root.
         let mut upload_target = repo.clone();
@@ -216,7 +369,7 @@ pub fn run(args: PublishArgs) -> Result<(), KamError> {
             }
         }
 
-        println!("  {} Uploading to {}", "→".cyan(), upload_target);
+        status!("  {} Uploading to {}", "→".cyan(), upload_target);
         // Resolve token: prefer CLI arg, then common environment vars (GITHUB_TOKEN, KAM_PUBLISH_TOKEN)
         let token_opt: Option<String> = args
             .token
@@ -225,18 +378,32 @@ pub fn run(args: PublishArgs) -> Result<(), KamError> {
             .or_else(|| std::env::var("KAM_PUBLISH_TOKEN").ok());
 
         let client = reqwest::blocking::Client::new();
-        let mut req = client.put(&upload_target).body(fs::read(&package_path)?);
-        if let Some(tok) = token_opt.as_ref() {
-            req = req.header("Authorization", format!("Bearer {}", tok));
-        }
-        let resp = req
-            .send()
+        let upload = |target: &str, body: Vec<u8>| {
+            let request = || {
+                let req = client.put(target).body(body.clone());
+                match token_opt.as_ref() {
+                    Some(tok) => req.header("Authorization", format!("Bearer {}", tok)),
+                    None => req,
+                }
+            };
+            let resp = crate::utils::send_with_retry(
+                request,
+                upload_attempts(&args),
+                crate::utils::HTTP_BASE_DELAY,
+            )
             .map_err(|e| KamError::UploadFailed(format!("upload failed: {}", e)))?;
-        if !resp.status().is_success() {
-            return Err(KamError::UploadFailed(format!(
-                "upload failed: HTTP {}",
-                resp.status()
-            )));
+            if !resp.status().is_success() {
+                return Err(KamError::UploadFailed(format!(
+                    "upload of {} failed: HTTP {}",
+                    target,
+                    resp.status()
+                )));
+            }
+            Ok(())
+        };
+        upload(&upload_target, fs::read(&package_path)?)?;
+        if let Some(signature) = &signature_path {
+            upload(&format!("{}.sig", upload_target), fs::read(signature)?)?;
         }
 
         println!("  {} Published to {}", "✓".green(), repo);
@@ -244,17 +411,32 @@ pub fn run(args: PublishArgs) -> Result<(), KamError> {
     } else {
         // Special handling for library modules - publish to local repo or cache by default
         if let Ok(local_repo) = std::env::var("KAM_LOCAL_REPO") {
-            println!(
+            status!(
                 "  {} Publishing library metadata to local repo: {}",
                 "→".cyan(),
                 local_repo
             );
             // Update repo index with metadata only
             let repo_path = PathBuf::from(local_repo);
-            let package_filename = package_path.file_name().ok_or_else(|| {
-                KamError::InvalidFilename("invalid package filename".to_string())
-            })?.to_string_lossy().to_string();
-            update_repo_index(&repo_path, &module_id, &version, &kam_toml, &package_filename)?;
+            let package_filename = package_path
+                .file_name()
+                .ok_or_else(|| KamError::InvalidFilename("invalid package filename".to_string()))?
+                .to_string_lossy()
+                .to_string();
+            update_repo_index(
+                &repo_path,
+                &module_id,
+                &version,
+                &kam_toml,
+                &IndexedPackage {
+                    filename: &package_filename,
+                    sha256: &package_sha256,
+                    size: package_size,
+                    signing_key: signing_fingerprint.as_deref(),
+                    zip_url: zip_url.as_deref(),
+                },
+                conflict_retries,
+            )?;
 
             // Copy package to repo/packages directory
             let packages_dir = repo_path.join("packages");
@@ -263,6 +445,7 @@ pub fn run(args: PublishArgs) -> Result<(), KamError> {
                 KamError::InvalidFilename("invalid package filename".to_string())
             })?);
             fs::copy(&package_path, &dest_package)?;
+            copy_signature(signature_path.as_deref(), &packages_dir)?;
             println!(
                 "  {} Published package to local repo: {}",
                 "✓".green(),
@@ -290,13 +473,50 @@ pub fn run(args: PublishArgs) -> Result<(), KamError> {
                         let owner = parts[3];
                         let repo = parts[4];
 
-                        let package_filename = package_path.file_name().ok_or_else(|| {
-                            KamError::InvalidFilename("invalid package filename".to_string())
-                        })?.to_string_lossy().to_string();
-
-                        create_github_issue(owner, repo, &module_id, &version, &kam_toml, &package_filename, args.token.as_deref())?;
-
-                        println!(
+                        let package_filename = package_path
+                            .file_name()
+                            .ok_or_else(|| {
+                                KamError::InvalidFilename("invalid package filename".to_string())
+                            })?
+                            .to_string_lossy()
+                            .to_string();
+
+                        let zip_url = if args.release {
+                            let url = create_github_release(
+                                owner,
+                                repo,
+                                &kam_toml,
+                                &package_path,
+                                signature_path.as_deref(),
+                                notes.as_deref(),
+                                &args,
+                            )?;
+                            println!("  {} Created GitHub release: {}", "✓".green(), url);
+                            url
+                        } else {
+                            let url = format!(
+                                "https://github.com/{}/{}/releases/download/{}-{}/{}",
+                                owner, repo, module_id, version, package_filename
+                            );
+                            println!(
+                                "  {} No release created; zipUrl is provisional and may not exist yet: {}",
+                                "!".yellow(),
+                                url
+                            );
+                            url
+                        };
+
+                        create_github_issue(
+                            owner,
+                            repo,
+                            &kam_toml,
+                            &zip_url,
+                            package_size,
+                            notes.as_deref(),
+                            args.token.as_deref(),
+                        )?;
+
+                        status!(
                             "  {} Created module submission issue in {}/{}",
                             "✓".green(),
                             owner,
@@ -308,7 +528,7 @@ pub fn run(args: PublishArgs) -> Result<(), KamError> {
             }
 
             // Fallback: publish to local cache
-            println!("  {} Publishing library to local cache", "→".cyan());
+            status!("  {} Publishing library to local cache", "→".cyan());
 
             let cache = crate::cache::KamCache::new()?;
             cache.ensure_dirs()?;
@@ -317,16 +537,28 @@ pub fn run(args: PublishArgs) -> Result<(), KamError> {
             install_library_to_cache(&package_path, &cache)?;
 
             // Update local index
-            let package_filename = package_path.file_name().ok_or_else(|| {
-                KamError::InvalidFilename("invalid package filename".to_string())
-            })?.to_string_lossy().to_string();
-            update_local_cache_index(&cache, &module_id, &version, &kam_toml, &package_filename)?;
-
-            println!(
-                "  {} Published library artifacts to cache",
-                "✓".green()
-            );
-            println!(
+            let package_filename = package_path
+                .file_name()
+                .ok_or_else(|| KamError::InvalidFilename("invalid package filename".to_string()))?
+                .to_string_lossy()
+                .to_string();
+            update_local_cache_index(
+                &cache,
+                &module_id,
+                &version,
+                &kam_toml,
+                &IndexedPackage {
+                    filename: &package_filename,
+                    sha256: &package_sha256,
+                    size: package_size,
+                    signing_key: signing_fingerprint.as_deref(),
+                    zip_url: zip_url.as_deref(),
+                },
+                conflict_retries,
+            )?;
+
+            println!("  {} Published library artifacts to cache", "✓".green());
+            status!(
                 "  {} Library can now be added with: kam add {}@{}",
                 "i".cyan(),
                 module_id,
@@ -337,13 +569,56 @@ pub fn run(args: PublishArgs) -> Result<(), KamError> {
     }
 }
 
+/// The external `zipUrl` to record in the index: `--zip-url`, or with
+/// `--index-only` `kam.build.zip_url` (placeholders rendered). An
+/// index-only publish without one is an error, since the index would point
+/// at nothing.
+fn external_zip_url(
+    args: &PublishArgs,
+    kam_toml: &KamToml,
+    project_path: &Path,
+) -> Result<Option<String>, KamError> {
+    if let Some(url) = args
+        .zip_url
+        .as_deref()
+        .map(str::trim)
+        .filter(|u| !u.is_empty())
+    {
+        return Ok(Some(url.to_string()));
+    }
+    if !args.index_only {
+        return Ok(None);
+    }
+    kam_toml
+        .kam
+        .build
+        .as_ref()
+        .and_then(|b| b.zip_url.as_deref())
+        .map(str::trim)
+        .filter(|u| !u.is_empty())
+        .map(|tpl| {
+            Some(crate::cmds::build::render_output_template(
+                tpl,
+                kam_toml,
+                project_path,
+            ))
+        })
+        .ok_or_else(|| {
+            KamError::InvalidConfig(
+                "--index-only needs the package's download URL; pass --zip-url or set kam.build.zip_url"
+                    .to_string(),
+            )
+        })
+}
+
 /// Update repo index for a published library
 fn update_repo_index(
     repo_path: &Path,
     module_id: &str,
     version: &str,
     kam_toml: &KamToml,
-    package_filename: &str,
+    package: &IndexedPackage,
+    retries: u32,
 ) -> Result<(), KamError> {
     // Create index directory structure based on module name
     let index_dir = repo_path.join("index");
@@ -351,18 +626,33 @@ fn update_repo_index(
     fs::create_dir_all(&module_index_path)?;
 
     // Create metadata JSON for this version
-    let metadata = serde_json::json!({
+    let mut metadata = serde_json::json!({
         "id": module_id,
+        "name": kam_toml.prop.name.get("en").unwrap_or(&module_id.to_string()),
         "version": version,
         "versionCode": kam_toml.prop.versionCode,
         "author": kam_toml.prop.author,
         "description": kam_toml.prop.description.get("en").unwrap_or(&String::new()),
+        "keywords": kam_toml.mmrl.as_ref()
+            .and_then(|m| m.repo.as_ref())
+            .and_then(|r| r.keywords.as_ref())
+            .unwrap_or(&Vec::new()),
         "provides": kam_toml.kam.lib.as_ref()
             .and_then(|l| l.provides.as_ref())
             .unwrap_or(&Vec::new()),
-        "package": package_filename,
+        "supported_arch": kam_toml.kam.supported_arch.as_ref().unwrap_or(&Vec::new()),
+        "package": package.filename,
+        "sha256": package.sha256,
+        "size": package.size,
+        "prerelease": crate::cmds::add::is_prerelease(&kam_toml.prop.version),
         "timestamp": chrono::Utc::now().to_rfc3339(),
     });
+    if let Some(fingerprint) = package.signing_key {
+        metadata["signingKey"] = json!(fingerprint);
+    }
+    if let Some(zip_url) = package.zip_url {
+        metadata["zipUrl"] = json!(zip_url);
+    }
 
     let metadata_file = module_index_path.join(format!("{}.json", version));
     let metadata_str =
@@ -371,26 +661,132 @@ fn update_repo_index(
 
     // Update latest.json to point to this version if it's newer
     let latest_file = module_index_path.join("latest.json");
-    let should_update_latest = if latest_file.exists() {
-        let latest_content = fs::read_to_string(&latest_file)?;
+    let lock_path = index_dir.join(INDEX_LOCK_FILE);
+    update_latest(
+        &latest_file,
+        &lock_path,
+        &metadata_str,
+        version,
+        kam_toml.prop.versionCode,
+        retries,
+    )
+}
+
+/// Lock file in the index directory serialising updates of shared index
+/// files between publishers
+const INDEX_LOCK_FILE: &str = ".publish.lock";
+
+/// Replace `latest_file` with `metadata` unless it records a newer release
+/// than `version` (`version_code`).
+///
+/// The file may be shared by several publishers, so the update is applied
+/// optimistically: remember the revision the decision was based on and only
+/// replace the file if it is still at that revision (see [`swap_latest`]),
+/// re-applying the update up to `retries` times.
+fn update_latest(
+    latest_file: &Path,
+    lock_path: &Path,
+    metadata: &str,
+    version: &str,
+    version_code: i64,
+    retries: u32,
+) -> Result<(), KamError> {
+    let mut attempt = 0;
+    loop {
+        let Some(base_revision) = latest_revision(latest_file, version, version_code)? else {
+            return Ok(());
+        };
+        if swap_latest(latest_file, lock_path, metadata, &base_revision)? {
+            return Ok(());
+        }
+        if attempt >= retries {
+            return Err(KamError::IndexConflict(format!(
+                "{} was modified by another publisher after {} attempt(s); \
+                 use --retry-on-conflict to re-apply the update",
+                latest_file.display(),
+                attempt + 1
+            )));
+        }
+        attempt += 1;
+        println!(
+            "  {} Index changed concurrently, re-applying update ({}/{})",
+            "!".yellow(),
+            attempt,
+            retries
+        );
+    }
+}
+
+/// The revision of `latest_file` to replace with `version` (`version_code`),
+/// or `None` when it already records a newer release. Its inner `None` is a
+/// file that does not exist yet.
+fn latest_revision(
+    latest_file: &Path,
+    version: &str,
+    version_code: i64,
+) -> Result<Option<Option<String>>, KamError> {
+    let base_revision = index_revision(latest_file)?;
+    if latest_file.exists() {
+        let latest_content = fs::read_to_string(latest_file)?;
         let latest: serde_json::Value = serde_json::from_str(&latest_content)
             .map_err(|e| KamError::JsonError(e.to_string()))?;
 
-        // Simple version comparison (could be improved)
-        latest
-            .get("version")
-            .and_then(|v| v.as_str())
-            .map(|v| version > v)
-            .unwrap_or(true)
-    } else {
-        true
-    };
+        let newer = match latest.get("versionCode").and_then(|v| v.as_i64()) {
+            Some(latest_code) => version_code > latest_code,
+            // Older entries without versionCode: compare version strings
+            None => latest
+                .get("version")
+                .and_then(|v| v.as_str())
+                .map(|v| version > v)
+                .unwrap_or(true),
+        };
+        if !newer {
+            return Ok(None);
+        }
+    }
+    Ok(Some(base_revision))
+}
 
-    if should_update_latest {
-        fs::write(&latest_file, &metadata_str)?;
+/// Replace `latest_file` with `metadata` if it is still at `base_revision`,
+/// checked and swapped under the index lock at `lock_path`. Returns whether
+/// it was replaced; the staged copy never outlives the call.
+fn swap_latest(
+    latest_file: &Path,
+    lock_path: &Path,
+    metadata: &str,
+    base_revision: &Option<String>,
+) -> Result<bool, KamError> {
+    // Stage the new content next to the target so the final rename is atomic
+    let staged = latest_file.with_file_name(format!(".latest.json.{}", std::process::id()));
+    let swapped = fs::write(&staged, metadata)
+        .map_err(KamError::from)
+        .and_then(|()| {
+            let lock = fs::OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(lock_path)?;
+            lock.lock()?;
+            let unchanged = index_revision(latest_file)? == *base_revision;
+            if unchanged {
+                fs::rename(&staged, latest_file)?;
+            }
+            Ok(unchanged)
+        });
+    if !matches!(swapped, Ok(true)) {
+        let _ = fs::remove_file(&staged);
     }
+    swapped
+}
 
-    Ok(())
+/// Revision of an index file: the sha256 of its contents, or `None` if it
+/// does not exist yet
+fn index_revision(path: &Path) -> Result<Option<String>, KamError> {
+    match fs::read(path) {
+        Ok(bytes) => Ok(Some(format!("{:x}", Sha256::digest(&bytes)))),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
 }
 
 /// Update local cache index for a published library
@@ -399,9 +795,22 @@ fn update_local_cache_index(
     module_id: &str,
     version: &str,
     kam_toml: &KamToml,
-    package_filename: &str,
+    package: &IndexedPackage,
+    retries: u32,
 ) -> Result<(), KamError> {
-    update_repo_index(cache.root(), module_id, version, kam_toml, package_filename)
+    update_repo_index(cache.root(), module_id, version, kam_toml, package, retries)
+}
+
+/// Copy the signature file of a signed package into `dest_dir`
+fn copy_signature(signature: Option<&Path>, dest_dir: &Path) -> Result<(), KamError> {
+    let Some(signature) = signature else {
+        return Ok(());
+    };
+    let file_name = signature
+        .file_name()
+        .ok_or_else(|| KamError::InvalidFilename("invalid signature filename".to_string()))?;
+    fs::copy(signature, dest_dir.join(file_name))?;
+    Ok(())
 }
 
 /// Install library artifacts to cache (lib, lib64, bin)
@@ -413,25 +822,7 @@ fn install_library_to_cache(
     let temp_dir = tempfile::tempdir()?;
     let temp_path = temp_dir.path();
 
-    if package_path.to_str().unwrap().ends_with(".tar.gz") {
-        let tar_gz = fs::File::open(package_path)?;
-        let dec = GzDecoder::new(tar_gz);
-        let mut archive = tar::Archive::new(dec);
-        archive
-            .unpack(temp_path)
-            .map_err(|e| KamError::ExtractFailed(e.to_string()))?;
-    } else if package_path.extension().and_then(|e| e.to_str()) == Some("zip") {
-        let file = fs::File::open(package_path)?;
-        let mut archive = zip::ZipArchive::new(file)
-            .map_err(|e| KamError::ExtractFailed(e.to_string()))?;
-        archive
-            .extract(temp_path)
-            .map_err(|e| KamError::ExtractFailed(e.to_string()))?;
-    } else {
-        return Err(KamError::UnsupportedFormat(
-            "Library packages must be .zip or .tar.gz format".to_string(),
-        ));
-    }
+    crate::types::modules::extract_archive(package_path, temp_path)?;
 
     // Copy lib to cache/lib
     let src_lib = temp_path.join("lib");
@@ -494,16 +885,96 @@ fn get_github_repo_info() -> Result<(String, String), KamError> {
     }
 }
 
-/// Create GitHub issue for module submission
+/// Release notes for the version being published, read from `--changelog-file`
+/// or the changelog `[mmrl.repo]` points at
+///
+/// The `## <version>` section matching `prop.version` is used (`## 1.0.0`,
+/// `## [1.0.0] - 2024-01-01` and `## v1.0.0` all match); without one the
+/// whole file is used with a warning. Returns `None` when there is no
+/// changelog to read.
+fn release_notes(
+    kam_toml: &KamToml,
+    project_path: &Path,
+    changelog_file: Option<&str>,
+) -> Result<Option<String>, KamError> {
+    let path = match changelog_file {
+        Some(file) => PathBuf::from(file),
+        None => {
+            let repo = kam_toml.mmrl.as_ref().and_then(|m| m.repo.as_ref());
+            let configured = repo
+                .and_then(|r| r.changelog_file.as_deref())
+                .into_iter()
+                .chain(repo.and_then(|r| r.changelog.as_deref()))
+                .map(str::trim)
+                .find(|f| !f.is_empty() && !f.starts_with("http://") && !f.starts_with("https://"));
+            match configured {
+                Some(file) if project_path.join(file).is_file() => project_path.join(file),
+                _ => return Ok(None),
+            }
+        }
+    };
+    let content = fs::read_to_string(&path).map_err(|e| {
+        KamError::InvalidConfig(format!("cannot read changelog {}: {}", path.display(), e))
+    })?;
+    let version = kam_toml.prop.version.as_str();
+    match changelog_section(&content, version) {
+        Some(section) => {
+            status!(
+                "  {} Release notes: {} ({})",
+                "✓".green(),
+                path.display(),
+                version
+            );
+            Ok(Some(section))
+        }
+        None => {
+            println!(
+                "  {} {} has no `## {}` section; using the whole file as release notes",
+                "!".yellow(),
+                path.display(),
+                version
+            );
+            Ok(Some(content.trim().to_string()))
+        }
+    }
+}
+
+/// The body of the `## <version>` section of a Markdown changelog, up to the
+/// next `##` (or `#`) heading
+fn changelog_section(content: &str, version: &str) -> Option<String> {
+    let wanted = version.trim().trim_start_matches('v');
+    let mut lines = content.lines();
+    lines.find(|line| {
+        line.strip_prefix("## ").is_some_and(|heading| {
+            let heading = heading.replace('\\', "");
+            heading
+                .split_whitespace()
+                .next()
+                .map(|word| word.trim_matches(['[', ']']).trim_start_matches('v'))
+                == Some(wanted)
+        })
+    })?;
+    let body: Vec<&str> = lines
+        .take_while(|line| !line.starts_with("## ") && !line.starts_with("# "))
+        .collect();
+    let body = body.join("\n").trim().to_string();
+    (!body.is_empty()).then_some(body)
+}
+
+/// Create GitHub issue for module submission; `size` is the package size in
+/// bytes. The metadata's `changelog` holds the release `notes` when there
+/// are any.
 fn create_github_issue(
     owner: &str,
     repo: &str,
-    module_id: &str,
-    version: &str,
     kam_toml: &KamToml,
-    package_filename: &str,
+    zip_url: &str,
+    size: u64,
+    notes: Option<&str>,
     token: Option<&str>,
 ) -> Result<(), KamError> {
+    let module_id = kam_toml.prop.id.as_str();
+    let version = kam_toml.prop.versionCode.to_string();
     let github_token = std::env::var("GITHUB_TOKEN").ok();
     let kam_token = std::env::var("KAM_PUBLISH_TOKEN").ok();
     let token = token
@@ -513,8 +984,13 @@ fn create_github_issue(
 
     let client = reqwest::blocking::Client::new();
 
+    // Version entry: the update.json manifest plus index-only fields
+    let mut version_entry = serde_json::to_value(kam_toml.to_update_json(zip_url))?;
+    version_entry["size"] = json!(size);
+    version_entry["timestamp"] = json!(chrono::Utc::now().timestamp() as f64);
+
     // Create module metadata JSON
-    let metadata = serde_json::json!({
+    let mut metadata = serde_json::json!({
         "id": module_id,
         "name": kam_toml.prop.name.get("en").unwrap_or(&module_id.to_string()),
         "version": version,
@@ -534,20 +1010,19 @@ fn create_github_issue(
         "require": kam_toml.mmrl.as_ref().and_then(|m| m.repo.as_ref()).and_then(|r| r.require.as_ref()).unwrap_or(&Vec::new()),
         "antifeatures": kam_toml.mmrl.as_ref().and_then(|m| m.repo.as_ref()).and_then(|r| r.antifeatures.as_ref()).unwrap_or(&Vec::new()),
         "provides": kam_toml.kam.lib.as_ref().and_then(|l| l.provides.as_ref()).unwrap_or(&Vec::new()),
-        "versions": [{
-            "version": version,
-            "versionCode": kam_toml.prop.versionCode,
-            "zipUrl": format!("https://github.com/{}/{}/releases/download/{}-{}/{}", owner, repo, module_id, version, package_filename),
-            "changelog": kam_toml.mmrl.as_ref().and_then(|m| m.repo.as_ref()).and_then(|r| r.changelog.as_ref()).unwrap_or(&String::new()),
-            "size": 0, // TODO: get actual size
-            "timestamp": chrono::Utc::now().timestamp() as f64
-        }],
+        "versions": [version_entry],
         "timestamp": chrono::Utc::now().timestamp() as f64
     });
+    if let Some(notes) = notes {
+        metadata["changelog"] = json!(notes);
+    }
 
     let create_issue_url = format!("https://api.github.com/repos/{}/{}/issues", owner, repo);
     let title = format!("Module Submission: {} v{}", module_id, version);
-    let body = format!("```json\n{}\n```", serde_json::to_string_pretty(&metadata).unwrap());
+    let body = format!(
+        "```json\n{}\n```",
+        serde_json::to_string_pretty(&metadata).unwrap()
+    );
 
     let issue_body = json!({
         "title": title,
@@ -573,72 +1048,215 @@ fn create_github_issue(
     Ok(())
 }
 
-/// Create GitHub release and upload asset
+/// Number of attempts for uploading the package: `--retry N` allows N
+/// retries after the first attempt
+fn upload_attempts(args: &PublishArgs) -> u32 {
+    args.retry
+        .map(|n| n.saturating_add(1))
+        .unwrap_or_else(crate::utils::http_attempts)
+}
+
+/// Create GitHub release and upload asset (and its signature, if any)
+///
+/// The release body is `notes` (see [`release_notes`]) when given. A release that already exists for the tag is an error unless `--force`
+/// is given, in which case it is reused and its assets are replaced.
+/// Returns the `browser_download_url` of the uploaded package asset.
 fn create_github_release(
     owner: &str,
     repo: &str,
-    module_id: &str,
-    version: &str,
+    kam_toml: &KamToml,
     package_path: &Path,
-    token: Option<&str>,
-) -> Result<(), KamError> {
+    signature_path: Option<&Path>,
+    notes: Option<&str>,
+    args: &PublishArgs,
+) -> Result<String, KamError> {
+    let module_id = kam_toml.prop.id.as_str();
+    let version = kam_toml.prop.versionCode.to_string();
     let github_token = std::env::var("GITHUB_TOKEN").ok();
     let kam_token = std::env::var("KAM_PUBLISH_TOKEN").ok();
-    let token = token
+    let token = args
+        .token
+        .as_deref()
         .or_else(|| github_token.as_deref())
         .or_else(|| kam_token.as_deref())
         .ok_or(KamError::InvalidConfig("GitHub token required".to_string()))?;
 
     let client = reqwest::blocking::Client::new();
-    let create_release_url = format!("https://api.github.com/repos/{}/{}/releases", owner, repo);
+    let releases_url = format!("https://api.github.com/repos/{}/{}/releases", owner, repo);
     let tag_name = format!("{}-{}", module_id, version);
-    let body = json!({
-        "tag_name": tag_name,
-        "name": format!("Release {} {}", module_id, version),
-        "body": format!("Auto release for {} {}", module_id, version),
-        "draft": false,
-        "prerelease": false
-    });
 
-    let resp = client
-        .post(&create_release_url)
+    let existing = client
+        .get(format!("{}/tags/{}", releases_url, tag_name))
         .header("Authorization", format!("Bearer {}", token))
         .header("User-Agent", "kam-cli")
-        .json(&body)
         .send()
-        .map_err(|e| KamError::UploadFailed(format!("create release failed: {}", e)))?;
+        .map_err(|e| KamError::UploadFailed(format!("release lookup failed: {}", e)))?;
+    let resp = if existing.status().is_success() {
+        if !args.force {
+            return Err(KamError::ReleaseExists(format!(
+                "{}/{} already has a release for tag {}; use --force to replace its assets",
+                owner, repo, tag_name
+            )));
+        }
+        println!(
+            "  {} Reusing the existing release {}",
+            "!".yellow(),
+            tag_name
+        );
+        existing
+    } else if existing.status() == reqwest::StatusCode::NOT_FOUND {
+        let body = json!({
+            "tag_name": tag_name,
+            "name": format!("Release {} {}", module_id, version),
+            "body": notes
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("Auto release for {} {}", module_id, version)),
+            "draft": false,
+            "prerelease": false
+        });
+        let resp = client
+            .post(&releases_url)
+            .header("Authorization", format!("Bearer {}", token))
+            .header("User-Agent", "kam-cli")
+            .json(&body)
+            .send()
+            .map_err(|e| KamError::UploadFailed(format!("create release failed: {}", e)))?;
 
-    if !resp.status().is_success() {
+        if !resp.status().is_success() {
+            return Err(KamError::UploadFailed(format!(
+                "create release failed: HTTP {}",
+                resp.status()
+            )));
+        }
+        resp
+    } else {
         return Err(KamError::UploadFailed(format!(
-            "create release failed: HTTP {}",
-            resp.status()
+            "release lookup failed: HTTP {}",
+            existing.status()
         )));
-    }
+    };
 
     let release: serde_json::Value = resp
         .json()
         .map_err(|e| KamError::JsonError(e.to_string()))?;
+    let attempts = upload_attempts(args);
+    let asset = upload_release_asset(&client, &release, package_path, token, attempts)?;
+    if let Some(signature_path) = signature_path {
+        upload_release_asset(&client, &release, signature_path, token, attempts)?;
+    }
+    asset["browser_download_url"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| {
+            KamError::UploadFailed("upload response has no browser_download_url".to_string())
+        })
+}
+
+/// Upload `path` as an asset of `release`, streaming it from disk
+///
+/// An asset of the same name, left by an earlier or interrupted upload, is
+/// deleted before each attempt, since GitHub refuses duplicate names.
+/// Returns the uploaded asset.
+fn upload_release_asset(
+    client: &reqwest::blocking::Client,
+    release: &serde_json::Value,
+    path: &Path,
+    token: &str,
+    attempts: u32,
+) -> Result<serde_json::Value, KamError> {
     let upload_url = release["upload_url"]
         .as_str()
-        .unwrap()
+        .ok_or_else(|| KamError::UploadFailed("release has no upload_url".to_string()))?
         .replace("{?name,label}", "");
-    let file_name = package_path.file_name().unwrap().to_str().unwrap();
+    let file_name = path.file_name().unwrap().to_str().unwrap();
+    let size = fs::metadata(path)?.len();
+
+    let mut attempt = 1;
+    loop {
+        delete_release_asset(client, release, file_name, token)?;
+        let progress =
+            crate::progress::Progress::bytes(&format!("Uploading {}", file_name), Some(size));
+        let body = reqwest::blocking::Body::sized(progress.wrap_read(fs::File::open(path)?), size);
+        let result = client
+            .post(format!("{}?name={}", upload_url, file_name))
+            .header("Authorization", format!("Bearer {}", token))
+            .header("User-Agent", "kam-cli")
+            .header("Content-Type", "application/octet-stream")
+            .body(body)
+            .send();
+        drop(progress);
+        let reason = match result {
+            Ok(resp) if resp.status().is_success() => {
+                return resp.json().map_err(|e| KamError::JsonError(e.to_string()));
+            }
+            Ok(resp) => format!("HTTP {}", resp.status()),
+            Err(e) => e.to_string(),
+        };
+        if attempt >= attempts {
+            return Err(KamError::UploadFailed(format!(
+                "upload of {} failed after {} attempt(s): {}",
+                file_name, attempt, reason
+            )));
+        }
+        let delay = crate::utils::HTTP_BASE_DELAY.saturating_mul(1 << (attempt - 1).min(16));
+        println!(
+            "  {} Upload of {} failed ({}); retrying in {:.1}s (attempt {}/{})",
+            "!".yellow(),
+            file_name,
+            reason,
+            delay.as_secs_f32(),
+            attempt + 1,
+            attempts
+        );
+        std::thread::sleep(delay);
+        attempt += 1;
+    }
+}
 
-    let upload_resp = client
-        .post(&format!("{}?name={}", upload_url, file_name))
+/// Delete the asset named `file_name` from `release`, if it has one
+fn delete_release_asset(
+    client: &reqwest::blocking::Client,
+    release: &serde_json::Value,
+    file_name: &str,
+    token: &str,
+) -> Result<(), KamError> {
+    let Some(assets_url) = release["assets_url"].as_str() else {
+        return Ok(());
+    };
+    let resp = client
+        .get(format!("{}?per_page=100", assets_url))
         .header("Authorization", format!("Bearer {}", token))
-        .header("Content-Type", "application/octet-stream")
-        .body(fs::read(package_path)?)
+        .header("User-Agent", "kam-cli")
         .send()
-        .map_err(|e| KamError::UploadFailed(format!("upload failed: {}", e)))?;
-
-    if !upload_resp.status().is_success() {
+        .map_err(|e| KamError::UploadFailed(format!("listing release assets failed: {}", e)))?;
+    if !resp.status().is_success() {
         return Err(KamError::UploadFailed(format!(
-            "upload failed: HTTP {}",
-            upload_resp.status()
+            "listing release assets failed: HTTP {}",
+            resp.status()
         )));
     }
-
+    let assets: Vec<serde_json::Value> = resp
+        .json()
+        .map_err(|e| KamError::JsonError(e.to_string()))?;
+    for asset in assets.iter().filter(|a| a["name"] == file_name) {
+        let Some(url) = asset["url"].as_str() else {
+            continue;
+        };
+        status!("  {} Replacing release asset {}", "→".cyan(), file_name);
+        let resp = client
+            .delete(url)
+            .header("Authorization", format!("Bearer {}", token))
+            .header("User-Agent", "kam-cli")
+            .send()
+            .map_err(|e| KamError::UploadFailed(format!("deleting {} failed: {}", file_name, e)))?;
+        if !resp.status().is_success() {
+            return Err(KamError::UploadFailed(format!(
+                "deleting {} failed: HTTP {}",
+                file_name,
+                resp.status()
+            )));
+        }
+    }
     Ok(())
 }
 
@@ -662,3 +1280,97 @@ fn compute_index_path(index_base: &Path, module_name: &str) -> PathBuf {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changelog_section() {
+        let changelog = "\
+# Changelog
+
+## [Unreleased]
+
+## \\[1.1.0\\] - 2024-05-01
+
+### Fixed
+
+- Crash on boot
+
+## v1.0.0
+
+- Initial release
+";
+        assert_eq!(
+            changelog_section(changelog, "1.1.0").as_deref(),
+            Some("### Fixed\n\n- Crash on boot")
+        );
+        assert_eq!(
+            changelog_section(changelog, "v1.0.0").as_deref(),
+            Some("- Initial release")
+        );
+        assert_eq!(changelog_section(changelog, "Unreleased"), None);
+        assert_eq!(changelog_section(changelog, "2.0.0"), None);
+    }
+
+    #[test]
+    fn test_update_latest_swaps_only_unchanged_files() {
+        let index = tempfile::tempdir().unwrap();
+        let latest = index.path().join("latest.json");
+        let lock = index.path().join(INDEX_LOCK_FILE);
+        fs::write(&latest, r#"{"version": "1.0.0", "versionCode": 100}"#).unwrap();
+        let metadata = r#"{"version": "1.1.0", "versionCode": 110}"#;
+        let staged = || {
+            fs::read_dir(index.path())
+                .unwrap()
+                .flatten()
+                .filter(|e| e.file_name().to_string_lossy().starts_with(".latest.json."))
+                .count()
+        };
+
+        // Another publisher rewrites the file after the revision was read
+        let base = latest_revision(&latest, "1.1.0", 110).unwrap().unwrap();
+        let other = r#"{"version": "1.0.9", "versionCode": 109}"#;
+        fs::write(&latest, other).unwrap();
+        assert!(!swap_latest(&latest, &lock, metadata, &base).unwrap());
+        assert_eq!(fs::read_to_string(&latest).unwrap(), other);
+        assert_eq!(staged(), 0);
+
+        // Failing to take the lock leaves nothing staged either
+        let missing_lock = index.path().join("missing").join(INDEX_LOCK_FILE);
+        let base = latest_revision(&latest, "1.1.0", 110).unwrap().unwrap();
+        assert!(swap_latest(&latest, &missing_lock, metadata, &base).is_err());
+        assert_eq!(staged(), 0);
+
+        // Re-reading the revision applies the update, and only once
+        update_latest(&latest, &lock, metadata, "1.1.0", 110, 0).unwrap();
+        assert_eq!(fs::read_to_string(&latest).unwrap(), metadata);
+        assert_eq!(latest_revision(&latest, "1.1.0", 110).unwrap(), None);
+        assert_eq!(staged(), 0);
+    }
+
+    #[test]
+    fn test_index_records_external_zip_url() {
+        let repo = tempfile::tempdir().unwrap();
+        let mut kam_toml = KamToml::default();
+        kam_toml.prop.id = "mylib".to_string();
+        kam_toml.prop.versionCode = 100;
+        let package = IndexedPackage {
+            filename: "mylib-100.zip",
+            sha256: "00",
+            size: 1,
+            signing_key: None,
+            zip_url: Some("https://cdn.example.com/mylib-100.zip"),
+        };
+        update_repo_index(repo.path(), "mylib", "100", &kam_toml, &package, 0).unwrap();
+
+        let index = compute_index_path(&repo.path().join("index"), "mylib");
+        for file in ["100.json", "latest.json"] {
+            let meta: serde_json::Value =
+                serde_json::from_str(&fs::read_to_string(index.join(file)).unwrap()).unwrap();
+            assert_eq!(meta["zipUrl"], "https://cdn.example.com/mylib-100.zip");
+        }
+        assert!(!repo.path().join("packages").exists());
+    }
+}
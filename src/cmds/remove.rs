@@ -0,0 +1,234 @@
+use crate::cmds::sync::project_cache;
+use crate::errors::KamError;
+use crate::status;
+use crate::types::kam_lock::KamLock;
+use crate::types::kam_toml::KamToml;
+use crate::venv::KamVenv;
+/// # Kam Remove Command
+///
+/// Remove library dependencies from `kam.toml` (the inverse of `kam add`)
+/// and `kam.lock`, and unlink them from the project's virtual environment.
+/// The cached modules are kept unless `--purge` is given, which still keeps
+/// pinned modules and versions `kam.lock` references.
+///
+/// ## Example
+///
+/// ```bash
+/// kam remove mylib
+/// kam remove --dev test-helpers
+/// kam remove mylib --purge
+/// ```
+use clap::Args;
+use colored::Colorize;
+use std::path::Path;
+
+/// Arguments for the remove command
+#[derive(Args, Debug)]
+pub struct RemoveArgs {
+    /// Library modules to remove (by id)
+    #[arg(required = true)]
+    pub library: Vec<String>,
+
+    /// Path to the project (default: current directory)
+    #[arg(short, long, default_value = ".")]
+    pub path: String,
+
+    /// Remove a development dependency
+    #[arg(short, long)]
+    pub dev: bool,
+
+    /// Remove a peer dependency
+    #[arg(long, conflicts_with = "dev")]
+    pub peer: bool,
+
+    /// Also delete the library's cached modules (by default they are kept
+    /// for other projects); pinned and locked versions are kept
+    #[arg(long)]
+    pub purge: bool,
+}
+
+/// Run the remove command
+pub fn run(args: RemoveArgs) -> Result<(), KamError> {
+    let project_path = Path::new(&args.path);
+    let mut kam_toml = KamToml::load_from_dir(project_path)?;
+    let group = if args.peer {
+        "peer"
+    } else if args.dev {
+        "dev"
+    } else {
+        "runtime"
+    };
+
+    let mut removed: Vec<String> = Vec::new();
+    for library in &args.library {
+        let section = kam_toml.kam.dependency.get_or_insert_with(Default::default);
+        let deps = match group {
+            "peer" => &mut section.peer,
+            "dev" => &mut section.dev,
+            _ => &mut section.kam,
+        };
+        let before = deps.as_ref().map_or(0, Vec::len);
+        if let Some(deps) = deps.as_mut() {
+            deps.retain(|d| d.id != *library);
+        }
+        if deps.as_ref().map_or(0, Vec::len) == before {
+            let elsewhere = [
+                ("runtime", &section.kam),
+                ("dev", &section.dev),
+                ("peer", &section.peer),
+            ]
+            .into_iter()
+            .find(|(_, deps)| deps.iter().flatten().any(|d| d.id == *library))
+            .map(|(name, _)| name);
+            match elsewhere {
+                Some(other) => println!(
+                    "{} {} is not a {} dependency (it is a {} dependency{})",
+                    "!".yellow(),
+                    library.bold(),
+                    group,
+                    other,
+                    match other {
+                        "dev" => "; use --dev",
+                        "peer" => "; use --peer",
+                        _ => "",
+                    }
+                ),
+                None => println!(
+                    "{} {} is not a {} dependency",
+                    "!".yellow(),
+                    library.bold(),
+                    group
+                ),
+            }
+            continue;
+        }
+        if deps.as_ref().is_some_and(Vec::is_empty) {
+            *deps = None;
+        }
+        println!(
+            "{} Removed {} from {} dependencies",
+            "✓".green(),
+            library.bold(),
+            group
+        );
+        removed.push(library.clone());
+    }
+    if removed.is_empty() {
+        return Ok(());
+    }
+    kam_toml.write_dependencies_to_dir(project_path)?;
+    status!("  {} Updated kam.toml", "✓".green());
+
+    // Libraries still declared in another group stay linked and pinned
+    let section = kam_toml.kam.dependency.clone().unwrap_or_default();
+    let still_declared = |id: &str| {
+        [&section.kam, &section.dev, &section.peer]
+            .into_iter()
+            .flatten()
+            .flatten()
+            .any(|d| d.id == id)
+    };
+    let dropped: Vec<&String> = removed.iter().filter(|id| !still_declared(id)).collect();
+    if dropped.is_empty() {
+        return Ok(());
+    }
+
+    // Their pins go with them, so kam.lock no longer references the modules
+    let lock_path = project_path.join("kam.lock");
+    let mut locked = Vec::new();
+    if lock_path.exists() {
+        let mut lock = KamLock::load_from_path(&lock_path)?;
+        let before = lock.packages.len();
+        lock.packages.retain(|p| !dropped.contains(&&p.name));
+        if lock.packages.len() != before {
+            lock.write_to_path(&lock_path)?;
+            status!("  {} Updated kam.lock", "✓".green());
+        }
+        locked = lock.packages;
+    }
+
+    // The cache `kam sync` populated for this project
+    let cache = project_cache(project_path)?;
+    let venv_path = project_path.join(".kam_venv");
+    if venv_path.exists() {
+        let venv = KamVenv::load(&venv_path)?;
+        for id in &dropped {
+            for entry in venv.unlink_library(id, &cache)? {
+                status!("  {} Unlinked {}", "✓".green(), entry);
+            }
+        }
+    }
+
+    if args.purge {
+        let _guard = cache.lock()?;
+        // Pinned modules and versions kam.lock still references are kept
+        let mut kept = cache.pins()?;
+        kept.extend(locked.iter().map(|p| format!("{}-{}", p.name, p.version)));
+        for id in &dropped {
+            for code in cache.cached_versions(id) {
+                let name = format!("{}-{}", id, code);
+                if kept.contains(&name) {
+                    status!("  {} Kept cached {} (pinned or locked)", "•".dimmed(), name);
+                    continue;
+                }
+                std::fs::remove_dir_all(cache.lib_module_path(id, &code.to_string()))?;
+                status!("  {} Purged cached {}-{}", "✓".green(), id, code);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::KamCache;
+    use crate::types::kam_lock::LockPackage;
+    use crate::types::kam_toml::sections::{Dependency, DependencySection, VersionSpec};
+
+    #[test]
+    fn test_remove_purges_unpinned_versions() {
+        let project = tempfile::tempdir().unwrap();
+        let cache_root = project.path().join(".kam");
+        std::fs::write(project.path().join(".env"), "KAM_CACHE_ROOT=./.kam\n").unwrap();
+        let cache = KamCache::with_root(&cache_root).unwrap();
+        for code in [1, 2] {
+            std::fs::create_dir_all(cache.lib_module_path("a", &code.to_string())).unwrap();
+        }
+        cache.pin("a", 1).unwrap();
+
+        let dep = |id: &str| Dependency {
+            versionCode: Some(VersionSpec::Exact(2)),
+            ..Dependency::new(id)
+        };
+        let mut kam_toml = KamToml::default();
+        kam_toml.prop.id = "app".to_string();
+        kam_toml.kam.dependency = Some(DependencySection {
+            kam: Some(vec![dep("a"), dep("b")]),
+            ..Default::default()
+        });
+        kam_toml.write_to_dir(project.path()).unwrap();
+        let mut lock = KamLock::new(1);
+        lock.packages = vec![LockPackage::new("a", "2"), LockPackage::new("b", "2")];
+        let lock_path = project.path().join("kam.lock");
+        lock.write_to_path(&lock_path).unwrap();
+
+        run(RemoveArgs {
+            library: vec!["a".to_string()],
+            path: project.path().to_str().unwrap().to_string(),
+            dev: false,
+            peer: false,
+            purge: true,
+        })
+        .unwrap();
+
+        let kam_toml = KamToml::load_from_dir(project.path()).unwrap();
+        let deps = kam_toml.kam.dependency.unwrap().kam.unwrap();
+        assert_eq!(deps, [dep("b")]);
+        let lock = KamLock::load_from_path(&lock_path).unwrap();
+        assert_eq!(lock.packages, [LockPackage::new("b", "2")]);
+        // The project's own cache was purged, except for the pinned version
+        assert!(!cache.lib_module_path("a", "2").exists());
+        assert!(cache.lib_module_path("a", "1").exists());
+    }
+}
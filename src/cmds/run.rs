@@ -0,0 +1,143 @@
+use crate::cache::KamCache;
+use crate::errors::KamError;
+use crate::venv::KamVenv;
+/// # Kam Run Command
+///
+/// Run a binary provided by a dependency without activating the venv, like
+/// `uv run` or `npx`. The binary is looked up in the venv's `bin/` (the one
+/// `KAM_VENV` points at, or the project's `.kam_venv`), then in the cache's
+/// `bin/`. It runs with the venv's `bin/` and `lib/` first on `PATH` and
+/// `KAM_VENV` set, and `kam run` exits with its exit code.
+///
+/// ## Example
+///
+/// ```bash
+/// kam run mytool --help
+/// kam run -p ../my-module mytool -- -v
+/// ```
+use clap::Args;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Arguments for the run command
+#[derive(Args, Debug)]
+pub struct RunArgs {
+    /// Path to the project (default: current directory)
+    #[arg(short, long, default_value = ".")]
+    pub path: String,
+
+    /// Name of the binary to run
+    pub name: String,
+
+    /// Arguments passed to the binary
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    pub args: Vec<OsString>,
+}
+
+/// Run the run command
+pub fn run(args: RunArgs) -> Result<(), KamError> {
+    let venv_path = std::env::var_os("KAM_VENV")
+        .map(PathBuf::from)
+        .filter(|p| p.is_dir())
+        .unwrap_or_else(|| Path::new(&args.path).join(".kam_venv"));
+    // PATH entries must survive the binary changing directories
+    let venv_path = venv_path.canonicalize().unwrap_or(venv_path);
+    let venv = KamVenv::load(&venv_path).ok();
+    let cache = KamCache::new()?;
+
+    let mut candidates = Vec::new();
+    if let Some(venv) = &venv {
+        candidates.push(venv.bin_dir());
+    }
+    candidates.push(cache.bin_dir());
+    let binary = find_binary(&candidates, &args.name).ok_or_else(|| {
+        KamError::BinaryNotFound(format!(
+            "'{}' is not linked into {} or the cache at {}; add the module providing it and run `kam sync`",
+            args.name,
+            venv_path.join("bin").display(),
+            cache.bin_dir().display()
+        ))
+    })?;
+
+    let mut command = Command::new(&binary);
+    command.args(&args.args);
+    if let Some(venv) = &venv {
+        let mut paths = vec![venv.bin_dir(), venv.lib_dir()];
+        paths.extend(std::env::split_paths(
+            &std::env::var_os("PATH").unwrap_or_default(),
+        ));
+        let path = std::env::join_paths(paths)
+            .map_err(|e| KamError::CommandFailed(format!("cannot set PATH: {}", e)))?;
+        command
+            .env("PATH", path)
+            .env("KAM_VENV", venv.root())
+            .env("KAM_VENV_ACTIVE", "1");
+    }
+    crate::detail!("Running {}", binary.display());
+
+    let status = command
+        .status()
+        .map_err(|e| KamError::CommandFailed(format!("cannot run {}: {}", binary.display(), e)))?;
+    if status.success() {
+        return Ok(());
+    }
+    Err(KamError::ExitStatus(exit_code(&status)))
+}
+
+/// The first `dir/name` (or, on Windows, `dir/name.exe` and the like) that
+/// is a file
+fn find_binary(dirs: &[PathBuf], name: &str) -> Option<PathBuf> {
+    let suffixes: &[&str] = if cfg!(windows) {
+        &["", ".exe", ".bat", ".cmd"]
+    } else {
+        &[""]
+    };
+    dirs.iter()
+        .flat_map(|dir| {
+            suffixes
+                .iter()
+                .map(move |suffix| dir.join(format!("{}{}", name, suffix)))
+        })
+        .find(|path| path.is_file())
+}
+
+/// The exit code to pass on for `status`; a process killed by a signal
+/// exits with 128 + the signal number, as in a shell
+fn exit_code(status: &std::process::ExitStatus) -> i32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+    status.code().unwrap_or(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_binary_prefers_venv() {
+        let venv_bin = tempfile::tempdir().unwrap();
+        let cache_bin = tempfile::tempdir().unwrap();
+        std::fs::write(cache_bin.path().join("tool"), "").unwrap();
+        let dirs = vec![
+            venv_bin.path().to_path_buf(),
+            cache_bin.path().to_path_buf(),
+        ];
+
+        assert_eq!(
+            find_binary(&dirs, "tool"),
+            Some(cache_bin.path().join("tool"))
+        );
+        std::fs::write(venv_bin.path().join("tool"), "").unwrap();
+        assert_eq!(
+            find_binary(&dirs, "tool"),
+            Some(venv_bin.path().join("tool"))
+        );
+        assert_eq!(find_binary(&dirs, "missing"), None);
+    }
+}
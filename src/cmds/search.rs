@@ -0,0 +1,435 @@
+use crate::cache::KamCache;
+use crate::cmds::add::compute_index_path;
+use crate::errors::KamError;
+use crate::types::kam_toml::KamToml;
+use crate::types::kam_toml::sections::Registries;
+/// # Kam Search Command
+///
+/// Search a repository index for modules. The query is matched
+/// (case-insensitively) against each module's id, name, description and
+/// keywords; exact and prefix id matches are listed first.
+///
+/// Without `--repo` the local repository named by `KAM_LOCAL_REPO` is
+/// searched when set; otherwise the cache index and the project's default
+/// registry are. Remote registries are not downloaded whole: only the index
+/// shard the query's prefix falls into (`index/ab/cd/` for `abcd...`) is
+/// listed, which needs a GitHub-hosted registry; other registries are only
+/// looked up by exact id.
+///
+/// ## Example
+///
+/// ```bash
+/// kam search zygisk
+/// kam search core --limit 5 --json
+/// kam search net --repo ./my-repo
+/// ```
+use clap::Args;
+use colored::Colorize;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Longest description shown in the table
+const DESCRIPTION_WIDTH: usize = 60;
+
+/// Arguments for the search command
+#[derive(Args, Debug)]
+pub struct SearchArgs {
+    /// Text to look for in module ids, names, descriptions and keywords
+    pub query: String,
+
+    /// Repository to search: a local repository directory or a registry
+    /// URL (default: KAM_LOCAL_REPO, else the cache index and the default
+    /// registry)
+    #[arg(short, long)]
+    pub repo: Option<String>,
+
+    /// Project whose default registry is searched
+    #[arg(short, long, default_value = ".")]
+    pub path: String,
+
+    /// Show at most this many results
+    #[arg(short, long, default_value_t = 20)]
+    pub limit: usize,
+
+    /// Print the results as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// A module found in an index, at its newest indexed version
+#[derive(Debug, Clone, Serialize)]
+#[allow(non_snake_case)]
+struct SearchHit {
+    id: String,
+    name: String,
+    version: String,
+    versionCode: Option<i64>,
+    description: String,
+    keywords: Vec<String>,
+    source: String,
+}
+
+impl SearchHit {
+    /// Read an index entry (`<version>.json`, `latest.json` or a module of
+    /// `modules_index.json`)
+    fn from_json(entry: &Value, source: &str) -> Option<SearchHit> {
+        let text = |key: &str| {
+            entry
+                .get(key)
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string()
+        };
+        let id = text("id");
+        if id.is_empty() {
+            return None;
+        }
+        let keywords = ["keywords", "categories"]
+            .iter()
+            .filter_map(|key| entry.get(*key).and_then(Value::as_array))
+            .flatten()
+            .filter_map(Value::as_str)
+            .map(str::to_string)
+            .collect();
+        Some(SearchHit {
+            name: text("name"),
+            version: text("version"),
+            versionCode: entry.get("versionCode").and_then(Value::as_i64),
+            description: text("description"),
+            keywords,
+            source: source.to_string(),
+            id,
+        })
+    }
+
+    /// How well the hit matches a lowercase `query` (lower is better), or
+    /// `None` when it doesn't
+    fn rank(&self, query: &str) -> Option<u8> {
+        let id = self.id.to_lowercase();
+        let contains = |text: &str| text.to_lowercase().contains(query);
+        if id == query {
+            Some(0)
+        } else if id.starts_with(query) {
+            Some(1)
+        } else if id.contains(query) {
+            Some(2)
+        } else if contains(&self.name) {
+            Some(3)
+        } else if self.keywords.iter().any(|k| contains(k)) {
+            Some(4)
+        } else if contains(&self.description) {
+            Some(5)
+        } else {
+            None
+        }
+    }
+}
+
+/// Run the search command
+pub fn run(args: SearchArgs) -> Result<(), KamError> {
+    let query = args.query.trim().to_lowercase();
+    if query.is_empty() {
+        return Err(KamError::InvalidConfig(
+            "the search query must not be empty".to_string(),
+        ));
+    }
+
+    let mut hits: BTreeMap<String, SearchHit> = BTreeMap::new();
+    let local_repo = args
+        .repo
+        .clone()
+        .or_else(|| std::env::var("KAM_LOCAL_REPO").ok());
+    match local_repo {
+        Some(repo) if is_remote(&repo) => search_registry(&repo, &query, args.limit, &mut hits)?,
+        Some(repo) => {
+            let repo = PathBuf::from(repo);
+            if !repo.is_dir() {
+                return Err(KamError::InvalidConfig(format!(
+                    "repository {} does not exist",
+                    repo.display()
+                )));
+            }
+            search_local_repo(&repo, &mut hits)?;
+        }
+        None => {
+            let cache = KamCache::new()?;
+            search_index_dir(&cache.root().join("index"), "cache", &mut hits);
+            let registries = match KamToml::load_from_dir(&args.path) {
+                Ok(kam_toml) => kam_toml.registries(),
+                Err(_) => Registries::new(None),
+            };
+            let registry = registries.default_source();
+            if is_remote(registry) {
+                // The cache index still answers when the registry can't
+                if !crate::utils::is_offline()
+                    && let Err(e) = search_registry(registry, &query, args.limit, &mut hits)
+                {
+                    eprintln!("{} Could not search {}: {}", "!".yellow(), registry, e);
+                }
+            } else {
+                search_local_repo(Path::new(registry), &mut hits)?;
+            }
+        }
+    }
+
+    let mut ranked: Vec<(u8, SearchHit)> = hits
+        .into_values()
+        .filter_map(|hit| Some((hit.rank(&query)?, hit)))
+        .collect();
+    ranked.sort_by(|(a, x), (b, y)| a.cmp(b).then_with(|| x.id.cmp(&y.id)));
+    let total = ranked.len();
+    let results: Vec<SearchHit> = ranked
+        .into_iter()
+        .take(args.limit)
+        .map(|(_, hit)| hit)
+        .collect();
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&results)?);
+        return Ok(());
+    }
+    if results.is_empty() {
+        println!("{} No modules match '{}'", "i".cyan(), args.query);
+        return Ok(());
+    }
+    let id_width = results.iter().map(|h| h.id.len()).max().unwrap_or(0);
+    let version_width = results.iter().map(|h| h.version.len()).max().unwrap_or(0);
+    for hit in &results {
+        println!(
+            "{:<id_width$}  {:<version_width$}  {}",
+            hit.id.bold(),
+            hit.version.green(),
+            truncate(&hit.description, DESCRIPTION_WIDTH)
+        );
+    }
+    if total > results.len() {
+        println!(
+            "{}",
+            format!(
+                "... and {} more (use --limit to show more)",
+                total - results.len()
+            )
+            .dimmed()
+        );
+    }
+    Ok(())
+}
+
+fn is_remote(repo: &str) -> bool {
+    repo.starts_with("http://") || repo.starts_with("https://")
+}
+
+/// Keep `hit` unless a newer version of the module was already found
+fn insert_newest(hits: &mut BTreeMap<String, SearchHit>, hit: SearchHit) {
+    match hits.get(&hit.id) {
+        Some(existing) if existing.versionCode >= hit.versionCode => {}
+        _ => {
+            hits.insert(hit.id.clone(), hit);
+        }
+    }
+}
+
+/// Search a local repository: its `index/` tree and the
+/// `json/modules_index.json` written by `kam dev collect`
+fn search_local_repo(repo: &Path, hits: &mut BTreeMap<String, SearchHit>) -> Result<(), KamError> {
+    let source = repo.display().to_string();
+    search_index_dir(&repo.join("index"), &source, hits);
+    let modules_json = repo.join("json").join("modules_index.json");
+    if modules_json.is_file() {
+        let content: Value = serde_json::from_str(&std::fs::read_to_string(&modules_json)?)?;
+        for module in content
+            .get("modules")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            if let Some(hit) = SearchHit::from_json(module, &source) {
+                insert_newest(hits, hit);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Read every version entry below an index directory laid out by
+/// [`compute_index_path`]
+fn search_index_dir(index: &Path, source: &str, hits: &mut BTreeMap<String, SearchHit>) {
+    for entry in WalkDir::new(index).into_iter().filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if !entry.file_type().is_file() || path.extension().and_then(|e| e.to_str()) != Some("json")
+        {
+            continue;
+        }
+        let Ok(content) = std::fs::read_to_string(path) else {
+            continue;
+        };
+        if let Some(hit) = serde_json::from_str::<Value>(&content)
+            .ok()
+            .and_then(|v| SearchHit::from_json(&v, source))
+        {
+            insert_newest(hits, hit);
+        }
+    }
+}
+
+/// `index/...` path of a module or shard directory as a URL path
+fn url_path(path: &Path) -> String {
+    path.components()
+        .filter_map(|c| match c {
+            Component::Normal(part) => part.to_str(),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// `(owner, repo)` of a registry hosted on GitHub
+fn github_repo(registry: &str) -> Option<(&str, &str)> {
+    let rest = registry.strip_prefix("https://github.com/")?;
+    let mut parts = rest.trim_end_matches('/').split('/');
+    let owner = parts.next().filter(|s| !s.is_empty())?;
+    let repo = parts.next().filter(|s| !s.is_empty())?;
+    Some((owner, repo.trim_end_matches(".git")))
+}
+
+/// GET `url` as JSON, or `None` when the server has no such file
+fn get_json(url: &str) -> Result<Option<Value>, KamError> {
+    let client = reqwest::blocking::Client::new();
+    let token = std::env::var("GITHUB_TOKEN").ok();
+    let request = || {
+        let req = client.get(url).header("User-Agent", "kam-package-manager");
+        match (&token, url.starts_with("https://api.github.com/")) {
+            (Some(token), true) => req.header("Authorization", format!("token {}", token)),
+            _ => req,
+        }
+    };
+    let resp = crate::utils::send_with_retry(
+        request,
+        crate::utils::http_attempts(),
+        crate::utils::HTTP_BASE_DELAY,
+    )
+    .map_err(|e| KamError::FetchFailed(format!("{}: {}", url, e)))?;
+    if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if !resp.status().is_success() {
+        return Err(KamError::FetchFailed(format!(
+            "{} returned {}",
+            url,
+            resp.status()
+        )));
+    }
+    resp.json()
+        .map(Some)
+        .map_err(|e| KamError::JsonError(format!("{}: {}", url, e)))
+}
+
+/// Search a remote registry. The query is looked up as an exact id; on
+/// GitHub the shard directory holding ids with the query's first four
+/// characters is listed too, and the `latest.json` of every id in it that
+/// starts with the query is fetched (at most `limit` of them).
+fn search_registry(
+    registry: &str,
+    query: &str,
+    limit: usize,
+    hits: &mut BTreeMap<String, SearchHit>,
+) -> Result<(), KamError> {
+    let base = registry.trim_end_matches('/');
+    let github = github_repo(base);
+    let raw_base = match github {
+        Some(_) => format!("{}/raw/main", base),
+        None => base.to_string(),
+    };
+
+    let mut ids = vec![query.to_string()];
+    if let Some((owner, repo)) = github
+        && query.chars().count() >= 4
+        && let Some(shard) = compute_index_path(Path::new("index"), query).parent()
+    {
+        let url = format!(
+            "https://api.github.com/repos/{}/{}/contents/{}",
+            owner,
+            repo,
+            url_path(shard)
+        );
+        let listing = get_json(&url)?;
+        ids.extend(
+            listing
+                .as_ref()
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter(|e| e.get("type").and_then(Value::as_str) == Some("dir"))
+                .filter_map(|e| e.get("name").and_then(Value::as_str))
+                .filter(|name| name.starts_with(query) && *name != query)
+                .map(str::to_string),
+        );
+    }
+
+    for id in ids.into_iter().take(limit.max(1)) {
+        let module_dir = compute_index_path(Path::new("index"), &id);
+        let url = format!("{}/{}/latest.json", raw_base, url_path(&module_dir));
+        if let Some(hit) = get_json(&url)?
+            .as_ref()
+            .and_then(|entry| SearchHit::from_json(entry, base))
+        {
+            insert_newest(hits, hit);
+        }
+    }
+    Ok(())
+}
+
+/// Cut `text` to its first line and at most `width` characters
+fn truncate(text: &str, width: usize) -> String {
+    let line = text.lines().next().unwrap_or_default().trim();
+    if line.chars().count() <= width {
+        return line.to_string();
+    }
+    let cut: String = line.chars().take(width.saturating_sub(3)).collect();
+    format!("{}...", cut.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_local_index() {
+        let repo = tempfile::tempdir().unwrap();
+        let index = repo.path().join("index");
+        let write = |id: &str, code: i64, extra: Value| {
+            let dir = compute_index_path(&index, id);
+            std::fs::create_dir_all(&dir).unwrap();
+            let mut entry = serde_json::json!({
+                "id": id,
+                "version": format!("1.0.{}", code),
+                "versionCode": code,
+                "description": format!("The {} module", id),
+            });
+            entry
+                .as_object_mut()
+                .unwrap()
+                .extend(extra.as_object().unwrap().clone());
+            std::fs::write(dir.join(format!("{}.json", code)), entry.to_string()).unwrap();
+        };
+        write("netlib", 1, serde_json::json!({}));
+        write("netlib", 2, serde_json::json!({}));
+        write("corelib", 1, serde_json::json!({ "keywords": ["network"] }));
+        write("ui", 1, serde_json::json!({ "name": "Network UI" }));
+
+        let mut hits = BTreeMap::new();
+        search_local_repo(repo.path(), &mut hits).unwrap();
+        assert_eq!(hits["netlib"].versionCode, Some(2));
+
+        let mut ranked: Vec<(u8, &str)> = hits
+            .values()
+            .filter_map(|h| Some((h.rank("net")?, h.id.as_str())))
+            .collect();
+        ranked.sort();
+        assert_eq!(ranked, [(1, "netlib"), (3, "ui"), (4, "corelib")]);
+        assert_eq!(truncate("a long description", 9), "a long...");
+    }
+}
@@ -1,9 +1,16 @@
-use crate::cache::KamCache;
+use crate::cache::{KamCache, VersionResolver, module_version_name, module_versions_in};
+use crate::cmds::add::{
+    compute_index_path, registry_index_file, resolve_latest, verify_package_checksum,
+};
+use crate::cmds::check::check_root_manager;
 use crate::errors::KamError;
-use crate::types::modules::KamModule;
-use crate::types::modules::ModuleBackend;
+use crate::types::kam_lock::{KamLock, LockPackage};
+use crate::types::kam_toml::enums::SupportedArch;
+use crate::types::kam_toml::sections::{Dependency, Registries, TrustSection, VersionSpec};
+use crate::types::modules::{ArchiveFormat, KamModule, extract_archive_as};
 use crate::types::source::Source;
-use crate::venv::{KamVenv, VenvType};
+use crate::venv::{KamVenv, VenvType, copy_dir_all};
+use crate::{detail, status};
 /// # Kam Sync Command
 ///
 /// Synchronize dependencies similar to `uv sync`, creating symbolic links.
@@ -14,6 +21,19 @@ use crate::venv::{KamVenv, VenvType};
 /// - Downloads and caches modules
 /// - Creates symbolic links to cached modules
 /// - Supports dev dependencies with `--dev` flag
+/// - Installs dependencies gated by features requested as `id[feature,...]`
+/// - Skips optional dependencies unless enabled with `--with <id>` or by a
+///   feature of the project (`--features`)
+/// - Verifies that peer dependencies of synced modules are provided by the project
+/// - Fetches the dependencies of each group concurrently (`--jobs`, default 4)
+/// - Records the resolved versions in `kam.lock`; `--frozen` installs exactly those
+/// - Prefers cached versions satisfying a range (`--prefer-cached`, the default);
+///   `--prefer-latest` picks the newest matching version in the local index instead
+/// - Copies the synced modules into a project directory with `--vendor <dir>`;
+///   `--vendored` then installs from that directory only, without the network
+/// - Skips dependencies whose `kam.supported_arch` excludes `--target <arch>`
+/// - On a device, warns when the root manager is older than
+///   `mmrl.repo.manager.<name>.min` (fails with `--strict`)
 ///
 /// ## Example
 ///
@@ -23,11 +43,33 @@ use crate::venv::{KamVenv, VenvType};
 ///
 /// # Sync including dev dependencies
 /// kam sync --dev
+///
+/// # Install exactly what kam.lock pins
+/// kam sync --frozen
+///
+/// # Move ranges to the newest indexed versions and re-pin them in kam.lock
+/// kam sync --prefer-latest
+///
+/// # Vendor dependencies into modules/, then install from it offline
+/// kam sync --vendor modules
+/// kam sync --vendored
+///
+/// # Only install what runs on an arm64 device
+/// kam sync --target arm64
+///
+/// # Also install the optional dependency webui
+/// kam sync --with webui
 /// ```
 use clap::Args;
 use colored::Colorize;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Default number of concurrent downloads
+pub const DEFAULT_JOBS: usize = 4;
 
 /// Arguments for the sync command
 #[derive(Args, Debug)]
@@ -39,89 +81,504 @@ pub struct SyncArgs {
     /// Include dev dependencies
     #[arg(long)]
     pub dev: bool,
+
+    /// Install exactly the versions pinned by kam.lock; fail if it is
+    /// missing or out of date instead of resolving ranges
+    #[arg(long)]
+    pub frozen: bool,
+
+    /// Satisfy version ranges with already cached versions when possible,
+    /// avoiding downloads (default)
+    #[arg(long, conflicts_with = "prefer_latest")]
+    pub prefer_cached: bool,
+
+    /// Resolve version ranges to the newest matching version in the local
+    /// index, ignoring the versions pinned by kam.lock
+    #[arg(long, conflicts_with = "frozen")]
+    pub prefer_latest: bool,
+
+    /// Also copy the synced modules into DIR (relative to the project) and
+    /// record it in kam.lock
+    #[arg(long, value_name = "DIR", conflicts_with = "vendored")]
+    pub vendor: Option<String>,
+
+    /// Install only from the vendor directory recorded in kam.lock
+    /// (default: modules), never from the network
+    #[arg(long, conflicts_with = "prefer_latest")]
+    pub vendored: bool,
+
+    /// Number of dependencies to download concurrently
+    #[arg(short, long, default_value_t = DEFAULT_JOBS)]
+    pub jobs: usize,
+
+    /// Features of this project to enable (comma separated); their gated
+    /// dependencies are synced with the runtime group
+    #[arg(long, value_delimiter = ',')]
+    pub features: Vec<String>,
+
+    /// Optional dependencies to install (comma separated ids)
+    #[arg(long, value_name = "ID", value_delimiter = ',')]
+    pub with: Vec<String>,
+
+    /// Only install dependencies whose kam.supported_arch includes ARCH (or
+    /// is empty); aliases such as aarch64 or arm64-v8a are accepted
+    #[arg(long, value_name = "ARCH", value_parser = parse_target)]
+    pub target: Option<SupportedArch>,
+
+    /// Fail instead of warning when the device's root manager is older than
+    /// mmrl.repo.manager.<name>.min
+    #[arg(long)]
+    pub strict: bool,
+
+    /// Dependencies whose kam.lock pins are ignored and re-resolved like
+    /// with --prefer-latest (set by `kam update`)
+    #[arg(skip)]
+    pub upgrade: Vec<String>,
 }
 
-/// Ensure a dependency module exists in the cache. Returns `Ok(true)` if a new
-/// placeholder was created, `Ok(false)` if it already existed.
-fn ensure_module_synced(
-    cache: &KamCache,
-    dep: &crate::types::kam_toml::sections::Dependency,
-) -> Result<bool, KamError> {
-    // Resolve a concrete version string to use for cache paths. If the
-    // dependency specifies an exact versionCode, use it. If it specifies a
-    // range, try to choose the highest cached version matching the range.
-    // If nothing is available, fall back to the lower bound or 0.
-    use crate::types::kam_toml::sections::VersionSpec;
-
-    let version = match &dep.versionCode {
-        Some(VersionSpec::Exact(v)) => v.to_string(),
-        Some(VersionSpec::Range(s)) => {
-            // parse a range like "[1000,2000)" or "[1000,)" or "(,2000]"
-            // extract min and max if present
-            let s = s.trim();
-            let min_incl = s.starts_with('[');
-            let max_incl = s.ends_with(']');
-            let inner = s
-                .trim_start_matches('[')
-                .trim_start_matches('(')
-                .trim_end_matches(']')
-                .trim_end_matches(')');
-            let parts: Vec<&str> = inner.split(',').map(|p| p.trim()).collect();
-            let min_opt = if parts.get(0).map(|p| !p.is_empty()).unwrap_or(false) {
-                parts[0].parse::<i64>().ok()
-            } else {
-                None
-            };
-            let max_opt = if parts.len() > 1 && parts[1].len() > 0 {
-                parts[1].parse::<i64>().ok()
-            } else {
-                None
-            };
+/// Parse `--target`, refusing names that are not a known architecture
+fn parse_target(name: &str) -> Result<SupportedArch, String> {
+    match SupportedArch::parse(name) {
+        SupportedArch::Other(other) => Err(match SupportedArch::closest(&other) {
+            Some(arch) => format!(
+                "unknown architecture '{}' (did you mean '{}'?)",
+                other, arch
+            ),
+            None => format!(
+                "unknown architecture '{}' (expected arm, arm64, x86 or x86_64)",
+                other
+            ),
+        }),
+        arch => Ok(arch),
+    }
+}
 
-            // list cached versions for id
-            let mut candidates: Vec<i64> = Vec::new();
-            if let Ok(entries) = std::fs::read_dir(cache.lib_dir()) {
-                for e in entries.flatten() {
-                    if let Some(name) = e.file_name().to_str() {
-                        if let Some(rest) = name.strip_prefix(&format!("{}-", dep.id)) {
-                            if let Ok(n) = rest.parse::<i64>() {
-                                // test against range
-                                let mut ok = true;
-                                if let Some(minv) = min_opt {
-                                    ok = ok && (if min_incl { n >= minv } else { n > minv });
-                                }
-                                if let Some(maxv) = max_opt {
-                                    ok = ok && (if max_incl { n <= maxv } else { n < maxv });
-                                }
-                                if ok {
-                                    candidates.push(n);
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+/// Vendor directory used by `--vendored` when kam.lock records none
+const DEFAULT_VENDOR_DIR: &str = "modules";
 
-            if let Some(max_match) = candidates.into_iter().max() {
-                max_match.to_string()
-            } else if let Some(minv) = min_opt {
-                minv.to_string()
-            } else {
-                "0".to_string()
-            }
+/// Lockfile written next to `kam.toml`
+const LOCK_FILE: &str = "kam.lock";
+
+/// File in a fetched module recording the sha256 of the package it was
+/// extracted from
+const PACKAGE_SHA256_FILE: &str = ".package.sha256";
+
+/// Version selection for one sync run: pins from `kam.lock` first, then the
+/// (memoized) highest matching cached version, or with `prefer_latest` the
+/// newest matching version in the local index. The `upgrade` ids get the
+/// `prefer_latest` treatment on their own. Requirements without a lower
+/// bound that match nothing cached take the newest matching release in the
+/// local or the registry's index; unconstrained dependencies found nowhere
+/// locally take the registry's `latest.json`. With a `vendored`
+/// directory, versions are chosen from (and fetched from) that directory
+/// only.
+struct Resolution<'a> {
+    resolver: VersionResolver<'a>,
+    lock: Option<KamLock>,
+    frozen: bool,
+    prefer_latest: bool,
+    upgrade: HashSet<String>,
+    vendored: Option<PathBuf>,
+    registries: Registries,
+    /// `[kam.trust]` keys packages are checked against
+    trust: TrustSection,
+}
+
+impl Resolution<'_> {
+    fn cache(&self) -> &KamCache {
+        self.resolver.cache()
+    }
+
+    /// The lock entry pinning `dep`, as long as its requirement is unchanged
+    fn pinned(&self, dep: &Dependency) -> Option<&LockPackage> {
+        if self.upgrade.contains(&dep.id) {
+            return None;
+        }
+        let requirement = requirement(dep);
+        self.lock
+            .as_ref()?
+            .packages
+            .iter()
+            .find(|p| p.name == dep.id && p.requirement.as_deref() == Some(requirement.as_str()))
+    }
+
+    /// Concrete version string used for a dependency's cache path
+    fn version(&self, dep: &Dependency) -> Result<String, KamError> {
+        if let Some(pinned) = self.pinned(dep) {
+            return Ok(pinned.version.clone());
+        }
+        if self.frozen {
+            return Err(KamError::Lockfile(format!(
+                "'{}' ({}) is not pinned by {}; run `kam sync` without --frozen to update it",
+                dep.id,
+                requirement(dep),
+                LOCK_FILE
+            )));
+        }
+        if let Some(dir) = &self.vendored {
+            return module_versions_in(dir, &dep.id)
+                .into_iter()
+                .rev()
+                .find(|code| {
+                    dep.versionCode.as_ref().is_none_or(|spec| {
+                        let module_dir = dir.join(format!("{}-{}", dep.id, code));
+                        let name = module_version_name(&module_dir).unwrap_or_default();
+                        spec.matches(&name, *code)
+                    })
+                })
+                .map(|code| code.to_string())
+                .ok_or_else(|| {
+                    KamError::FetchFailed(format!(
+                        "no vendored version of '{}' ({}) in {}",
+                        dep.id,
+                        requirement(dep),
+                        dir.display()
+                    ))
+                });
         }
-        None => "0".to_string(),
+        let cached = self.resolver.resolve(&dep.id, dep.versionCode.as_ref());
+        let version = if self.prefer_latest || self.upgrade.contains(&dep.id) {
+            newest_version(self.cache(), &dep.id, dep.versionCode.as_ref()).max(cached)
+        } else {
+            cached
+        };
+        if let Some(version) = version {
+            return Ok(version.to_string());
+        }
+        // Nothing cached matches. Git sources are cloned as-is.
+        let source = self.registries.source_for(dep)?;
+        if matches!(Source::parse(&source), Ok(Source::Git { .. })) {
+            return Ok("0".to_string());
+        }
+        // Semver requirements and ranges without a lower bound take the
+        // newest matching release the local or the registry's index knows
+        if let Some(spec) = &dep.versionCode {
+            return newest_version(self.cache(), &dep.id, Some(spec))
+                .or_else(|| newest_registry_version(&source, &dep.id, spec))
+                .map(|code| code.to_string())
+                .ok_or_else(|| {
+                    KamError::DependencyResolutionFailed(format!(
+                        "no release of '{}' in the local index or the index of {} matches {}",
+                        dep.id,
+                        source,
+                        spec.as_display()
+                    ))
+                });
+        }
+        // Without a requirement the registry's `latest` release is used
+        let (_, code) = resolve_latest(self.cache(), &source, &dep.id)?;
+        Ok(code.to_string())
+    }
+}
+
+/// Newest version code of `id` matching `spec` (any version without one)
+/// among the local index and the cached modules
+pub(crate) fn newest_version(
+    cache: &KamCache,
+    id: &str,
+    spec: Option<&VersionSpec>,
+) -> Option<i64> {
+    let cached = cache.cached_versions(id).into_iter().map(|code| {
+        (
+            cache.cached_version_name(id, code).unwrap_or_default(),
+            code,
+        )
+    });
+    index_versions(cache, id)
+        .into_iter()
+        .chain(cached)
+        .filter(|(version, code)| spec.is_none_or(|spec| spec.matches(version, *code)))
+        .map(|(_, code)| code)
+        .max()
+}
+
+/// Newest version code of `id` matching `spec` in the registry's own
+/// index: any release of a directory registry, or the `latest.json` of an
+/// HTTP one, which cannot be listed
+fn newest_registry_version(registry: &str, id: &str, spec: &VersionSpec) -> Option<i64> {
+    let entries = if registry.starts_with("http://") || registry.starts_with("https://") {
+        registry_index_file(registry, id, "latest.json")
+            .into_iter()
+            .collect()
+    } else {
+        index_entries_in(&Path::new(registry).join("index"), id)
     };
+    entries
+        .iter()
+        .filter_map(release_version)
+        .filter(|(version, code)| spec.matches(version, *code))
+        .map(|(_, code)| code)
+        .max()
+}
+
+/// Metadata of every release of `id` in the local index (`<cache>/index`)
+fn index_entries(cache: &KamCache, id: &str) -> Vec<serde_json::Value> {
+    index_entries_in(&cache.root().join("index"), id)
+}
+
+/// Metadata of every release of `id` in the index rooted at `index`
+fn index_entries_in(index: &Path, id: &str) -> Vec<serde_json::Value> {
+    let dir = compute_index_path(index, id);
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            p.extension().and_then(|e| e.to_str()) == Some("json")
+                && p.file_name().and_then(|n| n.to_str()) != Some("latest.json")
+        })
+        .filter_map(|p| serde_json::from_str(&fs::read_to_string(p).ok()?).ok())
+        .collect()
+}
 
-    let module_path = cache.lib_module_path(&dep.id, &version);
+/// `(version, versionCode)` of every release of `id` in the local index
+fn index_versions(cache: &KamCache, id: &str) -> Vec<(String, i64)> {
+    index_entries(cache, id)
+        .iter()
+        .filter_map(release_version)
+        .collect()
+}
+
+/// `(version, versionCode)` recorded by an index entry
+fn release_version(meta: &serde_json::Value) -> Option<(String, i64)> {
+    let version = meta.get("version")?.as_str()?.to_string();
+    Some((version, meta.get("versionCode")?.as_i64()?))
+}
+
+/// The local index entry of `id` at `version` (a version code)
+fn index_entry(cache: &KamCache, id: &str, version: &str) -> Option<serde_json::Value> {
+    index_entries(cache, id).into_iter().find(|meta| {
+        meta.get("versionCode")
+            .and_then(|c| c.as_i64())
+            .is_some_and(|code| code.to_string() == version)
+    })
+}
+
+/// The package sha256 the local index records for `id` at `version` (a
+/// version code)
+fn index_sha256(cache: &KamCache, id: &str, version: &str) -> Option<String> {
+    index_entry(cache, id, version)?
+        .get("sha256")?
+        .as_str()
+        .map(str::to_string)
+}
+
+/// The entry the registry's own index records for `id` at `version` (a
+/// version code). Registry entries are named after version strings, so the
+/// registry is asked for the entry the local index names for this version
+/// code and for `latest.json`.
+fn registry_entry(
+    cache: &KamCache,
+    registry: &str,
+    id: &str,
+    version: &str,
+) -> Option<serde_json::Value> {
+    let files = index_versions(cache, id)
+        .into_iter()
+        .filter(|(_, code)| code.to_string() == version)
+        .map(|(name, _)| format!("{}.json", name))
+        .chain(std::iter::once("latest.json".to_string()));
+    files
+        .filter_map(|file| registry_index_file(registry, id, &file))
+        .find(|meta| {
+            meta.get("versionCode")
+                .and_then(|c| c.as_i64())
+                .is_some_and(|code| code.to_string() == version)
+        })
+}
+
+/// The package sha256 recorded for `id` at `version` (a version code) by
+/// the registry's own index, or else by the local index
+fn expected_sha256(cache: &KamCache, registry: &str, id: &str, version: &str) -> Option<String> {
+    registry_entry(cache, registry, id, version)
+        .and_then(|meta| meta.get("sha256")?.as_str().map(str::to_string))
+        .or_else(|| index_sha256(cache, id, version))
+}
+
+/// The checksum `kam.lock` records for the cached module `id` at `version`:
+/// the sha256 of the package it was fetched from (recorded when `sync`
+/// fetched it, or in the local index when `add` did), else a hash of its
+/// files for modules that did not come from a package. `None` for a module
+/// that is neither cached nor indexed (one `--target` skipped).
+fn package_checksum(cache: &KamCache, id: &str, version: &str) -> Result<Option<String>, KamError> {
+    let module_path = cache.lib_module_path(id, version);
+    let recorded = fs::read_to_string(module_path.join(PACKAGE_SHA256_FILE))
+        .ok()
+        .map(|sha256| sha256.trim().to_string())
+        .or_else(|| index_sha256(cache, id, version));
+    match recorded {
+        Some(sha256) => Ok(Some(format!("sha256:{}", sha256))),
+        None if module_path.exists() => Ok(Some(cache.module_checksum(id, version)?)),
+        None => Ok(None),
+    }
+}
+
+/// The version requirement of a dependency as recorded in `kam.lock`
+fn requirement(dep: &Dependency) -> String {
+    dep.versionCode
+        .as_ref()
+        .map(|v| v.as_display())
+        .unwrap_or_else(|| "*".to_string())
+}
+
+/// Source recorded in a module's `.synced` marker (`Synced: id @ ver (source)`)
+fn synced_source(module_path: &Path) -> Option<String> {
+    let marker = fs::read_to_string(module_path.join(".synced")).ok()?;
+    let start = marker.rfind(" (")?;
+    marker[start + 2..]
+        .trim_end()
+        .strip_suffix(')')
+        .map(str::to_string)
+}
+
+/// Fetch the modules of `deps` into the cache using up to `jobs` worker
+/// threads. Returns, in declaration order, whether each dependency was newly
+/// fetched; the first failure in declaration order is returned as the error.
+fn fetch_all(
+    resolution: &Resolution,
+    deps: &[Dependency],
+    jobs: usize,
+) -> Result<Vec<bool>, KamError> {
+    // Versions are resolved up front: the resolver is not shared between
+    // threads, and each id@version is fetched only once
+    let mut tasks: Vec<(&Dependency, String)> = Vec::new();
+    let mut task_of: Vec<usize> = Vec::with_capacity(deps.len());
+    let mut seen: HashMap<(String, String), usize> = HashMap::new();
+    for dep in deps {
+        let version = resolution.version(dep)?;
+        let index = *seen
+            .entry((dep.id.clone(), version.clone()))
+            .or_insert_with(|| {
+                tasks.push((dep, version));
+                tasks.len() - 1
+            });
+        task_of.push(index);
+    }
+
+    let cache = resolution.cache();
+    let vendored = resolution.vendored.as_deref();
+    let registries = &resolution.registries;
+    let trust = &resolution.trust;
+    let next = AtomicUsize::new(0);
+    let results: Vec<Mutex<Option<Result<bool, KamError>>>> =
+        tasks.iter().map(|_| Mutex::new(None)).collect();
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, tasks.len().max(1)) {
+            scope.spawn(|| {
+                loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    let Some((dep, version)) = tasks.get(i) else {
+                        break;
+                    };
+                    let public_key = trust.key_for(&dep.id);
+                    let result =
+                        ensure_module_synced(cache, dep, version, vendored, registries, public_key);
+                    *results[i].lock().unwrap_or_else(|e| e.into_inner()) = Some(result);
+                }
+            });
+        }
+    });
+
+    let mut results: Vec<Option<Result<bool, KamError>>> = results
+        .into_iter()
+        .map(|r| r.into_inner().unwrap_or_else(|e| e.into_inner()))
+        .collect();
+    task_of
+        .into_iter()
+        .map(|i| results[i].take().unwrap_or(Ok(false)))
+        .collect()
+}
+
+/// Ensure a dependency module exists in the cache, copying it from the
+/// `vendored` directory when given and fetching it from its registry
+/// otherwise (checking its signature when `public_key` is pinned). Returns
+/// `Ok(true)` if it was fetched,
+/// `Ok(false)` if it already existed.
+///
+/// The module is assembled in a private staging directory and moved into
+/// place once complete, so concurrent fetches of the same module (or an
+/// interrupted one) never leave a half-written `lib_module_path` behind.
+fn ensure_module_synced(
+    cache: &KamCache,
+    dep: &Dependency,
+    version: &str,
+    vendored: Option<&Path>,
+    registries: &Registries,
+    public_key: Option<&str>,
+) -> Result<bool, KamError> {
+    let module_path = cache.lib_module_path(&dep.id, version);
 
     // Already cached
     if module_path.exists() {
         return Ok(false);
     }
 
-    // Ensure parent exists
-    fs::create_dir_all(&module_path)?;
+    static STAGING_SEQ: AtomicUsize = AtomicUsize::new(0);
+    let staging = cache.lib_dir().join(format!(
+        ".{}-{}.partial-{}-{}",
+        dep.id,
+        version,
+        std::process::id(),
+        STAGING_SEQ.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::create_dir_all(&staging)?;
+
+    let fetched = match vendored {
+        Some(dir) => {
+            let vendored_module = dir.join(format!("{}-{}", dep.id, version));
+            if vendored_module.is_dir() {
+                copy_dir_all(&vendored_module, &staging).map_err(KamError::from)
+            } else {
+                Err(KamError::FetchFailed(format!(
+                    "'{}@{}' is not vendored in {}",
+                    dep.id,
+                    version,
+                    dir.display()
+                )))
+            }
+        }
+        None => fetch_module(cache, dep, version, &staging, registries, public_key),
+    };
+    if let Err(e) = fetched {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(e);
+    }
+    if let Err(e) = fs::rename(&staging, &module_path) {
+        let _ = fs::remove_dir_all(&staging);
+        // Another sync finished the same module first
+        if !module_path.exists() {
+            return Err(e.into());
+        }
+    }
+    Ok(true)
+}
+
+/// Fetch a module from the local repo or its source into `module_path`
+fn fetch_module(
+    cache: &KamCache,
+    dep: &Dependency,
+    version: &str,
+    module_path: &Path,
+    registries: &Registries,
+    public_key: Option<&str>,
+) -> Result<(), KamError> {
+    // Git sources (`kam add --git`) are cloned rather than searched for
+    // packages
+    let source_base = registries.source_for(dep)?;
+    if let Ok(source @ Source::Git { .. }) = Source::parse(&source_base) {
+        let module = KamModule::new(crate::types::kam_toml::KamToml::default(), Some(source));
+        let clone = module.fetch_to_temp()?;
+        let copied = copy_dir_all(&clone, module_path);
+        let _ = fs::remove_dir_all(&clone);
+        copied?;
+        fs::write(
+            module_path.join(".synced"),
+            format!("Synced: {} @ {} ({})", dep.id, version, source_base),
+        )?;
+        return Ok(());
+    }
 
     // Candidate local repo locations
     let mut local_candidates = Vec::new();
@@ -139,18 +596,34 @@ fn ensure_module_synced(
     for repo_root in local_candidates {
         let candidate = repo_root.join(&zip_name);
         if candidate.exists() {
+            let sha256 = verify_package_checksum(
+                &candidate,
+                index_sha256(cache, &dep.id, version).as_deref(),
+            )?;
+            crate::signing::verify_pinned(&candidate, &dep.id, public_key, || {
+                fs::read_to_string(crate::signing::signature_path(&candidate)).ok()
+            })?;
             // Extract zip into module_path
-            let file = std::fs::File::open(&candidate)?;
-            let mut archive = zip::ZipArchive::new(file)?;
-            archive.extract(&module_path).map_err(KamError::from)?;
+            extract_archive_as(&candidate, ArchiveFormat::Zip, module_path)?;
+            fs::write(module_path.join(PACKAGE_SHA256_FILE), sha256)?;
             let marker = module_path.join(".synced");
-            fs::write(marker, format!("Synced: {} @ {} (local)", dep.id, version))?;
-            return Ok(true);
+            fs::write(
+                marker,
+                format!("Synced: {} @ {} ({})", dep.id, version, candidate.display()),
+            )?;
+            return Ok(());
         }
     }
 
-    // Try network sources using KamToml's effective source and the new Source/KamModule
-    let source_base = crate::types::kam_toml::KamToml::get_effective_source(dep);
+    if crate::utils::is_offline() {
+        return Err(KamError::OfflineUnavailable(format!(
+            "'{}@{}' is neither cached nor in a local repository",
+            dep.id, version
+        )));
+    }
+
+    // Try network sources: the dependency's source or registry, or the
+    // project's default registry
     let candidates = vec![
         format!("{}/{}", source_base.trim_end_matches('/'), zip_name),
         format!(
@@ -166,28 +639,31 @@ fn ensure_module_synced(
         ),
     ];
 
+    let expected_sha256 = expected_sha256(cache, &source_base, &dep.id, version);
     for url in candidates {
-        // Parse the candidate into a Source and attempt to install into cache using KamModule
-        match Source::parse(&url) {
-            Ok(src) => {
-                let module = KamModule::new(crate::types::kam_toml::KamToml::default(), Some(src));
-                match install_backend_into_cache(&module, cache) {
-                    Ok(_dst) => {
-                        let marker = module_path.join(".synced");
-                        fs::write(
-                            marker,
-                            format!("Synced: {} @ {} ({})", dep.id, version, url),
-                        )?;
-                        return Ok(true);
-                    }
-                    Err(_e) => {
-                        // try next candidate
-                        continue;
-                    }
-                }
-            }
-            Err(_) => continue,
+        detail!("    {} Trying {}", "•".dimmed(), url);
+        // Packages are downloaded and checked before they are installed
+        let Some(download) = download_package(&url)? else {
+            continue;
+        };
+        let sha256 = verify_package_checksum(download.path(), expected_sha256.as_deref())?;
+        crate::signing::verify_pinned(download.path(), &dep.id, public_key, || {
+            crate::signing::download_signature(&url)
+        })?;
+        if let Err(e) = extract_archive_as(download.path(), ArchiveFormat::Zip, module_path) {
+            // Not a usable package; start the next candidate from a clean
+            // directory
+            detail!("    {} {}", "•".dimmed(), e);
+            fs::remove_dir_all(module_path)?;
+            fs::create_dir_all(module_path)?;
+            continue;
         }
+        fs::write(module_path.join(PACKAGE_SHA256_FILE), sha256)?;
+        fs::write(
+            module_path.join(".synced"),
+            format!("Synced: {} @ {} ({})", dep.id, version, url),
+        )?;
+        return Ok(());
     }
 
     // If we reach here, we couldn't obtain the module
@@ -197,17 +673,349 @@ fn ensure_module_synced(
     )))
 }
 
-/// Install a ModuleBackend into the provided cache via the trait.
-///
-/// This small adapter centralizes the place where callers depend on the
-/// trait rather than the concrete `KamModule` type. It simply delegates to
-/// the backend's `install_into_cache` and exists to make call-sites accept
-/// `impl ModuleBackend` / `&dyn ModuleBackend` more explicitly.
-fn install_backend_into_cache(
-    backend: &impl ModuleBackend,
-    cache: &KamCache,
-) -> Result<std::path::PathBuf, KamError> {
-    backend.install_into_cache(cache)
+/// Download the package at `url` to a temporary file; `None` when it is not
+/// there
+fn download_package(url: &str) -> Result<Option<tempfile::NamedTempFile>, KamError> {
+    let Ok(resp) = crate::utils::http_get(url) else {
+        return Ok(None);
+    };
+    if !resp.status().is_success() {
+        return Ok(None);
+    }
+    let mut file = tempfile::Builder::new().suffix(".zip").tempfile()?;
+    let total = resp.content_length();
+    crate::progress::download(resp, file.as_file_mut(), url, total)
+        .map_err(|e| KamError::FetchFailed(format!("read download body: {}", e)))?;
+    Ok(Some(file))
+}
+
+/// Fail when the root project or one of the fetched modules lists another
+/// module of the sync in `kam.conflicts`
+fn check_conflicts<'a>(
+    resolution: &Resolution,
+    root: &crate::types::kam_toml::KamToml,
+    deps: impl IntoIterator<Item = &'a Dependency>,
+) -> Result<(), KamError> {
+    let mut modules: Vec<(String, Vec<String>)> = vec![(
+        root.prop.id.clone(),
+        root.kam.conflicts.clone().unwrap_or_default(),
+    )];
+    for dep in deps {
+        if modules.iter().any(|(id, _)| *id == dep.id) {
+            continue;
+        }
+        let module_toml = resolution
+            .cache()
+            .lib_module_path(&dep.id, &resolution.version(dep)?)
+            .join("kam.toml");
+        let conflicts = if module_toml.exists() {
+            crate::types::kam_toml::KamToml::load_from_file(&module_toml)?
+                .kam
+                .conflicts
+                .unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        modules.push((dep.id.clone(), conflicts));
+    }
+
+    let mut found = Vec::new();
+    for (id, conflicts) in &modules {
+        for other in conflicts {
+            if other != id && modules.iter().any(|(m, _)| m == other) {
+                found.push(format!("'{}' conflicts with '{}'", id, other));
+            }
+        }
+    }
+    if found.is_empty() {
+        Ok(())
+    } else {
+        Err(KamError::ConflictingModules(found.join("; ")))
+    }
+}
+
+/// The architectures `dep` supports at `version`: the `kam.supported_arch`
+/// of its cached `kam.toml`, else the `supported_arch` of its index entry
+/// (the local index, then its registry's). Empty when they are not known.
+fn supported_arch(
+    resolution: &Resolution,
+    dep: &Dependency,
+    version: &str,
+) -> Result<Vec<SupportedArch>, KamError> {
+    let cache = resolution.cache();
+    let module_toml = cache.lib_module_path(&dep.id, version).join("kam.toml");
+    if module_toml.exists() {
+        return Ok(
+            crate::types::kam_toml::KamToml::load_from_file(&module_toml)?
+                .kam
+                .supported_arch
+                .unwrap_or_default(),
+        );
+    }
+    let has_arch = |meta: &serde_json::Value| meta.get("supported_arch").is_some();
+    let meta = index_entry(cache, &dep.id, version)
+        .filter(has_arch)
+        .or_else(|| {
+            let registry = resolution.registries.source_for(dep).ok()?;
+            registry_entry(cache, &registry, &dep.id, version)
+        });
+    Ok(meta
+        .as_ref()
+        .and_then(|meta| meta.get("supported_arch")?.as_array())
+        .map(|archs| {
+            archs
+                .iter()
+                .filter_map(|a| a.as_str())
+                .map(SupportedArch::parse)
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+/// Split `deps` into the ones that run on `target` and the ones that don't,
+/// reporting each skipped dependency. A module runs on `target` when its
+/// [`supported_arch`] lists it or is empty, so this is called before
+/// fetching (to skip what is already known not to run) and again after.
+fn filter_target(
+    resolution: &Resolution,
+    deps: &[Dependency],
+    target: Option<&SupportedArch>,
+) -> Result<(Vec<Dependency>, Vec<Dependency>), KamError> {
+    let Some(target) = target else {
+        return Ok((deps.to_vec(), Vec::new()));
+    };
+    let mut kept = Vec::new();
+    let mut skipped = Vec::new();
+    for dep in deps {
+        let version = resolution.version(dep)?;
+        let supported = supported_arch(resolution, dep, &version)?;
+        if supported.is_empty() || supported.contains(target) {
+            kept.push(dep.clone());
+            continue;
+        }
+        let supported: Vec<String> = supported.iter().map(|a| a.to_string()).collect();
+        status!(
+            "  {} {}@{} {}",
+            "-".dimmed(),
+            dep.id.bold(),
+            version.dimmed(),
+            format!("skipped: supports {}, not {}", supported.join(", "), target).dimmed()
+        );
+        skipped.push(dep.clone());
+    }
+    Ok((kept, skipped))
+}
+
+/// Report a dependency fetched by [`fetch_all`] and link it (and its
+/// binaries) into the venv
+fn sync_dependency(
+    resolution: &Resolution,
+    venv: Option<&KamVenv>,
+    dep: &Dependency,
+) -> Result<(), KamError> {
+    // Use versionCode for dependency selection (fall back to 0 when absent)
+    let version_code = dep
+        .versionCode
+        .as_ref()
+        .map(|v| v.as_display())
+        .unwrap_or_else(|| "0".to_string());
+    let features = dep.requested_features();
+    let features = if features.is_empty() {
+        String::new()
+    } else {
+        format!("[{}]", features.join(","))
+    };
+    status!(
+        "  {} {}{}@{}",
+        "→".cyan(),
+        dep.id.bold(),
+        features,
+        version_code.dimmed()
+    );
+
+    // A frozen sync must install exactly the locked contents
+    let expected = resolution
+        .pinned(dep)
+        .and_then(|p| p.checksum.as_ref())
+        .filter(|_| resolution.frozen);
+    if let Some(expected) = expected {
+        let actual = package_checksum(resolution.cache(), &dep.id, &resolution.version(dep)?)?;
+        if actual.as_ref() != Some(expected) {
+            return Err(KamError::Lockfile(format!(
+                "checksum mismatch for '{}': {} pins {}, cache has {}",
+                dep.id,
+                LOCK_FILE,
+                expected,
+                actual.as_deref().unwrap_or("none")
+            )));
+        }
+    }
+
+    // If a venv was requested, link the library into it
+    if let Some(venv) = venv {
+        let cache = resolution.cache();
+        let ver = resolution.version(dep)?;
+        match venv.link_library(&dep.id, &ver, cache) {
+            Ok(_) => status!("  {} Linked {}@{} into venv", "✓".green(), dep.id, ver),
+            Err(e) => println!(
+                "  {} Failed to link {}@{}: {}",
+                "!".yellow(),
+                dep.id,
+                ver,
+                e
+            ),
+        }
+
+        // Link binaries
+        let lib_path = cache.lib_module_path(&dep.id, &ver);
+        if let Ok(entries) = std::fs::read_dir(lib_path.join("bin")) {
+            for entry in entries.flatten() {
+                if let Some(name_str) = entry.file_name().to_str() {
+                    match venv.link_binary(&entry.path()) {
+                        Ok(_) => status!("  {} Linked binary: {}", "✓".green(), name_str),
+                        Err(e) => println!(
+                            "  {} Failed to link binary {}: {}",
+                            "!".yellow(),
+                            name_str,
+                            e
+                        ),
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Copy the cached modules of `synced` into `vendor_dir`, replacing any
+/// previously vendored copy of the same version
+fn vendor_modules(
+    resolution: &Resolution,
+    synced: &[Dependency],
+    vendor_dir: &Path,
+) -> Result<(), KamError> {
+    let mut vendored = HashSet::new();
+    for dep in synced {
+        let version = resolution.version(dep)?;
+        let name = format!("{}-{}", dep.id, version);
+        if !vendored.insert(name.clone()) {
+            continue;
+        }
+        let dst = vendor_dir.join(&name);
+        if dst.exists() {
+            fs::remove_dir_all(&dst)?;
+        }
+        copy_dir_all(&resolution.cache().lib_module_path(&dep.id, &version), &dst)?;
+    }
+    status!(
+        "  {} Vendored {} module(s) into {}",
+        "✓".green(),
+        vendored.len(),
+        vendor_dir.display()
+    );
+    Ok(())
+}
+
+/// Build the lockfile for the dependencies synced in this run. The pins
+/// `previous` holds for the `unsynced` dependencies (those of groups this
+/// run left out) are carried over unchanged.
+fn build_lock(
+    resolution: &Resolution,
+    synced: &[Dependency],
+    previous: Option<&KamLock>,
+    unsynced: &[Dependency],
+) -> Result<KamLock, KamError> {
+    let cache = resolution.cache();
+    let mut lock = KamLock::new(1);
+    for dep in synced {
+        let requirement = requirement(dep);
+        if lock
+            .packages
+            .iter()
+            .any(|p| p.name == dep.id && p.requirement.as_deref() == Some(requirement.as_str()))
+        {
+            continue;
+        }
+        let version = resolution.version(dep)?;
+        let module_path = cache.lib_module_path(&dep.id, &version);
+        let mut package = LockPackage::new(&dep.id, &version);
+        package.requirement = Some(requirement);
+        package.source = synced_source(&module_path)
+            .or_else(|| resolution.pinned(dep).and_then(|p| p.source.clone()));
+        package.checksum = package_checksum(cache, &dep.id, &version)?;
+        lock.packages.push(package);
+    }
+    for dep in unsynced {
+        let requirement = requirement(dep);
+        let pins_dep = |p: &&LockPackage| {
+            p.name == dep.id && p.requirement.as_deref() == Some(requirement.as_str())
+        };
+        if lock.packages.iter().any(|p| pins_dep(&p)) {
+            continue;
+        }
+        if let Some(pin) = previous.and_then(|l| l.packages.iter().find(pins_dep)) {
+            lock.packages.push(pin.clone());
+        }
+    }
+    lock.packages.sort_by(|a, b| {
+        (a.name.as_str(), a.requirement.as_deref())
+            .cmp(&(b.name.as_str(), b.requirement.as_deref()))
+    });
+    Ok(lock)
+}
+
+/// The cache a command working on the project at `project_path` uses,
+/// honoring a project-local `.env` KAM_CACHE_ROOT. A relative value is
+/// resolved against the project directory (the location of the `.env`),
+/// canonicalized when possible, so `.env` may contain `./.kam`.
+pub(crate) fn project_cache(project_path: &Path) -> Result<KamCache, KamError> {
+    match read_project_env_value(project_path, "KAM_CACHE_ROOT") {
+        Some(root_val) => {
+            let p = PathBuf::from(root_val);
+            // Fall back to current_dir() when the project path cannot be
+            // canonicalized (missing)
+            let base = match project_path.canonicalize() {
+                Ok(abs) => abs,
+                Err(_) => std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
+            };
+            let abs = if p.is_absolute() { p } else { base.join(p) };
+            Ok(KamCache::with_root(abs)?)
+        }
+        None => Ok(KamCache::new()?),
+    }
+}
+
+/// The value of `key` in the project's `.env`, if any
+fn read_project_env_value(project_path: &Path, key: &str) -> Option<String> {
+    let env_file = project_path.join(".env");
+    if !env_file.exists() {
+        return None;
+    }
+
+    let content = std::fs::read_to_string(&env_file).ok()?;
+    content
+        .lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .find_map(|line| {
+            line.find('=').and_then(|pos| {
+                let k = line[..pos].trim();
+                if k != key {
+                    return None;
+                }
+                let mut val = line[pos + 1..].trim().to_string();
+                // strip optional surrounding quotes
+                if (val.starts_with('"') && val.ends_with('"'))
+                    || (val.starts_with('\'') && val.ends_with('\''))
+                {
+                    if val.len() >= 2 {
+                        val = val[1..val.len() - 1].to_string();
+                    }
+                }
+                Some(val)
+            })
+        })
 }
 
 /// Run the sync command
@@ -224,104 +1032,94 @@ pub fn run(args: SyncArgs) -> Result<(), KamError> {
 
     // Load kam.toml
     let kam_toml = crate::types::kam_toml::KamToml::load_from_dir(project_path)?;
-    println!(
+    status!(
         "  {} {}",
         "✓".green(),
         format!("Loaded kam.toml for '{}'", kam_toml.prop.id).dimmed()
     );
+    check_root_manager(&kam_toml, args.strict)?;
 
-    // Initialize cache, honoring project-local `.env` KAM_CACHE_ROOT.
-    // If the value in `.env` is a relative path, resolve it relative to the
-    // project directory (the location of the `.env`), using a canonicalized
-    // absolute base when possible. This allows `.env` to contain `./.kam`.
-    fn read_project_env_value(project_path: &Path, key: &str) -> Option<String> {
-        let env_file = project_path.join(".env");
-        if !env_file.exists() {
-            return None;
-        }
-
-        let content = std::fs::read_to_string(&env_file).ok()?;
-        content
-            .lines()
-            .map(|l| l.trim())
-            .filter(|l| !l.is_empty() && !l.starts_with('#'))
-            .find_map(|line| {
-                line.find('=').and_then(|pos| {
-                    let k = line[..pos].trim();
-                    if k != key {
-                        return None;
-                    }
-                    let mut val = line[pos + 1..].trim().to_string();
-                    // strip optional surrounding quotes
-                    if (val.starts_with('"') && val.ends_with('"'))
-                        || (val.starts_with('\'') && val.ends_with('\''))
-                    {
-                        if val.len() >= 2 {
-                            val = val[1..val.len() - 1].to_string();
-                        }
-                    }
-                    Some(val)
-                })
-            })
-    }
-
-    // Initialize cache, honoring project-local `.env` KAM_CACHE_ROOT.
-    // If the value in `.env` is a relative path, resolve it relative to the
-    // project directory (the location of the `.env`), using a canonicalized
-    // absolute base when possible. This allows `.env` to contain `./.kam`.
-    let cache = if let Some(root_val) = read_project_env_value(project_path, "KAM_CACHE_ROOT") {
-        let p = PathBuf::from(root_val);
-        // Try to get an absolute base path for the project. If the project
-        // path cannot be canonicalized (missing), fall back to current_dir().
-        let base = match project_path.canonicalize() {
-            Ok(abs) => abs,
-            Err(_) => std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
-        };
-        let abs = if p.is_absolute() { p } else { base.join(p) };
-        KamCache::with_root(abs)?
-    } else {
-        KamCache::new()?
-    };
+    let cache = project_cache(project_path)?;
     cache.ensure_dirs()?;
-    println!(
+    status!(
         "  {} {}",
         "✓".green(),
         format!("Cache: {}", cache.root().display()).dimmed()
     );
-    println!();
+    status!();
 
     // Ensure virtual environment exists and is up-to-date.
     // Per project policy, `kam sync` should always ensure the venv is present
     // and refreshed. The dedicated `kam venv` command remains available for
     // manual management.
-    println!();
-    println!("{} Ensuring virtual environment is present...", "→".cyan());
+    status!();
+    status!("{} Ensuring virtual environment is present...", "→".cyan());
     let venv_path = project_path.join(".kam_venv");
     let venv_type = if args.dev {
         VenvType::Development
     } else {
         VenvType::Runtime
     };
-    if venv_path.exists() {
-        // recreate to ensure it's the latest
-        fs::remove_dir_all(&venv_path)?;
-    }
-    let venv = KamVenv::create(&venv_path, venv_type)
-        .map_err(|e| KamError::VenvCreateFailed(format!("Venv error: {}", e)))?;
-    println!(
-        "  {} Created/updated at: {}",
-        "✓".green(),
-        venv.root().display()
-    );
+    // Reuse an existing venv of the requested type (only its links are
+    // refreshed); recreate it when the type changed or it cannot be loaded.
+    let existing = if venv_path.exists() {
+        KamVenv::load(&venv_path).ok()
+    } else {
+        None
+    };
+    let venv = match existing {
+        Some(venv) if venv.venv_type() == venv_type => {
+            venv.clear_links()?;
+            status!(
+                "  {} Reusing existing {} venv at: {}",
+                "✓".green(),
+                venv_type,
+                venv.root().display()
+            );
+            venv
+        }
+        other => {
+            if let Some(old) = other {
+                status!(
+                    "  {} Existing venv is {}, recreating as {}",
+                    "•".cyan(),
+                    old.venv_type(),
+                    venv_type
+                );
+            }
+            if venv_path.exists() {
+                fs::remove_dir_all(&venv_path)?;
+            }
+            let venv = KamVenv::create(&venv_path, venv_type)
+                .map_err(|e| KamError::VenvCreateFailed(format!("Venv error: {}", e)))?;
+            status!(
+                "  {} Created {} venv at: {}",
+                "✓".green(),
+                venv_type,
+                venv.root().display()
+            );
+            venv
+        }
+    };
+    venv.warn_if_copying();
     let maybe_venv: Option<KamVenv> = Some(venv);
 
-    println!("{}", "Synchronizing dependencies...".bold().cyan());
-    println!();
+    status!("{}", "Synchronizing dependencies...".bold().cyan());
+    status!();
 
     // Resolve dependencies
     let resolved = kam_toml
-        .resolve_dependencies()
+        .resolve_dependencies_with_features(&args.features, &args.with)
         .map_err(|e| KamError::FetchFailed(format!("dependency resolution failed: {}", e)))?;
+    for (feature, deps) in resolved.features() {
+        let ids: Vec<&str> = deps.iter().map(|d| d.id.as_str()).collect();
+        status!(
+            "  {} Feature {}: {}",
+            "•".cyan(),
+            feature.yellow(),
+            ids.join(", ")
+        );
+    }
 
     // Determine which groups to sync
     let groups_to_sync = if args.dev {
@@ -330,75 +1128,177 @@ pub fn run(args: SyncArgs) -> Result<(), KamError> {
         vec!["kam"]
     };
 
+    // Versions pinned by kam.lock take precedence; otherwise each id@spec is
+    // resolved once for the whole run. --prefer-latest re-resolves everything
+    // and the new choices are pinned when the lockfile is rewritten.
+    let lock_path = project_path.join(LOCK_FILE);
+    let lock = if args.prefer_latest {
+        None
+    } else if lock_path.exists() {
+        Some(KamLock::load_from_path(&lock_path)?)
+    } else if args.frozen {
+        return Err(KamError::Lockfile(format!(
+            "--frozen requires {} in {}",
+            LOCK_FILE,
+            project_path.display()
+        )));
+    } else {
+        None
+    };
+    let vendor_dir = args.vendor.clone().or_else(|| {
+        lock.as_ref()
+            .and_then(|l| l.vendor.clone())
+            .or_else(|| args.vendored.then(|| DEFAULT_VENDOR_DIR.to_string()))
+    });
+    let resolution = Resolution {
+        resolver: VersionResolver::new(&cache),
+        lock,
+        frozen: args.frozen,
+        prefer_latest: args.prefer_latest,
+        upgrade: args.upgrade.iter().cloned().collect(),
+        vendored: vendor_dir
+            .as_ref()
+            .filter(|_| args.vendored)
+            .map(|dir| project_path.join(dir)),
+        registries: kam_toml.registries(),
+        trust: kam_toml.kam.trust.clone().unwrap_or_default(),
+    };
+
+    // Fail before touching anything when the lockfile is out of date
+    if args.frozen {
+        for group_name in &groups_to_sync {
+            for dep in resolved
+                .get(group_name)
+                .iter()
+                .flat_map(|g| &g.dependencies)
+            {
+                resolution.version(dep)?;
+            }
+        }
+    }
+
     // Process each group
     let mut total_synced = 0;
-    for group_name in groups_to_sync {
+    // Every dependency synced in this run, recorded in kam.lock
+    let mut synced_deps: Vec<Dependency> = Vec::new();
+    // Dependencies left out because they don't run on --target; they stay
+    // pinned in kam.lock but are not linked or vendored
+    let mut skipped_deps: Vec<Dependency> = Vec::new();
+    // Dependencies requesting features of their target module
+    let mut with_features: Vec<Dependency> = Vec::new();
+    for group_name in groups_to_sync.iter().copied() {
         let group = match resolved.get(group_name) {
             Some(g) => g,
             None => continue,
         };
 
-        println!("{} {} dependencies:", "Syncing".bold(), group_name.yellow());
+        status!("{} {} dependencies:", "Syncing".bold(), group_name.yellow());
 
-        for dep in &group.dependencies {
-            // Use versionCode for dependency selection (fall back to 0 when absent)
-            let version_code = dep
-                .versionCode
-                .as_ref()
-                .map(|v| v.as_display())
-                .unwrap_or_else(|| "0".to_string());
-            println!(
-                "  {} {}@{}",
-                "→".cyan(),
-                dep.id.bold(),
-                version_code.dimmed()
-            );
-
-            // Delegate the (simulated) cache write to a helper to keep the
-            // loop body small and focused on presentation.
-            if ensure_module_synced(&cache, dep)? {
-                total_synced += 1;
+        let (deps, skipped) =
+            filter_target(&resolution, &group.dependencies, args.target.as_ref())?;
+        skipped_deps.extend(skipped);
+        let fetched = fetch_all(&resolution, &deps, args.jobs)?;
+        total_synced += fetched.iter().filter(|f| **f).count();
+        let (deps, skipped) = filter_target(&resolution, &deps, args.target.as_ref())?;
+        skipped_deps.extend(skipped);
+        // Nothing is linked into the venv while the modules conflict
+        check_conflicts(&resolution, &kam_toml, synced_deps.iter().chain(&deps))?;
+        for dep in &deps {
+            sync_dependency(&resolution, maybe_venv.as_ref(), dep)?;
+            synced_deps.push(dep.clone());
+            if !dep.requested_features().is_empty() {
+                with_features.push(dep.clone());
             }
+        }
 
-            // If a venv was requested, link the library into it
-            if let Some(venv) = &maybe_venv {
-                let ver = dep
-                    .versionCode
-                    .as_ref()
-                    .map(|v| v.as_display())
-                    .unwrap_or_else(|| "0".to_string());
-                match venv.link_library(&dep.id, &ver, &cache) {
-                    Ok(_) => println!("  {} Linked {}@{} into venv", "✓".green(), dep.id, ver),
-                    Err(e) => println!(
-                        "  {} Failed to link {}@{}: {}",
-                        "!".yellow(),
-                        dep.id,
-                        ver,
-                        e
-                    ),
-                }
+        status!();
+    }
 
-                // Link binaries
-                let lib_path = cache.lib_module_path(&dep.id, &ver);
-                if let Ok(entries) = std::fs::read_dir(lib_path.join("bin")) {
-                    for entry in entries.flatten() {
-                        if let Some(name_str) = entry.file_name().to_str() {
-                            match venv.link_binary(&entry.path()) {
-                                Ok(_) => println!("  {} Linked binary: {}", "✓".green(), name_str),
-                                Err(e) => println!(
-                                    "  {} Failed to link binary {}: {}",
-                                    "!".yellow(),
-                                    name_str,
-                                    e
-                                ),
-                            }
-                        }
-                    }
-                }
+    // Install the sub-dependencies gated by requested features. These may
+    // request features themselves, so keep going until nothing new appears.
+    let mut enabled: HashSet<(String, String)> = HashSet::new();
+    if !with_features.is_empty() {
+        status!("{} feature dependencies:", "Syncing".bold());
+    }
+    while let Some(dep) = with_features.pop() {
+        let features: Vec<String> = dep
+            .requested_features()
+            .iter()
+            .filter(|f| enabled.insert((dep.id.clone(), f.to_string())))
+            .cloned()
+            .collect();
+        if features.is_empty() {
+            continue;
+        }
+        let module_toml = cache
+            .lib_module_path(&dep.id, &resolution.version(&dep)?)
+            .join("kam.toml");
+        if !module_toml.exists() {
+            println!(
+                "  {} {} has no kam.toml in cache; cannot enable features [{}]",
+                "!".yellow(),
+                dep.id,
+                features.join(",")
+            );
+            continue;
+        }
+        let module = crate::types::kam_toml::KamToml::load_from_file(&module_toml)?;
+        let gated = module
+            .kam
+            .dependency
+            .unwrap_or_default()
+            .feature_dependencies(&dep.id, &features)?;
+        let (gated, skipped) = filter_target(&resolution, &gated, args.target.as_ref())?;
+        skipped_deps.extend(skipped);
+        let fetched = fetch_all(&resolution, &gated, args.jobs)?;
+        total_synced += fetched.iter().filter(|f| **f).count();
+        let (gated, skipped) = filter_target(&resolution, &gated, args.target.as_ref())?;
+        skipped_deps.extend(skipped);
+        check_conflicts(&resolution, &kam_toml, synced_deps.iter().chain(&gated))?;
+        for sub in gated {
+            sync_dependency(&resolution, maybe_venv.as_ref(), &sub)?;
+            synced_deps.push(sub.clone());
+            if !sub.requested_features().is_empty() {
+                with_features.push(sub);
             }
         }
+    }
 
-        println!();
+    // Peer dependencies of the synced modules are never installed for them;
+    // the root project has to provide a satisfying version itself.
+    let unsatisfied = kam_toml.unsatisfied_peers(&cache)?;
+    if !unsatisfied.is_empty() {
+        return Err(KamError::UnsatisfiedPeerDependencies(
+            unsatisfied
+                .iter()
+                .map(|p| format!("  - {}", p))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        ));
+    }
+
+    if let Some(dir) = &args.vendor {
+        vendor_modules(&resolution, &synced_deps, &project_path.join(dir))?;
+    }
+
+    if !args.frozen {
+        let locked: Vec<Dependency> = synced_deps.iter().chain(&skipped_deps).cloned().collect();
+        // Pins of the groups this run left out (`dev` without --dev) stay
+        let previous = match &resolution.lock {
+            Some(lock) => Some(lock.clone()),
+            None if lock_path.exists() => Some(KamLock::load_from_path(&lock_path)?),
+            None => None,
+        };
+        let unsynced: Vec<Dependency> = ["kam", "dev"]
+            .into_iter()
+            .filter(|group| !groups_to_sync.contains(group))
+            .filter_map(|group| resolved.get(group))
+            .flat_map(|group| group.dependencies.iter().cloned())
+            .collect();
+        let mut lock = build_lock(&resolution, &locked, previous.as_ref(), &unsynced)?;
+        lock.vendor = vendor_dir;
+        lock.write_to_path(&lock_path)?;
+        status!("  {} Wrote {}", "✓".green(), LOCK_FILE);
     }
 
     println!(
@@ -406,13 +1306,358 @@ pub fn run(args: SyncArgs) -> Result<(), KamError> {
         "✓".green().bold(),
         total_synced.to_string().green().bold()
     );
+    if let Some(target) = &args.target
+        && !skipped_deps.is_empty()
+    {
+        println!(
+            "  {} Skipped {} dependencies not built for {}",
+            "•".cyan(),
+            skipped_deps.len(),
+            target
+        );
+    }
 
     // Print activation instructions for the always-managed venv
-    println!();
-    println!("{} To activate the virtual environment:", "•".dimmed());
-    println!("  {}: source .kam_venv/activate", "Unix".yellow());
-    println!("  {}: .kam_venv\\activate.bat", "Windows".yellow());
-    println!("  {}: .kam_venv\\activate.ps1", "PowerShell".yellow());
+    status!();
+    status!("{} To activate the virtual environment:", "•".dimmed());
+    status!("  {}: source .kam_venv/activate", "Unix".yellow());
+    status!("  {}: .kam_venv\\activate.bat", "Windows".yellow());
+    status!("  {}: .kam_venv\\activate.ps1", "PowerShell".yellow());
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::{Digest, Sha256};
+
+    /// A zip package holding `kam_toml`
+    fn package(kam_toml: &str) -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        zip.start_file("kam.toml", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        std::io::Write::write_all(&mut zip, kam_toml.as_bytes()).unwrap();
+        zip.finish().unwrap().into_inner()
+    }
+
+    /// Install module `id` at version code `code` into `cache`, with `kam`
+    /// appended to the `[kam]` table of its `kam.toml`
+    fn cached_module(cache: &KamCache, id: &str, code: i64, kam: &str) -> Dependency {
+        let module = cache.lib_module_path(id, &code.to_string());
+        fs::create_dir_all(&module).unwrap();
+        let kam_toml = format!(
+            "[prop]\nid = \"{id}\"\nname = {{ en = \"{id}\" }}\nversion = \"1.0.0\"\n\
+             versionCode = {code}\nauthor = \"me\"\ndescription = {{ en = \"{id}\" }}\n\n\
+             [kam]\nmodule_type = \"kam\"\n{kam}\n"
+        );
+        fs::write(module.join("kam.toml"), kam_toml).unwrap();
+        Dependency {
+            versionCode: Some(VersionSpec::Exact(code)),
+            ..Dependency::new(id)
+        }
+    }
+
+    fn resolution(cache: &KamCache) -> Resolution<'_> {
+        Resolution {
+            resolver: VersionResolver::new(cache),
+            lock: None,
+            frozen: false,
+            prefer_latest: false,
+            upgrade: HashSet::new(),
+            vendored: None,
+            registries: Registries::new(None),
+            trust: TrustSection::default(),
+        }
+    }
+
+    #[test]
+    fn test_lock_keeps_pins_of_unsynced_groups() {
+        let cache_root = tempfile::tempdir().unwrap();
+        let cache = KamCache::with_root(cache_root.path()).unwrap();
+        let project = tempfile::tempdir().unwrap();
+        let lock_path = project.path().join(LOCK_FILE);
+        let dep = |id: &str, code: i64| {
+            let module = cache.lib_module_path(id, &code.to_string());
+            fs::create_dir_all(&module).unwrap();
+            fs::write(module.join("kam.toml"), id).unwrap();
+            Dependency {
+                versionCode: Some(VersionSpec::Exact(code)),
+                ..Dependency::new(id)
+            }
+        };
+        let core = dep("core", 100);
+        let bench = dep("bench", 5);
+
+        // `sync --dev` pins both groups
+        let lock = build_lock(
+            &resolution(&cache),
+            &[core.clone(), bench.clone()],
+            None,
+            &[],
+        )
+        .unwrap();
+        lock.write_to_path(&lock_path).unwrap();
+        let written = KamLock::load_from_path(&lock_path).unwrap();
+        assert_eq!(written.packages.len(), 2);
+        assert_eq!(written.find_package("bench").unwrap().version, "5");
+
+        // A plain sync re-resolves `kam` only and keeps the dev pin
+        let lock = build_lock(
+            &resolution(&cache),
+            std::slice::from_ref(&core),
+            Some(&written),
+            std::slice::from_ref(&bench),
+        )
+        .unwrap();
+        assert_eq!(lock, written);
+
+        // --frozen installs the pinned versions and rejects anything else
+        let frozen = Resolution {
+            lock: Some(written),
+            frozen: true,
+            ..resolution(&cache)
+        };
+        assert_eq!(frozen.version(&bench).unwrap(), "5");
+        let changed = Dependency {
+            versionCode: Some(VersionSpec::Exact(200)),
+            ..Dependency::new("core")
+        };
+        assert!(matches!(
+            frozen.version(&changed),
+            Err(KamError::Lockfile(_))
+        ));
+    }
+
+    #[test]
+    fn test_version_resolves_unbounded_specs_from_index() {
+        let cache_root = tempfile::tempdir().unwrap();
+        let cache = KamCache::with_root(cache_root.path()).unwrap();
+        let registry = tempfile::tempdir().unwrap();
+        let lib_index = compute_index_path(&registry.path().join("index"), "mylib");
+        fs::create_dir_all(&lib_index).unwrap();
+        for (version, code) in [("1.0.0", 100), ("1.5.0", 150), ("2.0.0", 200)] {
+            let meta = serde_json::json!({ "version": version, "versionCode": code });
+            fs::write(
+                lib_index.join(format!("{}.json", version)),
+                meta.to_string(),
+            )
+            .unwrap();
+        }
+        let run = resolution(&cache);
+        let dep = |spec: &str| Dependency {
+            versionCode: Some(VersionSpec::parse(spec)),
+            source: Some(registry.path().to_str().unwrap().to_string()),
+            ..Dependency::new("mylib")
+        };
+
+        assert_eq!(run.version(&dep("^1.0")).unwrap(), "150");
+        assert_eq!(run.version(&dep("(,120]")).unwrap(), "100");
+        assert!(matches!(
+            run.version(&dep("^3")),
+            Err(KamError::DependencyResolutionFailed(_))
+        ));
+
+        // The local index is consulted first
+        let local = compute_index_path(&cache.root().join("index"), "mylib");
+        fs::create_dir_all(&local).unwrap();
+        let meta = serde_json::json!({ "version": "1.9.0", "versionCode": 190 });
+        fs::write(local.join("1.9.0.json"), meta.to_string()).unwrap();
+        assert_eq!(resolution(&cache).version(&dep("^1.0")).unwrap(), "190");
+    }
+
+    #[test]
+    fn test_fetch_module_rejects_tampered_package() {
+        let cache_root = tempfile::tempdir().unwrap();
+        let cache = KamCache::with_root(cache_root.path()).unwrap();
+        let index = compute_index_path(Path::new("/index"), "mylib");
+        let meta = serde_json::json!({
+            "version": "1.0.0",
+            "versionCode": 100,
+            "sha256": "0".repeat(64),
+        });
+        let registry = crate::utils::serve_files(vec![
+            (
+                format!("{}/latest.json", index.display()),
+                meta.to_string().into_bytes(),
+            ),
+            ("/mylib-100.zip".to_string(), b"tampered".to_vec()),
+        ]);
+        let dep = Dependency {
+            source: Some(registry),
+            ..Dependency::new("mylib")
+        };
+
+        let module_path = cache.lib_module_path("mylib", "100");
+        let result = fetch_module(
+            &cache,
+            &dep,
+            "100",
+            &module_path,
+            &Registries::new(None),
+            None,
+        );
+        assert!(matches!(result, Err(KamError::ChecksumMismatch(_))));
+        assert!(!module_path.exists());
+    }
+
+    #[test]
+    fn test_fetch_module_extracts_verified_package() {
+        let cache_root = tempfile::tempdir().unwrap();
+        let cache = KamCache::with_root(cache_root.path()).unwrap();
+        let package = package("[prop]\nid = \"mylib\"\n");
+        let sha256 = format!("{:x}", Sha256::digest(&package));
+        let index = compute_index_path(Path::new("/index"), "mylib");
+        let meta = serde_json::json!({
+            "version": "1.0.0",
+            "versionCode": 100,
+            "sha256": sha256,
+        });
+        let registry = crate::utils::serve_files(vec![
+            (
+                format!("{}/latest.json", index.display()),
+                meta.to_string().into_bytes(),
+            ),
+            ("/mylib-100.zip".to_string(), package),
+        ]);
+        let dep = Dependency {
+            source: Some(registry),
+            ..Dependency::new("mylib")
+        };
+
+        let module_path = cache.lib_module_path("mylib", "100");
+        fs::create_dir_all(&module_path).unwrap();
+        fetch_module(
+            &cache,
+            &dep,
+            "100",
+            &module_path,
+            &Registries::new(None),
+            None,
+        )
+        .unwrap();
+        assert!(module_path.join("kam.toml").is_file());
+        assert!(module_path.join(".synced").is_file());
+        // Nothing else is installed into the cache
+        assert_eq!(fs::read_dir(cache.lib_dir()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_frozen_sync_rejects_changed_package() {
+        let dep = |registry: String| Dependency {
+            versionCode: Some(VersionSpec::Exact(100)),
+            source: Some(registry),
+            ..Dependency::new("mylib")
+        };
+        let registries = Registries::new(None);
+
+        // The lock records the sha256 of the fetched package
+        let cache_root = tempfile::tempdir().unwrap();
+        let cache = KamCache::with_root(cache_root.path()).unwrap();
+        let original = package("[prop]\nid = \"mylib\"\n");
+        let locked = dep(crate::utils::serve_files(vec![(
+            "/mylib-100.zip".to_string(),
+            original.clone(),
+        )]));
+        ensure_module_synced(&cache, &locked, "100", None, &registries, None).unwrap();
+        let lock = build_lock(
+            &resolution(&cache),
+            std::slice::from_ref(&locked),
+            None,
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            lock.packages[0].checksum,
+            Some(format!("sha256:{:x}", Sha256::digest(&original)))
+        );
+        let frozen = |cache| Resolution {
+            lock: Some(lock.clone()),
+            frozen: true,
+            ..resolution(cache)
+        };
+        sync_dependency(&frozen(&cache), None, &locked).unwrap();
+
+        // Another machine fetching changed contents for the same version
+        let other_root = tempfile::tempdir().unwrap();
+        let other = KamCache::with_root(other_root.path()).unwrap();
+        let changed = dep(crate::utils::serve_files(vec![(
+            "/mylib-100.zip".to_string(),
+            package("[prop]\nid = \"mylib\"\n# changed\n"),
+        )]));
+        ensure_module_synced(&other, &changed, "100", None, &registries, None).unwrap();
+        assert!(matches!(
+            sync_dependency(&frozen(&other), None, &changed),
+            Err(KamError::Lockfile(_))
+        ));
+    }
+
+    #[test]
+    fn test_check_conflicts_names_both_modules() {
+        let cache_root = tempfile::tempdir().unwrap();
+        let cache = KamCache::with_root(cache_root.path()).unwrap();
+        let mut root = crate::types::kam_toml::KamToml::default();
+        root.prop.id = "app".to_string();
+        let a = cached_module(&cache, "a", 1, "conflicts = [\"b\"]");
+        let b = cached_module(&cache, "b", 1, "");
+        let c = cached_module(&cache, "c", 1, "conflicts = [\"missing\"]");
+
+        check_conflicts(&resolution(&cache), &root, [&a, &c]).unwrap();
+        match check_conflicts(&resolution(&cache), &root, [&a, &b, &c]) {
+            Err(KamError::ConflictingModules(found)) => {
+                assert_eq!(found, "'a' conflicts with 'b'")
+            }
+            other => panic!("expected a conflict, got {:?}", other),
+        }
+
+        // The root project's own conflicts count too
+        root.kam.conflicts = Some(vec!["c".to_string()]);
+        assert!(matches!(
+            check_conflicts(&resolution(&cache), &root, [&c]),
+            Err(KamError::ConflictingModules(found)) if found == "'app' conflicts with 'c'"
+        ));
+    }
+
+    #[test]
+    fn test_filter_target_before_fetching() {
+        let cache_root = tempfile::tempdir().unwrap();
+        let cache = KamCache::with_root(cache_root.path()).unwrap();
+        let any = cached_module(&cache, "any", 1, "");
+        let arm = cached_module(&cache, "arm", 1, "supported_arch = [\"aarch64\"]");
+        let x86 = cached_module(&cache, "x86", 1, "supported_arch = [\"x86_64\"]");
+        // Not cached yet: the local index entry names the architectures, and
+        // without one the module is kept until it is fetched
+        let registry = tempfile::tempdir().unwrap();
+        let uncached = |id: &str, arch: Option<&str>| {
+            if let Some(arch) = arch {
+                let index = compute_index_path(&cache.root().join("index"), id);
+                fs::create_dir_all(&index).unwrap();
+                let meta = serde_json::json!({
+                    "version": "1.0.0",
+                    "versionCode": 1,
+                    "supported_arch": [arch],
+                });
+                fs::write(index.join("1.0.0.json"), meta.to_string()).unwrap();
+            }
+            Dependency {
+                versionCode: Some(VersionSpec::Exact(1)),
+                source: Some(registry.path().to_str().unwrap().to_string()),
+                ..Dependency::new(id)
+            }
+        };
+        let indexed_arm = uncached("indexed-arm", Some("arm64-v8a"));
+        let indexed_x86 = uncached("indexed-x86", Some("x86"));
+        let unknown = uncached("unknown", None);
+        let deps = vec![any, arm, x86, indexed_arm, indexed_x86, unknown];
+        let ids = |deps: &[Dependency]| deps.iter().map(|d| d.id.clone()).collect::<Vec<_>>();
+
+        let (kept, skipped) = filter_target(&resolution(&cache), &deps, None).unwrap();
+        assert_eq!(kept, deps);
+        assert!(skipped.is_empty());
+
+        let target = SupportedArch::parse("aarch64");
+        let (kept, skipped) = filter_target(&resolution(&cache), &deps, Some(&target)).unwrap();
+        assert_eq!(ids(&kept), ["any", "arm", "indexed-arm", "unknown"]);
+        assert_eq!(ids(&skipped), ["x86", "indexed-x86"]);
+    }
+}
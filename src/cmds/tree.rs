@@ -0,0 +1,279 @@
+use crate::cache::{KamCache, VersionResolver};
+use crate::errors::KamError;
+use crate::types::kam_toml::KamToml;
+use crate::types::kam_toml::sections::dependency::Dependency;
+use crate::types::kam_toml::sections::{DependencySection, parse_include};
+/// # Kam Tree Command
+///
+/// Print the dependency graph of a project: each group (kam/dev) with its
+/// `include:` expansions and the transitive runtime dependencies read from
+/// the cached modules' own `kam.toml`.
+///
+/// ## Example
+///
+/// ```bash
+/// kam tree
+/// kam tree --depth 1
+/// kam tree --duplicates
+/// ```
+use clap::Args;
+use colored::Colorize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Arguments for the tree command
+#[derive(Args, Debug)]
+pub struct TreeArgs {
+    /// Path to the project (default: current directory)
+    #[arg(default_value = ".")]
+    pub path: String,
+
+    /// Maximum depth of transitive dependencies to show (0: direct only)
+    #[arg(long, value_name = "N")]
+    pub depth: Option<usize>,
+
+    /// Highlight modules that appear more than once in the tree
+    #[arg(long)]
+    pub duplicates: bool,
+}
+
+/// A line of the tree and the lines nested below it
+pub(crate) struct Node {
+    pub(crate) label: String,
+    /// Module id, for duplicate detection (`None` for include nodes)
+    pub(crate) id: Option<String>,
+    pub(crate) note: Option<String>,
+    pub(crate) children: Vec<Node>,
+}
+
+/// Run the tree command
+pub fn run(args: TreeArgs) -> Result<(), KamError> {
+    let project_path = Path::new(&args.path);
+    let kam_toml = KamToml::load_from_dir(project_path)?;
+    let cache = KamCache::new()?;
+    let groups = dependency_groups(&kam_toml, &cache, args.depth);
+
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for (_, children) in &groups {
+        count_ids(children, &mut counts);
+    }
+
+    println!("{}@{}", kam_toml.prop.id.bold(), kam_toml.prop.versionCode);
+    for (group, children) in &groups {
+        println!("{}", format!("[{}]", group).yellow());
+        if children.is_empty() {
+            println!("  {}", "(none)".dimmed());
+        }
+        print_nodes(children, "  ", &counts, args.duplicates);
+    }
+
+    if args.duplicates {
+        let duplicated: Vec<String> = counts
+            .iter()
+            .filter(|(_, n)| **n > 1)
+            .map(|(id, n)| format!("{} (x{})", id, n))
+            .collect();
+        println!();
+        if duplicated.is_empty() {
+            println!("{} No duplicate modules", "✓".green());
+        } else {
+            println!("{} Duplicates: {}", "!".yellow(), duplicated.join(", "));
+        }
+    }
+    Ok(())
+}
+
+/// The project's dependency groups (kam, dev) as trees, with `include:`
+/// entries expanded and the transitive dependencies read from the cached
+/// modules, down to `max_depth` levels
+pub(crate) fn dependency_groups(
+    kam_toml: &KamToml,
+    cache: &KamCache,
+    max_depth: Option<usize>,
+) -> Vec<(&'static str, Vec<Node>)> {
+    let builder = TreeBuilder {
+        cache,
+        resolver: VersionResolver::new(cache),
+        max_depth,
+    };
+    let section = kam_toml.kam.dependency.clone().unwrap_or_default();
+    ["kam", "dev"]
+        .into_iter()
+        .map(|group| {
+            let mut ancestors = vec![kam_toml.prop.id.clone()];
+            let children = builder.group(&section, None, group, &mut Vec::new(), &mut ancestors, 0);
+            (group, children)
+        })
+        .collect()
+}
+
+struct TreeBuilder<'a> {
+    cache: &'a KamCache,
+    resolver: VersionResolver<'a>,
+    max_depth: Option<usize>,
+}
+
+impl TreeBuilder<'_> {
+    /// Nodes for the dependencies of `group` in `section` (of module
+    /// `owner`, `None` for the project), expanding `include:` entries.
+    /// `groups` holds the groups being expanded, to stop at include cycles;
+    /// `ancestors` the module ids above this level.
+    fn group(
+        &self,
+        section: &DependencySection,
+        owner: Option<&str>,
+        group: &str,
+        groups: &mut Vec<String>,
+        ancestors: &mut Vec<String>,
+        depth: usize,
+    ) -> Vec<Node> {
+        let group_key = |owner: Option<&str>, group: &str| match owner {
+            Some(id) => format!("{}#{}", id, group),
+            None => group.to_string(),
+        };
+        groups.push(group_key(owner, group));
+        let mut nodes = Vec::new();
+        for dep in section.group(group).unwrap_or_default() {
+            if let Some(included) = dep.id.strip_prefix("include:") {
+                let label = format!("include:{}", included);
+                let (module, included_group) = parse_include(included);
+                let included_owner = module.or(owner);
+                let node = if groups.contains(&group_key(included_owner, included_group)) {
+                    leaf(label, None, Some("(cycle)".to_string()))
+                } else if section.group(included_group).is_none() {
+                    leaf(label, None, Some("(unknown group)".to_string()))
+                } else {
+                    let external = module
+                        .map(|id| KamToml::cached_dependency_section(self.cache, id))
+                        .transpose();
+                    match external {
+                        Ok(external) => Node {
+                            label,
+                            id: None,
+                            note: None,
+                            children: self.group(
+                                external.as_ref().unwrap_or(section),
+                                included_owner,
+                                included_group,
+                                groups,
+                                ancestors,
+                                depth,
+                            ),
+                        },
+                        Err(_) => leaf(label, None, Some("(not cached)".to_string())),
+                    }
+                };
+                nodes.push(node);
+            } else {
+                nodes.push(self.module(dep, ancestors, depth));
+            }
+        }
+        groups.pop();
+        nodes
+    }
+
+    /// Node for a module dependency and its own runtime dependencies
+    fn module(&self, dep: &Dependency, ancestors: &mut Vec<String>, depth: usize) -> Node {
+        // Without a requirement, show the newest cached version
+        let code = match &dep.versionCode {
+            Some(spec) => self.resolver.resolve(&dep.id, Some(spec)),
+            None => self.resolver.cached_versions(&dep.id).last().copied(),
+        };
+        let version = match (code, &dep.versionCode) {
+            (Some(code), _) => code.to_string(),
+            (None, Some(spec)) => spec.as_display(),
+            (None, None) => "*".to_string(),
+        };
+        let label = format!("{}@{}", dep.id, version);
+        let id = Some(dep.id.clone());
+
+        if ancestors.contains(&dep.id) {
+            return leaf(label, id, Some("(cycle)".to_string()));
+        }
+        let module_toml = code.map(|code| {
+            self.cache
+                .lib_module_path(&dep.id, &code.to_string())
+                .join("kam.toml")
+        });
+        let Some(module_toml) = module_toml.filter(|p| p.exists()) else {
+            let note = if code.is_some_and(|code| {
+                self.cache
+                    .lib_module_path(&dep.id, &code.to_string())
+                    .exists()
+            }) {
+                "(no kam.toml)"
+            } else {
+                "(not cached)"
+            };
+            return leaf(label, id, Some(note.to_string()));
+        };
+        if self.max_depth.is_some_and(|max| depth >= max) {
+            return leaf(label, id, None);
+        }
+        let module = match KamToml::load_from_file(&module_toml) {
+            Ok(module) => module,
+            Err(e) => return leaf(label, id, Some(format!("(unreadable kam.toml: {})", e))),
+        };
+
+        let section = module.kam.dependency.unwrap_or_default();
+        ancestors.push(dep.id.clone());
+        let children = self.group(
+            &section,
+            Some(&dep.id),
+            "kam",
+            &mut Vec::new(),
+            ancestors,
+            depth + 1,
+        );
+        ancestors.pop();
+        Node {
+            label,
+            id,
+            note: None,
+            children,
+        }
+    }
+}
+
+fn leaf(label: String, id: Option<String>, note: Option<String>) -> Node {
+    Node {
+        label,
+        id,
+        note,
+        children: Vec::new(),
+    }
+}
+
+fn count_ids(nodes: &[Node], counts: &mut BTreeMap<String, usize>) {
+    for node in nodes {
+        if let Some(id) = &node.id {
+            *counts.entry(id.clone()).or_default() += 1;
+        }
+        count_ids(&node.children, counts);
+    }
+}
+
+fn print_nodes(nodes: &[Node], prefix: &str, counts: &BTreeMap<String, usize>, duplicates: bool) {
+    for (i, node) in nodes.iter().enumerate() {
+        let last = i + 1 == nodes.len();
+        let branch = if last { "└── " } else { "├── " };
+        let duplicated = duplicates
+            && node
+                .id
+                .as_ref()
+                .is_some_and(|id| counts.get(id).copied().unwrap_or(0) > 1);
+        let label = if node.id.is_none() {
+            node.label.cyan().to_string()
+        } else if duplicated {
+            node.label.yellow().bold().to_string()
+        } else {
+            node.label.clone()
+        };
+        match &node.note {
+            Some(note) => println!("{}{}{} {}", prefix, branch, label, note.dimmed()),
+            None => println!("{}{}{}", prefix, branch, label),
+        }
+        let child_prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+        print_nodes(&node.children, &child_prefix, counts, duplicates);
+    }
+}
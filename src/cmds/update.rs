@@ -0,0 +1,211 @@
+use crate::cache::KamCache;
+use crate::cmds::sync::{SyncArgs, newest_version};
+use crate::errors::KamError;
+use crate::status;
+use crate::types::kam_lock::KamLock;
+use crate::types::kam_toml::KamToml;
+use crate::types::kam_toml::sections::{Dependency, VersionSpec};
+use crate::types::source::Source;
+/// # Kam Update Command
+///
+/// Move dependencies to the newest available versions. Exact `versionCode`
+/// pins in `kam.toml` are bumped; ranges and semver requirements are
+/// re-resolved and re-pinned in `kam.lock`. The cache and venv are then
+/// synced and a before/after table of the changed versions is printed.
+///
+/// With a library id only that dependency is updated; everything else
+/// stays at its pinned version.
+///
+/// ## Example
+///
+/// ```bash
+/// kam update
+/// kam update mylib
+/// kam update --dev
+/// ```
+use clap::Args;
+use colored::Colorize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Arguments for the update command
+#[derive(Args, Debug)]
+pub struct UpdateArgs {
+    /// Only update this dependency (by id)
+    pub library: Option<String>,
+
+    /// Path to the project (default: current directory)
+    #[arg(short, long, default_value = ".")]
+    pub path: String,
+
+    /// Also update dev dependencies
+    #[arg(long)]
+    pub dev: bool,
+
+    /// Number of dependencies to download concurrently
+    #[arg(short, long, default_value_t = crate::cmds::sync::DEFAULT_JOBS)]
+    pub jobs: usize,
+}
+
+/// Run the update command
+pub fn run(args: UpdateArgs) -> Result<(), KamError> {
+    let project_path = Path::new(&args.path);
+    let mut kam_toml = KamToml::load_from_dir(project_path)?;
+    let registries = kam_toml.registries();
+    let cache = KamCache::new()?;
+    cache.ensure_dirs()?;
+
+    let section = kam_toml.kam.dependency.clone().unwrap_or_default();
+    let mut groups = vec![section.kam.unwrap_or_default()];
+    if args.dev {
+        groups.push(section.dev.unwrap_or_default());
+    }
+    let targets: Vec<Dependency> = groups
+        .iter()
+        .flatten()
+        .filter(|d| args.library.as_ref().is_none_or(|id| d.id == *id))
+        .cloned()
+        .collect();
+    if let Some(id) = &args.library
+        && targets.is_empty()
+    {
+        let group = if args.dev {
+            "runtime or dev"
+        } else {
+            "runtime"
+        };
+        return Err(KamError::LibraryNotFound(format!(
+            "'{}' is not a {} dependency of {}",
+            id,
+            group,
+            project_path.display()
+        )));
+    }
+
+    let lock_path = project_path.join("kam.lock");
+    let before = pinned_versions(&lock_path)?;
+
+    // Look for newer releases first, so the newest ones are in the index
+    // (or the cache) when the versions are chosen
+    status!("{} Checking for newer versions...", "→".cyan());
+    if !crate::utils::is_offline() {
+        for dep in &targets {
+            let source = registries.source_for(dep)?;
+            if matches!(Source::parse(&source), Ok(Source::Git { .. })) {
+                status!(
+                    "  {} {} is a git dependency; its revision is kept",
+                    "•".dimmed(),
+                    dep.id
+                );
+                continue;
+            }
+            let repo = (dep.source.is_some() || dep.registry.is_some()).then_some(source.as_str());
+            let public_key = kam_toml.trusted_key(&dep.id);
+            if let Err(e) = crate::cmds::add::fetch_library(
+                &cache,
+                &dep.id,
+                "latest",
+                repo,
+                &registries,
+                public_key,
+                false,
+            ) {
+                println!(
+                    "  {} Could not check {} for a newer version: {}",
+                    "!".yellow(),
+                    dep.id,
+                    e
+                );
+            }
+        }
+    }
+
+    // Exact pins are bumped in kam.toml, which makes their lock entries stale
+    let mut bumped = false;
+    let section = kam_toml.kam.dependency.get_or_insert_with(Default::default);
+    for deps in [Some(&mut section.kam), args.dev.then_some(&mut section.dev)]
+        .into_iter()
+        .flatten()
+        .flatten()
+    {
+        for dep in deps
+            .iter_mut()
+            .filter(|d| args.library.as_ref().is_none_or(|id| d.id == *id))
+        {
+            let Some(VersionSpec::Exact(current)) = dep.versionCode else {
+                continue;
+            };
+            if let Some(newest) = newest_version(&cache, &dep.id, None)
+                && newest > current
+            {
+                dep.versionCode = Some(VersionSpec::Exact(newest));
+                bumped = true;
+            }
+        }
+    }
+    if bumped {
+        kam_toml.write_dependencies_to_dir(project_path)?;
+        status!("  {} Updated kam.toml", "✓".green());
+    }
+
+    crate::cmds::sync::run(SyncArgs {
+        path: args.path.clone(),
+        dev: args.dev,
+        frozen: false,
+        prefer_cached: false,
+        prefer_latest: false,
+        vendor: None,
+        vendored: false,
+        jobs: args.jobs,
+        features: Vec::new(),
+        with: Vec::new(),
+        target: None,
+        strict: false,
+        upgrade: targets.iter().map(|d| d.id.clone()).collect(),
+    })?;
+
+    let after = pinned_versions(&lock_path)?;
+    let changed: Vec<(&String, Option<&String>, &String)> = after
+        .iter()
+        .filter(|(id, _)| targets.iter().any(|d| d.id == **id))
+        .filter(|(id, version)| before.get(*id) != Some(*version))
+        .map(|(id, version)| (id, before.get(id), version))
+        .collect();
+    status!();
+    if changed.is_empty() {
+        println!("{} All dependencies are up to date", "✓".green().bold());
+        return Ok(());
+    }
+    let width = changed
+        .iter()
+        .map(|(id, _, _)| id.len())
+        .fold("Library".len(), usize::max);
+    println!("{}", "Updated dependencies:".bold());
+    println!("  {:<width$}  {:>10}  {:>10}", "Library", "Before", "After");
+    for (id, old, new) in changed {
+        println!(
+            "  {:<width$}  {:>10}  {}",
+            id,
+            old.map(String::as_str).unwrap_or("-"),
+            format!("{:>10}", new).green()
+        );
+    }
+    Ok(())
+}
+
+/// Version codes pinned by `kam.lock`, by library id (the highest when an id
+/// is locked for several requirements)
+fn pinned_versions(lock_path: &Path) -> Result<BTreeMap<String, String>, KamError> {
+    let mut pinned: BTreeMap<String, String> = BTreeMap::new();
+    if !lock_path.exists() {
+        return Ok(pinned);
+    }
+    for package in KamLock::load_from_path(lock_path)?.packages {
+        let code = |v: &str| v.parse::<i64>().unwrap_or(0);
+        let entry = pinned.entry(package.name).or_default();
+        if entry.is_empty() || code(&package.version) > code(entry) {
+            *entry = package.version;
+        }
+    }
+    Ok(pinned)
+}
@@ -4,7 +4,8 @@ use std::path::Path;
 
 use crate::cache::KamCache;
 use crate::errors::KamError;
-use crate::venv::{KamVenv, VenvType};
+use crate::status;
+use crate::venv::{KamVenv, VenvLink, VenvType};
 
 /// Arguments for the venv command
 #[derive(Args, Debug)]
@@ -37,9 +38,27 @@ pub enum VenvCommands {
         yes: bool,
     },
 
+    /// Remove the virtual environment and create an empty one in its place
+    /// (run `kam sync` to link the dependencies again)
+    Recreate {
+        /// Create as development venv (includes dev deps)
+        #[arg(long)]
+        dev: bool,
+    },
+
+    /// Show the type and location of the virtual environment
+    Status,
+
     /// Show info about the virtual environment
     Info,
 
+    /// List the binaries and libraries linked into the venv
+    List,
+
+    /// Re-point the venv's links at the current cache after moving the
+    /// project or the cache
+    Relocate,
+
     /// Print activation instructions
     Activate,
 
@@ -70,7 +89,7 @@ pub fn run(args: VenvArgs) -> Result<(), KamError> {
         Some(VenvCommands::Create { dev, force }) => {
             if venv_path.exists() {
                 if force {
-                    std::fs::remove_dir_all(&venv_path)?;
+                    KamVenv::load(&venv_path)?.remove()?;
                 } else {
                     return Err(KamError::VenvExists(format!(
                         "Virtual environment already exists at {}. Use --force to recreate.",
@@ -78,25 +97,42 @@ pub fn run(args: VenvArgs) -> Result<(), KamError> {
                     )));
                 }
             }
+            create_venv(&venv_path, dev)
+        }
 
-            println!("{} Creating virtual environment...", "→".cyan());
-            let venv_type = if dev {
-                VenvType::Development
-            } else {
-                VenvType::Runtime
-            };
-            let venv = KamVenv::create(&venv_path, venv_type)
-                .map_err(|e| KamError::VenvCreateFailed(format!("Venv create failed: {}", e)))?;
-            println!("  {} Created at: {}", "✓".green(), venv.root().display());
+        Some(VenvCommands::Recreate { dev }) => {
+            if venv_path.exists() {
+                let venv = KamVenv::load(&venv_path)?;
+                println!(
+                    "{} Removing {} venv at {}",
+                    "→".cyan(),
+                    venv.venv_type(),
+                    venv.root().display()
+                );
+                venv.remove()?;
+            }
+            create_venv(&venv_path, dev)?;
             println!();
-            println!("To activate the virtual environment:");
             println!(
-                "  {}: source {}/activate",
-                "Unix".yellow(),
-                venv.root().display()
+                "  {} The venv is empty; run `kam sync` to link the dependencies",
+                "i".cyan()
             );
-            println!("  {}: {0}\\activate.bat", "Windows".yellow(),);
-            println!("  {}: {0}\\activate.ps1", "PowerShell".yellow());
+            Ok(())
+        }
+
+        Some(VenvCommands::Status) => {
+            if !venv_path.exists() {
+                println!(
+                    "{} No virtual environment at {}",
+                    "!".yellow(),
+                    venv_path.display()
+                );
+                return Ok(());
+            }
+            let venv = KamVenv::load(&venv_path)?;
+            println!("{} {}", "Root:".bold(), venv.root().display());
+            println!("{} {}", "Type:".bold(), venv.venv_type());
+            println!("{} {}", "Links:".bold(), venv.link_mode());
             Ok(())
         }
 
@@ -127,7 +163,7 @@ pub fn run(args: VenvArgs) -> Result<(), KamError> {
                 }
             }
 
-            std::fs::remove_dir_all(&venv_path)?;
+            KamVenv::load(&venv_path)?.remove()?;
             println!(
                 "{} Removed virtual environment at {}",
                 "✓".green(),
@@ -173,6 +209,87 @@ pub fn run(args: VenvArgs) -> Result<(), KamError> {
             Ok(())
         }
 
+        Some(VenvCommands::List) => {
+            if !venv_path.exists() {
+                return Err(KamError::VenvNotFound(format!(
+                    "Virtual environment not found at {}",
+                    venv_path.display()
+                )));
+            }
+
+            let venv = KamVenv::load(&venv_path)?;
+            let mut dangling = 0;
+
+            println!("{}", "Binaries (bin/):".bold());
+            dangling += print_links(&venv.bin_links());
+
+            println!();
+            match venv.lib_target() {
+                Some(target) if venv.lib_dir().exists() => {
+                    println!("{} -> {}", "Libraries (lib/)".bold(), target.display());
+                }
+                Some(target) => {
+                    dangling += 1;
+                    println!(
+                        "{} -> {}",
+                        "Libraries (lib/)".bold(),
+                        format!("{} (missing)", target.display()).red()
+                    );
+                }
+                None => println!("{}", "Libraries (lib/):".bold()),
+            }
+            dangling += print_links(&venv.lib_links());
+
+            if dangling > 0 {
+                println!();
+                println!(
+                    "{} {} dangling link(s); run `kam sync` to relink from the cache",
+                    "!".yellow(),
+                    dangling
+                );
+            }
+            Ok(())
+        }
+
+        Some(VenvCommands::Relocate) => {
+            if !venv_path.exists() {
+                return Err(KamError::VenvNotFound(format!(
+                    "Virtual environment not found at {}",
+                    venv_path.display()
+                )));
+            }
+
+            let cache = KamCache::new()?;
+            let venv = KamVenv::load(&venv_path)?;
+            status!(
+                "{} Relocating {} to cache {}",
+                "→".cyan(),
+                venv.root().display(),
+                cache.root().display()
+            );
+            let report = venv.relocate(&cache)?;
+            for (name, source) in &report.fixed {
+                status!("  {} {} -> {}", "✓".green(), name, source.display());
+            }
+            for name in &report.unresolved {
+                println!("  {} {} {}", "✗".red(), name, "(not in cache)".red());
+            }
+            println!(
+                "{} {} fixed, {} already correct, {} unresolved",
+                "Summary:".bold(),
+                report.fixed.len(),
+                report.unchanged,
+                report.unresolved.len()
+            );
+            if !report.unresolved.is_empty() {
+                println!(
+                    "  {} Run `kam sync` to fetch the missing modules and relink them",
+                    "!".yellow()
+                );
+            }
+            Ok(())
+        }
+
         Some(VenvCommands::Activate) => {
             println!("To activate the virtual environment:");
             println!("  Unix: source .kam_venv/activate");
@@ -227,7 +344,7 @@ pub fn run(args: VenvArgs) -> Result<(), KamError> {
         None => {
             // Default behaviour for `kam venv` with no subcommand:
             // Ensure virtual environment exists, sync dependencies, and print activation instructions.
-            println!(
+            status!(
                 "{} Ensuring virtual environment and synchronizing dependencies...",
                 "→".cyan()
             );
@@ -235,6 +352,17 @@ pub fn run(args: VenvArgs) -> Result<(), KamError> {
             let sync_args = crate::cmds::sync::SyncArgs {
                 path: args.path.clone(),
                 dev: false,
+                frozen: false,
+                prefer_cached: false,
+                prefer_latest: false,
+                vendor: None,
+                vendored: false,
+                jobs: crate::cmds::sync::DEFAULT_JOBS,
+                features: Vec::new(),
+                with: Vec::new(),
+                target: None,
+                strict: false,
+                upgrade: Vec::new(),
             };
             crate::cmds::sync::run(sync_args)?;
             // After sync/run, activation hints are printed by sync when appropriate.
@@ -242,3 +370,75 @@ pub fn run(args: VenvArgs) -> Result<(), KamError> {
         }
     }
 }
+
+/// Create a venv at `venv_path` and print how to activate it
+fn create_venv(venv_path: &Path, dev: bool) -> Result<(), KamError> {
+    status!("{} Creating virtual environment...", "→".cyan());
+    let venv_type = if dev {
+        VenvType::Development
+    } else {
+        VenvType::Runtime
+    };
+    let venv = KamVenv::create(venv_path, venv_type)
+        .map_err(|e| KamError::VenvCreateFailed(format!("Venv create failed: {}", e)))?;
+    println!(
+        "  {} Created {} venv at: {}",
+        "✓".green(),
+        venv_type,
+        venv.root().display()
+    );
+    venv.warn_if_copying();
+    status!();
+    status!("To activate the virtual environment:");
+    status!(
+        "  {}: source {}/activate",
+        "Unix".yellow(),
+        venv.root().display()
+    );
+    status!(
+        "  {}: {}\\activate.bat",
+        "Windows".yellow(),
+        venv.root().display()
+    );
+    status!(
+        "  {}: {}\\activate.ps1",
+        "PowerShell".yellow(),
+        venv.root().display()
+    );
+    Ok(())
+}
+
+/// Print `name  source  status` rows for venv entries; returns the number
+/// of dangling links
+fn print_links(links: &[VenvLink]) -> usize {
+    if links.is_empty() {
+        println!("  {}", "(none)".dimmed());
+        return 0;
+    }
+    let sources: Vec<String> = links
+        .iter()
+        .map(|link| match &link.target {
+            Some(target) => target.display().to_string(),
+            None => "copied".to_string(),
+        })
+        .collect();
+    let name_width = links.iter().map(|l| l.name.len()).max().unwrap_or(0);
+    let source_width = sources.iter().map(|s| s.len()).max().unwrap_or(0);
+    let mut dangling = 0;
+    for (link, source) in links.iter().zip(&sources) {
+        let row = format!(
+            "  {:<name_width$}  {:<source_width$}",
+            link.name,
+            source,
+            name_width = name_width,
+            source_width = source_width
+        );
+        if link.exists {
+            println!("{}  {}", row, "ok".green());
+        } else {
+            dangling += 1;
+            println!("{}", format!("{}  missing", row).red());
+        }
+    }
+    dangling
+}
@@ -0,0 +1,129 @@
+use crate::cache::KamCache;
+use crate::cmds::tree::{Node, dependency_groups};
+use crate::errors::KamError;
+use crate::types::kam_toml::KamToml;
+/// # Kam Why Command
+///
+/// Explain why a module is in a project's dependency graph: print every
+/// path from the project's declared dependencies (and the `include:` groups
+/// on the way) down to the module. The graph is the one `kam tree` shows.
+///
+/// ## Example
+///
+/// ```bash
+/// kam why libfoo
+/// kam why libfoo --path ../my-module
+/// ```
+use clap::Args;
+use colored::Colorize;
+use std::path::Path;
+
+/// Arguments for the why command
+#[derive(Args, Debug)]
+pub struct WhyArgs {
+    /// Module id to explain
+    pub id: String,
+
+    /// Path to the project (default: current directory)
+    #[arg(short, long, default_value = ".")]
+    pub path: String,
+}
+
+/// Run the why command
+pub fn run(args: WhyArgs) -> Result<(), KamError> {
+    let project_path = Path::new(&args.path);
+    let kam_toml = KamToml::load_from_dir(project_path)?;
+    let cache = KamCache::new()?;
+
+    let mut paths = Vec::new();
+    for (group, nodes) in dependency_groups(&kam_toml, &cache, None) {
+        let mut path = vec![format!("{} [{}]", kam_toml.prop.id, group)];
+        find_paths(&nodes, &args.id, &mut path, &mut paths);
+    }
+
+    if paths.is_empty() {
+        println!(
+            "{} {} is not in the dependency graph of {}",
+            "!".yellow(),
+            args.id,
+            kam_toml.prop.id
+        );
+        return Ok(());
+    }
+    println!(
+        "{} is required through {} path(s):",
+        args.id.bold(),
+        paths.len()
+    );
+    for path in &paths {
+        println!("  {}", path.join(&" → ".dimmed().to_string()));
+    }
+    Ok(())
+}
+
+/// Collect into `found` the label paths (starting with `path`) that lead
+/// from `nodes` to a module `id`. Paths end at the module: what it pulls in
+/// doesn't explain why it is there.
+fn find_paths(nodes: &[Node], id: &str, path: &mut Vec<String>, found: &mut Vec<Vec<String>>) {
+    for node in nodes {
+        let label = match &node.note {
+            Some(note) => format!("{} {}", node.label, note),
+            None => node.label.clone(),
+        };
+        path.push(label);
+        if node.id.as_deref() == Some(id) {
+            found.push(path.clone());
+        } else {
+            find_paths(&node.children, id, path, found);
+        }
+        path.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(label: &str, id: Option<&str>, children: Vec<Node>) -> Node {
+        Node {
+            label: label.to_string(),
+            id: id.map(str::to_string),
+            note: None,
+            children,
+        }
+    }
+
+    #[test]
+    fn test_find_paths_through_includes() {
+        let nodes = vec![
+            node(
+                "a@1",
+                Some("a"),
+                vec![node(
+                    "include:net",
+                    None,
+                    vec![node("target@3", Some("target"), Vec::new())],
+                )],
+            ),
+            node(
+                "target@3",
+                Some("target"),
+                vec![node("c@1", Some("c"), Vec::new())],
+            ),
+            node("b@2", Some("b"), Vec::new()),
+        ];
+        let mut found = Vec::new();
+        find_paths(&nodes, "target", &mut vec!["root".to_string()], &mut found);
+        assert_eq!(
+            found,
+            vec![
+                vec!["root", "a@1", "include:net", "target@3"],
+                vec!["root", "target@3"],
+            ]
+        );
+
+        found.clear();
+        find_paths(&nodes, "missing", &mut Vec::new(), &mut found);
+        assert!(found.is_empty());
+    }
+}
@@ -3,10 +3,12 @@
 pub mod cache;
 pub mod kam;
 pub mod kam_toml;
+pub mod source;
 
 pub use cache::CacheError;
 pub use kam_toml::KamTomlError;
 pub use kam_toml::ValidationResult;
+pub use source::SourceParseError;
 
 pub use kam::KamError;
 pub type Result<T> = std::result::Result<T, KamError>;
@@ -1,4 +1,5 @@
 use std::io;
+use std::path::PathBuf;
 use thiserror::Error;
 
 /// Errors that can occur when working with the cache
@@ -12,6 +13,23 @@ pub enum CacheError {
 
     #[error("Invalid path: {0}")]
     InvalidPath(String),
+
+    #[error("Not in the cache: {0}")]
+    NotCached(String),
+
+    #[error("Invalid pins file: {0}")]
+    InvalidPins(String),
+
+    #[error(
+        "Timed out after {seconds}s waiting for the cache lock {} held by {holder}; \
+         check `kam cache lock-status` or raise KAM_LOCK_TIMEOUT",
+        path.display()
+    )]
+    LockTimeout {
+        path: PathBuf,
+        seconds: u64,
+        holder: String,
+    },
 }
 
 impl CacheError {
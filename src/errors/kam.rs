@@ -43,6 +43,9 @@ pub enum KamError {
     #[error("Cache error: {0}")]
     Cache(#[from] crate::errors::CacheError),
 
+    #[error("Source error: {0}")]
+    SourceParse(#[from] crate::errors::SourceParseError),
+
     #[error("Command failed: {0}")]
     CommandFailed(String),
 
@@ -58,6 +61,12 @@ pub enum KamError {
     #[error("Upload failed: {0}")]
     UploadFailed(String),
 
+    #[error("Index conflict: {0}")]
+    IndexConflict(String),
+
+    #[error("Lockfile error: {0}")]
+    Lockfile(String),
+
     #[error("Fetch failed: {0}")]
     FetchFailed(String),
 
@@ -76,6 +85,9 @@ pub enum KamError {
     #[error("Unsupported archive format: {0}")]
     UnsupportedArchive(String),
 
+    #[error("Unsafe path in archive: {0}")]
+    UnsafeArchivePath(String),
+
     #[error("Template not found: {0}")]
     TemplateNotFound(String),
 
@@ -127,12 +139,70 @@ pub enum KamError {
     #[error("Venv create failed: {0}")]
     VenvCreateFailed(String),
 
+    #[error("Unsatisfied peer dependencies:\n{0}")]
+    UnsatisfiedPeerDependencies(String),
+
+    #[error("Duplicate provides: {0}")]
+    DuplicateProvides(String),
+
+    #[error("Unknown feature: {0}")]
+    UnknownFeature(String),
+
     #[error("Dependency resolution failed: {0}")]
     DependencyResolutionFailed(String),
 
+    #[error("Archive verification failed: {0}")]
+    VerificationFailed(String),
+
     #[error("Invalid module structure: {0}")]
     InvalidModuleStructure(String),
 
     #[error("Template render error: {0}")]
     TemplateRenderError(String),
+
+    #[error("Checksum mismatch: {0}")]
+    ChecksumMismatch(String),
+
+    #[error("Invalid kam.toml: {0}")]
+    InvalidKamToml(String),
+
+    #[error("Conflicting modules: {0}")]
+    ConflictingModules(String),
+
+    #[error("Not available offline: {0}")]
+    OfflineUnavailable(String),
+
+    #[error("Cannot load signing key: {0}")]
+    SigningKey(String),
+
+    #[error("Invalid signature: {0}")]
+    SignatureInvalid(String),
+
+    #[error("Build failed: {0}")]
+    BuildFailed(String),
+
+    #[error("Already cached: {0}")]
+    AlreadyCached(String),
+
+    #[error("Release already exists: {0}")]
+    ReleaseExists(String),
+
+    #[error("Invalid value for template variable: {0}")]
+    InvalidTemplateVar(String),
+
+    #[error("Doctor checks failed: {0}")]
+    DoctorFailed(String),
+
+    #[error("Root manager too old: {0}")]
+    ManagerTooOld(String),
+
+    #[error("Not formatted: {0}")]
+    NotFormatted(String),
+
+    #[error("Binary not found: {0}")]
+    BinaryNotFound(String),
+
+    /// A process run by `kam run` failed; kam exits with the same code
+    #[error("Process exited with status {0}")]
+    ExitStatus(i32),
 }
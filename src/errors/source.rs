@@ -0,0 +1,99 @@
+use thiserror::Error;
+
+/// Schemes understood by `Source::parse`. Used to suggest a fix when a user
+/// mistypes a scheme (e.g. `htps://`).
+pub const KNOWN_SCHEMES: &[&str] = &[
+    "http",
+    "https",
+    "file",
+    "ssh",
+    "git",
+    "git+https",
+    "git+http",
+    "git+ssh",
+    "git+file",
+];
+
+/// Errors that can occur when parsing a source spec
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SourceParseError {
+    #[error("Empty source spec; expected a path, an http(s) URL or git+<url>[@rev]")]
+    Empty,
+
+    #[error("Empty path in '{0}'; expected file:///path/to/module")]
+    EmptyPath(String),
+
+    #[error("Unknown scheme '{scheme}' in '{spec}'{}", suggestion_suffix(.suggestion))]
+    UnknownScheme {
+        scheme: String,
+        spec: String,
+        suggestion: Option<String>,
+    },
+
+    #[error("Malformed git spec '{spec}': {reason}{}", suggestion_suffix(.suggestion))]
+    MalformedGitSpec {
+        spec: String,
+        reason: String,
+        suggestion: Option<String>,
+    },
+
+    #[error("Invalid subdirectory in '{spec}': {reason}; expected <spec>#path=sub/dir")]
+    InvalidSubdir { spec: String, reason: String },
+}
+
+fn suggestion_suffix(suggestion: &Option<String>) -> String {
+    match suggestion {
+        Some(s) => format!("; did you mean '{}'?", s),
+        None => String::new(),
+    }
+}
+
+impl SourceParseError {
+    /// Suggested corrected spec, if one could be derived
+    pub fn suggestion(&self) -> Option<&str> {
+        match self {
+            SourceParseError::UnknownScheme { suggestion, .. }
+            | SourceParseError::MalformedGitSpec { suggestion, .. } => suggestion.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+/// Return the known scheme closest to `scheme` (edit distance <= 2).
+pub fn closest_scheme(scheme: &str) -> Option<&'static str> {
+    let scheme = scheme.to_ascii_lowercase();
+    KNOWN_SCHEMES
+        .iter()
+        .map(|k| (*k, edit_distance(&scheme, k)))
+        .filter(|(_, d)| *d <= 2)
+        .min_by_key(|(_, d)| *d)
+        .map(|(k, _)| k)
+}
+
+/// Return the known scheme `scheme` is a one-character typo of, if any.
+/// Known schemes in any case (`HTTPS`) and schemes further away (`s3`,
+/// `ftp`) are left for the fetcher to handle.
+pub fn mistyped_scheme(scheme: &str) -> Option<&'static str> {
+    let scheme = scheme.to_ascii_lowercase();
+    if KNOWN_SCHEMES.contains(&scheme.as_str()) {
+        return None;
+    }
+    KNOWN_SCHEMES
+        .iter()
+        .find(|k| edit_distance(&scheme, k) == 1)
+        .copied()
+}
+
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+    let b_chars: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b_chars.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1; b_chars.len() + 1];
+        for (j, cb) in b_chars.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            cur[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        prev = cur;
+    }
+    prev[b_chars.len()]
+}
@@ -4,6 +4,10 @@ pub mod assets;
 pub mod cache;
 pub mod cmds;
 pub mod errors;
+pub mod log;
+pub mod output;
+pub mod progress;
+pub mod signing;
 pub mod template;
 pub mod types;
 pub mod utils;
@@ -0,0 +1,132 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde_json::json;
+
+use crate::cache::KamCache;
+use crate::errors::KamError;
+
+/// Environment variable selecting the log level: `off`, `info` (default) or
+/// `debug`
+pub const LOG_ENV: &str = "KAM_LOG";
+
+/// Size at which a day's log file is rotated
+const MAX_LOG_SIZE: u64 = 1024 * 1024;
+
+/// Rotated files kept per day (`kam-<date>.1.jsonl` is the newest)
+const MAX_ROTATED: usize = 3;
+
+/// How much `kam` records about each command it runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Off,
+    Info,
+    Debug,
+}
+
+impl LogLevel {
+    /// The level from [`LOG_ENV`]; unset or unknown values mean `info`
+    pub fn from_env() -> LogLevel {
+        match std::env::var(LOG_ENV)
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "off" | "0" | "false" | "none" => LogLevel::Off,
+            "debug" | "trace" => LogLevel::Debug,
+            _ => LogLevel::Info,
+        }
+    }
+}
+
+/// # Command Log
+///
+/// Append one JSON line describing a finished command to
+/// `<cache>/log/kam-<YYYYMMDD>.jsonl`: the timestamp, the command, its
+/// arguments, whether it succeeded (and the error if not) and how long it
+/// took. `debug` adds the working directory, kam's version and whether
+/// offline mode was on.
+///
+/// Logging never fails a command: problems writing the log are ignored.
+pub fn record(command: &str, result: &Result<(), KamError>, duration: Duration) {
+    let level = LogLevel::from_env();
+    if level == LogLevel::Off {
+        return;
+    }
+    let Ok(cache) = KamCache::new() else {
+        return;
+    };
+    let now = chrono::Utc::now();
+    let mut entry = json!({
+        "timestamp": now.to_rfc3339(),
+        "command": command,
+        "args": std::env::args().skip(1).collect::<Vec<_>>(),
+        "result": if result.is_ok() { "ok" } else { "error" },
+        "duration_ms": duration.as_millis() as u64,
+    });
+    if let Err(e) = result {
+        entry["error"] = json!(e.to_string());
+    }
+    if level >= LogLevel::Debug {
+        entry["cwd"] = json!(
+            std::env::current_dir()
+                .map(|d| d.display().to_string())
+                .unwrap_or_default()
+        );
+        entry["version"] = json!(env!("CARGO_PKG_VERSION"));
+        entry["offline"] = json!(crate::utils::is_offline());
+    }
+    let day = now.format("%Y%m%d").to_string();
+    let _ = append(&cache.log_dir(), &day, &entry.to_string(), MAX_LOG_SIZE);
+}
+
+/// The log file of `day` (or its `n`th rotation)
+fn log_file(dir: &Path, day: &str, n: usize) -> PathBuf {
+    match n {
+        0 => dir.join(format!("kam-{}.jsonl", day)),
+        n => dir.join(format!("kam-{}.{}.jsonl", day, n)),
+    }
+}
+
+/// Append `line` to the log of `day`, rotating the file first once it has
+/// grown past `max_size`
+fn append(dir: &Path, day: &str, line: &str, max_size: u64) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let current = log_file(dir, day, 0);
+    if fs::metadata(&current).is_ok_and(|m| m.len() >= max_size) {
+        for n in (1..MAX_ROTATED).rev() {
+            let from = log_file(dir, day, n);
+            if from.exists() {
+                fs::rename(&from, log_file(dir, day, n + 1))?;
+            }
+        }
+        fs::rename(&current, log_file(dir, day, 1))?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&current)?;
+    writeln!(file, "{}", line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_append_rotates_by_size() {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..6 {
+            append(dir.path(), "20260101", &format!("{{\"n\":{}}}", i), 16).unwrap();
+        }
+        let read = |n| fs::read_to_string(log_file(dir.path(), "20260101", n)).unwrap();
+        // Each line is 8 bytes, so every file holds two lines
+        assert_eq!(read(0), "{\"n\":4}\n{\"n\":5}\n");
+        assert_eq!(read(1), "{\"n\":2}\n{\"n\":3}\n");
+        assert_eq!(read(2), "{\"n\":0}\n{\"n\":1}\n");
+        assert!(!log_file(dir.path(), "20260101", MAX_ROTATED + 1).exists());
+    }
+}
@@ -1,7 +1,7 @@
 //
 // 👀
 //
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
 use dotenvy::dotenv;
 use kam::errors::KamError;
 
@@ -17,6 +17,21 @@ use kam::errors::KamError;
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// Never use the network: only the cache, local repositories and local
+    /// sources (also enabled by KAM_OFFLINE=1)
+    #[arg(long, global = true)]
+    offline: bool,
+
+    /// Only print errors and final results (no status lines or progress
+    /// bars)
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
+    /// Also print timing and per-file detail (no `-v`: that is
+    /// `kam add --version`)
+    #[arg(long, global = true, conflicts_with = "quiet")]
+    verbose: bool,
 }
 
 #[derive(Subcommand)]
@@ -27,6 +42,12 @@ enum Commands {
     /// Add a library dependency to the project
     Add(kam::cmds::add::AddArgs),
 
+    /// Remove a library dependency from the project
+    Remove(kam::cmds::remove::RemoveArgs),
+
+    /// Update dependencies to newer versions
+    Update(kam::cmds::update::UpdateArgs),
+
     /// Manage the global cache
     Cache(kam::cmds::cache::CacheArgs),
 
@@ -36,9 +57,36 @@ enum Commands {
     /// Development tools
     Dev(kam::cmds::dev::DevArgs),
 
+    /// Diagnose environment and configuration problems
+    Doctor(kam::cmds::doctor::DoctorArgs),
+
+    /// Write module.prop from kam.toml without building
+    ExportProp(kam::cmds::export_prop::ExportPropArgs),
+
+    /// Format kam.toml in the canonical style
+    Fmt(kam::cmds::fmt::FmtArgs),
+
+    /// Show information about a module
+    Info(kam::cmds::info::InfoArgs),
+
+    /// Rewrite legacy kam.toml keys to the current schema
+    Migrate(kam::cmds::migrate::MigrateArgs),
+
+    /// Run a binary provided by a dependency with the venv applied
+    Run(kam::cmds::run::RunArgs),
+
+    /// Search a repository index for modules
+    Search(kam::cmds::search::SearchArgs),
+
     /// Synchronize dependencies
     Sync(kam::cmds::sync::SyncArgs),
 
+    /// Show the resolved dependency tree
+    Tree(kam::cmds::tree::TreeArgs),
+
+    /// Explain why a module is in the dependency graph
+    Why(kam::cmds::why::WhyArgs),
+
     /// Build the module
     Build(kam::cmds::build::BuildArgs),
 
@@ -51,17 +99,50 @@ enum Commands {
 
 fn main() -> Result<(), KamError> {
     dotenv().ok();
-    let cli = Cli::parse();
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    if cli.offline {
+        kam::utils::set_offline();
+    }
+    if cli.quiet {
+        kam::output::set_verbosity(kam::output::Verbosity::Quiet);
+    } else if cli.verbose {
+        kam::output::set_verbosity(kam::output::Verbosity::Verbose);
+    }
 
-    match cli.command {
+    let started = std::time::Instant::now();
+    let result = match cli.command {
         Commands::Init(args) => kam::cmds::init::run(args),
         Commands::Add(args) => kam::cmds::add::run(args),
+        Commands::Remove(args) => kam::cmds::remove::run(args),
+        Commands::Update(args) => kam::cmds::update::run(args),
         Commands::Cache(args) => kam::cmds::cache::run(args),
         Commands::Check(args) => kam::cmds::check::run(args),
         Commands::Dev(args) => kam::cmds::dev::run(args),
+        Commands::Doctor(args) => kam::cmds::doctor::run(args),
+        Commands::ExportProp(args) => kam::cmds::export_prop::run(args),
+        Commands::Fmt(args) => kam::cmds::fmt::run(args),
+        Commands::Info(args) => kam::cmds::info::run(args),
+        Commands::Migrate(args) => kam::cmds::migrate::run(args),
+        Commands::Run(args) => kam::cmds::run::run(args),
+        Commands::Search(args) => kam::cmds::search::run(args),
         Commands::Sync(args) => kam::cmds::sync::run(args),
+        Commands::Tree(args) => kam::cmds::tree::run(args),
+        Commands::Why(args) => kam::cmds::why::run(args),
         Commands::Build(args) => kam::cmds::build::run(args),
         Commands::Publish(args) => kam::cmds::publish::run(args),
         Commands::Venv(args) => kam::cmds::venv::run(args),
+    };
+    let command = matches.subcommand_name().unwrap_or_default();
+    kam::detail!(
+        "Finished `kam {}` in {:.2}s",
+        command,
+        started.elapsed().as_secs_f64()
+    );
+    kam::log::record(command, &result, started.elapsed());
+    // `kam run` exits like the process it ran
+    if let Err(KamError::ExitStatus(code)) = result {
+        std::process::exit(code);
     }
+    result
 }
@@ -0,0 +1,58 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// How much `kam` prints (`--quiet` / `--verbose`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    /// Only errors and final results
+    Quiet,
+    Normal,
+    /// Also timing and per-file detail
+    Verbose,
+}
+
+static VERBOSITY: AtomicU8 = AtomicU8::new(Verbosity::Normal as u8);
+
+/// Set the verbosity for the rest of the process. Quiet also hides
+/// progress bars.
+pub fn set_verbosity(verbosity: Verbosity) {
+    VERBOSITY.store(verbosity as u8, Ordering::Relaxed);
+    if verbosity == Verbosity::Quiet {
+        crate::progress::set_quiet();
+    }
+}
+
+pub fn verbosity() -> Verbosity {
+    match VERBOSITY.load(Ordering::Relaxed) {
+        0 => Verbosity::Quiet,
+        1 => Verbosity::Normal,
+        _ => Verbosity::Verbose,
+    }
+}
+
+/// # Status Output
+///
+/// Print a status line of a running command, like `println!`, unless
+/// `--quiet` is on. Errors and the results a command exists to print use
+/// `println!`/`eprintln!` directly.
+///
+/// Colors come from `colored`, which turns them off when `NO_COLOR` is set
+/// or stdout is not a terminal.
+#[macro_export]
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if $crate::output::verbosity() > $crate::output::Verbosity::Quiet {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// Print a line of detail (timing, individual files, URLs tried) only
+/// with `--verbose`
+#[macro_export]
+macro_rules! detail {
+    ($($arg:tt)*) => {
+        if $crate::output::verbosity() >= $crate::output::Verbosity::Verbose {
+            println!($($arg)*);
+        }
+    };
+}
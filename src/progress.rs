@@ -0,0 +1,100 @@
+use std::io::{self, IsTerminal, Read, Write};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Hide progress bars and spinners for the rest of the process (`--quiet`)
+pub fn set_quiet() {
+    QUIET.store(true, Ordering::Relaxed);
+}
+
+/// Whether progress is drawn: not `--quiet` and stdout is a terminal
+pub fn enabled() -> bool {
+    !QUIET.load(Ordering::Relaxed) && io::stdout().is_terminal()
+}
+
+/// Bars of concurrent downloads (e.g. `kam sync -j`) are drawn together
+fn multi() -> &'static MultiProgress {
+    static MULTI: OnceLock<MultiProgress> = OnceLock::new();
+    MULTI.get_or_init(MultiProgress::new)
+}
+
+/// # Progress
+///
+/// A progress bar or spinner shown while a download or extraction runs.
+/// It is cleared from the terminal when dropped, and draws nothing when
+/// progress is disabled (see [`enabled`]).
+pub struct Progress {
+    bar: ProgressBar,
+}
+
+impl Progress {
+    /// A byte counter for a download of `total` bytes (when the server
+    /// sent a `Content-Length`), showing its size and speed
+    pub fn bytes(label: &str, total: Option<u64>) -> Progress {
+        if !enabled() {
+            return Progress {
+                bar: ProgressBar::hidden(),
+            };
+        }
+        let bar = match total {
+            Some(total) => ProgressBar::new(total).with_style(
+                ProgressStyle::with_template(
+                    "  {msg} [{bar:30.cyan/blue}] {bytes}/{total_bytes} {bytes_per_sec} {eta}",
+                )
+                .unwrap()
+                .progress_chars("=> "),
+            ),
+            None => ProgressBar::new_spinner().with_style(
+                ProgressStyle::with_template("  {spinner} {msg} {bytes} {bytes_per_sec}").unwrap(),
+            ),
+        };
+        Progress::start(bar.with_message(label.to_string()))
+    }
+
+    /// A spinner for a step of unknown length
+    pub fn spinner(message: impl Into<String>) -> Progress {
+        if !enabled() {
+            return Progress {
+                bar: ProgressBar::hidden(),
+            };
+        }
+        let bar = ProgressBar::new_spinner()
+            .with_style(ProgressStyle::with_template("  {spinner} {msg}").unwrap())
+            .with_message(message.into());
+        Progress::start(bar)
+    }
+
+    fn start(bar: ProgressBar) -> Progress {
+        let bar = multi().add(bar);
+        bar.enable_steady_tick(Duration::from_millis(100));
+        Progress { bar }
+    }
+
+    /// Count the bytes read through `reader`
+    pub fn wrap_read<R: Read + Send>(&self, reader: R) -> impl Read + Send + use<R> {
+        self.bar.wrap_read(reader)
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        self.bar.finish_and_clear();
+    }
+}
+
+/// Copy a download `body` of `total` bytes into `writer` behind a progress
+/// bar labelled `label`. Returns the number of bytes copied.
+pub fn download<R: Read + Send, W: Write + ?Sized>(
+    body: R,
+    writer: &mut W,
+    label: &str,
+    total: Option<u64>,
+) -> io::Result<u64> {
+    let progress = Progress::bytes(label, total);
+    io::copy(&mut progress.wrap_read(body), writer)
+}
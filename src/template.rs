@@ -180,4 +180,50 @@ impl TemplateManager {
         }
         result
     }
+
+    /// Find Tera constructs that plain `{{name}}` replacement leaves untouched:
+    /// `{% ... %}` tags, `{# ... #}` comments and `{{ ... }}` expressions that
+    /// are not a bare variable name (whitespace, filters, attribute access).
+    ///
+    /// Returns `(line, construct)` pairs with 1-based line numbers.
+    pub fn tera_constructs(text: &str) -> Vec<(usize, String)> {
+        let tag_re = regex::Regex::new(r"\{%.*?%\}|\{#.*?#\}|\{\{(.*?)\}\}").unwrap();
+        let mut found = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            for caps in tag_re.captures_iter(line) {
+                let plain = caps.get(1).is_some_and(|inner| {
+                    !inner.as_str().is_empty()
+                        && inner
+                            .as_str()
+                            .chars()
+                            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                });
+                if !plain {
+                    found.push((i + 1, caps[0].to_string()));
+                }
+            }
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tera_constructs() {
+        let text = "id={{id}}\n{% if debug %}\nname={{ name | upper }} {# note #}\n{% endif %}\n";
+        let found = TemplateManager::tera_constructs(text);
+        assert_eq!(
+            found,
+            vec![
+                (2, "{% if debug %}".to_string()),
+                (3, "{{ name | upper }}".to_string()),
+                (3, "{# note #}".to_string()),
+                (4, "{% endif %}".to_string()),
+            ]
+        );
+        assert!(TemplateManager::tera_constructs("{{versionCode}} {{my-var}}").is_empty());
+    }
 }
@@ -1,4 +1,6 @@
 pub mod kam_lock;
 pub mod kam_toml;
 pub mod modules;
+pub mod root_manager;
 pub mod source;
+pub mod update_json;
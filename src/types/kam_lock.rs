@@ -6,6 +6,10 @@ use std::path::Path;
 pub struct LockPackage {
     pub name: String,
     pub version: String,
+    /// Version requirement from `kam.toml` this entry was resolved from;
+    /// the entry is stale once the requirement changes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requirement: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub source: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -19,6 +23,7 @@ impl LockPackage {
         LockPackage {
             name: name.into(),
             version: version.into(),
+            requirement: None,
             source: None,
             checksum: None,
             dependencies: Vec::new(),
@@ -35,6 +40,11 @@ pub struct KamLock {
     /// Lockfile schema version (e.g. 1, 2, 3...); mirrors Cargo.lock's `version`.
     pub version: u32,
 
+    /// Project-relative directory the packages are vendored into
+    /// (`kam sync --vendor <dir>`), read by `kam sync --vendored`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vendor: Option<String>,
+
     /// Vec of package entries. This is serialized as `[[package]]` in TOML.
     #[serde(rename = "package")]
     #[serde(default)]
@@ -46,6 +56,7 @@ impl KamLock {
         KamLock {
             version,
             packages: Vec::new(),
+            vendor: None,
         }
     }
 
@@ -6,9 +6,13 @@ use toml;
 pub mod sections;
 use sections::*;
 
-use crate::types::modules::DEFAULT_DEPENDENCY_SOURCE;
+use crate::errors::KamError;
+use crate::types::update_json::UpdateJson;
 
+mod edit;
 pub mod enums;
+mod format;
+mod migrate;
 
 /// Workspace section for Kam workspace management, similar to Cargo workspaces
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
@@ -31,6 +35,9 @@ pub struct KamToml {
     pub tmpl: Option<TmplSection>,
     // lib字段在kam.lib!
     // tool字段在kam.tool!
+    /// Third-party tool configuration (`[tool.<name>]` tables), kept as-is
+    /// so it survives loading and writing `kam.toml`
+    pub tool: Option<toml::Table>,
     #[serde(skip)]
     pub raw: String,
 }
@@ -55,6 +62,7 @@ impl KamToml {
             mmrl: Some(MmrlSection::default()),
             kam: KamSection::default(),
             tmpl: Some(TmplSection::default()),
+            tool: None,
             raw: String::new(),
         }
     }
@@ -92,7 +100,7 @@ impl KamToml {
 
     /// Load KamToml from a file
     pub fn load_from_file<P: AsRef<std::path::Path>>(path: P) -> crate::errors::Result<Self> {
-        println!("DEBUG: current_dir: {:?}", std::env::current_dir());
+        crate::detail!("DEBUG: current_dir: {:?}", std::env::current_dir());
         let content = std::fs::read_to_string(path)?;
         let mut kt: KamToml = toml::from_str(&content)?;
         kt.raw = content;
@@ -138,19 +146,660 @@ impl KamToml {
         }
     }
 
-    /// Get effective source URL for dependencies
-    pub fn get_effective_source(dep: &Dependency) -> String {
-        dep.source
-            .clone()
-            .unwrap_or_else(|| DEFAULT_DEPENDENCY_SOURCE.to_string())
+    /// A trimmed copy holding only what an installed module needs at runtime:
+    /// `prop`, `kam.min_api`/`max_api`/`supported_arch`/`conflicts`,
+    /// `kam.module_type`, `kam.lib` (provides) and the non-dev dependency
+    /// groups. Build hooks, template and tool sections and `[mmrl]` are
+    /// dropped.
+    pub fn to_runtime(&self) -> KamToml {
+        let dependency = self.kam.dependency.as_ref().map(|d| DependencySection {
+            dev: None,
+            ..d.clone()
+        });
+        KamToml {
+            prop: self.prop.clone(),
+            mmrl: None,
+            kam: KamSection {
+                min_api: self.kam.min_api,
+                max_api: self.kam.max_api,
+                supported_arch: self.kam.supported_arch.clone(),
+                conflicts: self.kam.conflicts.clone(),
+                dependency,
+                registry: self.kam.registry.clone(),
+                trust: self.kam.trust.clone(),
+                build: None,
+                module_type: self.kam.module_type.clone(),
+                tmpl: None,
+                lib: self.kam.lib.clone(),
+                tool: None,
+                workspace: None,
+            },
+            tmpl: None,
+            tool: None,
+            raw: String::new(),
+        }
     }
 
-    /// Resolve dependencies into flattened groups
-    pub fn resolve_dependencies(&self) -> crate::errors::Result<sections::FlatDependencyGroups> {
-        self.kam
-            .dependency
+    /// Render the flat `module.prop` read by root managers at install time:
+    /// the `prop` fields plus `minMagisk` (from `mmrl.repo.manager.magisk.min`)
+    /// and `minApi` (from `kam.min_api`) when they are set.
+    pub fn to_module_prop(&self) -> String {
+        let mut prop = self.prop.to_module_prop();
+        let min_magisk = self
+            .mmrl
+            .as_ref()
+            .and_then(|m| m.repo.as_ref())
+            .and_then(|r| r.manager.as_ref())
+            .and_then(|m| m.magisk.as_ref())
+            .and_then(|m| m.min)
+            .filter(|min| *min > 0);
+        if let Some(min) = min_magisk {
+            prop.push_str(&format!("minMagisk={}\n", min));
+        }
+        if let Some(min_api) = self.kam.min_api.filter(|api| *api > 0) {
+            prop.push_str(&format!("minApi={}\n", min_api));
+        }
+        prop
+    }
+
+    /// Build the `update.json` manifest for this module, pointing at `zip_url`.
+    ///
+    /// The changelog URL comes from `[mmrl.repo].changelog`.
+    pub fn to_update_json(&self, zip_url: impl Into<String>) -> UpdateJson {
+        UpdateJson {
+            version: self.prop.version.clone(),
+            versionCode: self.prop.versionCode,
+            zipUrl: zip_url.into(),
+            changelog: self
+                .mmrl
+                .as_ref()
+                .and_then(|m| m.repo.as_ref())
+                .and_then(|r| r.changelog.clone())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Check that `module_type` matches the project layout in `project_dir`.
+    ///
+    /// - `kam` needs a `src/<id>` directory
+    /// - `library` needs at least one `kam.lib.provides` entry
+    /// - `template` needs template variables (`[kam.tmpl]` or `[tmpl]`)
+    /// - `repo` needs `packages/` and `index/` directories
+    ///
+    /// Returns one message per mismatch.
+    pub fn layout_mismatches(&self, project_dir: &std::path::Path) -> Vec<String> {
+        let mut issues = Vec::new();
+        match self.kam.module_type {
+            ModuleType::Kam => {
+                let src = project_dir.join("src").join(&self.prop.id);
+                if !src.is_dir() {
+                    issues.push(format!(
+                        "module_type = \"kam\" but src/{} does not exist",
+                        self.prop.id
+                    ));
+                }
+            }
+            ModuleType::Library => {
+                let provides = self
+                    .kam
+                    .lib
+                    .as_ref()
+                    .and_then(|l| l.provides.as_ref())
+                    .map_or(0, |p| p.len());
+                if provides == 0 {
+                    issues.push(
+                        "module_type = \"library\" but kam.lib.provides is empty".to_string(),
+                    );
+                }
+            }
+            ModuleType::Template => {
+                let has_variables = [self.kam.tmpl.as_ref(), self.tmpl.as_ref()]
+                    .into_iter()
+                    .flatten()
+                    .any(|t| !t.variables.is_empty());
+                if !has_variables {
+                    issues.push(
+                        "module_type = \"template\" but no template variables are declared"
+                            .to_string(),
+                    );
+                }
+            }
+            ModuleType::Repo => {
+                for dir in ["packages", "index"] {
+                    if !project_dir.join(dir).is_dir() {
+                        issues.push(format!(
+                            "module_type = \"repo\" but {}/ does not exist",
+                            dir
+                        ));
+                    }
+                }
+            }
+        }
+        issues
+    }
+
+    /// Check the `kam.lib.provides` entries against the module in
+    /// `project_dir`: every `name` must be unique, and a `path`, when set,
+    /// must exist relative to the module root. Returns one message per
+    /// problem.
+    pub fn provide_errors(&self, project_dir: &std::path::Path) -> Vec<String> {
+        let Some(provides) = self.kam.lib.as_ref().and_then(|l| l.provides.as_ref()) else {
+            return Vec::new();
+        };
+        let mut errors = Vec::new();
+        let mut reported = std::collections::HashSet::new();
+        for provide in provides {
+            let count = provides.iter().filter(|p| p.name == provide.name).count();
+            if count > 1 && reported.insert(provide.name.as_str()) {
+                errors.push(format!(
+                    "kam.lib.provides declares '{}' {} times",
+                    provide.name, count
+                ));
+            }
+        }
+        for provide in provides {
+            let Some(path) = provide
+                .path
+                .as_deref()
+                .map(str::trim)
+                .filter(|p| !p.is_empty())
+            else {
+                continue;
+            };
+            if !project_dir.join(path).exists() {
+                errors.push(format!(
+                    "kam.lib.provides '{}' points at {}, which does not exist",
+                    provide.name, path
+                ));
+            }
+        }
+        errors
+    }
+
+    /// Check the MMRL display assets declared in `[mmrl.repo]`.
+    ///
+    /// `icon`, `cover` and each `screenshots` entry must be an absolute
+    /// http(s) URL or a file that exists relative to `project_dir`; empty
+    /// values count as unset. Modules declaring the `webroot` feature must
+    /// provide at least one screenshot. Returns one message per problem.
+    pub fn media_warnings(&self, project_dir: &std::path::Path) -> Vec<String> {
+        let Some(repo) = self.mmrl.as_ref().and_then(|m| m.repo.as_ref()) else {
+            return Vec::new();
+        };
+        let url_re = regex::Regex::new(r"^https?://[^\s/?#]+[^\s]*$").unwrap();
+        let mut warnings = Vec::new();
+        let mut check = |field: String, value: &str| {
+            let value = value.trim();
+            if !value.is_empty() && !url_re.is_match(value) && !project_dir.join(value).is_file() {
+                warnings.push(format!(
+                    "mmrl.repo.{} '{}' is neither an absolute http(s) URL nor an existing file",
+                    field, value
+                ));
+            }
+        };
+
+        if let Some(icon) = &repo.icon {
+            check("icon".to_string(), icon);
+        }
+        if let Some(cover) = &repo.cover {
+            check("cover".to_string(), cover);
+        }
+        let screenshots: Vec<&String> = repo
+            .screenshots
+            .iter()
+            .flatten()
+            .filter(|s| !s.trim().is_empty())
+            .collect();
+        for (i, screenshot) in screenshots.iter().enumerate() {
+            check(format!("screenshots[{}]", i), screenshot);
+        }
+
+        let has_ui = repo.features.iter().flatten().any(|f| f == "webroot");
+        if has_ui && screenshots.is_empty() {
+            warnings.push(
+                "mmrl.repo.features declares 'webroot' but mmrl.repo.screenshots is empty"
+                    .to_string(),
+            );
+        }
+        warnings
+    }
+
+    /// Check the architectures a module declares.
+    ///
+    /// Each `kam.supported_arch` entry that is not a known architecture or
+    /// alias is reported, with the closest canonical name when there is one.
+    /// When both `kam.supported_arch` and `mmrl.repo.arch` are set, they
+    /// must list the same architectures (aliases compare equal). Returns one
+    /// message per problem.
+    pub fn arch_warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        let supported = self.kam.supported_arch.as_deref().unwrap_or_default();
+        for arch in supported {
+            if let SupportedArch::Other(name) = arch {
+                let hint = match SupportedArch::closest(name) {
+                    Some(closest) => format!(" (did you mean '{}'?)", closest),
+                    None => String::new(),
+                };
+                warnings.push(format!(
+                    "kam.supported_arch: unknown architecture '{}'{}",
+                    name, hint
+                ));
+            }
+        }
+
+        let repo_arch: Vec<SupportedArch> = self
+            .mmrl
             .as_ref()
-            .unwrap_or(&DependencySection::default())
-            .resolve()
+            .and_then(|m| m.repo.as_ref())
+            .and_then(|r| r.arch.as_ref())
+            .into_iter()
+            .flatten()
+            .map(|a| SupportedArch::parse(a))
+            .collect();
+        if supported.is_empty() || repo_arch.is_empty() {
+            return warnings;
+        }
+        for arch in &repo_arch {
+            if !supported.contains(arch) {
+                warnings.push(format!(
+                    "mmrl.repo.arch lists '{}', which kam.supported_arch does not",
+                    arch
+                ));
+            }
+        }
+        // Unknown entries were reported above
+        for arch in supported
+            .iter()
+            .filter(|a| !matches!(a, SupportedArch::Other(_)))
+        {
+            if !repo_arch.contains(arch) {
+                warnings.push(format!(
+                    "kam.supported_arch lists '{}', which mmrl.repo.arch does not",
+                    arch
+                ));
+            }
+        }
+        warnings
+    }
+
+    /// Semantic errors that the TOML schema does not catch: an invalid
+    /// `prop.id`, a `prop.version` not in `x.y.z` format, dependency `source`
+    /// specs that do not parse, include cycles or unknown groups in the
+    /// dependency section and `mmrl.repo.manager.<name>.min` values that are
+    /// not version codes. Returns one message per problem.
+    pub fn semantic_errors(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if let Err(e) = validate_id(&self.prop.id) {
+            errors.push(format!("prop.id: {}", e));
+        }
+        if let Err(e) = validate_version(&self.prop.version) {
+            errors.push(format!("prop.version '{}': {}", self.prop.version, e));
+        }
+
+        if let Some(section) = &self.kam.dependency {
+            let groups = [
+                ("kam", section.kam.as_ref()),
+                ("dev", section.dev.as_ref()),
+                ("peer", section.peer.as_ref()),
+            ]
+            .into_iter()
+            .map(|(name, deps)| (name.to_string(), deps))
+            .chain(
+                section
+                    .features
+                    .iter()
+                    .flatten()
+                    .map(|(name, deps)| (format!("features.{}", name), Some(deps))),
+            );
+            let registries = self.registries();
+            for (group, deps) in groups {
+                for dep in deps.into_iter().flatten() {
+                    if let Err(e) = registries.source_for(dep) {
+                        errors.push(format!("kam.dependency.{}: {}", group, e));
+                    }
+                    let Some(source) = &dep.source else {
+                        continue;
+                    };
+                    if let Err(e) = crate::types::source::Source::parse(source) {
+                        errors.push(format!(
+                            "kam.dependency.{}: source of '{}': {}",
+                            group, dep.id, e
+                        ));
+                    }
+                }
+            }
+        }
+        if let Err(e) = self.resolve_dependencies() {
+            errors.push(format!("kam.dependency: {}", e));
+        }
+        if let Some(manager) = self
+            .mmrl
+            .as_ref()
+            .and_then(|m| m.repo.as_ref())
+            .and_then(|r| r.manager.as_ref())
+        {
+            errors.extend(manager.validate());
+        }
+        errors
+    }
+
+    /// Check that the documents referenced by `[mmrl.repo]` exist relative to
+    /// `project_dir`: `readme_file`, `license_file` and `changelog_file`, plus
+    /// `readme` and `changelog` when they are paths rather than http(s) URLs.
+    /// `license` is an SPDX identifier and is not checked. Returns one message
+    /// per missing file.
+    pub fn missing_repo_files(&self, project_dir: &std::path::Path) -> Vec<String> {
+        let Some(repo) = self.mmrl.as_ref().and_then(|m| m.repo.as_ref()) else {
+            return Vec::new();
+        };
+        let fields = [
+            ("readme", &repo.readme),
+            ("readme_file", &repo.readme_file),
+            ("license_file", &repo.license_file),
+            ("changelog", &repo.changelog),
+            ("changelog_file", &repo.changelog_file),
+        ];
+        let mut missing = Vec::new();
+        for (field, value) in fields {
+            let Some(value) = value.as_deref().map(str::trim) else {
+                continue;
+            };
+            if value.is_empty()
+                || value.starts_with("http://")
+                || value.starts_with("https://")
+                || project_dir.join(value).is_file()
+            {
+                continue;
+            }
+            missing.push(format!(
+                "mmrl.repo.{} points at '{}', which does not exist",
+                field, value
+            ));
+        }
+        missing
+    }
+
+    /// Registries dependencies are fetched from (`[kam.registry]`, then
+    /// `KAM_DEFAULT_REGISTRY`, then the built-in index)
+    pub fn registries(&self) -> Registries {
+        Registries::new(self.kam.registry.as_ref())
+    }
+
+    /// Public key `[kam.trust]` pins for the packages of module `id`
+    pub fn trusted_key(&self, id: &str) -> Option<&str> {
+        self.kam.trust.as_ref()?.key_for(id)
+    }
+
+    /// Get effective source URL for a dependency: its `source`, its named
+    /// registry or the default registry
+    pub fn get_effective_source(&self, dep: &Dependency) -> crate::errors::Result<String> {
+        self.registries().source_for(dep)
+    }
+
+    /// Load the `kam.toml` of each dependency in `groups` from the cache.
+    ///
+    /// Dependencies are resolved to the concrete version the cache would
+    /// use; modules that are not cached yet are skipped.
+    pub fn cached_dependency_tomls(
+        &self,
+        cache: &crate::cache::KamCache,
+        groups: &[&str],
+    ) -> crate::errors::Result<Vec<(Dependency, KamToml)>> {
+        let resolved = self.resolve_dependencies()?;
+        let mut modules = Vec::new();
+        for group in groups {
+            let Some(group) = resolved.get(group) else {
+                continue;
+            };
+            for dep in &group.dependencies {
+                let Some(version) = cache.resolve_version(&dep.id, dep.versionCode.as_ref()) else {
+                    continue;
+                };
+                let module_toml = cache
+                    .lib_module_path(&dep.id, &version.to_string())
+                    .join("kam.toml");
+                if module_toml.exists() {
+                    modules.push((dep.clone(), Self::load_from_file(&module_toml)?));
+                }
+            }
+        }
+        Ok(modules)
+    }
+
+    /// Check the peer dependencies of this project's runtime dependencies.
+    ///
+    /// Each cached runtime dependency's `peer` group is checked against the
+    /// modules this project provides: its runtime dependencies and the
+    /// project itself.
+    pub fn unsatisfied_peers(
+        &self,
+        cache: &crate::cache::KamCache,
+    ) -> crate::errors::Result<Vec<UnsatisfiedPeer>> {
+        let resolved = self.resolve_dependencies()?;
+        let runtime = resolved
+            .get("kam")
+            .map(|g| g.dependencies.as_slice())
+            .unwrap_or_default();
+
+        let mut provided: BTreeMap<String, Option<(String, i64)>> = BTreeMap::new();
+        provided.insert(
+            self.prop.id.clone(),
+            Some((self.prop.version.clone(), self.prop.versionCode)),
+        );
+        for dep in runtime {
+            let code = cache.resolve_version(&dep.id, dep.versionCode.as_ref());
+            provided.insert(
+                dep.id.clone(),
+                code.map(|code| {
+                    let version = cache.cached_version_name(&dep.id, code).unwrap_or_default();
+                    (version, code)
+                }),
+            );
+        }
+
+        let mut issues = Vec::new();
+        for (dep, module) in self.cached_dependency_tomls(cache, &["kam"])? {
+            let peers = module.kam.dependency.as_ref().and_then(|d| d.peer.as_ref());
+            if let Some(peers) = peers {
+                issues.extend(find_unsatisfied_peers(&dep.id, peers, &provided));
+            }
+        }
+        Ok(issues)
+    }
+
+    /// Find features requested by this project's dependencies (`id[feature]`)
+    /// which the cached target module does not declare.
+    ///
+    /// Returns `(module id, feature)` pairs.
+    pub fn unknown_requested_features(
+        &self,
+        cache: &crate::cache::KamCache,
+    ) -> crate::errors::Result<Vec<(String, String)>> {
+        let mut unknown = Vec::new();
+        for (dep, module) in self.cached_dependency_tomls(cache, &["kam", "dev"])? {
+            let section = module.kam.dependency.unwrap_or_default();
+            let declared = section.declared_features();
+            for feature in dep.requested_features() {
+                if !declared.contains(&feature.as_str()) {
+                    unknown.push((dep.id.clone(), feature.clone()));
+                }
+            }
+        }
+        Ok(unknown)
+    }
+
+    /// Find `lib.provides` names claimed by more than one module among this
+    /// project and its cached dependencies in `groups`.
+    ///
+    /// Returns each duplicated name with the ids of the modules providing it.
+    pub fn duplicate_provides(
+        &self,
+        cache: &crate::cache::KamCache,
+        groups: &[&str],
+    ) -> crate::errors::Result<BTreeMap<String, Vec<String>>> {
+        let dependencies = self.cached_dependency_tomls(cache, groups)?;
+        let modules = std::iter::once(self).chain(dependencies.iter().map(|(_, m)| m));
+
+        let mut providers: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for module in modules {
+            let provides = module.kam.lib.as_ref().and_then(|l| l.provides.as_ref());
+            for provide in provides.into_iter().flatten() {
+                let ids = providers.entry(provide.name.clone()).or_default();
+                if !ids.contains(&module.prop.id) {
+                    ids.push(module.prop.id.clone());
+                }
+            }
+        }
+        providers.retain(|_, ids| ids.len() > 1);
+        Ok(providers)
+    }
+
+    /// Resolve dependencies into flattened groups, without the optional ones
+    pub fn resolve_dependencies(&self) -> crate::errors::Result<sections::FlatDependencyGroups> {
+        self.resolve_dependencies_with_features(&[], &[])
+    }
+
+    /// Resolve dependencies into flattened groups with the given features of
+    /// this module and the optional dependencies `with` enabled (see
+    /// [`DependencySection::resolve_with_modules`]). Groups of other modules
+    /// (`include:<module-id>#<group>`) are read from the cached modules.
+    ///
+    /// Fails if an id in `with` is not an optional dependency of this module.
+    pub fn resolve_dependencies_with_features(
+        &self,
+        features: &[String],
+        with: &[String],
+    ) -> crate::errors::Result<sections::FlatDependencyGroups> {
+        let default = DependencySection::default();
+        let section = self.kam.dependency.as_ref().unwrap_or(&default);
+        // Report unknown features against this module's id
+        section.feature_dependencies(&self.prop.id, features)?;
+        let optional = section.optional_ids();
+        if let Some(id) = with.iter().find(|id| !optional.contains(&id.as_str())) {
+            return Err(KamError::InvalidConfig(format!(
+                "'{}' is not an optional dependency of '{}' (optional: {})",
+                id,
+                self.prop.id,
+                if optional.is_empty() {
+                    "none".to_string()
+                } else {
+                    optional.join(", ")
+                }
+            )));
+        }
+        section.resolve_with_modules(features, with, &|id| {
+            Self::cached_dependency_section(&crate::cache::KamCache::new()?, id)
+        })
+    }
+
+    /// The dependency section of the newest cached version of module `id`
+    pub fn cached_dependency_section(
+        cache: &crate::cache::KamCache,
+        id: &str,
+    ) -> crate::errors::Result<DependencySection> {
+        let code = cache.cached_versions(id).last().copied().ok_or_else(|| {
+            KamError::DependencyResolutionFailed(format!(
+                "module '{}' is included by an include:{}#<group> entry but is not cached; \
+                 install it with `kam cache add`",
+                id, id
+            ))
+        })?;
+        let module_dir = cache.lib_module_path(id, &code.to_string());
+        if !module_dir.join("kam.toml").is_file() {
+            return Err(KamError::DependencyResolutionFailed(format!(
+                "cached module '{}' ({}) has no kam.toml to include groups from",
+                id,
+                module_dir.display()
+            )));
+        }
+        let module = KamToml::load_from_dir(module_dir)?;
+        Ok(module.kam.dependency.unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_tables_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let content = "\
+[prop]
+id = \"my-mod\"
+name = { en = \"My module\" }
+version = \"1.0.0\"
+versionCode = 1
+author = \"me\"
+description = { en = \"desc\" }
+
+[kam]
+module_type = \"kam\"
+
+[kam.tool.data]
+released = 2024-05-01T12:30:00Z
+
+[tool.mytool]
+enabled = true
+ratio = 0.5
+since = 1979-05-27
+at = 07:32:00
+matrix = [[1, 2], [3, 4]]
+env = { CI = \"1\" }
+
+[[tool.mytool.targets]]
+name = \"a\"
+flags = [\"-O2\"]
+
+[[tool.mytool.targets]]
+name = \"b\"
+
+[tool.other.nested.deeper]
+key = \"value\"
+";
+        std::fs::write(dir.path().join("kam.toml"), content).unwrap();
+        let loaded = KamToml::load_from_dir(dir.path()).unwrap();
+        loaded.write_to_dir(dir.path()).unwrap();
+        let reloaded = KamToml::load_from_dir(dir.path()).unwrap();
+
+        let original: toml::Table = toml::from_str(content).unwrap();
+        assert_eq!(reloaded.tool.as_ref(), original["tool"].as_table());
+        assert_eq!(
+            reloaded.kam.tool.and_then(|t| t.data),
+            Some(original["kam"]["tool"]["data"].clone())
+        );
+        let written: toml::Table = toml::from_str(&reloaded.raw).unwrap();
+        assert_eq!(written["tool"], original["tool"]);
+    }
+
+    #[test]
+    fn test_provide_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("lib")).unwrap();
+        let provide = |name: &str, path: Option<&str>| sections::kamlib::Provide {
+            name: name.to_string(),
+            path: path.map(str::to_string),
+        };
+        let mut kam_toml = KamToml::default();
+        kam_toml.kam.lib = Some(LibSection {
+            provides: Some(vec![
+                provide("net", Some("lib")),
+                provide("log", None),
+                provide("net", Some("net.sh")),
+                provide("net", None),
+            ]),
+        });
+        assert_eq!(
+            kam_toml.provide_errors(dir.path()),
+            vec![
+                "kam.lib.provides declares 'net' 3 times",
+                "kam.lib.provides 'net' points at net.sh, which does not exist",
+            ]
+        );
+
+        kam_toml.kam.lib = Some(LibSection {
+            provides: Some(vec![provide("net", Some("lib")), provide("log", None)]),
+        });
+        assert!(kam_toml.provide_errors(dir.path()).is_empty());
     }
 }
@@ -0,0 +1,334 @@
+use toml_edit::{Array, ArrayOfTables, DocumentMut, Item, Table, TableLike, Value};
+
+use super::KamToml;
+use super::sections::Dependency;
+use crate::errors::KamError;
+
+/// Keys of a dependency entry that `kam.toml` edits manage; other keys of
+/// an entry are left alone
+const DEPENDENCY_KEYS: &[&str] = &["id", "versionCode", "source", "registry", "features"];
+
+impl KamToml {
+    /// Write the dependency groups of this configuration to `kam.toml` in
+    /// `dir` by editing [`KamToml::raw`] in place, so the rest of the file
+    /// keeps its comments and layout.
+    ///
+    /// Entries are matched by id: dropped dependencies are removed, changed
+    /// ones are updated key by key and new ones are appended to their group
+    /// (`[[kam.dependency.<group>]]` when the group does not exist yet).
+    /// Legacy group and key names of the section are migrated on the way.
+    /// Without `raw` the whole file is serialized like
+    /// [`KamToml::write_to_dir`].
+    pub fn write_dependencies_to_dir<P: AsRef<std::path::Path>>(
+        &mut self,
+        dir: P,
+    ) -> crate::errors::Result<()> {
+        if self.raw.trim().is_empty() {
+            return self.write_to_dir(dir);
+        }
+        let mut doc: DocumentMut = self.raw.parse()?;
+        super::migrate::migrate_dependencies(&mut doc)?;
+        let section = self.kam.dependency.clone().unwrap_or_default();
+        for (group, deps) in [
+            ("kam", &section.kam),
+            ("dev", &section.dev),
+            ("peer", &section.peer),
+        ] {
+            edit_group(&mut doc, group, deps.as_deref().unwrap_or_default())?;
+        }
+        self.raw = doc.to_string();
+        std::fs::write(dir.as_ref().join("kam.toml"), &self.raw)?;
+        Ok(())
+    }
+
+    /// Write `kam.workspace.members` of this configuration to `kam.toml` in
+    /// `dir` by editing [`KamToml::raw`] in place: members are removed from
+    /// or appended to the existing array, so the rest of the file keeps its
+    /// comments and layout. Without `raw` the whole file is serialized like
+    /// [`KamToml::write_to_dir`].
+    pub fn write_workspace_members_to_dir<P: AsRef<std::path::Path>>(
+        &mut self,
+        dir: P,
+    ) -> crate::errors::Result<()> {
+        if self.raw.trim().is_empty() {
+            return self.write_to_dir(dir);
+        }
+        let mut doc: DocumentMut = self.raw.parse()?;
+        let members = self
+            .kam
+            .workspace
+            .as_ref()
+            .and_then(|w| w.members.clone())
+            .unwrap_or_default();
+        let workspace = doc
+            .entry("kam")
+            .or_insert_with(implicit_table)
+            .as_table_like_mut()
+            .ok_or_else(|| not_a_table("kam"))?
+            .entry("workspace")
+            .or_insert_with(|| Item::Table(Table::new()))
+            .as_table_like_mut()
+            .ok_or_else(|| not_a_table("kam.workspace"))?;
+        match workspace.get_mut("members") {
+            Some(item) => {
+                let array = item.as_array_mut().ok_or_else(|| {
+                    KamError::InvalidConfig(
+                        "kam.workspace.members must be an array of paths".to_string(),
+                    )
+                })?;
+                array.retain(|v| v.as_str().is_some_and(|m| members.iter().any(|x| x == m)));
+                for member in &members {
+                    if !array.iter().any(|v| v.as_str() == Some(member.as_str())) {
+                        array.push(member.as_str());
+                    }
+                }
+            }
+            None => {
+                let array: Array = members.iter().map(String::as_str).collect();
+                workspace.insert("members", toml_edit::value(array));
+            }
+        }
+        self.raw = doc.to_string();
+        std::fs::write(dir.as_ref().join("kam.toml"), &self.raw)?;
+        Ok(())
+    }
+}
+
+/// Make `kam.dependency.<group>` of `doc` list exactly `deps`
+fn edit_group(
+    doc: &mut DocumentMut,
+    group: &str,
+    deps: &[Dependency],
+) -> crate::errors::Result<()> {
+    let existing = doc
+        .get("kam")
+        .and_then(|kam| kam.get("dependency"))
+        .and_then(|dependency| dependency.get(group))
+        .is_some();
+    if !existing {
+        if deps.is_empty() {
+            return Ok(());
+        }
+        let mut tables = ArrayOfTables::new();
+        for dep in deps {
+            tables.push(dependency_table(dep)?);
+        }
+        let kam = doc.entry("kam").or_insert_with(implicit_table);
+        let dependency = kam
+            .as_table_like_mut()
+            .ok_or_else(|| not_a_table("kam"))?
+            .entry("dependency")
+            .or_insert_with(implicit_table);
+        dependency
+            .as_table_like_mut()
+            .ok_or_else(|| not_a_table("kam.dependency"))?
+            .insert(group, Item::ArrayOfTables(tables));
+        return Ok(());
+    }
+
+    let item = &mut doc["kam"]["dependency"][group];
+    match item {
+        Item::ArrayOfTables(tables) => {
+            tables.retain(|t| deps.iter().any(|d| Some(d.id.as_str()) == entry_id(t)));
+            for dep in deps {
+                let new = dependency_table(dep)?;
+                let existing = tables
+                    .iter_mut()
+                    .find(|t| entry_id(*t) == Some(dep.id.as_str()));
+                if let Some(table) = existing {
+                    update_entry(table, &new);
+                } else {
+                    tables.push(new);
+                }
+            }
+        }
+        Item::Value(Value::Array(array)) => {
+            array.retain(|v| {
+                v.as_inline_table()
+                    .is_some_and(|t| deps.iter().any(|d| Some(d.id.as_str()) == entry_id(t)))
+            });
+            for dep in deps {
+                let new = dependency_table(dep)?.into_inline_table();
+                let existing = array.iter_mut().find_map(|v| {
+                    v.as_inline_table_mut()
+                        .filter(|t| entry_id(*t) == Some(dep.id.as_str()))
+                });
+                match existing {
+                    Some(table) => update_entry(table, &new),
+                    None => array.push(new),
+                }
+            }
+        }
+        _ => {
+            return Err(KamError::InvalidConfig(format!(
+                "kam.dependency.{} must be an array of dependency tables",
+                group
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// The `id` of a dependency entry
+fn entry_id(entry: &dyn TableLike) -> Option<&str> {
+    entry.get("id")?.as_str()
+}
+
+/// Bring the managed keys of `entry` in line with `new`, touching only the
+/// values that changed
+fn update_entry(entry: &mut dyn TableLike, new: &dyn TableLike) {
+    for key in DEPENDENCY_KEYS {
+        match new.get(key).and_then(Item::as_value) {
+            Some(value) => {
+                let unchanged = entry
+                    .get(key)
+                    .and_then(Item::as_value)
+                    .is_some_and(|old| plain(old) == plain(value));
+                if unchanged {
+                    continue;
+                }
+                match entry.get_mut(key).and_then(Item::as_value_mut) {
+                    Some(old) => {
+                        let decor = old.decor().clone();
+                        *old = value.clone();
+                        *old.decor_mut() = decor;
+                    }
+                    None => {
+                        entry.insert(key, Item::Value(value.clone()));
+                    }
+                }
+            }
+            None => {
+                entry.remove(key);
+            }
+        }
+    }
+}
+
+/// A value without its surrounding whitespace and comments
+fn plain(value: &Value) -> String {
+    let mut value = value.clone();
+    value.decor_mut().clear();
+    value.to_string()
+}
+
+/// A dependency as a `[[kam.dependency.<group>]]` table
+fn dependency_table(dep: &Dependency) -> crate::errors::Result<Table> {
+    let doc = toml_edit::ser::to_document(dep)
+        .map_err(|e| KamError::TomlSerializeError(e.to_string()))?;
+    Ok(doc.as_table().clone())
+}
+
+fn implicit_table() -> Item {
+    let mut table = Table::new();
+    table.set_implicit(true);
+    Item::Table(table)
+}
+
+fn not_a_table(key: &str) -> KamError {
+    KamError::InvalidConfig(format!("{} must be a table", key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::kam_toml::sections::VersionSpec;
+
+    #[test]
+    fn test_dependency_edits_keep_comments() {
+        let dir = tempfile::tempdir().unwrap();
+        let original = "\
+# My module
+[prop]
+id = \"my-mod\" # keep this
+name = { en = \"My module\" }
+version = \"1.0.0\"
+versionCode = 1
+author = \"me\"
+description = { en = \"desc\" }
+
+[kam]
+module_type = \"kam\"
+
+# Runtime libraries
+[[kam.dependency.kam]]
+id = \"a\"
+versionCode = 1 # pinned on purpose
+
+[[kam.dependency.kam]]
+id = \"b\"
+versionCode = 2
+";
+        std::fs::write(dir.path().join("kam.toml"), original).unwrap();
+        let mut kam_toml = KamToml::load_from_dir(dir.path()).unwrap();
+
+        let deps = kam_toml
+            .kam
+            .dependency
+            .as_mut()
+            .unwrap()
+            .kam
+            .as_mut()
+            .unwrap();
+        deps.retain(|d| d.id != "b");
+        deps.push(Dependency {
+            versionCode: Some(VersionSpec::Exact(3)),
+            ..Dependency::new("c")
+        });
+        kam_toml.write_dependencies_to_dir(dir.path()).unwrap();
+
+        let written = std::fs::read_to_string(dir.path().join("kam.toml")).unwrap();
+        assert_eq!(
+            written,
+            original.replace(
+                "[[kam.dependency.kam]]\nid = \"b\"\nversionCode = 2\n",
+                "[[kam.dependency.kam]]\nid = \"c\"\nversionCode = 3\n"
+            )
+        );
+
+        // The edited file loads back to the same dependencies
+        let reloaded = KamToml::load_from_dir(dir.path()).unwrap();
+        assert_eq!(reloaded.kam.dependency, kam_toml.kam.dependency);
+    }
+
+    #[test]
+    fn test_workspace_member_edits_keep_comments() {
+        let dir = tempfile::tempdir().unwrap();
+        let original = "\
+[prop]
+id = \"my-ws\" # keep this
+name = { en = \"My workspace\" }
+version = \"1.0.0\"
+versionCode = 1
+author = \"me\"
+description = { en = \"desc\" }
+
+[kam]
+module_type = \"kam\"
+
+# Member modules
+[kam.workspace]
+members = [\"core\"] # more to come
+";
+        std::fs::write(dir.path().join("kam.toml"), original).unwrap();
+        let mut kam_toml = KamToml::load_from_dir(dir.path()).unwrap();
+        let workspace = kam_toml.kam.workspace.as_mut().unwrap();
+        workspace
+            .members
+            .as_mut()
+            .unwrap()
+            .push("plugins/net".to_string());
+        kam_toml.write_workspace_members_to_dir(dir.path()).unwrap();
+
+        let written = std::fs::read_to_string(dir.path().join("kam.toml")).unwrap();
+        assert_eq!(
+            written,
+            original.replace(
+                "members = [\"core\"]",
+                "members = [\"core\", \"plugins/net\"]"
+            )
+        );
+        let reloaded = KamToml::load_from_dir(dir.path()).unwrap();
+        assert_eq!(reloaded.kam.workspace, kam_toml.kam.workspace);
+    }
+}
@@ -13,6 +13,63 @@ pub enum SupportedArch {
     Other(String),
 }
 
+/// Canonical architecture names and the spellings accepted for them
+const ARCH_ALIASES: &[(&str, &[&str])] = &[
+    // ARM family aliases (armeabi-v7a is the Android ABI name)
+    (
+        "arm",
+        &[
+            "arm",
+            "armv7",
+            "armv7l",
+            "armv6",
+            "armhf",
+            "armeabi-v7a",
+            "armeabi",
+        ],
+    ),
+    // ARM64 / AArch64 (arm64-v8a is the Android ABI name)
+    ("arm64", &["arm64", "aarch64", "arm64-v8a"]),
+    // 32-bit x86 aliases
+    ("x86", &["x86", "i386", "i486", "i586", "i686"]),
+    // 64-bit x86 aliases
+    ("x86_64", &["x86_64", "x64", "amd64"]),
+];
+
+impl SupportedArch {
+    /// Parse an architecture name, mapping known aliases (case-insensitive)
+    /// to their canonical variant; anything else becomes `Other`
+    pub fn parse(name: &str) -> SupportedArch {
+        let key = name.trim().to_ascii_lowercase();
+        let canonical = ARCH_ALIASES
+            .iter()
+            .find(|(_, aliases)| aliases.contains(&key.as_str()))
+            .map(|(canonical, _)| *canonical);
+        match canonical {
+            Some("arm") => SupportedArch::Arm,
+            Some("arm64") => SupportedArch::Arm64,
+            Some("x86") => SupportedArch::X86,
+            Some("x86_64") => SupportedArch::X86_64,
+            _ => SupportedArch::Other(key),
+        }
+    }
+
+    /// The canonical architecture an unknown name is most likely a typo of
+    /// (edit distance <= 2 from the name or one of its aliases)
+    pub fn closest(name: &str) -> Option<SupportedArch> {
+        let key = name.trim().to_ascii_lowercase();
+        ARCH_ALIASES
+            .iter()
+            .flat_map(|(canonical, aliases)| aliases.iter().map(move |a| (*canonical, *a)))
+            .map(|(canonical, alias)| {
+                (canonical, crate::errors::source::edit_distance(&key, alias))
+            })
+            .filter(|(_, d)| *d <= 2)
+            .min_by_key(|(_, d)| *d)
+            .map(|(canonical, _)| SupportedArch::parse(canonical))
+    }
+}
+
 impl Serialize for SupportedArch {
     fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
     where
@@ -46,19 +103,7 @@ impl<'de> Deserialize<'de> for SupportedArch {
             where
                 E: de::Error,
             {
-                let key = v.trim();
-                let key_lc = key.to_ascii_lowercase();
-                Ok(match key_lc.as_str() {
-                    // ARM family aliases
-                    "arm" | "armv7" | "armv7l" | "armv6" | "armhf" => SupportedArch::Arm,
-                    // ARM64 / AArch64
-                    "arm64" | "aarch64" => SupportedArch::Arm64,
-                    // 32-bit x86 aliases
-                    "x86" | "i386" | "i486" | "i586" | "i686" => SupportedArch::X86,
-                    // 64-bit x86 aliases
-                    "x86_64" | "x64" | "amd64" => SupportedArch::X86_64,
-                    other => SupportedArch::Other(other.to_string()),
-                })
+                Ok(SupportedArch::parse(v))
             }
         }
 
@@ -108,3 +153,26 @@ pub enum ModuleType {
     Library,
     Repo,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_supported_arch_aliases() {
+        assert_eq!(SupportedArch::parse("arm64-v8a"), SupportedArch::Arm64);
+        assert_eq!(SupportedArch::parse("armeabi-v7a"), SupportedArch::Arm);
+        assert_eq!(SupportedArch::parse(" AMD64 "), SupportedArch::X86_64);
+        assert_eq!(
+            SupportedArch::parse("riscv64"),
+            SupportedArch::Other("riscv64".to_string())
+        );
+
+        assert_eq!(SupportedArch::closest("aarch"), Some(SupportedArch::Arm64));
+        assert_eq!(
+            SupportedArch::closest("x86-64"),
+            Some(SupportedArch::X86_64)
+        );
+        assert_eq!(SupportedArch::closest("riscv64"), None);
+    }
+}
@@ -0,0 +1,236 @@
+use toml_edit::{Decor, DocumentMut, Item, Table, Value};
+
+use super::KamToml;
+
+/// Top-level sections in the order they are formatted; other tables follow
+/// them in their original order
+const SECTION_ORDER: &[&str] = &["prop", "mmrl", "kam"];
+
+/// Language maps of `[prop]` whose keys are sorted
+const LANGUAGE_MAPS: &[&str] = &["name", "description"];
+
+impl KamToml {
+    /// Format a `kam.toml` document in the canonical style, keeping its
+    /// comments:
+    ///
+    /// - `[prop]`, `[mmrl]` and `[kam]` come first, in that order, each
+    ///   followed by its subtables; other tables keep their relative order
+    /// - keys are not indented and read `key = value`, with one space before
+    ///   a trailing comment
+    /// - every table header is preceded by one blank line, and runs of blank
+    ///   lines are collapsed into one
+    /// - the language keys of `prop.name` and `prop.description` are sorted
+    ///
+    /// Formatting a formatted document changes nothing.
+    ///
+    /// ```
+    /// use kam::types::kam_toml::KamToml;
+    ///
+    /// let formatted =
+    ///     KamToml::format_str("[kam]\nmodule_type=\"kam\"\n[prop]\n  id = \"demo\"   # id\n")
+    ///         .unwrap();
+    /// assert_eq!(
+    ///     formatted,
+    ///     "[prop]\nid = \"demo\" # id\n\n[kam]\nmodule_type = \"kam\"\n"
+    /// );
+    /// ```
+    pub fn format_str(content: &str) -> crate::errors::Result<String> {
+        let mut doc: DocumentMut = content.parse()?;
+        order_tables(&mut doc);
+        if let Some(prop) = doc.get_mut("prop").and_then(Item::as_table_like_mut) {
+            for key in LANGUAGE_MAPS {
+                match prop.get_mut(key) {
+                    Some(Item::Table(table)) => table.sort_values(),
+                    Some(Item::Value(Value::InlineTable(table))) => {
+                        table.sort_values();
+                        table.fmt();
+                    }
+                    _ => {}
+                }
+            }
+        }
+        visit_tables(doc.as_table_mut(), &mut format_table);
+        let trailing = format!("{}\n", raw(doc.trailing().as_str()));
+        doc.set_trailing(comment_lines(&trailing, true));
+        Ok(format!("{}\n", doc.to_string().trim_matches('\n')))
+    }
+}
+
+/// Renumber the table positions so the sections of [`SECTION_ORDER`] come
+/// first; tables within a section keep their order
+fn order_tables(doc: &mut DocumentMut) {
+    let root = doc.as_table_mut();
+    root.set_position(0);
+    // Tables without a position (added programmatically) are rendered after
+    // the table before them
+    let mut last = 0;
+    let mut keys = Vec::new();
+    for (key, item) in root.iter_mut() {
+        let rank = SECTION_ORDER
+            .iter()
+            .position(|s| *s == key.get())
+            .unwrap_or(SECTION_ORDER.len());
+        visit_item_tables(item, &mut |table| {
+            if let Some(position) = table.position() {
+                last = position;
+            }
+            keys.push((rank, last));
+        });
+    }
+
+    let mut order: Vec<usize> = (0..keys.len()).collect();
+    order.sort_by_key(|&i| keys[i]);
+    let mut positions = vec![0; keys.len()];
+    for (position, i) in order.into_iter().enumerate() {
+        positions[i] = position as isize + 1;
+    }
+    let mut positions = positions.into_iter();
+    for (_, item) in root.iter_mut() {
+        visit_item_tables(item, &mut |table| {
+            table.set_position(positions.next().unwrap_or_default())
+        });
+    }
+}
+
+/// Call `f` on the tables of `item`, in the order they are written
+fn visit_item_tables(item: &mut Item, f: &mut impl FnMut(&mut Table)) {
+    match item {
+        Item::Table(table) => visit_tables(table, f),
+        Item::ArrayOfTables(tables) => {
+            for table in tables.iter_mut() {
+                visit_tables(table, f);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Call `f` on `table` and its subtables (dotted keys are part of the table
+/// they appear in)
+fn visit_tables(table: &mut Table, f: &mut impl FnMut(&mut Table)) {
+    if !table.is_dotted() {
+        f(table);
+    }
+    for (_, item) in table.iter_mut() {
+        visit_item_tables(item, f);
+    }
+}
+
+/// Normalize the whitespace around the header and the values of `table`
+fn format_table(table: &mut Table) {
+    let decor = table.decor_mut();
+    let prefix = comment_lines(raw(decor.prefix().and_then(|p| p.as_str())), false);
+    decor.set_prefix(format!("\n{}", prefix));
+    set_trailing_comment(decor);
+
+    let mut first = true;
+    for (mut key, item) in table.iter_mut() {
+        let Item::Value(value) = item else {
+            continue;
+        };
+        let decor = key.leaf_decor_mut();
+        let prefix = comment_lines(raw(decor.prefix().and_then(|p| p.as_str())), !first);
+        decor.set_prefix(prefix);
+        decor.set_suffix(" ");
+        let decor = value.decor_mut();
+        decor.set_prefix(" ");
+        set_trailing_comment(decor);
+        first = false;
+    }
+}
+
+fn raw(s: Option<&str>) -> &str {
+    s.unwrap_or_default()
+}
+
+/// Keep only the comment after a header or value, one space away from it
+fn set_trailing_comment(decor: &mut Decor) {
+    let comment = raw(decor.suffix().and_then(|s| s.as_str())).trim();
+    let suffix = if comment.is_empty() {
+        String::new()
+    } else {
+        format!(" {}", comment)
+    };
+    decor.set_suffix(suffix);
+}
+
+/// The comment lines of a key or header prefix without their indentation,
+/// runs of blank lines collapsed into one. Leading blank lines are kept
+/// (as one) only with `keep_leading_blank`.
+fn comment_lines(prefix: &str, keep_leading_blank: bool) -> String {
+    // What follows the last newline is the indentation of the key itself
+    let Some((lines, _)) = prefix.rsplit_once('\n') else {
+        return String::new();
+    };
+    let mut out = String::new();
+    let mut blank = false;
+    for line in lines.split('\n').map(str::trim) {
+        if line.is_empty() {
+            blank = true;
+            continue;
+        }
+        if blank && (keep_leading_blank || !out.is_empty()) {
+            out.push('\n');
+        }
+        blank = false;
+        out.push_str(line);
+        out.push('\n');
+    }
+    if blank && (keep_leading_blank || !out.is_empty()) {
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_keeps_comments() {
+        let original = "\
+# Build settings first
+[kam]
+  module_type   =   \"kam\"    # kind
+
+
+
+[[kam.dependency.kam]]
+id = \"a\"
+
+[prop]
+id = \"my-mod\"
+name = { zh = \"模块\", en = \"Module\" }
+
+# Repository metadata
+[mmrl.repo]
+repository = \"https://example.com\"
+# end of file
+";
+        let formatted = KamToml::format_str(original).unwrap();
+        assert_eq!(
+            formatted,
+            "\
+[prop]
+id = \"my-mod\"
+name = { en = \"Module\", zh = \"模块\" }
+
+# Repository metadata
+[mmrl.repo]
+repository = \"https://example.com\"
+
+# Build settings first
+[kam]
+module_type = \"kam\" # kind
+
+[[kam.dependency.kam]]
+id = \"a\"
+# end of file
+"
+        );
+        assert_eq!(KamToml::format_str(&formatted).unwrap(), formatted);
+        let before: toml::Table = toml::from_str(original).unwrap();
+        let after: toml::Table = toml::from_str(&formatted).unwrap();
+        assert_eq!(before, after);
+    }
+}
@@ -0,0 +1,293 @@
+use toml_edit::{DocumentMut, Item, TableLike, Value};
+
+use super::KamToml;
+use crate::errors::KamError;
+
+/// `module_type` values accepted by the current schema
+const MODULE_TYPES: &[&str] = &["kam", "template", "library", "repo"];
+
+/// Dependency groups whose entries are migrated, besides the named
+/// `features` groups
+const DEPENDENCY_GROUPS: &[&str] = &["kam", "dev", "peer"];
+
+impl KamToml {
+    /// Rewrite the legacy spellings in a `kam.toml` document to the current
+    /// schema, keeping its comments and layout:
+    ///
+    /// - `module_type = "Normal"` becomes `"kam"`, and capitalized module
+    ///   types (`"Library"`) are lowercased
+    /// - the `kam.dependency.normal` group becomes `kam.dependency.kam`
+    ///   (merged into it when both exist)
+    /// - the `version` key of dependency entries becomes `versionCode`
+    ///
+    /// Returns the migrated document and a description of each change.
+    /// Migrating a current document changes nothing.
+    ///
+    /// ```
+    /// use kam::types::kam_toml::KamToml;
+    ///
+    /// let (migrated, changes) =
+    ///     KamToml::migrate_str("[kam]\nmodule_type = \"Normal\" # legacy\n").unwrap();
+    /// assert_eq!(migrated, "[kam]\nmodule_type = \"kam\" # legacy\n");
+    /// assert_eq!(changes.len(), 1);
+    /// ```
+    pub fn migrate_str(content: &str) -> crate::errors::Result<(String, Vec<String>)> {
+        let mut doc: DocumentMut = content.parse()?;
+        let mut changes = Vec::new();
+        if let Some(kam) = doc.get_mut("kam").and_then(Item::as_table_like_mut) {
+            migrate_module_type(kam, &mut changes);
+        }
+        changes.extend(migrate_dependencies(&mut doc)?);
+        Ok((doc.to_string(), changes))
+    }
+
+    /// Migrate the legacy keys of this configuration (see
+    /// [`KamToml::migrate_str`]) and reload it from the migrated document.
+    /// Returns the changes; `raw` holds the document to write back.
+    pub fn migrate(&mut self) -> crate::errors::Result<Vec<String>> {
+        let (content, changes) = Self::migrate_str(&self.raw)?;
+        if !changes.is_empty() {
+            *self = toml::from_str(&content)?;
+            self.raw = content;
+        }
+        Ok(changes)
+    }
+}
+
+fn migrate_module_type(kam: &mut dyn TableLike, changes: &mut Vec<String>) {
+    let Some(value) = kam.get_mut("module_type").and_then(Item::as_value_mut) else {
+        return;
+    };
+    let Some(old) = value.as_str().map(str::to_string) else {
+        return;
+    };
+    let new = match old.to_ascii_lowercase().as_str() {
+        "normal" => "kam".to_string(),
+        other => other.to_string(),
+    };
+    if new == old || !MODULE_TYPES.contains(&new.as_str()) {
+        return;
+    }
+    let decor = value.decor().clone();
+    *value = Value::from(new.as_str());
+    *value.decor_mut() = decor;
+    changes.push(format!("kam.module_type: \"{}\" -> \"{}\"", old, new));
+}
+
+/// Migrate the legacy group and key names of `kam.dependency` in `doc`.
+/// Dependency edits run this first, so they never write a legacy name next
+/// to its current spelling.
+pub(super) fn migrate_dependencies(doc: &mut DocumentMut) -> crate::errors::Result<Vec<String>> {
+    let mut changes = Vec::new();
+    let dependency = doc
+        .get_mut("kam")
+        .and_then(|kam| kam.get_mut("dependency"))
+        .and_then(Item::as_table_like_mut);
+    if let Some(dependency) = dependency {
+        migrate_dependency_groups(dependency, &mut changes)?;
+        migrate_dependency_versions(dependency, &mut changes);
+    }
+    Ok(changes)
+}
+
+fn migrate_dependency_groups(
+    dependency: &mut dyn TableLike,
+    changes: &mut Vec<String>,
+) -> crate::errors::Result<()> {
+    let Some((key, legacy)) = dependency.get_key_value("normal") else {
+        return Ok(());
+    };
+    let decor = key.leaf_decor().clone();
+    let mergeable = matches!(
+        (dependency.get("kam"), legacy),
+        (None, _)
+            | (Some(Item::ArrayOfTables(_)), Item::ArrayOfTables(_))
+            | (
+                Some(Item::Value(Value::Array(_))),
+                Item::Value(Value::Array(_))
+            )
+    );
+    if !mergeable {
+        return Err(KamError::InvalidConfig(
+            "kam.dependency has both a `kam` group and a legacy `normal` group written \
+             differently; merge them into `kam` by hand"
+                .to_string(),
+        ));
+    }
+
+    let Some(legacy) = dependency.remove("normal") else {
+        return Ok(());
+    };
+    match (dependency.get_mut("kam"), legacy) {
+        (Some(Item::ArrayOfTables(kam)), Item::ArrayOfTables(legacy)) => {
+            for table in legacy {
+                kam.push(table);
+            }
+            changes.push("kam.dependency.normal: merged into kam.dependency.kam".to_string());
+        }
+        (Some(Item::Value(Value::Array(kam))), Item::Value(Value::Array(legacy))) => {
+            for value in legacy {
+                kam.push_formatted(value);
+            }
+            changes.push("kam.dependency.normal: merged into kam.dependency.kam".to_string());
+        }
+        (_, legacy) => {
+            dependency.insert("kam", legacy);
+            if let Some(mut key) = dependency.key_mut("kam") {
+                *key.leaf_decor_mut() = decor;
+            }
+            changes.push("kam.dependency.normal: renamed to kam.dependency.kam".to_string());
+        }
+    }
+    Ok(())
+}
+
+fn migrate_dependency_versions(dependency: &mut dyn TableLike, changes: &mut Vec<String>) {
+    for group in DEPENDENCY_GROUPS {
+        if let Some(item) = dependency.get_mut(group) {
+            migrate_entry_versions(item, &format!("kam.dependency.{}", group), changes);
+        }
+    }
+    if let Some(features) = dependency
+        .get_mut("features")
+        .and_then(Item::as_table_like_mut)
+    {
+        for (feature, item) in features.iter_mut() {
+            let group = format!("kam.dependency.features.{}", feature.get());
+            migrate_entry_versions(item, &group, changes);
+        }
+    }
+}
+
+/// Rename `version` to `versionCode` in the entries of a dependency group
+fn migrate_entry_versions(item: &mut Item, group: &str, changes: &mut Vec<String>) {
+    let entries: Vec<&mut dyn TableLike> = match item {
+        Item::ArrayOfTables(tables) => tables.iter_mut().map(|t| t as &mut dyn TableLike).collect(),
+        Item::Value(Value::Array(array)) => array
+            .iter_mut()
+            .filter_map(Value::as_inline_table_mut)
+            .map(|t| t as &mut dyn TableLike)
+            .collect(),
+        _ => return,
+    };
+    for entry in entries {
+        if !entry.contains_key("version") || entry.contains_key("versionCode") {
+            continue;
+        }
+        rename_key(entry, "version", "versionCode");
+        let id = entry.get("id").and_then(Item::as_str).unwrap_or("?");
+        changes.push(format!("{} ({}): version -> versionCode", group, id));
+    }
+}
+
+/// Rename the key `from` of `table` to `to`, keeping its position and
+/// comments
+fn rename_key(table: &mut dyn TableLike, from: &str, to: &str) {
+    let keys: Vec<String> = table.iter().map(|(key, _)| key.to_string()).collect();
+    let entries: Vec<(toml_edit::Key, Item)> = keys
+        .iter()
+        .filter_map(|key| table.get_key_value(key))
+        .map(|(key, item)| (key.clone(), item.clone()))
+        .collect();
+    table.clear();
+    for (key, item) in entries {
+        let name = if key.get() == from { to } else { key.get() };
+        table.insert(name, item);
+        if let Some(mut new_key) = table.key_mut(name) {
+            *new_key.leaf_decor_mut() = key.leaf_decor().clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_legacy_keys() {
+        let legacy = "\
+# project
+[kam]
+module_type = \"Normal\"
+
+[kam.dependency]
+# runtime dependencies
+normal = [{ id = \"a\", versionCode = 1 }]
+dev = []
+";
+        let (migrated, changes) = KamToml::migrate_str(legacy).unwrap();
+        assert_eq!(changes.len(), 2);
+        assert!(migrated.contains("module_type = \"kam\""));
+        assert!(
+            migrated.contains("# runtime dependencies\nkam = [{ id = \"a\", versionCode = 1 }]")
+        );
+        assert!(!migrated.contains("normal"));
+
+        // Idempotent
+        let (again, changes) = KamToml::migrate_str(&migrated).unwrap();
+        assert_eq!(again, migrated);
+        assert!(changes.is_empty());
+
+        // An existing kam group absorbs the legacy one
+        let both = "\
+[[kam.dependency.kam]]
+id = \"a\"
+
+[[kam.dependency.normal]]
+id = \"b\"
+";
+        let (migrated, changes) = KamToml::migrate_str(both).unwrap();
+        assert_eq!(changes.len(), 1);
+        let doc: toml::Table = toml::from_str(&migrated).unwrap();
+        let ids: Vec<&str> = doc["kam"]["dependency"]["kam"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|d| d["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(ids, vec!["a", "b"]);
+        assert!(
+            KamToml::migrate_str(
+                "[kam.dependency]\nkam = []\n[[kam.dependency.normal]]\nid = \"b\"\n"
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_legacy_dependency_names_load() {
+        let legacy = "\
+[[kam.dependency.normal]]
+id = \"a\"
+version = \"^1.2\" # legacy key
+source = \"https://example.com/a.zip\"
+
+[kam.dependency]
+dev = [{ id = \"b\", version = 3 }]
+";
+        // Old files load as they are...
+        let doc: toml::Table = toml::from_str(legacy).unwrap();
+        let section: crate::types::kam_toml::sections::DependencySection =
+            doc["kam"]["dependency"].clone().try_into().unwrap();
+        let kam = section.kam.unwrap();
+        assert_eq!(kam[0].id, "a");
+        assert_eq!(
+            kam[0].versionCode,
+            Some(crate::types::kam_toml::sections::VersionSpec::Semver(
+                "^1.2".to_string()
+            ))
+        );
+
+        // ...and migrate to the current names, keeping the key order
+        let (migrated, changes) = KamToml::migrate_str(legacy).unwrap();
+        assert_eq!(changes.len(), 3);
+        assert!(migrated.contains(
+            "id = \"a\"\nversionCode = \"^1.2\" # legacy key\nsource = \"https://example.com/a.zip\""
+        ));
+        assert!(migrated.contains("dev = [{ id = \"b\", versionCode = 3 }]"));
+        let reloaded: toml::Table = toml::from_str(&migrated).unwrap();
+        let migrated_section: crate::types::kam_toml::sections::DependencySection =
+            reloaded["kam"]["dependency"].clone().try_into().unwrap();
+        assert_eq!(migrated_section.kam, Some(kam));
+    }
+}
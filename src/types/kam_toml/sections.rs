@@ -8,15 +8,18 @@ pub mod mmrl;
 pub mod note;
 pub mod options;
 pub mod prop;
+pub mod registry;
 pub mod repo;
 pub mod tmpl;
 pub mod tool;
+pub mod trust;
 
 // Re-export main types
 pub use crate::types::kam_toml::enums::{ModuleType, SupportedArch};
 pub use build::BuildSection;
 pub use dependency::{
-    Dependency, DependencySection, FlatDependencyGroup, FlatDependencyGroups, VersionSpec,
+    Dependency, DependencySection, FlatDependencyGroup, FlatDependencyGroups, SectionLoader,
+    UnsatisfiedPeer, VersionSpec, find_unsatisfied_peers, parse_include,
 };
 pub use kam::KamSection;
 pub use kamlib::LibSection;
@@ -24,7 +27,9 @@ pub use manager::ManagerSection;
 pub use mmrl::MmrlSection;
 pub use note::NoteSection;
 pub use options::OptionsSection;
-pub use prop::PropSection;
+pub use prop::{PropSection, validate_id, validate_version};
+pub use registry::{Registries, RegistrySection};
 pub use repo::RepoSection;
 pub use tmpl::{TmplSection, VariableDefinition};
 pub use tool::ToolSection;
+pub use trust::TrustSection;
@@ -10,24 +10,98 @@ pub struct ExtraInclude {
     pub dest: String,
 }
 
+/// 构建钩子命令：单个命令字符串，或按顺序执行的命令数组
+///
+/// 每条命令通过 `sh -c`（Windows 上为 `cmd /C`）执行，任一命令失败即中止构建。
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum HookCommand {
+    /// 单个命令，如 `pre_build = "make"`
+    Single(String),
+    /// 命令列表，如 `pre_build = ["make", "make check"]`
+    Multiple(Vec<String>),
+}
+
+impl HookCommand {
+    /// 要执行的命令（跳过空白命令）
+    pub fn commands(&self) -> Vec<&str> {
+        let commands: Vec<&str> = match self {
+            HookCommand::Single(cmd) => vec![cmd.as_str()],
+            HookCommand::Multiple(cmds) => cmds.iter().map(String::as_str).collect(),
+        };
+        commands
+            .into_iter()
+            .filter(|cmd| !cmd.trim().is_empty())
+            .collect()
+    }
+}
+
+/// 某一平台的钩子覆盖（`[kam.build.hooks.windows]` / `[kam.build.hooks.unix]`）
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct PlatformHooks {
+    pub pre_build: Option<HookCommand>,
+    pub post_build: Option<HookCommand>,
+}
+
+/// 按平台覆盖的构建钩子（`[kam.build.hooks]`）
+///
+/// - `windows`：在 Windows 上使用
+/// - `unix`：在其他平台上使用
+///
+/// 平台表中设置的钩子优先于 `kam.build.pre_build`/`post_build`，未设置的仍使用后者。
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct BuildHooks {
+    pub windows: Option<PlatformHooks>,
+    pub unix: Option<PlatformHooks>,
+}
+
+impl BuildHooks {
+    /// 当前平台的钩子覆盖
+    pub fn current(&self) -> Option<&PlatformHooks> {
+        if cfg!(target_os = "windows") {
+            self.windows.as_ref()
+        } else {
+            self.unix.as_ref()
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[allow(non_snake_case)]
 /// 打包/构建配置节
 ///
 /// - `target_dir`：打包输出目录，默认 "dist"
-/// - `output_file`：可选的输出文件名（为空时使用 `<id>-<version>.zip`）
-/// - `pre_build` / `post_build`：可选的 shell/PowerShell 钩子命令字符串
+/// - `output_file`：可选的输出文件名（为空时使用 `<id>-<version>.zip`），支持占位符
+///   `{{id}}`、`{{version}}`、`{{versionCode}}`、`{{author}}`，以及构建时的
+///   `{{date}}`（UTC `YYYYMMDD`）、`{{datetime}}`（UTC `YYYYMMDD-HHMMSS`）和
+///   `{{gitsha}}`（HEAD 的短哈希，不在 git 仓库中时为空）
+/// - `pre_build` / `post_build`：可选的钩子命令，字符串或按顺序执行的字符串数组
+/// - `hooks`：按平台（`windows`/`unix`）覆盖 `pre_build`/`post_build`
 /// - `extra_includes`：额外包含的文件列表
-/// - `exclude`：额外的排除路径列表（支持 glob 模式）
-/// - `include`：强制包含的路径列表（覆盖 exclude，支持 glob 模式）
+/// - `exclude`：排除路径列表（glob，相对项目根目录）；同时作用于模块压缩包
+///   （`src/<id>` 下的文件及 `mmrl.repo` 引用的 readme/license/changelog）与源码包
+/// - `include`：包含路径列表（glob，相对项目根目录，覆盖 exclude）；匹配的项目文件
+///   （如 `service.sh`、`META-INF/**`）按原相对路径打入模块压缩包
+/// - `overlay`：叠加目录列表，按顺序合并到模块压缩包的 `src/<id>` 之上（后者优先）
+/// - `generate_installer`：为模块压缩包生成 `customize.sh`，安装时按
+///   `min_api`/`max_api`/`supported_arch`/`conflicts` 检查设备。项目根目录的
+///   `META-INF` 总会原样打包，缺少的 `update-binary`/`updater-script` 由内置的
+///   Magisk 安装脚本补齐（`kam build --no-installer` 可关闭）
+/// - `zip_url`：构建时生成的 `update.json` 中的 `zipUrl`，支持与 `output_file`
+///   相同的占位符；
+///   未设置时取 `prop.updateJson` 所在目录下的模块压缩包
 pub struct BuildSection {
     pub target_dir: Option<String>,
     pub output_file: Option<String>,
-    pub pre_build: Option<String>,
-    pub post_build: Option<String>,
+    pub pre_build: Option<HookCommand>,
+    pub post_build: Option<HookCommand>,
+    pub hooks: Option<BuildHooks>,
     pub extra_includes: Option<Vec<ExtraInclude>>,
     pub exclude: Option<Vec<String>>,
     pub include: Option<Vec<String>>,
+    pub overlay: Option<Vec<String>>,
+    pub generate_installer: Option<bool>,
+    pub zip_url: Option<String>,
 }
 
 impl Default for BuildSection {
@@ -36,25 +110,84 @@ impl Default for BuildSection {
         // On Windows we use a simple echo, on other platforms use echo as well
         // but prefer single quotes to avoid PowerShell vs shell quoting issues.
         let pre = if cfg!(target_os = "windows") {
-            Some("echo \"pre build...\"".to_string())
+            "echo \"pre build...\""
         } else {
-            Some("echo 'pre build...'".to_string())
+            "echo 'pre build...'"
         };
 
         let post = if cfg!(target_os = "windows") {
-            Some("echo \"post build...\"".to_string())
+            "echo \"post build...\""
         } else {
-            Some("echo 'post build...'".to_string())
+            "echo 'post build...'"
         };
 
         BuildSection {
             target_dir: Some("dist".to_string()),
             output_file: Some("{{id}}-{{versionCode}}".to_string()),
-            pre_build: pre,
-            post_build: post,
+            pre_build: Some(HookCommand::Single(pre.to_string())),
+            post_build: Some(HookCommand::Single(post.to_string())),
+            hooks: None,
             extra_includes: None,
             exclude: None,
             include: None,
+            overlay: None,
+            generate_installer: None,
+            zip_url: None,
+        }
+    }
+}
+
+impl BuildSection {
+    /// 当前平台要执行的预构建命令
+    pub fn pre_build_commands(&self) -> Vec<&str> {
+        let platform = self.hooks.as_ref().and_then(BuildHooks::current);
+        platform
+            .and_then(|h| h.pre_build.as_ref())
+            .or(self.pre_build.as_ref())
+            .map(HookCommand::commands)
+            .unwrap_or_default()
+    }
+
+    /// 当前平台要执行的构建后命令
+    pub fn post_build_commands(&self) -> Vec<&str> {
+        let platform = self.hooks.as_ref().and_then(BuildHooks::current);
+        platform
+            .and_then(|h| h.post_build.as_ref())
+            .or(self.post_build.as_ref())
+            .map(HookCommand::commands)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hook_forms_and_platform_overrides() {
+        let build: BuildSection = toml::from_str(
+            r#"
+pre_build = "echo one"
+post_build = ["echo two", "", "echo three"]
+
+[hooks.windows]
+pre_build = ["echo win"]
+
+[hooks.unix]
+post_build = "echo unix"
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            build.pre_build,
+            Some(HookCommand::Single("echo one".to_string()))
+        );
+        if cfg!(target_os = "windows") {
+            assert_eq!(build.pre_build_commands(), ["echo win"]);
+            assert_eq!(build.post_build_commands(), ["echo two", "echo three"]);
+        } else {
+            assert_eq!(build.pre_build_commands(), ["echo one"]);
+            assert_eq!(build.post_build_commands(), ["echo unix"]);
         }
     }
 }
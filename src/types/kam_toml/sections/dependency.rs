@@ -1,24 +1,150 @@
 use crate::errors::KamError;
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// One end of a version range: the bound and whether it is inclusive
+type RangeBound = Option<(i64, bool)>;
 
 /// Version specification for dependencies
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+///
+/// In `kam.toml` an integer is an exact version code, a string in interval
+/// notation (`"[1000,2000)"`) is a version code range, and any other string
+/// is a semantic version requirement matched against `prop.version`
+/// (`"^1.2"`, `"~1.2.3"`, `">=1.0, <2.0"`).
+#[derive(Serialize, Debug, Clone, PartialEq)]
 #[serde(untagged)]
 pub enum VersionSpec {
     /// Exact version code
     Exact(i64),
     /// Version range (e.g., "[1000,2000)")
     Range(String),
+    /// Semantic version requirement (e.g., "^1.2")
+    Semver(String),
+}
+
+impl<'de> Deserialize<'de> for VersionSpec {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Code(i64),
+            Text(String),
+        }
+        Ok(match Raw::deserialize(deserializer)? {
+            Raw::Code(v) => VersionSpec::Exact(v),
+            Raw::Text(s) => VersionSpec::parse(&s),
+        })
+    }
 }
 
 impl VersionSpec {
+    /// Classify a version spec string.
+    ///
+    /// Interval notation and bare numbers are version code ranges; anything
+    /// else is a semantic version requirement.
+    pub fn parse(spec: &str) -> Self {
+        let s = spec.trim();
+        if s.starts_with(['[', '(']) || s.parse::<i64>().is_ok() {
+            VersionSpec::Range(s.to_string())
+        } else {
+            VersionSpec::Semver(s.to_string())
+        }
+    }
+
     pub fn as_display(&self) -> String {
         match self {
             VersionSpec::Exact(v) => v.to_string(),
-            VersionSpec::Range(r) => r.clone(),
+            VersionSpec::Range(r) | VersionSpec::Semver(r) => r.clone(),
+        }
+    }
+
+    /// Check whether a module version satisfies this spec.
+    ///
+    /// `version` is the module's `prop.version` and `version_code` its
+    /// `prop.versionCode`. Exact specs and ranges compare the version code;
+    /// ranges use interval notation: `[1000,2000)`, `[1000,)`, `(,2000]`.
+    /// Semver requirements compare the version string, which is parsed
+    /// leniently (`v1.2` is read as `1.2.0`); an unparseable version or
+    /// requirement never matches.
+    pub fn matches(&self, version: &str, version_code: i64) -> bool {
+        match self {
+            VersionSpec::Exact(_) | VersionSpec::Range(_) => self.matches_code(version_code),
+            VersionSpec::Semver(req) => {
+                match (
+                    semver::VersionReq::parse(req),
+                    parse_lenient_version(version),
+                ) {
+                    (Ok(req), Some(version)) => req.matches(&version),
+                    _ => false,
+                }
+            }
+        }
+    }
+
+    /// Version code comparison for exact specs and ranges. Semver
+    /// requirements carry no version code and never match here.
+    fn matches_code(&self, code: i64) -> bool {
+        match self {
+            VersionSpec::Exact(v) => *v == code,
+            VersionSpec::Range(r) => {
+                let (min, max) = Self::range_bounds(r);
+                min.is_none_or(|(v, incl)| if incl { code >= v } else { code > v })
+                    && max.is_none_or(|(v, incl)| if incl { code <= v } else { code < v })
+            }
+            VersionSpec::Semver(_) => false,
+        }
+    }
+
+    /// Lowest version code allowed by this spec, if it is bounded below
+    pub fn lower_bound(&self) -> Option<i64> {
+        match self {
+            VersionSpec::Exact(v) => Some(*v),
+            VersionSpec::Range(r) => Self::range_bounds(r).0.map(|(v, _)| v),
+            VersionSpec::Semver(_) => None,
+        }
+    }
+
+    /// Parse a range string into `(min, max)` bounds, each carrying whether it
+    /// is inclusive.
+    fn range_bounds(range: &str) -> (RangeBound, RangeBound) {
+        let s = range.trim();
+        if let Ok(v) = s.parse::<i64>() {
+            return (Some((v, true)), Some((v, true)));
         }
+        let min_incl = s.starts_with('[');
+        let max_incl = s.ends_with(']');
+        let inner = s
+            .trim_start_matches(['[', '('])
+            .trim_end_matches([']', ')']);
+        let mut parts = inner.splitn(2, ',').map(|p| p.trim());
+        let min = parts
+            .next()
+            .and_then(|p| p.parse::<i64>().ok())
+            .map(|v| (v, min_incl));
+        let max = parts
+            .next()
+            .and_then(|p| p.parse::<i64>().ok())
+            .map(|v| (v, max_incl));
+        (min, max)
+    }
+}
+
+/// Parse a `prop.version` as semver, accepting a leading `v` and missing
+/// minor/patch components (`v1.2` is `1.2.0`)
+fn parse_lenient_version(version: &str) -> Option<semver::Version> {
+    let v = version.trim();
+    let v = v.strip_prefix(['v', 'V']).unwrap_or(v);
+    if let Ok(parsed) = semver::Version::parse(v) {
+        return Some(parsed);
+    }
+    let split = v.find(['-', '+']).unwrap_or(v.len());
+    let (core, rest) = v.split_at(split);
+    let mut parts: Vec<&str> = core.split('.').collect();
+    if parts.is_empty() || parts.len() > 3 {
+        return None;
     }
+    parts.resize(3, "0");
+    semver::Version::parse(&format!("{}{}", parts.join("."), rest)).ok()
 }
 
 /// A dependency entry
@@ -27,19 +153,78 @@ impl VersionSpec {
 pub struct Dependency {
     /// Module ID
     pub id: String,
-    /// Version specification
+    /// Version specification (the legacy `version` key is read as well)
+    #[serde(alias = "version")]
     pub versionCode: Option<VersionSpec>,
     /// Optional source URL
     pub source: Option<String>,
+    /// Named registry from `[kam.registry]` to fetch from when no `source`
+    /// is given
+    pub registry: Option<String>,
+    /// Features of the dependency to enable (`mylib[net,tls]`)
+    pub features: Option<Vec<String>>,
+    /// Recorded but only installed when enabled, by `kam sync --with <id>`
+    /// or by an enabled feature that gates a dependency with the same id
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub optional: bool,
 }
 
-/// Dependency section with kam and dev groups
+impl Dependency {
+    /// A dependency on any version of `id`, without a source, registry or
+    /// features
+    pub fn new(id: impl Into<String>) -> Self {
+        Dependency {
+            id: id.into(),
+            versionCode: None,
+            source: None,
+            registry: None,
+            features: None,
+            optional: false,
+        }
+    }
+
+    /// Split a `<id>[feature,...]` spec into the module id and the requested
+    /// features. A spec without brackets requests no features.
+    pub fn parse_spec(spec: &str) -> crate::errors::Result<(String, Vec<String>)> {
+        let spec = spec.trim();
+        let Some(open) = spec.find('[') else {
+            return Ok((spec.to_string(), Vec::new()));
+        };
+        let inner = spec[open + 1..].strip_suffix(']').ok_or_else(|| {
+            KamError::InvalidConfig(format!(
+                "invalid dependency spec '{}': expected <id>[feature,...]",
+                spec
+            ))
+        })?;
+        let features = inner
+            .split(',')
+            .map(|f| f.trim())
+            .filter(|f| !f.is_empty())
+            .map(|f| f.to_string())
+            .collect();
+        Ok((spec[..open].trim().to_string(), features))
+    }
+
+    /// Requested features, empty when none are requested
+    pub fn requested_features(&self) -> &[String] {
+        self.features.as_deref().unwrap_or_default()
+    }
+}
+
+/// Dependency section with kam, dev and peer groups
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct DependencySection {
-    /// Runtime dependencies
+    /// Runtime dependencies (the legacy `normal` group is read as well)
+    #[serde(alias = "normal")]
     pub kam: Option<Vec<Dependency>>,
     /// Development dependencies
     pub dev: Option<Vec<Dependency>>,
+    /// Peer dependencies: shared modules that must be provided by the root
+    /// project instead of being installed for this module
+    pub peer: Option<Vec<Dependency>>,
+    /// Named features, each gating extra dependencies that are installed
+    /// only when a dependent requests the feature
+    pub features: Option<BTreeMap<String, Vec<Dependency>>>,
 }
 
 impl Default for DependencySection {
@@ -47,10 +232,77 @@ impl Default for DependencySection {
         DependencySection {
             kam: Some(Vec::new()),
             dev: Some(Vec::new()),
+            peer: None,
+            features: None,
+        }
+    }
+}
+
+/// A peer dependency which the root project does not satisfy
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnsatisfiedPeer {
+    /// Module that declared the peer dependency
+    pub required_by: String,
+    /// The peer dependency as declared
+    pub peer: Dependency,
+    /// Whether the root project provides the module at all
+    pub provided: bool,
+    /// Version code the root provides, when known
+    pub provided_version: Option<i64>,
+}
+
+impl std::fmt::Display for UnsatisfiedPeer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let wanted = self
+            .peer
+            .versionCode
+            .as_ref()
+            .map(|v| v.as_display())
+            .unwrap_or_else(|| "*".to_string());
+        write!(
+            f,
+            "'{}' requires peer '{}@{}'",
+            self.required_by, self.peer.id, wanted
+        )?;
+        match (self.provided, self.provided_version) {
+            (false, _) => write!(f, ", but the root project does not provide it"),
+            (true, Some(v)) => write!(f, ", but the root project provides {}", v),
+            (true, None) => write!(f, ", but the root project does not pin a version"),
         }
     }
 }
 
+/// Check `peers` declared by module `required_by` against the modules
+/// provided by the root project.
+///
+/// `provided` maps module ids to the version (`prop.version`) and version
+/// code the root installs (`None` when the version is unknown). A peer
+/// without a version constraint is satisfied by any provided version.
+pub fn find_unsatisfied_peers(
+    required_by: &str,
+    peers: &[Dependency],
+    provided: &BTreeMap<String, Option<(String, i64)>>,
+) -> Vec<UnsatisfiedPeer> {
+    peers
+        .iter()
+        .filter_map(|peer| {
+            let provided_version = provided.get(&peer.id);
+            let satisfied = match (provided_version, &peer.versionCode) {
+                (None, _) => false,
+                (Some(_), None) => true,
+                (Some(Some((version, code))), Some(spec)) => spec.matches(version, *code),
+                (Some(None), Some(_)) => false,
+            };
+            (!satisfied).then(|| UnsatisfiedPeer {
+                required_by: required_by.to_string(),
+                peer: peer.clone(),
+                provided: provided_version.is_some(),
+                provided_version: provided_version.and_then(|v| v.as_ref().map(|(_, code)| *code)),
+            })
+        })
+        .collect()
+}
+
 #[derive(Debug, Clone)]
 pub struct FlatDependencyGroup {
     pub dependencies: Vec<Dependency>,
@@ -68,6 +320,8 @@ impl Default for FlatDependencyGroup {
 #[derive(Debug, Default, Clone)]
 pub struct FlatDependencyGroups {
     groups: std::collections::BTreeMap<String, FlatDependencyGroup>,
+    /// Enabled features and the dependencies each one gates
+    features: BTreeMap<String, Vec<Dependency>>,
 }
 
 impl FlatDependencyGroups {
@@ -75,74 +329,250 @@ impl FlatDependencyGroups {
     pub fn get(&self, name: &str) -> Option<&FlatDependencyGroup> {
         self.groups.get(name)
     }
+
+    /// Enabled features with the dependencies each one gates
+    pub fn features(&self) -> &BTreeMap<String, Vec<Dependency>> {
+        &self.features
+    }
+
+    /// Enabled features that pulled in the dependency `id`
+    pub fn enabled_by(&self, id: &str) -> Vec<&str> {
+        self.features
+            .iter()
+            .filter(|(_, deps)| deps.iter().any(|d| d.id == id))
+            .map(|(feature, _)| feature.as_str())
+            .collect()
+    }
 }
 
 impl DependencySection {
-    /// Resolve dependencies into flattened groups, supporting include syntax with recursion and cycle detection
+    /// Names of the features this module declares
+    pub fn declared_features(&self) -> Vec<&str> {
+        self.features
+            .as_ref()
+            .map(|f| f.keys().map(|k| k.as_str()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Dependencies gated by the given features, in feature order.
+    ///
+    /// Fails with `UnknownFeature` if a feature is not declared.
+    pub fn feature_dependencies(
+        &self,
+        module_id: &str,
+        features: &[String],
+    ) -> crate::errors::Result<Vec<Dependency>> {
+        let mut deps = Vec::new();
+        for feature in features {
+            let gated = self
+                .features
+                .as_ref()
+                .and_then(|f| f.get(feature))
+                .ok_or_else(|| {
+                    KamError::UnknownFeature(format!(
+                        "'{}' does not declare feature '{}' (available: {})",
+                        module_id,
+                        feature,
+                        self.declared_features().join(", ")
+                    ))
+                })?;
+            for dep in gated {
+                if !deps.iter().any(|d: &Dependency| d.id == dep.id) {
+                    deps.push(dep.clone());
+                }
+            }
+        }
+        Ok(deps)
+    }
+
+    /// Resolve dependencies into flattened groups, supporting include syntax with recursion and cycle detection.
+    /// Optional dependencies are left out.
     pub fn resolve(&self) -> crate::errors::Result<FlatDependencyGroups> {
-        use std::collections::{BTreeMap, HashSet};
+        self.resolve_with_features(&[])
+    }
+
+    /// Resolve like [`resolve`](Self::resolve) with the given features of
+    /// this module enabled: the dependencies each feature gates are added to
+    /// the runtime (`kam`) group unless it already has them, and optional
+    /// dependencies with the id of a gated one are kept.
+    ///
+    /// Fails with `UnknownFeature` if a feature is not declared.
+    pub fn resolve_with_features(
+        &self,
+        features: &[String],
+    ) -> crate::errors::Result<FlatDependencyGroups> {
+        self.resolve_with_modules(features, &[], &|id| {
+            Err(KamError::DependencyResolutionFailed(format!(
+                "cannot include groups of module '{}' here",
+                id
+            )))
+        })
+    }
 
+    /// Resolve like [`resolve_with_features`](Self::resolve_with_features),
+    /// also keeping the optional dependencies whose ids are in `with` and
+    /// expanding `include:<module-id>#<group>` entries: `load` returns the
+    /// dependency section of the module with that id, whose group is merged
+    /// in. Include cycles are detected across modules.
+    pub fn resolve_with_modules(
+        &self,
+        features: &[String],
+        with: &[String],
+        load: &SectionLoader<'_>,
+    ) -> crate::errors::Result<FlatDependencyGroups> {
+        let mut active: BTreeSet<String> = with.iter().cloned().collect();
+        for dep in self.feature_dependencies("the project", features)? {
+            active.insert(dep.id);
+        }
+        let mut resolver = GroupResolver {
+            load,
+            active,
+            modules: BTreeMap::new(),
+            visiting: Vec::new(),
+        };
         let mut groups = BTreeMap::new();
-        let mut visited = HashSet::new();
 
         // Resolve each predefined group
-        self.resolve_group("kam", &mut groups, &mut visited)?;
-        self.resolve_group("dev", &mut groups, &mut visited)?;
+        for group in ["kam", "dev", "peer"] {
+            let dependencies = resolver.resolve(None, self, group)?;
+            groups.insert(group.to_string(), FlatDependencyGroup { dependencies });
+        }
 
-        Ok(FlatDependencyGroups { groups })
+        let mut enabled = BTreeMap::new();
+        let runtime: &mut FlatDependencyGroup = groups.entry("kam".to_string()).or_default();
+        for feature in features {
+            let gated = self.feature_dependencies("the project", std::slice::from_ref(feature))?;
+            for dep in &gated {
+                if !runtime.dependencies.iter().any(|d| d.id == dep.id) {
+                    runtime.dependencies.push(dep.clone());
+                }
+            }
+            enabled.insert(feature.clone(), gated);
+        }
+
+        Ok(FlatDependencyGroups {
+            groups,
+            features: enabled,
+        })
     }
 
-    /// Recursively resolve a dependency group, handling includes
-    fn resolve_group(
-        &self,
+    /// Ids of the optional dependencies declared in the `kam`, `dev` and
+    /// `peer` groups
+    pub fn optional_ids(&self) -> Vec<&str> {
+        ["kam", "dev", "peer"]
+            .into_iter()
+            .flat_map(|group| self.group(group).unwrap_or_default())
+            .filter(|dep| dep.optional)
+            .map(|dep| dep.id.as_str())
+            .collect()
+    }
+
+    /// The entries of one of the `kam`, `dev` and `peer` groups
+    pub fn group(&self, name: &str) -> Option<&[Dependency]> {
+        let deps = match name {
+            "kam" => &self.kam,
+            "dev" => &self.dev,
+            "peer" => &self.peer,
+            _ => return None,
+        };
+        Some(deps.as_deref().unwrap_or_default())
+    }
+}
+
+/// Split the target of an `include:` entry into the module it names (for
+/// `<module-id>#<group>`) and the group
+pub fn parse_include(include: &str) -> (Option<&str>, &str) {
+    match include.split_once('#') {
+        Some((module, group)) => (Some(module), group),
+        None => (None, include),
+    }
+}
+
+/// Loads the dependency section of a module by id, for
+/// `include:<module-id>#<group>` entries
+pub type SectionLoader<'a> = dyn Fn(&str) -> crate::errors::Result<DependencySection> + 'a;
+
+/// Expands the `include:` entries of dependency groups, across modules
+struct GroupResolver<'a> {
+    load: &'a SectionLoader<'a>,
+    /// Ids of the optional dependencies to keep
+    active: BTreeSet<String>,
+    /// Dependency sections of the included modules, by id
+    modules: BTreeMap<String, DependencySection>,
+    /// Groups being expanded, outermost first (`<module-id>#<group>` for
+    /// groups of other modules)
+    visiting: Vec<String>,
+}
+
+impl GroupResolver<'_> {
+    /// Recursively resolve group `group_name` of `section`, which belongs to
+    /// module `owner` (`None` for the project itself)
+    fn resolve(
+        &mut self,
+        owner: Option<&str>,
+        section: &DependencySection,
         group_name: &str,
-        resolved_groups: &mut BTreeMap<String, FlatDependencyGroup>,
-        visited: &mut HashSet<String>,
-    ) -> crate::errors::Result<()> {
-        if !visited.insert(group_name.to_string()) {
+    ) -> crate::errors::Result<Vec<Dependency>> {
+        let label = match owner {
+            Some(id) => format!("{}#{}", id, group_name),
+            None => group_name.to_string(),
+        };
+        let owner_name = match owner {
+            Some(id) => format!("module '{}'", id),
+            None => "the project".to_string(),
+        };
+        if self.visiting.contains(&label) {
             return Err(KamError::DependencyResolutionFailed(format!(
-                "Circular dependency detected involving group '{}'",
-                group_name
+                "Circular dependency detected involving group '{}' of {} ({} -> {})",
+                group_name,
+                owner_name,
+                self.visiting.join(" -> "),
+                label
             )));
         }
+        let deps = section.group(group_name).ok_or_else(|| {
+            KamError::DependencyResolutionFailed(format!(
+                "Unknown dependency group '{}' in {}",
+                group_name, owner_name
+            ))
+        })?;
 
-        let empty = Vec::new();
-        let deps = match group_name {
-            "kam" => self.kam.as_ref().unwrap_or(&empty),
-            "dev" => self.dev.as_ref().unwrap_or(&empty),
-            _ => {
-                return Err(KamError::DependencyResolutionFailed(format!(
-                    "Unknown dependency group '{}'",
-                    group_name
-                )));
-            }
-        };
-
+        self.visiting.push(label);
         let mut flattened = Vec::new();
-
         for dep in deps {
-            if let Some(include_group) = dep.id.strip_prefix("include:") {
-                // Recursively resolve the included group
-                self.resolve_group(include_group, resolved_groups, visited)?;
-                // Add the dependencies from the included group
-                if let Some(included) = resolved_groups.get(include_group) {
-                    flattened.extend(included.dependencies.clone());
+            let Some(include) = dep.id.strip_prefix("include:") else {
+                if !dep.optional || self.active.contains(&dep.id) {
+                    flattened.push(dep.clone());
                 }
-            } else {
-                flattened.push(dep.clone());
+                continue;
+            };
+            // Add the dependencies from the included group
+            match parse_include(include) {
+                (Some(module), group) => {
+                    let included = self.module(module)?;
+                    flattened.extend(self.resolve(Some(module), &included, group)?);
+                }
+                (None, group) => flattened.extend(self.resolve(owner, section, group)?),
             }
         }
+        self.visiting.pop();
 
-        resolved_groups.insert(
-            group_name.to_string(),
-            FlatDependencyGroup {
-                dependencies: flattened,
-            },
-        );
-
-        visited.remove(group_name);
+        Ok(flattened)
+    }
 
-        Ok(())
+    /// The dependency section of module `id`, loaded once
+    fn module(&mut self, id: &str) -> crate::errors::Result<DependencySection> {
+        if id.is_empty() {
+            return Err(KamError::DependencyResolutionFailed(
+                "include:<module-id>#<group> is missing the module id".to_string(),
+            ));
+        }
+        if let Some(section) = self.modules.get(id) {
+            return Ok(section.clone());
+        }
+        let section = (self.load)(id)?;
+        self.modules.insert(id.to_string(), section.clone());
+        Ok(section)
     }
 }
 
@@ -154,15 +584,15 @@ mod tests {
     fn test_resolve_simple() {
         let dep_section = DependencySection {
             kam: Some(vec![Dependency {
-                id: "lib1".to_string(),
                 versionCode: Some(VersionSpec::Exact(100i64)),
-                source: None,
+                ..Dependency::new("lib1")
             }]),
             dev: Some(vec![Dependency {
-                id: "lib2".to_string(),
                 versionCode: Some(VersionSpec::Exact(200i64)),
-                source: None,
+                ..Dependency::new("lib2")
             }]),
+            peer: None,
+            features: None,
         };
 
         let result = dep_section.resolve().unwrap();
@@ -177,21 +607,17 @@ mod tests {
         let dep_section = DependencySection {
             kam: Some(vec![
                 Dependency {
-                    id: "lib1".to_string(),
                     versionCode: Some(VersionSpec::Exact(100i64)),
-                    source: None,
-                },
-                Dependency {
-                    id: "include:dev".to_string(),
-                    versionCode: None,
-                    source: None,
+                    ..Dependency::new("lib1")
                 },
+                Dependency::new("include:dev"),
             ]),
             dev: Some(vec![Dependency {
-                id: "lib2".to_string(),
                 versionCode: Some(VersionSpec::Exact(200)),
-                source: None,
+                ..Dependency::new("lib2")
             }]),
+            peer: None,
+            features: None,
         };
 
         let result = dep_section.resolve().unwrap();
@@ -202,19 +628,114 @@ mod tests {
         assert_eq!(result.get("dev").unwrap().dependencies[0].id, "lib2");
     }
 
+    #[test]
+    fn test_resolve_with_features() {
+        let mut declared = BTreeMap::new();
+        declared.insert(
+            "webroot".to_string(),
+            vec![Dependency::new("webui"), Dependency::new("lib1")],
+        );
+        declared.insert("net".to_string(), vec![Dependency::new("curl")]);
+        let dep_section = DependencySection {
+            kam: Some(vec![
+                Dependency::new("lib1"),
+                Dependency::new("include:dev"),
+            ]),
+            dev: Some(vec![Dependency::new("lib2")]),
+            peer: None,
+            features: Some(declared),
+        };
+
+        let result = dep_section.resolve().unwrap();
+        assert_eq!(result.get("kam").unwrap().dependencies.len(), 2);
+        assert!(result.features().is_empty());
+
+        let result = dep_section
+            .resolve_with_features(&["webroot".to_string()])
+            .unwrap();
+        let ids: Vec<&str> = result
+            .get("kam")
+            .unwrap()
+            .dependencies
+            .iter()
+            .map(|d| d.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["lib1", "lib2", "webui"]);
+        assert_eq!(result.get("dev").unwrap().dependencies.len(), 1);
+        assert_eq!(result.enabled_by("webui"), vec!["webroot"]);
+        assert_eq!(result.enabled_by("lib1"), vec!["webroot"]);
+        assert!(result.enabled_by("curl").is_empty());
+
+        assert!(
+            dep_section
+                .resolve_with_features(&["gui".to_string()])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_resolve_optional() {
+        let parsed: DependencySection = toml::from_str(
+            r#"
+[[kam]]
+id = "core"
+
+[[kam]]
+id = "webui"
+optional = true
+
+[[kam]]
+id = "include:dev"
+
+[[dev]]
+id = "bench"
+optional = true
+
+[features]
+ui = [{ id = "webui" }]
+"#,
+        )
+        .unwrap();
+        assert_eq!(parsed.optional_ids(), vec!["webui", "bench"]);
+        let ids = |result: &FlatDependencyGroups, group: &str| -> Vec<String> {
+            result
+                .get(group)
+                .unwrap()
+                .dependencies
+                .iter()
+                .map(|d| d.id.clone())
+                .collect()
+        };
+        let load = |id: &str| -> crate::errors::Result<DependencySection> {
+            Err(KamError::DependencyResolutionFailed(id.to_string()))
+        };
+
+        // Excluded by default, also when reached through an include
+        let result = parsed.resolve().unwrap();
+        assert_eq!(ids(&result, "kam"), vec!["core"]);
+        assert!(ids(&result, "dev").is_empty());
+
+        // Included when requested by id or by an enabled feature
+        let with = vec!["webui".to_string(), "bench".to_string()];
+        let result = parsed.resolve_with_modules(&[], &with, &load).unwrap();
+        assert_eq!(ids(&result, "kam"), vec!["core", "webui", "bench"]);
+        assert_eq!(ids(&result, "dev"), vec!["bench"]);
+        let result = parsed.resolve_with_features(&["ui".to_string()]).unwrap();
+        assert_eq!(ids(&result, "kam"), vec!["core", "webui"]);
+        assert!(result.get("kam").unwrap().dependencies[1].optional);
+
+        // Only set flags are written back
+        let written = toml::to_string(&parsed.kam.unwrap()[0]).unwrap();
+        assert!(!written.contains("optional"));
+    }
+
     #[test]
     fn test_resolve_circular_dependency() {
         let dep_section = DependencySection {
-            kam: Some(vec![Dependency {
-                id: "include:dev".to_string(),
-                versionCode: None,
-                source: None,
-            }]),
-            dev: Some(vec![Dependency {
-                id: "include:kam".to_string(),
-                versionCode: None,
-                source: None,
-            }]),
+            kam: Some(vec![Dependency::new("include:dev")]),
+            dev: Some(vec![Dependency::new("include:kam")]),
+            peer: None,
+            features: None,
         };
 
         let result = dep_section.resolve();
@@ -227,15 +748,85 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_resolve_include_from_other_module() {
+        let section = |kam: Vec<Dependency>, dev: Vec<Dependency>| DependencySection {
+            kam: Some(kam),
+            dev: Some(dev),
+            peer: None,
+            features: None,
+        };
+        let mut modules = BTreeMap::new();
+        modules.insert(
+            "base".to_string(),
+            section(
+                vec![Dependency::new("busybox"), Dependency::new("include:dev")],
+                vec![Dependency::new("shellcheck")],
+            ),
+        );
+        modules.insert(
+            "loop-a".to_string(),
+            section(vec![Dependency::new("include:loop-b#kam")], vec![]),
+        );
+        modules.insert(
+            "loop-b".to_string(),
+            section(vec![Dependency::new("include:loop-a#kam")], vec![]),
+        );
+        let loads = std::cell::Cell::new(0);
+        let load = |id: &str| {
+            loads.set(loads.get() + 1);
+            modules.get(id).cloned().ok_or_else(|| {
+                KamError::DependencyResolutionFailed(format!("'{}' is not cached", id))
+            })
+        };
+
+        // The included module's group is merged, with its own includes
+        let project = section(
+            vec![Dependency::new("lib1"), Dependency::new("include:base#kam")],
+            vec![Dependency::new("include:base#dev")],
+        );
+        let result = project.resolve_with_modules(&[], &[], &load).unwrap();
+        let ids = |group: &str| -> Vec<String> {
+            result
+                .get(group)
+                .unwrap()
+                .dependencies
+                .iter()
+                .map(|d| d.id.clone())
+                .collect()
+        };
+        assert_eq!(ids("kam"), vec!["lib1", "busybox", "shellcheck"]);
+        assert_eq!(ids("dev"), vec!["shellcheck"]);
+        assert_eq!(loads.get(), 1);
+
+        // Errors name the module owning the group
+        let cycle = section(vec![Dependency::new("include:loop-a#kam")], vec![])
+            .resolve_with_modules(&[], &[], &load)
+            .unwrap_err()
+            .to_string();
+        assert!(cycle.contains("Circular dependency"));
+        assert!(cycle.contains("module 'loop-a'"));
+        let unknown = section(vec![Dependency::new("include:base#extras")], vec![])
+            .resolve_with_modules(&[], &[], &load)
+            .unwrap_err()
+            .to_string();
+        assert!(unknown.contains("Unknown dependency group 'extras' in module 'base'"));
+        assert!(
+            section(vec![Dependency::new("include:missing#kam")], vec![])
+                .resolve_with_modules(&[], &[], &load)
+                .is_err()
+        );
+        // Without a loader, other modules can't be included
+        assert!(project.resolve().is_err());
+    }
+
     #[test]
     fn test_resolve_unknown_group() {
         let dep_section = DependencySection {
-            kam: Some(vec![Dependency {
-                id: "include:unknown".to_string(),
-                versionCode: None,
-                source: None,
-            }]),
+            kam: Some(vec![Dependency::new("include:unknown")]),
             dev: None,
+            peer: None,
+            features: None,
         };
 
         let result = dep_section.resolve();
@@ -247,4 +838,128 @@ mod tests {
                 .contains("Unknown dependency group")
         );
     }
+
+    #[test]
+    fn test_version_spec_matches_code() {
+        let range = VersionSpec::Range("[1000,2000)".to_string());
+        assert!(range.matches("", 1000));
+        assert!(range.matches("", 1999));
+        assert!(!range.matches("", 2000));
+        assert!(!range.matches("", 999));
+        let exclusive = VersionSpec::Range("(1000,2000]".to_string());
+        assert!(!exclusive.matches("", 1000));
+        assert!(exclusive.matches("", 2000));
+        assert!(VersionSpec::Range("(,2000]".to_string()).matches("", i64::MIN));
+        assert!(VersionSpec::Range("[1000,)".to_string()).matches("", i64::MAX));
+        assert!(VersionSpec::Exact(5).matches("", 5));
+        assert_eq!(range.lower_bound(), Some(1000));
+        assert_eq!(
+            VersionSpec::Range("(,2000]".to_string()).lower_bound(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_version_spec_semver() {
+        let caret = VersionSpec::parse("^1.2");
+        assert_eq!(caret, VersionSpec::Semver("^1.2".to_string()));
+        assert!(caret.matches("1.2.0", 0));
+        assert!(caret.matches("v1.9", 0));
+        assert!(!caret.matches("2.0.0", 0));
+        assert!(!caret.matches("1.1.9", 0));
+
+        let tilde = VersionSpec::parse("~1.2.3");
+        assert!(tilde.matches("1.2.9", 0));
+        assert!(!tilde.matches("1.3.0", 0));
+
+        let bounded = VersionSpec::parse(">=1.0, <2.0");
+        assert!(bounded.matches("1.0", 0));
+        assert!(bounded.matches("1.99.0", 0));
+        assert!(!bounded.matches("2.0.0", 0));
+        assert!(VersionSpec::parse(">1.0.0").matches("1.0.1", 0));
+        assert!(!VersionSpec::parse(">1.0.0").matches("1.0.0", 0));
+        assert!(VersionSpec::parse("<=2.0").matches("2.0.0", 0));
+        assert!(!caret.matches("not a version", 0));
+
+        assert_eq!(
+            VersionSpec::parse("[1,2)"),
+            VersionSpec::Range("[1,2)".to_string())
+        );
+        assert_eq!(
+            VersionSpec::parse("100"),
+            VersionSpec::Range("100".to_string())
+        );
+        assert_eq!(caret.lower_bound(), None);
+
+        #[derive(Deserialize)]
+        struct Wrapper {
+            v: Vec<VersionSpec>,
+        }
+        let parsed: Wrapper = toml::from_str(r#"v = [100, "[1,2)", "^1.2"]"#).unwrap();
+        assert_eq!(
+            parsed.v,
+            vec![
+                VersionSpec::Exact(100),
+                VersionSpec::Range("[1,2)".to_string()),
+                VersionSpec::Semver("^1.2".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_unsatisfied_peers() {
+        let peer = |id: &str, spec: Option<VersionSpec>| Dependency {
+            versionCode: spec,
+            ..Dependency::new(id)
+        };
+        let peers = vec![
+            peer("shared", Some(VersionSpec::Range("[100,200)".to_string()))),
+            peer("other", Some(VersionSpec::Exact(3))),
+            peer("any", None),
+        ];
+        let mut provided = BTreeMap::new();
+        provided.insert("shared".to_string(), Some(("1.5.0".to_string(), 150)));
+        provided.insert("any".to_string(), None);
+
+        let issues = find_unsatisfied_peers("plugin", &peers, &provided);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].peer.id, "other");
+        assert!(!issues[0].provided);
+
+        provided.insert("shared".to_string(), Some(("2.5.0".to_string(), 250)));
+        let issues = find_unsatisfied_peers("plugin", &peers, &provided);
+        assert_eq!(issues.len(), 2);
+        assert!(issues[0].to_string().contains("provides 250"));
+    }
+
+    #[test]
+    fn test_features() {
+        let (id, features) = Dependency::parse_spec("mylib[net, tls]").unwrap();
+        assert_eq!(id, "mylib");
+        assert_eq!(features, vec!["net", "tls"]);
+        assert_eq!(Dependency::parse_spec("mylib").unwrap().1.len(), 0);
+        assert!(Dependency::parse_spec("mylib[net").is_err());
+
+        let mut declared = BTreeMap::new();
+        declared.insert("net".to_string(), vec![Dependency::new("curl")]);
+        declared.insert(
+            "tls".to_string(),
+            vec![Dependency::new("openssl"), Dependency::new("curl")],
+        );
+        let section = DependencySection {
+            features: Some(declared),
+            ..Default::default()
+        };
+
+        let deps = section.feature_dependencies("mylib", &features).unwrap();
+        let ids: Vec<&str> = deps.iter().map(|d| d.id.as_str()).collect();
+        assert_eq!(ids, vec!["curl", "openssl"]);
+        assert!(
+            section
+                .feature_dependencies("mylib", &["gui".to_string()])
+                .unwrap_err()
+                .to_string()
+                .contains("available: net, tls")
+        );
+    }
 }
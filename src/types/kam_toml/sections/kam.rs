@@ -1,6 +1,6 @@
 use super::{
-    BuildSection, DependencySection, LibSection, ModuleType, SupportedArch, TmplSection,
-    ToolSection,
+    BuildSection, DependencySection, LibSection, ModuleType, RegistrySection, SupportedArch,
+    TmplSection, ToolSection, TrustSection,
 };
 use crate::types::kam_toml::WorkspaceSection;
 use serde::{Deserialize, Serialize};
@@ -22,6 +22,10 @@ pub struct KamSection {
     pub conflicts: Option<Vec<String>>,
     /// 依赖声明（分 kam / dev）
     pub dependency: Option<DependencySection>,
+    /// 依赖下载来源（默认注册表与命名注册表）
+    pub registry: Option<RegistrySection>,
+    /// 为依赖固定的签名公钥
+    pub trust: Option<TrustSection>,
     /// 打包/构建相关的配置
     pub build: Option<BuildSection>,
     /// 模块类型（kam/template/library）
@@ -44,6 +48,8 @@ impl Default for KamSection {
             supported_arch: Some(Vec::new()),
             conflicts: Some(Vec::new()),
             dependency: Some(DependencySection::default()),
+            registry: None,
+            trust: None,
             build: Some(BuildSection::default()),
             module_type: ModuleType::Kam,
             tmpl: Some(TmplSection::default()),
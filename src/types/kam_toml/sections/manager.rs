@@ -14,6 +14,13 @@ pub struct ManagerConfig {
     pub require: Option<Vec<String>>,
 }
 
+impl ManagerConfig {
+    /// 已安装的管理器版本号 `version_code` 是否满足 `min`（未设置 `min` 时总是满足）
+    pub fn satisfied_by(&self, version_code: i64) -> bool {
+        self.min.is_none_or(|min| version_code >= min)
+    }
+}
+
 impl Default for ManagerConfig {
     fn default() -> Self {
         ManagerConfig {
@@ -46,3 +53,54 @@ impl Default for ManagerSection {
         }
     }
 }
+
+impl ManagerSection {
+    /// 按名称（`magisk`、`kernelsu`、`apatch`）获取管理器配置
+    pub fn get(&self, name: &str) -> Option<&ManagerConfig> {
+        match name {
+            "magisk" => self.magisk.as_ref(),
+            "kernelsu" => self.kernelsu.as_ref(),
+            "apatch" => self.apatch.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// 检查各管理器的 `min` 是否为合法的版本号（正整数），每个问题返回一条消息
+    pub fn validate(&self) -> Vec<String> {
+        ["magisk", "kernelsu", "apatch"]
+            .into_iter()
+            .filter_map(|name| {
+                let min = self.get(name)?.min?;
+                (min <= 0).then(|| {
+                    format!(
+                        "mmrl.repo.manager.{}.min must be a positive version code, got {}",
+                        name, min
+                    )
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manager_min_versions() {
+        let mut section = ManagerSection::default();
+        section.magisk.as_mut().unwrap().min = Some(26000);
+        section.apatch.as_mut().unwrap().min = Some(0);
+
+        let magisk = section.get("magisk").unwrap();
+        assert!(magisk.satisfied_by(27000));
+        assert!(magisk.satisfied_by(26000));
+        assert!(!magisk.satisfied_by(25200));
+        assert!(section.get("kernelsu").unwrap().satisfied_by(1));
+        assert!(section.get("ksu").is_none());
+        assert_eq!(
+            section.validate(),
+            vec!["mmrl.repo.manager.apatch.min must be a positive version code, got 0"]
+        );
+    }
+}
@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 #[allow(non_snake_case)]
 /// 归档选项（比如压缩算法名称或参数）
 pub struct ArchiveOptions {
-    /// 压缩方式（如 "Deflate"、"Store" 等），空字符串表示未指定
+    /// 模块 zip 的压缩方式（"Store"、"Deflate"、"Bzip2" 或 "Zstd"），空字符串表示默认的 Deflate
     pub compression: Option<String>,
 }
 
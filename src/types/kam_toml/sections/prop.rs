@@ -1,6 +1,38 @@
+use crate::errors::KamTomlError;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
+/// Validate a module id against the Magisk rule `^[a-zA-Z][a-zA-Z0-9._-]+$`
+pub fn validate_id(id: &str) -> Result<(), KamTomlError> {
+    let mut chars = id.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && id.len() >= 2
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+    if valid {
+        Ok(())
+    } else {
+        Err(KamTomlError::InvalidId(format!(
+            "'{}' must start with a letter and contain only letters, digits, '.', '_' or '-' (at least 2 characters)",
+            id
+        )))
+    }
+}
+
+/// Validate a module version against the `x.y.z` format (three numeric
+/// components)
+pub fn validate_version(version: &str) -> Result<(), KamTomlError> {
+    let parts: Vec<&str> = version.split('.').collect();
+    let valid = parts.len() == 3
+        && parts
+            .iter()
+            .all(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_digit()));
+    if valid {
+        Ok(())
+    } else {
+        Err(KamTomlError::InvalidVersionFormat)
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[allow(non_snake_case)]
 pub struct PropSection {
@@ -33,6 +65,27 @@ impl PropSection {
             ""
         }
     }
+
+    /// Render the flat Magisk `module.prop`. Localized name/description
+    /// collapse to `en` (or the first available language); `updateJson` is
+    /// omitted when unset.
+    pub fn to_module_prop(&self) -> String {
+        // module.prop is line based, so values must stay on one line
+        let flat = |s: &str| s.replace(['\r', '\n'], " ");
+        let mut prop = format!(
+            "id={}\nname={}\nversion={}\nversionCode={}\nauthor={}\ndescription={}\n",
+            flat(&self.id),
+            flat(self.get_name()),
+            flat(&self.version),
+            self.versionCode,
+            flat(&self.author),
+            flat(self.get_description()),
+        );
+        if let Some(update_json) = self.updateJson.as_deref().filter(|u| !u.trim().is_empty()) {
+            prop.push_str(&format!("updateJson={}\n", flat(update_json)));
+        }
+        prop
+    }
 }
 
 impl Default for PropSection {
@@ -52,3 +105,34 @@ impl Default for PropSection {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_module_prop() {
+        let mut prop = PropSection::default();
+        prop.name
+            .insert("zh-CN".to_string(), "我的模块".to_string());
+        prop.description
+            .insert("en".to_string(), "Line one\nline two".to_string());
+        assert_eq!(
+            prop.to_module_prop(),
+            "id=my_module\nname=My Module\nversion=0.1.0\nversionCode=1\nauthor=Author\n\
+             description=Line one line two\nupdateJson=https://example.com/update.json\n"
+        );
+
+        prop.updateJson = Some(String::new());
+        assert!(!prop.to_module_prop().contains("updateJson"));
+    }
+
+    #[test]
+    fn test_validate_version() {
+        assert!(validate_version("1.0.0").is_ok());
+        assert!(validate_version("10.20.300").is_ok());
+        for bad in ["1.0", "1.0.0.0", "v1.0.0", "1.0.0-beta", "1..0", ""] {
+            assert!(validate_version(bad).is_err(), "{}", bad);
+        }
+    }
+}
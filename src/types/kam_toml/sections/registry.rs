@@ -0,0 +1,110 @@
+use super::Dependency;
+use crate::errors::KamError;
+use crate::types::modules::DEFAULT_DEPENDENCY_SOURCE;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Environment variable overriding the built-in default registry
+pub const DEFAULT_REGISTRY_ENV: &str = "KAM_DEFAULT_REGISTRY";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+/// `[kam.registry]`：依赖的下载来源（例如团队自建的镜像）
+///
+/// ```toml
+/// [kam.registry]
+/// default = "https://git.example.com/team/Kam-Index"
+/// internal = "https://git.example.com/team/internal-modules"
+/// ```
+pub struct RegistrySection {
+    /// 未指定 `source` 的依赖所使用的默认注册表 URL
+    pub default: Option<String>,
+    /// 命名注册表（名称 -> URL），依赖通过 `registry = "<名称>"` 选用
+    #[serde(flatten)]
+    pub named: BTreeMap<String, String>,
+}
+
+/// Registries a project fetches its dependencies from, with the default
+/// already resolved
+#[derive(Debug, Clone, PartialEq)]
+pub struct Registries {
+    default: String,
+    named: BTreeMap<String, String>,
+}
+
+impl Registries {
+    /// Resolve the registries of a project: the `kam.toml` default, then
+    /// `KAM_DEFAULT_REGISTRY`, then [`DEFAULT_DEPENDENCY_SOURCE`]
+    pub fn new(section: Option<&RegistrySection>) -> Self {
+        let default = section
+            .and_then(|s| s.default.clone())
+            .filter(|url| !url.trim().is_empty())
+            .or_else(|| {
+                std::env::var(DEFAULT_REGISTRY_ENV)
+                    .ok()
+                    .filter(|url| !url.trim().is_empty())
+            })
+            .unwrap_or_else(|| DEFAULT_DEPENDENCY_SOURCE.to_string());
+        Registries {
+            default,
+            named: section.map(|s| s.named.clone()).unwrap_or_default(),
+        }
+    }
+
+    /// Source URL used for dependencies that name neither a `source` nor a
+    /// `registry`
+    pub fn default_source(&self) -> &str {
+        &self.default
+    }
+
+    /// Source URL of `dep`: its own `source`, then its named `registry`,
+    /// then the default registry
+    pub fn source_for(&self, dep: &Dependency) -> Result<String, KamError> {
+        if let Some(source) = &dep.source {
+            return Ok(source.clone());
+        }
+        match &dep.registry {
+            Some(name) => self.named.get(name).cloned().ok_or_else(|| {
+                KamError::InvalidConfig(format!(
+                    "dependency '{}' uses registry '{}', which is not declared in [kam.registry]",
+                    dep.id, name
+                ))
+            }),
+            None => Ok(self.default.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_precedence() {
+        let section: RegistrySection = toml::from_str(
+            "default = \"https://mirror.example.com/index\"\ninternal = \"https://internal.example.com/mods\"\n",
+        )
+        .unwrap();
+        let registries = Registries::new(Some(&section));
+        let dep = |source: Option<&str>, registry: Option<&str>| Dependency {
+            source: source.map(str::to_string),
+            registry: registry.map(str::to_string),
+            ..Dependency::new("lib")
+        };
+
+        assert_eq!(
+            registries
+                .source_for(&dep(Some("https://own.example.com"), Some("internal")))
+                .unwrap(),
+            "https://own.example.com"
+        );
+        assert_eq!(
+            registries.source_for(&dep(None, Some("internal"))).unwrap(),
+            "https://internal.example.com/mods"
+        );
+        assert_eq!(
+            registries.source_for(&dep(None, None)).unwrap(),
+            "https://mirror.example.com/index"
+        );
+        assert!(registries.source_for(&dep(None, Some("missing"))).is_err());
+    }
+}
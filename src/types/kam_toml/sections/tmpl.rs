@@ -1,3 +1,4 @@
+use crate::errors::KamError;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
@@ -8,6 +9,9 @@ use std::collections::BTreeMap;
 /// 用于描述模板中可被替换的变量的类型、是否必需以及可选的默认值。
 pub struct VariableDefinition {
     /// 变量类型（例如 "string"、"bool"、"number" 等，自由约定）
+    ///
+    /// `bool`/`boolean` 与 `number`/`int`/`integer` 会在初始化时校验，
+    /// 其余类型不做检查。
     pub var_type: String,
     /// 是否为必需变量（未提供时模板引擎应报错或提示）
     pub required: bool,
@@ -24,9 +28,45 @@ pub struct VariableDefinition {
     /// 示例值，供模板作者给出可选的示例输入。
     pub example: Option<String>,
     /// 可选的枚举候选项，模板或交互式提示可以用来展示可选值。
+    ///
+    /// 设置后，变量的值必须是其中之一。
     pub choices: Option<Vec<String>>,
 }
 
+impl VariableDefinition {
+    /// Check `value` of variable `name` against `choices` and `var_type`,
+    /// returning the value to substitute: booleans are normalized to
+    /// `true`/`false` (`yes`, `y`, `1`, ... are accepted) and numbers are
+    /// trimmed.
+    pub fn validate(&self, name: &str, value: &str) -> Result<String, KamError> {
+        let invalid = |reason: String| {
+            KamError::InvalidTemplateVar(format!("'{}' = '{}': {}", name, value, reason))
+        };
+        if let Some(choices) = &self.choices
+            && !choices.iter().any(|c| c == value)
+        {
+            return Err(invalid(format!("expected one of {}", choices.join(", "))));
+        }
+        let trimmed = value.trim();
+        match self.var_type.to_ascii_lowercase().as_str() {
+            "bool" | "boolean" => match trimmed.to_ascii_lowercase().as_str() {
+                "true" | "yes" | "y" | "on" | "1" => Ok("true".to_string()),
+                "false" | "no" | "n" | "off" | "0" => Ok("false".to_string()),
+                _ => Err(invalid("expected a boolean (true/false)".to_string())),
+            },
+            "int" | "integer" => match trimmed.parse::<i64>() {
+                Ok(_) => Ok(trimmed.to_string()),
+                Err(_) => Err(invalid("expected an integer".to_string())),
+            },
+            "number" => match trimmed.parse::<f64>() {
+                Ok(n) if n.is_finite() => Ok(trimmed.to_string()),
+                _ => Err(invalid("expected a number".to_string())),
+            },
+            _ => Ok(value.to_string()),
+        }
+    }
+}
+
 impl Default for VariableDefinition {
     fn default() -> Self {
         VariableDefinition {
@@ -60,3 +100,45 @@ impl Default for TmplSection {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_choices_and_types() {
+        let flavor = VariableDefinition {
+            choices: Some(vec!["zygisk".to_string(), "riru".to_string()]),
+            ..Default::default()
+        };
+        assert_eq!(flavor.validate("flavor", "riru").unwrap(), "riru");
+        assert!(matches!(
+            flavor.validate("flavor", "magic"),
+            Err(KamError::InvalidTemplateVar(_))
+        ));
+
+        let enabled = VariableDefinition {
+            var_type: "bool".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(enabled.validate("enabled", "Yes").unwrap(), "true");
+        assert_eq!(enabled.validate("enabled", "0").unwrap(), "false");
+        assert!(enabled.validate("enabled", "maybe").is_err());
+
+        let port = VariableDefinition {
+            var_type: "number".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(port.validate("port", " 8080 ").unwrap(), "8080");
+        assert!(port.validate("port", "eighty").is_err());
+        assert!(port.validate("port", "NaN").is_err());
+
+        // Free-form types are substituted as given
+        assert_eq!(
+            VariableDefinition::default()
+                .validate("name", " x ")
+                .unwrap(),
+            " x "
+        );
+    }
+}
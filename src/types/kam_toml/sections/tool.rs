@@ -4,8 +4,8 @@ use serde::{Deserialize, Serialize};
 #[allow(non_snake_case)]
 /// Tool section for custom tool configurations
 pub struct ToolSection {
-    // Add fields as needed
-    pub data: Option<serde_json::Value>,
+    /// 任意工具配置，按 TOML 原样保存（日期时间、嵌套表等类型不会丢失）
+    pub data: Option<toml::Value>,
 }
 
 impl Default for ToolSection {
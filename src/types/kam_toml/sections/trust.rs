@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+/// `[kam.trust]`：为依赖固定发布者的 ed25519 公钥（base64）
+///
+/// 固定了公钥的依赖在 `kam add` / `kam sync` 下载时必须带有有效的
+/// `<package>.sig` 签名（由 `kam publish --sign` 生成）。
+///
+/// ```toml
+/// [kam.trust]
+/// mylib = "3zqDLvQ2pR1fK0kCjW0tC2b2m0cX7qgYy0ZKz1dJfE8="
+/// ```
+pub struct TrustSection {
+    /// 模块 ID -> 公钥
+    #[serde(flatten)]
+    pub keys: BTreeMap<String, String>,
+}
+
+impl TrustSection {
+    /// 模块 `id` 被固定的公钥
+    pub fn key_for(&self, id: &str) -> Option<&str> {
+        self.keys.get(id).map(String::as_str)
+    }
+}
@@ -15,7 +15,10 @@ pub mod repo;
 pub mod template;
 
 // Re-export the common KamModule type for convenience
-pub use base::{DEFAULT_DEPENDENCY_SOURCE, KamModule, KamToml, ModuleBackend};
+pub use base::{
+    ArchiveFormat, DEFAULT_DEPENDENCY_SOURCE, KamModule, KamToml, ModuleBackend, extract_archive,
+    extract_archive_as, safe_entry_path,
+};
 pub use kam::KamSpecific;
 pub use library::LibraryModule;
 pub use repo::RepoModule;
@@ -1,6 +1,7 @@
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 // use git2 for repository operations instead of shelling out to `git`
-use git2::{Cred, CredentialType, FetchOptions, RemoteCallbacks, build::RepoBuilder};
+use git2::build::{CheckoutBuilder, RepoBuilder};
+use git2::{Cred, CredentialType, FetchOptions, ObjectType, RemoteCallbacks, Repository};
 
 use std::fs;
 use std::io::{self};
@@ -44,11 +45,11 @@ pub trait ModuleBackend {
 /// - `install_into_cache`: move or copy the fetched contents into the
 ///   provided `KamCache` and return the destination path inside the cache.
 ///
-/// Concurrency / atomicity: this trait does not prescribe locking semantics.
-/// The default `KamModule` implementation will overwrite an existing
-/// destination (remove + copy). If callers require concurrent-safe installs
-/// they should implement higher-level locking (for example file locks or
-/// a per-cache mutex) around calls to `install_into_cache`.
+/// Concurrency / atomicity: implementations should hold the cache lock
+/// (`KamCache::lock`) while writing into the cache. The default `KamModule`
+/// implementation fetches without the lock, then takes it to overwrite an
+/// existing destination (remove + copy), so concurrent `kam` invocations
+/// never interleave their writes.
 ///
 /// Note: the trait is intentionally small so callers can mock or provide
 /// alternate backends (HTTP, Git, local archives, etc.).
@@ -61,17 +62,16 @@ impl KamModule {
 
     /// Parse a source spec string and attach it to the KamModule constructed from KamToml.
     pub fn from_spec_and_toml(spec: &str, toml: KamToml) -> Result<Self> {
-        let src = Source::parse(spec)
-            .map_err(|e| KamError::ParseSourceFailed(format!("parse source spec: {}", e)))?;
+        let src = Source::parse(spec)?;
         Ok(Self::new(toml, Some(src)))
     }
 
-    /// Return a canonical name for installing into cache: id-version when available.
+    /// Return a canonical name for installing into cache: id-versionCode
+    /// (the layout of `KamCache::lib_module_path`) when an id is available.
     pub fn canonical_cache_name(&self) -> Option<String> {
         let id = &self.toml.prop.id;
-        let ver = &self.toml.prop.version;
-        if !id.is_empty() && !ver.is_empty() {
-            Some(format!("{}-{}", id, ver))
+        if !id.is_empty() {
+            Some(format!("{}-{}", id, self.toml.prop.versionCode))
         } else {
             None
         }
@@ -90,15 +90,33 @@ impl KamModule {
             }
         };
 
+        if crate::utils::is_offline() && !src.is_local() {
+            let location = match &src {
+                Source::Git { url, .. } | Source::Url { url, .. } => url.clone(),
+                Source::Local { path, .. } => path.display().to_string(),
+            };
+            return Err(KamError::OfflineUnavailable(format!(
+                "fetching {} needs the network",
+                location
+            )));
+        }
+
         match src {
-            Source::Local { path } => {
+            Source::Local { path, subdir } => {
                 let p = fs::canonicalize(&path).map_err(|e| KamError::Io(e))?;
                 if p.is_file() {
                     let tmp = tempdir()?;
                     extract_archive(&p, tmp.path())?;
                     let kept = tmp.keep();
-                    Ok(kept)
+                    narrow_to_subdir(kept, subdir.as_deref())
                 } else {
+                    let p = match subdir {
+                        Some(subdir) if !p.join(&subdir).is_dir() => {
+                            return Err(subdir_not_found(&subdir));
+                        }
+                        Some(subdir) => p.join(subdir),
+                        None => p,
+                    };
                     let tmp = tempdir()?;
                     let dst = tmp.path().join("src");
                     fs::create_dir_all(&dst)?;
@@ -107,9 +125,9 @@ impl KamModule {
                     Ok(kept)
                 }
             }
-            Source::Url { url } => {
+            Source::Url { url, subdir } => {
                 let tmp = tempdir()?;
-                let resp = reqwest::blocking::get(&url).map_err(|e| {
+                let resp = crate::utils::http_get(&url).map_err(|e| {
                     KamError::FetchFailed(format!("failed to download {}: {}", url, e))
                 })?;
                 if !resp.status().is_success() {
@@ -121,114 +139,37 @@ impl KamModule {
                 }
 
                 let mut data = Vec::new();
-                let mut reader = resp;
-                reader
-                    .copy_to(&mut data)
+                let total = resp.content_length();
+                crate::progress::download(resp, &mut data, &url, total)
                     .map_err(|e| KamError::FetchFailed(format!("read download body: {}", e)))?;
 
-                if url.ends_with(".tar.gz") || url.ends_with(".tgz") {
-                    let file = tmp.path().join("download.tar.gz");
+                if let Some(format) = ArchiveFormat::from_name(&url) {
+                    let file = tmp.path().join(format!("download{}", format.extension()));
                     fs::write(&file, &data)?;
-                    extract_tar_gz(&file, tmp.path())?;
+                    extract_archive_as(&file, format, tmp.path())?;
                     let kept = tmp.keep();
-                    return Ok(kept);
-                } else if url.ends_with(".zip") {
-                    let file = tmp.path().join("download.zip");
-                    fs::write(&file, &data)?;
-                    extract_zip(&file, tmp.path())?;
-                    let kept = tmp.keep();
-                    return Ok(kept);
+                    narrow_to_subdir(kept, subdir.as_deref())
                 } else {
                     let file = tmp.path().join("download.bin");
                     fs::write(&file, &data)?;
                     let kept = tmp.keep();
-                    return Ok(kept);
+                    narrow_to_subdir(kept, subdir.as_deref())
                 }
             }
-            Source::Git { url, rev } => {
+            Source::Git { url, rev, subdir } => {
                 let tmp = tempdir()?;
-
-                // Prepare credential callbacks: try SSH agent first, then optional
-                // SSH key path (KAM_GIT_SSH_KEY_PATH), token (KAM_GIT_TOKEN), or
-                // username/password (KAM_GIT_USERNAME / KAM_GIT_PASSWORD).
-                let mut callbacks = RemoteCallbacks::new();
-                callbacks.credentials(move |_, username_from_url, allowed| {
-                    // 1) SSH agent
-                    if allowed.contains(CredentialType::SSH_KEY) {
-                        if let Some(user) = username_from_url {
-                            if let Ok(c) = Cred::ssh_key_from_agent(user) {
-                                return Ok(c);
-                            }
-                        }
-                        if let Ok(c) = Cred::ssh_key_from_agent("git") {
-                            return Ok(c);
-                        }
-                    }
-
-                    // 2) SSH key file provided via env
-                    if allowed.contains(CredentialType::SSH_KEY) {
-                        if let Ok(key_path) = std::env::var("KAM_GIT_SSH_KEY_PATH") {
-                            let user = username_from_url.unwrap_or("git");
-                            // try public key path as key_path + ".pub"
-                            let pubkey_buf = std::path::PathBuf::from(format!("{}.pub", key_path));
-                            let privkey_buf = std::path::PathBuf::from(&key_path);
-                            let pubkey = pubkey_buf.as_path();
-                            let privkey = privkey_buf.as_path();
-                            if privkey.exists() {
-                                // ignore potential errors and try
-                                if let Ok(c) = Cred::ssh_key(user, Some(pubkey), privkey, None) {
-                                    return Ok(c);
-                                }
-                            }
-                        }
-                    }
-
-                    // 3) Token via env (use as basic auth password)
-                    if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) {
-                        if let Ok(token) = std::env::var("KAM_GIT_TOKEN") {
-                            // Some providers accept username 'x-access-token' or 'git'
-                            return Cred::userpass_plaintext("x-access-token", &token);
-                        }
-                        if let (Ok(user), Ok(pass)) = (
-                            std::env::var("KAM_GIT_USERNAME"),
-                            std::env::var("KAM_GIT_PASSWORD"),
-                        ) {
-                            return Cred::userpass_plaintext(&user, &pass);
-                        }
+                match KamCache::new() {
+                    Ok(cache) => fetch_git_cached(&cache, &url, rev.as_deref(), tmp.path())?,
+                    Err(_) => {
+                        let repo = clone_git(&url, rev.as_deref(), tmp.path())?;
+                        checkout_rev(&repo, rev.as_deref())?;
+                        drop(repo);
+                        // Only the checked-out files make up the module
+                        fs::remove_dir_all(tmp.path().join(".git"))?;
                     }
-
-                    // Fallback
-                    Cred::default()
-                });
-
-                let mut fo = FetchOptions::new();
-                fo.remote_callbacks(callbacks);
-                // request a shallow clone (depth 1) for remote transports.
-                // Some local transports (file://) don't support shallow fetches,
-                // so only set depth for non-file URLs.
-                if !url.starts_with("file://") {
-                    fo.depth(1);
-                }
-
-                let mut builder = RepoBuilder::new();
-                builder.fetch_options(fo);
-
-                let repo = builder
-                    .clone(&url, tmp.path())
-                    .map_err(|e| KamError::FetchFailed(format!("git clone {}: {}", url, e)))?;
-
-                if let Some(r) = rev {
-                    let obj = repo
-                        .revparse_single(&r)
-                        .map_err(|e| KamError::FetchFailed(format!("resolve rev {}: {}", r, e)))?;
-                    repo.checkout_tree(&obj, None)
-                        .map_err(|e| KamError::FetchFailed(format!("checkout tree: {}", e)))?;
-                    repo.set_head_detached(obj.id())
-                        .map_err(|e| KamError::FetchFailed(format!("set HEAD: {}", e)))?;
                 }
-
                 let kept = tmp.keep();
-                Ok(kept)
+                narrow_to_subdir(kept, subdir.as_deref())
             }
         }
     }
@@ -244,8 +185,8 @@ impl KamModule {
         } else {
             match &self.source {
                 Some(Source::Git { url, .. }) => sanitize_name(url),
-                Some(Source::Url { url }) => sanitize_name(url),
-                Some(Source::Local { path }) => sanitize_name(&path.to_string_lossy()),
+                Some(Source::Url { url, .. }) => sanitize_name(url),
+                Some(Source::Local { path, .. }) => sanitize_name(&path.to_string_lossy()),
                 None => {
                     return Err(KamError::ParseSourceFailed(
                         "no source available to derive name".to_string(),
@@ -255,6 +196,7 @@ impl KamModule {
         };
 
         let dest = cache.lib_dir().join(dest_name);
+        let _guard = cache.lock()?;
 
         // Remove any existing destination to ensure a clean install
         if dest.exists() {
@@ -311,6 +253,244 @@ impl ModuleBackend for KamModule {
     }
 }
 
+/// Whether a git `rev` names a commit (a hex object id), which never moves
+fn is_commit_id(rev: &str) -> bool {
+    (7..=40).contains(&rev.len()) && rev.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Cache directory name for a git source: the start of the sha256 of `key`
+fn git_cache_key(key: &str) -> String {
+    use sha2::{Digest, Sha256};
+    let digest = format!("{:x}", Sha256::digest(key.as_bytes()));
+    digest[..16].to_string()
+}
+
+/// Fetch options for `url`: credentials and, where possible, a shallow
+/// fetch
+fn git_fetch_options(url: &str, rev: Option<&str>) -> FetchOptions<'static> {
+    // Prepare credential callbacks: try SSH agent first, then optional
+    // SSH key path (KAM_GIT_SSH_KEY_PATH), token (KAM_GIT_TOKEN), or
+    // username/password (KAM_GIT_USERNAME / KAM_GIT_PASSWORD).
+    let mut callbacks = RemoteCallbacks::new();
+    callbacks.credentials(move |_, username_from_url, allowed| {
+        // 1) SSH agent
+        if allowed.contains(CredentialType::SSH_KEY) {
+            if let Some(user) = username_from_url
+                && let Ok(c) = Cred::ssh_key_from_agent(user)
+            {
+                return Ok(c);
+            }
+            if let Ok(c) = Cred::ssh_key_from_agent("git") {
+                return Ok(c);
+            }
+        }
+
+        // 2) SSH key file provided via env
+        if allowed.contains(CredentialType::SSH_KEY)
+            && let Ok(key_path) = std::env::var("KAM_GIT_SSH_KEY_PATH")
+        {
+            let user = username_from_url.unwrap_or("git");
+            // try public key path as key_path + ".pub"
+            let pubkey_buf = std::path::PathBuf::from(format!("{}.pub", key_path));
+            let privkey_buf = std::path::PathBuf::from(&key_path);
+            let pubkey = pubkey_buf.as_path();
+            let privkey = privkey_buf.as_path();
+            // ignore potential errors and try
+            if privkey.exists()
+                && let Ok(c) = Cred::ssh_key(user, Some(pubkey), privkey, None)
+            {
+                return Ok(c);
+            }
+        }
+
+        // 3) Token via env (use as basic auth password)
+        if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) {
+            if let Ok(token) = std::env::var("KAM_GIT_TOKEN") {
+                // Some providers accept username 'x-access-token' or 'git'
+                return Cred::userpass_plaintext("x-access-token", &token);
+            }
+            if let (Ok(user), Ok(pass)) = (
+                std::env::var("KAM_GIT_USERNAME"),
+                std::env::var("KAM_GIT_PASSWORD"),
+            ) {
+                return Cred::userpass_plaintext(&user, &pass);
+            }
+        }
+
+        // Fallback
+        Cred::default()
+    });
+
+    let mut fo = FetchOptions::new();
+    fo.remote_callbacks(callbacks);
+    // request a shallow clone (depth 1) for remote transports.
+    // Local transports (file:// and plain paths) don't support
+    // shallow fetches, so only set depth for remote URLs. A pinned rev may be
+    // outside the default branch's tip, so it needs full history.
+    if !url.starts_with("file://") && !Path::new(url).exists() && rev.is_none() {
+        fo.depth(1);
+    }
+    fo
+}
+
+/// Clone `url` into `dest`
+fn clone_git(url: &str, rev: Option<&str>, dest: &Path) -> Result<Repository> {
+    let mut builder = RepoBuilder::new();
+    builder.fetch_options(git_fetch_options(url, rev));
+    builder
+        .clone(url, dest)
+        .map_err(|e| KamError::FetchFailed(format!("git clone {}: {}", url, e)))
+}
+
+/// Check out `rev` (the remote's default branch without one) in `repo`,
+/// detaching HEAD. Remote-tracking refs come first: local branches of a
+/// reused clone are stale, and branches other than the default only exist
+/// as `origin/<rev>`.
+fn checkout_rev(repo: &Repository, rev: Option<&str>) -> Result<()> {
+    let candidates = match rev {
+        Some(r) => [format!("origin/{}", r), r.to_string()],
+        None => ["origin/HEAD".to_string(), "HEAD".to_string()],
+    };
+    let name = rev.unwrap_or("HEAD");
+    let obj = candidates
+        .iter()
+        .find_map(|spec| repo.revparse_single(spec).ok())
+        .ok_or_else(|| KamError::FetchFailed(format!("resolve rev {}: not found", name)))?;
+    let obj = obj
+        .peel(ObjectType::Commit)
+        .map_err(|e| KamError::FetchFailed(format!("resolve rev {}: {}", name, e)))?;
+    repo.checkout_tree(&obj, Some(CheckoutBuilder::new().force()))
+        .map_err(|e| KamError::FetchFailed(format!("checkout tree: {}", e)))?;
+    repo.set_head_detached(obj.id())
+        .map_err(|e| KamError::FetchFailed(format!("set HEAD: {}", e)))?;
+    Ok(())
+}
+
+/// Fetch a git source into `dest` through the cache.
+///
+/// Each repository URL has one clone under `git/db/` that later fetches
+/// update in place instead of cloning again. Pinned commits are also kept
+/// as plain files under `git/checkouts/` (keyed by URL and commit) and
+/// copied from there without touching the network.
+fn fetch_git_cached(cache: &KamCache, url: &str, rev: Option<&str>, dest: &Path) -> Result<()> {
+    let git_dir = cache.git_dir();
+    let checkout = rev.filter(|r| is_commit_id(r)).map(|r| {
+        git_dir
+            .join("checkouts")
+            .join(git_cache_key(&format!("{}@{}", url, r)))
+    });
+    if let Some(checkout) = &checkout
+        && checkout.is_dir()
+    {
+        return Ok(copy_dir_all(checkout, dest)?);
+    }
+
+    let key = git_cache_key(url);
+    let _guard = cache.lock_named(&format!("git-{}", key))?;
+    let db = git_dir.join("db").join(&key);
+    // A shallow clone lacks the history a pinned rev may need
+    let existing = Repository::open(&db)
+        .ok()
+        .filter(|repo| rev.is_none() || !repo.is_shallow());
+    let repo = match existing {
+        Some(repo) => {
+            repo.find_remote("origin")
+                .and_then(|mut remote| {
+                    remote.fetch::<&str>(&[], Some(&mut git_fetch_options(url, rev)), None)
+                })
+                .map_err(|e| KamError::FetchFailed(format!("git fetch {}: {}", url, e)))?;
+            repo
+        }
+        None => {
+            if db.exists() {
+                fs::remove_dir_all(&db)?;
+            }
+            fs::create_dir_all(&db)?;
+            clone_git(url, rev, &db).inspect_err(|_| {
+                let _ = fs::remove_dir_all(&db);
+            })?
+        }
+    };
+    checkout_rev(&repo, rev)?;
+    copy_worktree(&db, dest)?;
+
+    if let Some(checkout) = checkout {
+        // Copied under another name first, so a checkout that exists is
+        // always complete
+        let staging = checkout.with_extension("partial");
+        if staging.exists() {
+            fs::remove_dir_all(&staging)?;
+        }
+        copy_worktree(&db, &staging)?;
+        fs::rename(&staging, &checkout)?;
+    }
+    Ok(())
+}
+
+/// Copy the checked-out files of the clone at `src` (not `.git`) to `dst`
+fn copy_worktree(src: &Path, dst: &Path) -> io::Result<()> {
+    fs::create_dir_all(dst)?;
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        if entry.file_name() == ".git" {
+            continue;
+        }
+        let dest_path = dst.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir_all(&entry.path(), &dest_path)?;
+        } else {
+            fs::copy(entry.path(), &dest_path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Narrow a fetched tree at `root` to its `subdir` (a `#path=` fragment):
+/// the subdirectory's contents become the root and the rest is removed.
+///
+/// Archives often wrap everything in a single top-level directory
+/// (`repo-main/`), so the subdirectory is also looked up inside it.
+fn narrow_to_subdir(root: PathBuf, subdir: Option<&str>) -> Result<PathBuf> {
+    let Some(subdir) = subdir else {
+        return Ok(root);
+    };
+    let mut dir = root.join(subdir);
+    if !dir.is_dir() {
+        let entries: Vec<PathBuf> = fs::read_dir(&root)?.flatten().map(|e| e.path()).collect();
+        if let [only] = entries.as_slice()
+            && only.is_dir()
+        {
+            dir = only.join(subdir);
+        }
+    }
+    if !dir.is_dir() {
+        let _ = fs::remove_dir_all(&root);
+        return Err(subdir_not_found(subdir));
+    }
+
+    let narrowed = tempdir()?.keep();
+    for entry in fs::read_dir(&dir)? {
+        let entry = entry?;
+        let dest = narrowed.join(entry.file_name());
+        if fs::rename(entry.path(), &dest).is_err() {
+            if entry.file_type()?.is_dir() {
+                copy_dir_all(&entry.path(), &dest)?;
+            } else {
+                fs::copy(entry.path(), &dest)?;
+            }
+        }
+    }
+    let _ = fs::remove_dir_all(&root);
+    Ok(narrowed)
+}
+
+fn subdir_not_found(subdir: &str) -> KamError {
+    KamError::FetchFailed(format!(
+        "subdirectory '{}' not found in the fetched source",
+        subdir
+    ))
+}
+
 fn sanitize_name(s: &str) -> String {
     let mut out = s.replace("https://", "").replace("http://", "");
     out = out.replace(['/', ':', '@'], "-");
@@ -346,44 +526,311 @@ fn copy_dir_all(src: &Path, dst: &Path) -> io::Result<()> {
     Ok(())
 }
 
-fn extract_zip(zip_path: &Path, dst: &Path) -> Result<()> {
-    let file = fs::File::open(zip_path)?;
-    let mut archive = zip::ZipArchive::new(file)?;
-    for i in 0..archive.len() {
-        let mut f = archive.by_index(i)?;
-        let outpath = dst.join(f.name());
-        if f.name().ends_with('/') {
-            fs::create_dir_all(&outpath)?;
+/// Archive formats understood by [`extract_archive`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Zip,
+    TarGz,
+    TarXz,
+    TarZst,
+}
+
+impl ArchiveFormat {
+    /// Detect the format from the suffix of a file name, path or URL
+    /// (case-insensitive)
+    pub fn from_name(name: &str) -> Option<ArchiveFormat> {
+        let name = name.to_ascii_lowercase();
+        let has = |suffixes: &[&str]| suffixes.iter().any(|s| name.ends_with(s));
+        if has(&[".zip"]) {
+            Some(ArchiveFormat::Zip)
+        } else if has(&[".tar.gz", ".tgz"]) {
+            Some(ArchiveFormat::TarGz)
+        } else if has(&[".tar.xz", ".txz"]) {
+            Some(ArchiveFormat::TarXz)
+        } else if has(&[".tar.zst", ".tzst"]) {
+            Some(ArchiveFormat::TarZst)
         } else {
-            if let Some(p) = outpath.parent() {
-                fs::create_dir_all(p)?;
-            }
-            let mut outfile = fs::File::create(&outpath)?;
-            io::copy(&mut f, &mut outfile)?;
+            None
+        }
+    }
+
+    /// Canonical file extension, including the leading dot
+    pub fn extension(self) -> &'static str {
+        match self {
+            ArchiveFormat::Zip => ".zip",
+            ArchiveFormat::TarGz => ".tar.gz",
+            ArchiveFormat::TarXz => ".tar.xz",
+            ArchiveFormat::TarZst => ".tar.zst",
+        }
+    }
+}
+
+/// Extract a `.zip`, `.tar.gz`/`.tgz`, `.tar.xz` or `.tar.zst` archive into
+/// `dst`, picking the format from the file name
+pub fn extract_archive(path: &Path, dst: &Path) -> Result<()> {
+    let format = ArchiveFormat::from_name(&path.to_string_lossy()).ok_or_else(|| {
+        KamError::UnsupportedArchive(format!(
+            "unsupported archive format: {} (expected .zip, .tar.gz, .tar.xz or .tar.zst)",
+            path.display()
+        ))
+    })?;
+    extract_archive_as(path, format, dst)
+}
+
+/// Extract the archive at `path` as `format` into `dst`.
+///
+/// Entries that would land outside `dst` (absolute names, `..` components,
+/// or links pointing out of it) fail the extraction with
+/// [`KamError::UnsafeArchivePath`]; archives may come from arbitrary URLs.
+pub fn extract_archive_as(path: &Path, format: ArchiveFormat, dst: &Path) -> Result<()> {
+    let _spinner = crate::progress::Progress::spinner(format!(
+        "Extracting {}",
+        path.file_name().unwrap_or_default().to_string_lossy()
+    ));
+    let file = fs::File::open(path)?;
+    match format {
+        ArchiveFormat::Zip => extract_zip(zip::ZipArchive::new(file)?, dst)?,
+        ArchiveFormat::TarGz => unpack_tar(flate2::read::GzDecoder::new(file), dst)?,
+        ArchiveFormat::TarXz => unpack_tar(
+            lzma_rust2::XzReader::new(io::BufReader::new(file), true),
+            dst,
+        )?,
+        ArchiveFormat::TarZst => unpack_tar(zstd::Decoder::new(file)?, dst)?,
+    }
+    Ok(())
+}
+
+/// Where the archive entry `name` goes below `dst`, or
+/// [`KamError::UnsafeArchivePath`] when it would escape `dst`
+pub fn safe_entry_path(dst: &Path, name: &str) -> Result<PathBuf> {
+    let unsafe_path = || KamError::UnsafeArchivePath(name.to_string());
+    let normalized = name.replace('\\', "/");
+    let relative = Path::new(&normalized);
+    // `C:/...` or `C:...` is absolute (or drive-relative) on Windows
+    let drive = normalized
+        .split('/')
+        .next()
+        .is_some_and(|s| s.contains(':'));
+    if relative.has_root() || drive {
+        return Err(unsafe_path());
+    }
+    let mut path = dst.to_path_buf();
+    for component in relative.components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            _ => return Err(unsafe_path()),
+        }
+    }
+    Ok(path)
+}
+
+/// Check that a link entry `name` pointing at `target` stays inside the
+/// extraction directory
+fn check_link_target(name: &str, target: &Path) -> Result<()> {
+    let escapes = || KamError::UnsafeArchivePath(format!("{} -> {}", name, target.display()));
+    if target.has_root() {
+        return Err(escapes());
+    }
+    // Depth below the extraction directory, starting at the link's parent
+    let mut depth = Path::new(name).components().count().saturating_sub(1);
+    for component in target.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::ParentDir if depth > 0 => depth -= 1,
+            _ => return Err(escapes()),
         }
     }
     Ok(())
 }
 
-fn extract_tar_gz(tar_path: &Path, dst: &Path) -> Result<()> {
-    let f = fs::File::open(tar_path)?;
-    let decompressor = flate2::read::GzDecoder::new(f);
-    let mut archive = tar::Archive::new(decompressor);
-    archive.unpack(dst)?;
+/// Extract a zip after checking every entry stays inside `dst`. On Unix the
+/// stored permission bits of each entry (the build writes `0o755`) are
+/// applied to the extracted file, so `service.sh` and `bin/` tools stay
+/// executable.
+fn extract_zip(mut archive: zip::ZipArchive<fs::File>, dst: &Path) -> Result<()> {
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        let name = entry.name().to_string();
+        safe_entry_path(dst, &name)?;
+        if entry.is_symlink() {
+            let mut target = String::new();
+            io::Read::read_to_string(&mut entry, &mut target)?;
+            check_link_target(&name, Path::new(&target))?;
+        }
+    }
+    archive.extract(dst)?;
     Ok(())
 }
 
-fn extract_archive(path: &Path, dst: &Path) -> Result<()> {
-    let s = path.to_string_lossy().to_lowercase();
-    if s.ends_with(".zip") {
-        extract_zip(path, dst)?;
-    } else if s.ends_with(".tar.gz") || s.ends_with(".tgz") {
-        extract_tar_gz(path, dst)?;
-    } else {
-        return Err(KamError::UnsupportedArchive(format!(
-            "unsupported archive format: {}",
-            path.display()
-        )));
+fn unpack_tar(reader: impl io::Read, dst: &Path) -> Result<()> {
+    fs::create_dir_all(dst)?;
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().into_owned();
+        safe_entry_path(dst, &name)?;
+        if let Some(target) = entry.link_name()? {
+            if entry.header().entry_type().is_hard_link() {
+                // Hard links name another entry of the archive
+                safe_entry_path(dst, &target.to_string_lossy())?;
+            } else {
+                check_link_target(&name, &target)?;
+            }
+        }
+        entry.unpack_in(dst)?;
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_compressed_tars() {
+        let tmp = tempdir().unwrap();
+        let mut tar_data = Vec::new();
+        {
+            let mut builder = tar::Builder::new(&mut tar_data);
+            let content = b"id = \"demo\"\n";
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, "demo/kam.toml", &content[..])
+                .unwrap();
+            builder.finish().unwrap();
+        }
+
+        let xz_path = tmp.path().join("demo.tar.xz");
+        let mut xz = lzma_rust2::XzWriter::new(
+            fs::File::create(&xz_path).unwrap(),
+            lzma_rust2::XzOptions::default(),
+        )
+        .unwrap();
+        io::Write::write_all(&mut xz, &tar_data).unwrap();
+        xz.finish().unwrap();
+
+        let zst_path = tmp.path().join("demo.TAR.ZST");
+        fs::write(&zst_path, zstd::encode_all(&tar_data[..], 0).unwrap()).unwrap();
+
+        for path in [&xz_path, &zst_path] {
+            let dst = tmp
+                .path()
+                .join(path.file_name().unwrap())
+                .with_extension("out");
+            extract_archive(path, &dst).unwrap();
+            assert_eq!(
+                fs::read_to_string(dst.join("demo/kam.toml")).unwrap(),
+                "id = \"demo\"\n"
+            );
+        }
+
+        assert_eq!(
+            ArchiveFormat::from_name("https://example.com/m.tgz"),
+            Some(ArchiveFormat::TarGz)
+        );
+        assert!(extract_archive(&tmp.path().join("demo.rar"), tmp.path()).is_err());
+    }
+
+    #[test]
+    fn test_extract_rejects_path_traversal() {
+        let tmp = tempdir().unwrap();
+        let zip_path = tmp.path().join("evil.zip");
+        {
+            let mut zip = zip::ZipWriter::new(fs::File::create(&zip_path).unwrap());
+            let options = zip::write::SimpleFileOptions::default();
+            zip.start_file("kam.toml", options).unwrap();
+            io::Write::write_all(&mut zip, b"ok").unwrap();
+            zip.start_file("../evil", options).unwrap();
+            io::Write::write_all(&mut zip, b"pwned").unwrap();
+            zip.finish().unwrap();
+        }
+        let dst = tmp.path().join("out");
+        assert!(matches!(
+            extract_archive(&zip_path, &dst),
+            Err(KamError::UnsafeArchivePath(name)) if name == "../evil"
+        ));
+        assert!(!tmp.path().join("evil").exists());
+        assert!(!dst.join("kam.toml").exists());
+
+        // A symlink pointing out of the destination is rejected as well
+        let mut tar_data = Vec::new();
+        {
+            let mut builder = tar::Builder::new(&mut tar_data);
+            let mut header = tar::Header::new_gnu();
+            header.set_entry_type(tar::EntryType::Symlink);
+            header.set_size(0);
+            builder
+                .append_link(&mut header, "demo/link", "../../outside")
+                .unwrap();
+            builder.finish().unwrap();
+        }
+        let tgz_path = tmp.path().join("evil.tar.gz");
+        let mut gz = flate2::write::GzEncoder::new(
+            fs::File::create(&tgz_path).unwrap(),
+            flate2::Compression::default(),
+        );
+        io::Write::write_all(&mut gz, &tar_data).unwrap();
+        gz.finish().unwrap();
+        assert!(matches!(
+            extract_archive(&tgz_path, &dst),
+            Err(KamError::UnsafeArchivePath(_))
+        ));
+        assert!(!dst.join("demo/link").exists());
+
+        assert_eq!(
+            safe_entry_path(Path::new("/d"), "./a/b").unwrap(),
+            Path::new("/d/a/b")
+        );
+        assert!(safe_entry_path(Path::new("/d"), "a/b:c").is_ok());
+        for bad in ["/etc/passwd", "a/../../b", "..\\evil", "C:/evil", "C:evil"] {
+            assert!(safe_entry_path(Path::new("/d"), bad).is_err(), "{}", bad);
+        }
+    }
+    #[test]
+    fn test_git_sources_reuse_cached_clones() {
+        let tmp = tempdir().unwrap();
+        let cache = KamCache::with_root(tmp.path().join("cache")).unwrap();
+        let origin_path = tmp.path().join("origin");
+        let origin = Repository::init(&origin_path).unwrap();
+        let commit = |content: &str| {
+            fs::write(origin_path.join("kam.toml"), content).unwrap();
+            let mut index = origin.index().unwrap();
+            index.add_path(Path::new("kam.toml")).unwrap();
+            index.write().unwrap();
+            let tree = origin.find_tree(index.write_tree().unwrap()).unwrap();
+            let sig = git2::Signature::now("kam", "kam@example.com").unwrap();
+            let parent = origin.head().ok().and_then(|h| h.peel_to_commit().ok());
+            let parents: Vec<_> = parent.iter().collect();
+            origin
+                .commit(Some("HEAD"), &sig, &sig, content, &tree, &parents)
+                .unwrap()
+        };
+        let url = origin_path.to_str().unwrap();
+        let fetch = |rev: Option<&str>| {
+            let dest = tmp.path().join(format!("out-{}", rev.unwrap_or("head")));
+            let _ = fs::remove_dir_all(&dest);
+            fetch_git_cached(&cache, url, rev, &dest).map(|_| {
+                assert!(!dest.join(".git").exists());
+                fs::read_to_string(dest.join("kam.toml")).unwrap()
+            })
+        };
+
+        let first = commit("v1").to_string();
+        assert_eq!(fetch(None).unwrap(), "v1");
+        // The clone is reused: a later fetch picks up new commits
+        commit("v2");
+        assert_eq!(fetch(None).unwrap(), "v2");
+        assert_eq!(fs::read_dir(cache.git_dir().join("db")).unwrap().count(), 1);
+
+        // Pinned commits are served from their checkout once cached
+        assert_eq!(fetch(Some(&first[..10])).unwrap(), "v1");
+        fs::remove_dir_all(&origin_path).unwrap();
+        assert_eq!(fetch(Some(&first[..10])).unwrap(), "v1");
+        assert!(fetch(None).is_err());
+    }
+}
@@ -0,0 +1,206 @@
+use std::ffi::OsStr;
+use std::path::Path;
+use std::process::Command;
+
+use crate::types::kam_toml::KamToml;
+use crate::types::kam_toml::sections::ManagerSection;
+
+/// Directory where root managers keep their files on a device
+const ADB_DIR: &str = "/data/adb";
+
+/// A root solution that loads Magisk-style modules
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RootManager {
+    Magisk,
+    KernelSu,
+    APatch,
+}
+
+impl RootManager {
+    pub const ALL: [RootManager; 3] = [
+        RootManager::Magisk,
+        RootManager::KernelSu,
+        RootManager::APatch,
+    ];
+
+    /// Key of the manager in `[mmrl.repo.manager]`
+    pub fn key(self) -> &'static str {
+        match self {
+            RootManager::Magisk => "magisk",
+            RootManager::KernelSu => "kernelsu",
+            RootManager::APatch => "apatch",
+        }
+    }
+
+    /// Directory under `/data/adb` that exists when the manager is installed
+    fn marker(self) -> &'static str {
+        match self {
+            RootManager::Magisk => "magisk",
+            RootManager::KernelSu => "ksu",
+            RootManager::APatch => "ap",
+        }
+    }
+
+    /// Version code of the installed manager, when it can be read
+    fn installed_version(self, adb: &Path) -> Option<i64> {
+        match self {
+            RootManager::Magisk => std::fs::read_to_string(adb.join("magisk/util_functions.sh"))
+                .ok()
+                .and_then(|script| magisk_version_code(&script))
+                .or_else(|| command_version("magisk", &["-V"])),
+            RootManager::KernelSu => command_version(adb.join("ksud"), &["debug", "version"]),
+            RootManager::APatch => command_version(adb.join("apd"), &["-V"]),
+        }
+    }
+}
+
+/// A root manager found on the device
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstalledManager {
+    pub manager: RootManager,
+    /// `None` when the manager is present but its version can't be read
+    pub version_code: Option<i64>,
+}
+
+/// Root managers installed on this device, or `None` when not running on
+/// Android (no `/data/adb`, the same check the cache uses)
+pub fn detect() -> Option<Vec<InstalledManager>> {
+    let adb = Path::new(ADB_DIR);
+    if !adb.is_dir() {
+        return None;
+    }
+    Some(
+        RootManager::ALL
+            .into_iter()
+            .filter(|m| adb.join(m.marker()).is_dir())
+            .map(|manager| InstalledManager {
+                manager,
+                version_code: manager.installed_version(adb),
+            })
+            .collect(),
+    )
+}
+
+/// The `[mmrl.repo.manager]` section of `kam_toml`
+fn manager_section(kam_toml: &KamToml) -> Option<&ManagerSection> {
+    kam_toml
+        .mmrl
+        .as_ref()
+        .and_then(|m| m.repo.as_ref())
+        .and_then(|r| r.manager.as_ref())
+}
+
+/// Whether `kam_toml` sets a `min` version for any root manager
+pub fn requires_min_version(kam_toml: &KamToml) -> bool {
+    manager_section(kam_toml).is_some_and(|section| {
+        RootManager::ALL
+            .iter()
+            .any(|m| section.get(m.key()).is_some_and(|c| c.min.is_some()))
+    })
+}
+
+/// Compare `installed` against the `[mmrl.repo.manager]` minimums of
+/// `kam_toml`. Returns one message per manager that is older than required
+/// or whose version could not be read.
+pub fn version_problems(kam_toml: &KamToml, installed: &[InstalledManager]) -> Vec<String> {
+    let Some(section) = manager_section(kam_toml) else {
+        return Vec::new();
+    };
+    let mut problems = Vec::new();
+    for found in installed {
+        let key = found.manager.key();
+        let Some(config) = section.get(key) else {
+            continue;
+        };
+        let Some(min) = config.min else {
+            continue;
+        };
+        match found.version_code {
+            Some(version) if config.satisfied_by(version) => {}
+            Some(version) => problems.push(format!(
+                "{} {} is installed, {} requires at least {}",
+                key, version, kam_toml.prop.id, min
+            )),
+            None => problems.push(format!(
+                "{} is installed but its version could not be read ({} requires at least {})",
+                key, kam_toml.prop.id, min
+            )),
+        }
+    }
+    problems
+}
+
+/// `MAGISK_VER_CODE` from Magisk's `util_functions.sh`
+fn magisk_version_code(script: &str) -> Option<i64> {
+    script.lines().find_map(|line| {
+        line.trim()
+            .strip_prefix("MAGISK_VER_CODE=")?
+            .trim_matches(['"', '\''])
+            .parse()
+            .ok()
+    })
+}
+
+/// The last number `program` prints when run with `args`
+fn command_version(program: impl AsRef<OsStr>, args: &[&str]) -> Option<i64> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    last_number(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// The last run of digits in `text`
+fn last_number(text: &str) -> Option<i64> {
+    text.split(|c: char| !c.is_ascii_digit())
+        .rfind(|part| !part.is_empty())?
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manager_versions() {
+        assert_eq!(
+            magisk_version_code("MAGISK_VER='27.0'\nMAGISK_VER_CODE=27000\n"),
+            Some(27000)
+        );
+        assert_eq!(last_number("Kernel Version: 11986\n"), Some(11986));
+        assert_eq!(last_number("apd"), None);
+
+        let mut kam_toml = KamToml::default();
+        let manager = kam_toml
+            .mmrl
+            .get_or_insert_with(Default::default)
+            .repo
+            .get_or_insert_with(Default::default)
+            .manager
+            .get_or_insert_with(Default::default);
+        manager.magisk.as_mut().unwrap().min = Some(26000);
+        manager.kernelsu.as_mut().unwrap().min = Some(11000);
+        let installed = [
+            InstalledManager {
+                manager: RootManager::Magisk,
+                version_code: Some(25200),
+            },
+            InstalledManager {
+                manager: RootManager::KernelSu,
+                version_code: Some(11986),
+            },
+            InstalledManager {
+                manager: RootManager::APatch,
+                version_code: None,
+            },
+        ];
+        assert_eq!(
+            version_problems(&kam_toml, &installed),
+            vec![format!(
+                "magisk 25200 is installed, {} requires at least 26000",
+                kam_toml.prop.id
+            )]
+        );
+    }
+}
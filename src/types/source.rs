@@ -1,15 +1,33 @@
-use crate::errors::Result;
-use std::path::PathBuf;
+use crate::errors::SourceParseError;
+use crate::errors::source::{closest_scheme, mistyped_scheme};
+use std::path::{Path, PathBuf};
+
+/// Shorthand prefixes accepted by `Source::parse` and the hosts they expand to
+pub const SHORTHAND_HOSTS: &[(&str, &str)] =
+    &[("gh:", "https://github.com"), ("gl:", "https://gitlab.com")];
+
+/// Fragment narrowing a source to a subdirectory: `<spec>#path=sub/dir`
+pub const SUBDIR_FRAGMENT: &str = "#path=";
 
 /// Flexible source specification for a Kam module.
+///
+/// `subdir` is the directory inside the fetched tree (repository, archive or
+/// local directory) that holds the module, for sources hosting several.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Source {
     /// Git repository URL with optional revision (branch/tag/commit)
-    Git { url: String, rev: Option<String> },
+    Git {
+        url: String,
+        rev: Option<String>,
+        subdir: Option<String>,
+    },
     /// Local filesystem path
-    Local { path: PathBuf },
+    Local {
+        path: PathBuf,
+        subdir: Option<String>,
+    },
     /// HTTP(S) URL pointing to an archive or raw source
-    Url { url: String },
+    Url { url: String, subdir: Option<String> },
 }
 
 impl Source {
@@ -17,50 +35,130 @@ impl Source {
     ///
     /// Supported forms (examples):
     /// - git+https://github.com/org/repo.git@v1.2.3
+    /// - git+https://github.com/org/repo.git#main (branch, tag or commit)
     /// - https://example.com/module.tar.gz
     /// - /path/to/local/module
     /// - file:///C:/path/to/module.tar.gz
-    pub fn parse(spec: &str) -> Result<Self> {
+    /// - gh:owner/repo@v1.2.3 (GitHub shorthand; `gl:` for GitLab)
+    ///
+    /// Any of them may end in `#path=sub/dir` to use only that subdirectory
+    /// of the fetched tree (e.g. `gh:org/monorepo@v2#path=modules/foo`).
+    ///
+    /// Mistyped specs are reported as a `SourceParseError` whose message
+    /// suggests the likely intended form (e.g. `htps://` -> `https://`).
+    pub fn parse(spec: &str) -> Result<Self, SourceParseError> {
         let s = spec.trim();
+        let (s, subdir) = match s.rsplit_once(SUBDIR_FRAGMENT) {
+            Some((s, subdir)) => (s.trim_end(), Some(Self::parse_subdir(spec, subdir)?)),
+            None => (s, None),
+        };
+        Ok(Self::parse_location(s)?.with_subdir(subdir))
+    }
+
+    /// The subdirectory of the fetched tree holding the module
+    pub fn subdir(&self) -> Option<&str> {
+        match self {
+            Source::Git { subdir, .. }
+            | Source::Local { subdir, .. }
+            | Source::Url { subdir, .. } => subdir.as_deref(),
+        }
+    }
+
+    /// This source narrowed to `subdir` (or to the whole tree with `None`)
+    pub fn with_subdir(mut self, subdir: Option<String>) -> Self {
+        match &mut self {
+            Source::Git { subdir: s, .. }
+            | Source::Local { subdir: s, .. }
+            | Source::Url { subdir: s, .. } => *s = subdir,
+        }
+        self
+    }
+
+    /// Check the `sub/dir` of a `#path=` fragment: a non-empty relative path
+    /// that stays inside the fetched tree
+    fn parse_subdir(spec: &str, subdir: &str) -> Result<String, SourceParseError> {
+        let invalid = |reason: &str| SourceParseError::InvalidSubdir {
+            spec: spec.trim().to_string(),
+            reason: reason.to_string(),
+        };
+        let subdir = subdir.trim().trim_end_matches('/');
+        if subdir.is_empty() {
+            return Err(invalid("empty path"));
+        }
+        let path = Path::new(subdir);
+        if path.has_root() || subdir.contains(':') {
+            return Err(invalid("the path must be relative"));
+        }
+        if path
+            .components()
+            .any(|c| matches!(c, std::path::Component::ParentDir))
+        {
+            return Err(invalid("the path must not leave the source with '..'"));
+        }
+        Ok(subdir.to_string())
+    }
+
+    /// Parse a spec without its `#path=` fragment
+    fn parse_location(s: &str) -> Result<Self, SourceParseError> {
+        if s.is_empty() {
+            return Err(SourceParseError::Empty);
+        }
 
         // git+...@rev
         if let Some(rest) = s.strip_prefix("git+") {
-            // split on last '@' to allow @ in URLs (rare) but handle rev
-            if let Some(idx) = rest.rfind('@') {
-                let (url_part, rev_part) = rest.split_at(idx);
-                let rev = rev_part.trim_start_matches('@').to_string();
-                return Ok(Source::Git {
-                    url: url_part.to_string(),
-                    rev: Some(rev),
-                });
+            return Self::parse_git(s, rest);
+        }
+
+        // gh:owner/repo[@rev] and gl:group/repo[@rev] shorthands
+        for (prefix, host) in SHORTHAND_HOSTS {
+            if let Some(rest) = s.strip_prefix(prefix) {
+                return Self::parse_shorthand(s, rest, host);
             }
-            return Ok(Source::Git {
-                url: rest.to_string(),
-                rev: None,
-            });
         }
 
         // file:// local path
         if let Some(rest) = s.strip_prefix("file://") {
+            if rest.trim().is_empty() {
+                return Err(SourceParseError::EmptyPath(s.to_string()));
+            }
             return Ok(Source::Local {
                 path: PathBuf::from(rest),
+                subdir: None,
             });
         }
 
         // http(s) URL
         if s.starts_with("http://") || s.starts_with("https://") {
-            return Ok(Source::Url { url: s.to_string() });
+            return Ok(Source::Url {
+                url: s.to_string(),
+                subdir: None,
+            });
         }
 
         // otherwise treat as local path if it exists or looks like a path
         let p = PathBuf::from(s);
         if p.exists() {
-            return Ok(Source::Local { path: p });
+            return Ok(Source::Local {
+                path: p,
+                subdir: None,
+            });
         }
 
-        // If it contains a scheme-like prefix (://) treat as URL
-        if s.contains("://") {
-            return Ok(Source::Url { url: s.to_string() });
+        // If it contains a scheme-like prefix (://) treat as URL, unless the
+        // scheme looks like a typo of one we know (e.g. `htps://`).
+        if let Some(idx) = s.find("://") {
+            let scheme = &s[..idx];
+            if let Some(known) = mistyped_scheme(scheme) {
+                return Err(SourceParseError::UnknownScheme {
+                    scheme: scheme.to_string(),
+                    spec: s.to_string(),
+                    suggestion: Some(format!("{}{}", known, &s[idx..])),
+                });
+            }
+            return Ok(Source::Url {
+                url: s.to_string(),
+                subdir: None,
+            });
         }
 
         // Fallback: treat as a Git URL if it ends with .git or contains ':' (scp-like)
@@ -68,10 +166,306 @@ impl Source {
             return Ok(Source::Git {
                 url: s.to_string(),
                 rev: None,
+                subdir: None,
             });
         }
 
         // As last resort, treat as local path (may not exist yet)
-        Ok(Source::Local { path: p })
+        Ok(Source::Local {
+            path: p,
+            subdir: None,
+        })
+    }
+
+    /// Whether fetching this source stays on the local machine: local paths
+    /// and `file://` git repositories or paths to a repository on disk
+    pub fn is_local(&self) -> bool {
+        match self {
+            Source::Local { .. } => true,
+            Source::Git { url, .. } => url.starts_with("file://") || Path::new(url).exists(),
+            Source::Url { .. } => false,
+        }
+    }
+
+    /// Expand the part of a `gh:`/`gl:` spec after the prefix into a git
+    /// source on `host`: `owner/repo[@rev]` (GitLab also allows subgroups).
+    fn parse_shorthand(spec: &str, rest: &str, host: &str) -> Result<Self, SourceParseError> {
+        let (path, rev) = match rest.rsplit_once('@') {
+            Some((path, rev)) => (path, Some(rev)),
+            None => (rest, None),
+        };
+        let path = path.trim_end_matches(".git");
+        let segments: Vec<&str> = path.split('/').collect();
+        let too_deep = host == "https://github.com" && segments.len() > 2;
+        if segments.len() < 2 || too_deep || segments.iter().any(|s| s.is_empty()) {
+            return Err(SourceParseError::MalformedGitSpec {
+                spec: spec.to_string(),
+                reason: format!(
+                    "expected {}owner/repo[@rev]",
+                    &spec[..spec.len() - rest.len()]
+                ),
+                suggestion: None,
+            });
+        }
+        if rev == Some("") {
+            return Err(SourceParseError::MalformedGitSpec {
+                spec: spec.to_string(),
+                reason: "empty revision after '@'".to_string(),
+                suggestion: Some(spec.trim_end_matches('@').to_string()),
+            });
+        }
+        Ok(Source::Git {
+            url: format!("{}/{}.git", host, path),
+            rev: rev.map(|r| r.to_string()),
+            subdir: None,
+        })
+    }
+
+    /// Parse the part of a `git+<url>[@rev]` spec after the `git+` prefix.
+    fn parse_git(spec: &str, rest: &str) -> Result<Self, SourceParseError> {
+        let malformed =
+            |reason: &str, suggestion: Option<String>| SourceParseError::MalformedGitSpec {
+                spec: spec.to_string(),
+                reason: reason.to_string(),
+                suggestion,
+            };
+
+        // `#rev` pins explicitly; otherwise split on the last '@' to allow @
+        // in URLs (rare) but handle rev
+        let (url, rev) = match rest.split_once('#') {
+            Some((url, rev)) => (url, Some(rev)),
+            None => match rest.rfind('@') {
+                Some(idx) => (&rest[..idx], Some(&rest[idx + 1..])),
+                None => (rest, None),
+            },
+        };
+
+        if url.is_empty() {
+            return Err(malformed("missing repository URL", None));
+        }
+        if rev == Some("") {
+            return Err(malformed("empty revision", Some(format!("git+{}", url))));
+        }
+
+        let rev_suffix = rev.map(|r| format!("@{}", r)).unwrap_or_default();
+        if let Some(idx) = url.find("://") {
+            let scheme = url[..idx].to_ascii_lowercase();
+            if !["https", "http", "ssh", "file", "git"].contains(&scheme.as_str()) {
+                return Err(malformed(
+                    &format!("unknown scheme '{}'", &url[..idx]),
+                    closest_scheme(&scheme).map(|k| {
+                        format!(
+                            "git+{}{}{}",
+                            k.trim_start_matches("git+"),
+                            &url[idx..],
+                            rev_suffix
+                        )
+                    }),
+                ));
+            }
+        } else if url
+            .split('/')
+            .next()
+            .map(|host| host.contains('.') && !host.starts_with('.') && !host.contains(':'))
+            .unwrap_or(false)
+        {
+            // `git+github.com/org/repo` - a host without a scheme
+            return Err(malformed(
+                "missing URL scheme",
+                Some(format!("git+https://{}{}", url, rev_suffix)),
+            ));
+        }
+
+        Ok(Source::Git {
+            url: url.to_string(),
+            rev: rev.map(|r| r.to_string()),
+            subdir: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_valid_specs_unchanged() {
+        assert_eq!(
+            Source::parse("git+https://github.com/org/repo.git@v1.2.3").unwrap(),
+            Source::Git {
+                url: "https://github.com/org/repo.git".to_string(),
+                rev: Some("v1.2.3".to_string()),
+                subdir: None,
+            }
+        );
+        assert_eq!(
+            Source::parse("https://example.com/module.tar.gz").unwrap(),
+            Source::Url {
+                url: "https://example.com/module.tar.gz".to_string(),
+                subdir: None,
+            }
+        );
+        assert_eq!(
+            Source::parse("git@github.com:org/repo.git").unwrap(),
+            Source::Git {
+                url: "git@github.com:org/repo.git".to_string(),
+                rev: None,
+                subdir: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_git_fragment_rev() {
+        assert_eq!(
+            Source::parse("git+https://example.com/org/repo.git#release/1.x").unwrap(),
+            Source::Git {
+                url: "https://example.com/org/repo.git".to_string(),
+                rev: Some("release/1.x".to_string()),
+                subdir: None,
+            }
+        );
+        assert_eq!(
+            Source::parse("git+git@github.com:org/repo.git#4f2c1a9").unwrap(),
+            Source::Git {
+                url: "git@github.com:org/repo.git".to_string(),
+                rev: Some("4f2c1a9".to_string()),
+                subdir: None,
+            }
+        );
+        assert!(matches!(
+            Source::parse("git+https://example.com/repo.git#"),
+            Err(SourceParseError::MalformedGitSpec { .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_shorthand() {
+        assert_eq!(
+            Source::parse("gh:foo/bar@abc123").unwrap(),
+            Source::Git {
+                url: "https://github.com/foo/bar.git".to_string(),
+                rev: Some("abc123".to_string()),
+                subdir: None,
+            }
+        );
+        assert_eq!(
+            Source::parse("gh:foo/bar").unwrap(),
+            Source::Git {
+                url: "https://github.com/foo/bar.git".to_string(),
+                rev: None,
+                subdir: None,
+            }
+        );
+        assert_eq!(
+            Source::parse("gl:group/sub/repo@v1.2.0").unwrap(),
+            Source::Git {
+                url: "https://gitlab.com/group/sub/repo.git".to_string(),
+                rev: Some("v1.2.0".to_string()),
+                subdir: None,
+            }
+        );
+        for bad in ["gh:foo", "gh:foo/", "gh:a/b/c", "gh:foo/bar@", "gl:/repo"] {
+            assert!(
+                matches!(
+                    Source::parse(bad),
+                    Err(SourceParseError::MalformedGitSpec { .. })
+                ),
+                "{}",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_subdir_fragment() {
+        assert_eq!(
+            Source::parse("gh:org/monorepo@v2#path=modules/foo").unwrap(),
+            Source::Git {
+                url: "https://github.com/org/monorepo.git".to_string(),
+                rev: Some("v2".to_string()),
+                subdir: Some("modules/foo".to_string()),
+            }
+        );
+        assert_eq!(
+            Source::parse("git+https://example.com/repo.git#main#path=foo/").unwrap(),
+            Source::Git {
+                url: "https://example.com/repo.git".to_string(),
+                rev: Some("main".to_string()),
+                subdir: Some("foo".to_string()),
+            }
+        );
+        assert_eq!(
+            Source::parse("https://example.com/all.tar.gz#path=foo")
+                .unwrap()
+                .subdir(),
+            Some("foo")
+        );
+        assert_eq!(
+            Source::parse("./mods#path=foo").unwrap(),
+            Source::Local {
+                path: PathBuf::from("./mods"),
+                subdir: Some("foo".to_string()),
+            }
+        );
+        for bad in ["./mods#path=", "./mods#path=/abs", "./mods#path=a/../../b"] {
+            assert!(
+                matches!(
+                    Source::parse(bad),
+                    Err(SourceParseError::InvalidSubdir { .. })
+                ),
+                "{}",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(Source::parse("  "), Err(SourceParseError::Empty));
+        assert!(matches!(
+            Source::parse("file://"),
+            Err(SourceParseError::EmptyPath(_))
+        ));
+        assert!(matches!(
+            Source::parse("git+"),
+            Err(SourceParseError::MalformedGitSpec { .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_suggests_fix() {
+        let err = Source::parse("htps://example.com/m.zip").unwrap_err();
+        assert_eq!(err.suggestion(), Some("https://example.com/m.zip"));
+        assert!(err.to_string().contains("did you mean"));
+
+        // Schemes that are not near a known one, and known ones in upper
+        // case, stay plain URLs
+        for spec in [
+            "s3://bucket/m.zip",
+            "ftp://example.com/m.zip",
+            "HTTPS://example.com/m.zip",
+            "HTTP://example.com/m.zip",
+        ] {
+            assert_eq!(
+                Source::parse(spec).unwrap(),
+                Source::Url {
+                    url: spec.to_string(),
+                    subdir: None
+                }
+            );
+        }
+
+        let err = Source::parse("git+github.com/org/repo.git@v1").unwrap_err();
+        assert_eq!(
+            err.suggestion(),
+            Some("git+https://github.com/org/repo.git@v1")
+        );
+
+        let err = Source::parse("git+htps://github.com/org/repo.git").unwrap_err();
+        assert_eq!(
+            err.suggestion(),
+            Some("git+https://github.com/org/repo.git")
+        );
     }
 }
@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+
+/// The MMRL/Magisk `update.json` manifest referenced by `prop.updateJson`.
+///
+/// ```json
+/// {
+///   "version": "1.0.0",
+///   "versionCode": 100,
+///   "zipUrl": "https://example.com/my_module-100.zip",
+///   "changelog": "https://example.com/CHANGELOG.md"
+/// }
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[allow(non_snake_case)]
+pub struct UpdateJson {
+    /// Human readable version, e.g. `1.0.0`
+    pub version: String,
+    /// Monotonic version code used by managers to detect updates
+    pub versionCode: i64,
+    /// Download URL of the module zip
+    pub zipUrl: String,
+    /// Changelog URL (empty when the module declares none)
+    pub changelog: String,
+}
+
+impl UpdateJson {
+    /// Serialize as pretty-printed JSON, the form written to `update.json`
+    pub fn to_json_string(&self) -> crate::errors::Result<String> {
+        serde_json::to_string_pretty(self).map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::kam_toml::KamToml;
+
+    #[test]
+    fn test_to_update_json() {
+        let mut kt = KamToml::default();
+        kt.prop.version = "1.2.3".to_string();
+        kt.prop.versionCode = 123;
+        // Without `[mmrl]` there is no changelog to point at
+        kt.mmrl = None;
+
+        let update = kt.to_update_json("https://example.com/m.zip");
+        assert_eq!(update.changelog, "");
+
+        let value: serde_json::Value =
+            serde_json::from_str(&update.to_json_string().unwrap()).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "version": "1.2.3",
+                "versionCode": 123,
+                "zipUrl": "https://example.com/m.zip",
+                "changelog": "",
+            })
+        );
+    }
+
+    #[test]
+    fn test_to_update_json_with_changelog() {
+        let mut kt = KamToml::default();
+        kt.prop.version = "1.2.3".to_string();
+        kt.prop.versionCode = 123;
+        kt.mmrl
+            .get_or_insert_with(Default::default)
+            .repo
+            .get_or_insert_with(Default::default)
+            .changelog = Some("https://example.com/CHANGELOG.md".to_string());
+
+        let update = kt.to_update_json("https://example.com/m.zip");
+        assert_eq!(update.changelog, "https://example.com/CHANGELOG.md");
+        let value: serde_json::Value =
+            serde_json::from_str(&update.to_json_string().unwrap()).unwrap();
+        assert_eq!(value["changelog"], "https://example.com/CHANGELOG.md");
+    }
+}
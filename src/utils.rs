@@ -1,5 +1,9 @@
 use colored::{Color, Colorize};
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use crate::status;
 
 pub struct Utils;
 
@@ -22,16 +26,16 @@ impl Utils {
             match op {
                 PrintOp::Create { is_dir } => {
                     let color = if is_dir { Color::Blue } else { Color::Green };
-                    println!("{}", format!("+ {}", rel).color(color));
+                    status!("{}", format!("+ {}", rel).color(color));
                 }
                 PrintOp::Update => {
-                    println!("{}", format!("~ {}", rel).color(Color::Yellow));
+                    status!("{}", format!("~ {}", rel).color(Color::Yellow));
                 }
                 PrintOp::Delete => {
-                    println!("{}", format!("- {}", rel).color(Color::Red));
+                    status!("{}", format!("- {}", rel).color(Color::Red));
                 }
                 PrintOp::Copy { from, to } => {
-                    println!(
+                    status!(
                         "{}",
                         format!("{} -> {} (copy)", from, to).color(Color::Cyan)
                     );
@@ -41,7 +45,7 @@ impl Utils {
                         LinkType::Soft => "-->",
                         LinkType::Hard => "==>",
                     };
-                    println!(
+                    status!(
                         "{}",
                         format!("{} {} {} (symlink)", rel, symbol, target).color(Color::Magenta)
                     );
@@ -49,7 +53,186 @@ impl Utils {
             }
         } else {
             // For existing files without force, perhaps do nothing or print update
-            println!("{}", format!("~ {}", rel).color(Color::Yellow));
+            status!("{}", format!("~ {}", rel).color(Color::Yellow));
+        }
+    }
+}
+
+/// Hex-encoded sha256 of a file's contents
+pub fn sha256_file(path: &Path) -> std::io::Result<String> {
+    use sha2::{Digest, Sha256};
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut std::fs::File::open(path)?, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Environment variable that enables offline mode, like `--offline`
+pub const OFFLINE_ENV: &str = "KAM_OFFLINE";
+
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// Enable offline mode for the rest of the process (`--offline`)
+pub fn set_offline() {
+    OFFLINE.store(true, Ordering::Relaxed);
+}
+
+/// Whether the network must not be used: `--offline`, or `KAM_OFFLINE` set
+/// to anything but an empty value, `0` or `false`
+pub fn is_offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+        || std::env::var(OFFLINE_ENV).is_ok_and(|v| {
+            let v = v.trim();
+            !v.is_empty() && v != "0" && !v.eq_ignore_ascii_case("false")
+        })
+}
+
+/// Default delay before the first HTTP retry; doubled on each further retry
+pub const HTTP_BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// Upper bound for a single wait between HTTP attempts, including a
+/// server-provided `Retry-After`
+const HTTP_MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// Number of attempts for HTTP requests: `KAM_HTTP_RETRIES`, default 3
+pub fn http_attempts() -> u32 {
+    std::env::var("KAM_HTTP_RETRIES")
+        .ok()
+        .and_then(|v| v.trim().parse::<u32>().ok())
+        .unwrap_or(3)
+        .max(1)
+}
+
+/// Send the request produced by `build`, retrying network errors and
+/// 5xx/429 responses up to `attempts` times in total.
+///
+/// Waits `base_delay * 2^n` between attempts, or the response's
+/// `Retry-After` when present. The last response is returned as is, so
+/// callers still see (and report) a final error status. `build` is called
+/// once per attempt so the request body can be rebuilt.
+pub fn send_with_retry<F>(
+    build: F,
+    attempts: u32,
+    base_delay: Duration,
+) -> reqwest::Result<reqwest::blocking::Response>
+where
+    F: Fn() -> reqwest::blocking::RequestBuilder,
+{
+    let mut attempt = 1;
+    loop {
+        let result = build().send();
+        let (reason, retry_after) = match &result {
+            Ok(resp) if is_transient_status(resp.status()) => {
+                (format!("HTTP {}", resp.status()), retry_after(resp))
+            }
+            Ok(_) => return result,
+            Err(e) if e.is_builder() => return result,
+            Err(e) => (e.to_string(), None),
+        };
+        if attempt >= attempts {
+            return result;
+        }
+        let backoff = base_delay.saturating_mul(1 << (attempt - 1).min(16));
+        let delay = retry_after.unwrap_or(backoff).min(HTTP_MAX_DELAY);
+        println!(
+            "  {} {}; retrying in {:.1}s (attempt {}/{})",
+            "!".yellow(),
+            reason,
+            delay.as_secs_f32(),
+            attempt + 1,
+            attempts
+        );
+        std::thread::sleep(delay);
+        attempt += 1;
+    }
+}
+
+/// GET `url`, retrying transient failures (see [`send_with_retry`])
+pub fn http_get_with_retry(
+    url: &str,
+    attempts: u32,
+    base_delay: Duration,
+) -> reqwest::Result<reqwest::blocking::Response> {
+    let client = reqwest::blocking::Client::new();
+    send_with_retry(|| client.get(url), attempts, base_delay)
+}
+
+/// GET `url` with the configured number of attempts (`KAM_HTTP_RETRIES`)
+pub fn http_get(url: &str) -> reqwest::Result<reqwest::blocking::Response> {
+    http_get_with_retry(url, http_attempts(), HTTP_BASE_DELAY)
+}
+
+fn is_transient_status(status: reqwest::StatusCode) -> bool {
+    status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+}
+
+/// `Retry-After` as delay-seconds or an HTTP date
+fn retry_after(resp: &reqwest::blocking::Response) -> Option<Duration> {
+    let value = resp
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    (date.with_timezone(&chrono::Utc) - chrono::Utc::now())
+        .to_std()
+        .ok()
+}
+
+/// Serve `files` (URL path and body) over HTTP on a local port for tests;
+/// other paths answer 404. Returns the base URL.
+#[cfg(test)]
+pub(crate) fn serve_files(files: Vec<(String, Vec<u8>)>) -> String {
+    let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+    let base = format!("http://{}", server.server_addr());
+    std::thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let response = match files.iter().find(|(path, _)| path == request.url()) {
+                Some((_, body)) => tiny_http::Response::from_data(body.clone()),
+                None => tiny_http::Response::from_data(Vec::new()).with_status_code(404),
+            };
+            let _ = request.respond(response);
         }
+    });
+    base
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_http_get_with_retry() {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/pkg.zip", server.server_addr());
+        let handle = std::thread::spawn(move || {
+            for status in [503, 429, 200] {
+                let request = server.recv().unwrap();
+                let mut response = tiny_http::Response::from_string("ok").with_status_code(status);
+                if status == 429 {
+                    response.add_header(tiny_http::Header::from_bytes("Retry-After", "0").unwrap());
+                }
+                request.respond(response).unwrap();
+            }
+            server
+        });
+
+        let resp = http_get_with_retry(&url, 3, Duration::from_millis(1)).unwrap();
+        assert!(resp.status().is_success());
+        assert_eq!(resp.text().unwrap(), "ok");
+
+        // Out of attempts: the last error response is returned
+        let server = handle.join().unwrap();
+        std::thread::spawn(move || {
+            let request = server.recv().unwrap();
+            request
+                .respond(tiny_http::Response::from_string("busy").with_status_code(502))
+                .unwrap();
+        });
+        let resp = http_get_with_retry(&url, 1, Duration::from_millis(1)).unwrap();
+        assert_eq!(resp.status().as_u16(), 502);
     }
 }
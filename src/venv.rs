@@ -1,7 +1,12 @@
 use crate::cache::KamCache;
 use crate::errors::KamError;
+use colored::Colorize;
+use std::collections::HashSet;
 use std::fs;
 use std::io::{BufReader, Read};
+
+mod activation;
+
 /// # Kam Virtual Environment System
 ///
 /// Virtual environment support for Kam modules, similar to Python's virtualenv.
@@ -15,14 +20,19 @@ use std::io::{BufReader, Read};
 ///
 /// ```text
 /// .kam_venv/
-/// ├── bin/         # Symlinks to cached binaries
-/// ├── lib/         # Symlinks to cached libraries
-/// ├── activate     # Activation script (Unix)
-/// ├── activate.sh  # Activation script (Unix)
-/// ├── activate.ps1 # Activation script (PowerShell)
-/// ├── activate.bat # Activation script (Windows)
-/// └── deactivate   # Deactivation script
+/// ├── bin/           # Symlinks to cached binaries
+/// ├── lib/           # Symlinks to cached libraries
+/// ├── activate       # Activation script (Unix)
+/// ├── activate.sh    # Activation script (Unix)
+/// ├── activate.ps1   # Activation script (PowerShell)
+/// ├── activate.bat   # Activation script (Windows)
+/// ├── deactivate     # Deactivation script
+/// ├── deactivate.bat # Deactivation script (Windows)
+/// └── .link_mode     # `symlink` or `copy`, detected when the venv is created
 /// ```
+///
+/// Activating puts the absolute `bin/` path on `PATH` and sets `KAM_VENV` to
+/// the venv root; deactivating restores both.
 use std::path::{Path, PathBuf};
 
 /// Virtual environment type
@@ -34,6 +44,91 @@ pub enum VenvType {
     Runtime,
 }
 
+impl std::fmt::Display for VenvType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VenvType::Development => write!(f, "development"),
+            VenvType::Runtime => write!(f, "runtime"),
+        }
+    }
+}
+
+/// How dependencies are linked into a venv
+///
+/// Detected once when the venv is created and recorded in `.link_mode`, so
+/// every later `sync` links the same way. Windows only allows symlinks with
+/// Developer Mode enabled (or elevated privileges); without it the venv holds
+/// copies that are refreshed on each sync.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkMode {
+    /// Symbolic links into the global cache
+    Symlink,
+    /// Copies of the cached files
+    Copy,
+}
+
+impl std::fmt::Display for LinkMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LinkMode::Symlink => write!(f, "symlink"),
+            LinkMode::Copy => write!(f, "copy"),
+        }
+    }
+}
+
+impl LinkMode {
+    /// Probe whether symlinks can be created inside `dir`
+    pub fn detect(dir: &Path) -> LinkMode {
+        let target = dir.join(".link_probe_target");
+        let link = dir.join(".link_probe");
+        let _ = fs::remove_file(&link);
+        let supported = fs::write(&target, "").is_ok() && symlink_file(&target, &link).is_ok();
+        let _ = fs::remove_file(&link);
+        let _ = fs::remove_file(&target);
+        if supported {
+            LinkMode::Symlink
+        } else {
+            LinkMode::Copy
+        }
+    }
+
+    fn parse(s: &str) -> Option<LinkMode> {
+        match s.trim() {
+            "symlink" => Some(LinkMode::Symlink),
+            "copy" => Some(LinkMode::Copy),
+            _ => None,
+        }
+    }
+}
+
+/// File in the venv root recording the [`LinkMode`]
+const LINK_MODE_FILE: &str = ".link_mode";
+/// File in the venv root listing binaries copied into `bin/` (copy mode)
+const COPIED_BINS_FILE: &str = ".copied_bins";
+
+/// An entry of a venv's `bin/` or `lib/` directory
+#[derive(Debug, Clone, PartialEq)]
+pub struct VenvLink {
+    /// File name inside the venv directory
+    pub name: String,
+    /// Cache path the entry points to; `None` for copied entries
+    pub target: Option<PathBuf>,
+    /// Whether the target still exists (always true for copies)
+    pub exists: bool,
+}
+
+/// Outcome of [`KamVenv::relocate`]
+#[derive(Debug, Default)]
+pub struct RelocateReport {
+    /// Entries re-pointed (or re-copied) at the current cache, with their
+    /// new source
+    pub fixed: Vec<(String, PathBuf)>,
+    /// Links that already pointed at the current cache
+    pub unchanged: usize,
+    /// Entries with no counterpart in the current cache
+    pub unresolved: Vec<String>,
+}
+
 /// Virtual environment for a Kam module
 #[derive(Debug)]
 pub struct KamVenv {
@@ -41,6 +136,8 @@ pub struct KamVenv {
     root: PathBuf,
     /// Type of environment
     venv_type: VenvType,
+    /// How dependencies are linked into the venv
+    link_mode: LinkMode,
 }
 
 impl KamVenv {
@@ -58,7 +155,9 @@ impl KamVenv {
         let v = KamVenv {
             root: root.to_path_buf(),
             venv_type,
+            link_mode: LinkMode::detect(root),
         };
+        fs::write(v.root.join(LINK_MODE_FILE), v.link_mode.to_string())?;
 
         // mark dev if requested
         if v.venv_type == VenvType::Development {
@@ -152,7 +251,7 @@ impl KamVenv {
                 };
 
                 let replaced = replace_placeholders(&name);
-                let outpath = v.root.join(replaced);
+                let outpath = crate::types::modules::safe_entry_path(&v.root, &replaced)?;
                 if entry.header().entry_type().is_dir() {
                     fs::create_dir_all(&outpath).map_err(|e| KamError::Io(e))?;
                 } else {
@@ -173,7 +272,7 @@ impl KamVenv {
                     }
                 }
             }
-            return Ok(v);
+            return v.with_activation_scripts();
         }
 
         // zip support
@@ -201,7 +300,7 @@ impl KamVenv {
 
                 // apply replacements to the path
                 let replaced = replace_placeholders(&name);
-                let outpath = v.root.join(replaced);
+                let outpath = crate::types::modules::safe_entry_path(&v.root, &replaced)?;
                 if entry.is_dir() {
                     fs::create_dir_all(&outpath).map_err(|e| KamError::Io(e))?;
                 } else {
@@ -222,7 +321,7 @@ impl KamVenv {
                     }
                 }
             }
-            return Ok(v);
+            return v.with_activation_scripts();
         }
 
         // finally, accept a pre-unpacked directory named by base
@@ -250,7 +349,7 @@ impl KamVenv {
                 };
 
                 let replaced = replace_placeholders(&name);
-                let outpath = v.root.join(replaced);
+                let outpath = crate::types::modules::safe_entry_path(&v.root, &replaced)?;
                 if entry.file_type().is_dir() {
                     fs::create_dir_all(&outpath).map_err(|e| KamError::Io(e))?;
                 } else if entry.file_type().is_file() {
@@ -270,7 +369,7 @@ impl KamVenv {
                     }
                 }
             }
-            return Ok(v);
+            return v.with_activation_scripts();
         }
 
         // Not found: fail rather than generating fallback scripts.
@@ -281,6 +380,13 @@ impl KamVenv {
         )))
     }
 
+    /// Point the activation scripts at this venv (see
+    /// [`activation::write_activation_scripts`])
+    fn with_activation_scripts(self) -> Result<KamVenv, KamError> {
+        activation::write_activation_scripts(&self.root)?;
+        Ok(self)
+    }
+
     /// Load an existing venv (no validation beyond existence)
     pub fn load(root: &Path) -> Result<KamVenv, KamError> {
         if !root.exists() {
@@ -295,9 +401,22 @@ impl KamVenv {
         } else {
             VenvType::Runtime
         };
+        // Venvs created before the link mode was recorded get it detected now
+        let link_mode = match fs::read_to_string(root.join(LINK_MODE_FILE))
+            .ok()
+            .and_then(|s| LinkMode::parse(&s))
+        {
+            Some(mode) => mode,
+            None => {
+                let mode = LinkMode::detect(root);
+                fs::write(root.join(LINK_MODE_FILE), mode.to_string())?;
+                mode
+            }
+        };
         Ok(KamVenv {
             root: root.to_path_buf(),
             venv_type,
+            link_mode,
         })
     }
 
@@ -307,6 +426,19 @@ impl KamVenv {
     pub fn venv_type(&self) -> VenvType {
         self.venv_type
     }
+    pub fn link_mode(&self) -> LinkMode {
+        self.link_mode
+    }
+    /// Warn that this venv holds copies when it could not use symlinks
+    pub fn warn_if_copying(&self) {
+        if self.link_mode == LinkMode::Copy {
+            println!(
+                "  {} Symlinks are unavailable, so dependencies are copied into the venv \
+                 (on Windows, enable Developer Mode to use symlinks)",
+                "!".yellow()
+            );
+        }
+    }
     pub fn bin_dir(&self) -> PathBuf {
         self.root.join("bin")
     }
@@ -329,23 +461,21 @@ impl KamVenv {
             )));
         }
 
-        // Create symlink (Unix) or copy (Windows)
-        #[cfg(unix)]
-        {
-            if venv_bin.exists() {
-                fs::remove_file(&venv_bin).map_err(|e| KamError::Io(e))?;
-            }
-            std::os::unix::fs::symlink(source_path, &venv_bin).map_err(|e| KamError::Io(e))?;
+        fs::create_dir_all(self.bin_dir())?;
+        if venv_bin.exists() || venv_bin.is_symlink() {
+            fs::remove_file(&venv_bin)?;
         }
-        #[cfg(not(unix))]
-        {
-            fs::create_dir_all(self.bin_dir()).map_err(|e| KamError::Io(e))?;
-            if venv_bin.exists() {
-                fs::remove_file(&venv_bin).map_err(|e| KamError::Io(e))?;
-            }
-            // Try symlink first, fallback to copy
-            if std::os::windows::fs::symlink_file(source_path, &venv_bin).is_err() {
-                fs::copy(source_path, &venv_bin).map_err(|e| KamError::Io(e))?;
+        match self.link_mode {
+            LinkMode::Symlink => symlink_file(source_path, &venv_bin)?,
+            LinkMode::Copy => {
+                fs::copy(source_path, &venv_bin)?;
+                let manifest = self.root.join(COPIED_BINS_FILE);
+                let mut copied = fs::read_to_string(&manifest).unwrap_or_default();
+                if !copied.lines().any(|l| l == name) {
+                    copied.push_str(name);
+                    copied.push('\n');
+                    fs::write(&manifest, copied)?;
+                }
             }
         }
 
@@ -354,12 +484,7 @@ impl KamVenv {
 
     /// Link a library (module id and version) from cache into the venv
     pub fn link_library(&self, id: &str, version: &str, cache: &KamCache) -> Result<(), KamError> {
-        // For libraries, link from global cache lib or lib64 based on arch
-        let cache_lib = if std::env::consts::ARCH == "x86_64" {
-            cache.lib64_dir()
-        } else {
-            cache.lib_dir()
-        };
+        let cache_lib = cache_lib_dir(cache);
         let venv_lib = self.lib_dir();
 
         if !cache_lib.exists() {
@@ -369,27 +494,185 @@ impl KamVenv {
             )));
         }
 
-        #[cfg(unix)]
-        {
-            if venv_lib.exists() {
-                fs::remove_dir_all(&venv_lib).map_err(|e| KamError::Io(e))?;
+        if venv_lib.is_symlink() {
+            fs::remove_file(&venv_lib)?;
+        } else if venv_lib.exists() {
+            fs::remove_dir_all(&venv_lib)?;
+        }
+        match self.link_mode {
+            LinkMode::Symlink => symlink_dir(&cache_lib, &venv_lib)?,
+            LinkMode::Copy => copy_dir_all(&cache_lib, &venv_lib)?,
+        }
+
+        Ok(())
+    }
+
+    /// Entries of `bin/`, sorted by name
+    pub fn bin_links(&self) -> Vec<VenvLink> {
+        dir_links(&self.bin_dir())
+    }
+
+    /// Entries of `lib/`, sorted by name. When `lib/` itself links to the
+    /// cache, each entry's target is the module directory inside it.
+    pub fn lib_links(&self) -> Vec<VenvLink> {
+        dir_links(&self.lib_dir())
+    }
+
+    /// Cache directory `lib/` links to, if it is a symlink
+    pub fn lib_target(&self) -> Option<PathBuf> {
+        link_target(&self.lib_dir())
+    }
+
+    /// Re-point the links in `bin/` and `lib/` at their counterparts in
+    /// `cache`, e.g. after the cache root moved, and refresh the activation
+    /// scripts for the venv's current location. Copied entries are copied
+    /// again from the cache.
+    ///
+    /// Binaries are matched on the module directory and file name of their
+    /// old target (`lib/<id>-<version>/bin/<name>`), then on the file name
+    /// alone.
+    pub fn relocate(&self, cache: &KamCache) -> Result<RelocateReport, KamError> {
+        let mut report = RelocateReport::default();
+
+        let copied = fs::read_to_string(self.root.join(COPIED_BINS_FILE)).unwrap_or_default();
+        for link in self.bin_links() {
+            let path = self.bin_dir().join(&link.name);
+            let is_link = path.is_symlink();
+            if !is_link && !copied.lines().any(|l| l == link.name) {
+                // Not created by link_binary (e.g. a template file)
+                continue;
             }
-            std::os::unix::fs::symlink(&cache_lib, &venv_lib).map_err(|e| KamError::Io(e))?;
+            let source = relocated_binary(cache, &link.name, link.target.as_deref());
+            let label = format!("bin/{}", link.name);
+            relink(&path, source, is_link, &label, &mut report)?;
         }
-        #[cfg(not(unix))]
-        {
-            if venv_lib.exists() {
-                fs::remove_dir_all(&venv_lib).map_err(|e| KamError::Io(e))?;
+
+        let venv_lib = self.lib_dir();
+        let cache_lib = cache_lib_dir(cache);
+        if venv_lib.is_symlink() {
+            let source = Some(cache_lib).filter(|p| p.is_dir());
+            relink(&venv_lib, source, true, "lib/", &mut report)?;
+        } else if venv_lib.is_dir() {
+            for link in self.lib_links() {
+                let source = [cache_lib.join(&link.name), cache.lib_dir().join(&link.name)]
+                    .into_iter()
+                    .find(|p| p.exists());
+                let path = venv_lib.join(&link.name);
+                let is_link = path.is_symlink();
+                let label = format!("lib/{}", link.name);
+                relink(&path, source, is_link, &label, &mut report)?;
             }
-            // Try symlink recursively, fallback to copy
-            if symlink_dir_all(&cache_lib, &venv_lib).is_err() {
-                copy_dir_all(&cache_lib, &venv_lib).map_err(|e| KamError::Io(e))?;
+        }
+
+        activation::write_activation_scripts(&self.root)?;
+        Ok(report)
+    }
+
+    /// Remove the links created by `link_binary`/`link_library`, keeping
+    /// the activation scripts, so dependencies can be relinked from scratch.
+    /// In copy mode the copied library tree and binaries are removed instead,
+    /// so no stale copies survive a sync.
+    pub fn clear_links(&self) -> Result<(), KamError> {
+        let venv_lib = self.lib_dir();
+        if venv_lib.is_symlink() {
+            fs::remove_file(&venv_lib)?;
+        } else if self.link_mode == LinkMode::Copy && venv_lib.exists() {
+            fs::remove_dir_all(&venv_lib)?;
+        }
+
+        if let Ok(entries) = fs::read_dir(self.bin_dir()) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.is_symlink() {
+                    fs::remove_file(&path)?;
+                }
             }
         }
 
+        let manifest = self.root.join(COPIED_BINS_FILE);
+        if let Ok(copied) = fs::read_to_string(&manifest) {
+            for name in copied.lines().filter(|l| !l.is_empty()) {
+                let path = self.bin_dir().join(name);
+                if path.is_file() {
+                    fs::remove_file(&path)?;
+                }
+            }
+            fs::remove_file(&manifest)?;
+        }
         Ok(())
     }
 
+    /// Remove what `sync` linked into the venv for library `id`: binaries
+    /// linked from its cached module directories and, in copy mode, its
+    /// copied module directories under `lib/`. A symlinked `lib/` is the
+    /// whole cache directory and is left alone. Returns the removed entries
+    /// relative to the venv root.
+    pub fn unlink_library(&self, id: &str, cache: &KamCache) -> Result<Vec<String>, KamError> {
+        let module_dirs: Vec<PathBuf> = cache
+            .cached_versions(id)
+            .into_iter()
+            .map(|code| cache.lib_module_path(id, &code.to_string()))
+            .collect();
+        let is_module_dir = |dir: &Path| {
+            dir.file_name()
+                .and_then(|n| n.to_str())
+                .and_then(|n| n.strip_prefix(id)?.strip_prefix('-'))
+                .is_some_and(|code| code.parse::<i64>().is_ok())
+        };
+        let mut removed = Vec::new();
+
+        // Symlinked binaries point into <cache>/lib/<id>-<version>/bin
+        for link in self.bin_links() {
+            let from_module = link
+                .target
+                .as_deref()
+                .and_then(|t| t.parent())
+                .filter(|bin| bin.file_name().is_some_and(|n| n == "bin"))
+                .and_then(|bin| bin.parent())
+                .is_some_and(is_module_dir);
+            if from_module {
+                fs::remove_file(self.bin_dir().join(&link.name))?;
+                removed.push(format!("bin/{}", link.name));
+            }
+        }
+
+        // Copied binaries are recognized by name
+        let manifest = self.root.join(COPIED_BINS_FILE);
+        if let Ok(copied) = fs::read_to_string(&manifest) {
+            let module_bins: HashSet<String> = module_dirs
+                .iter()
+                .flat_map(|dir| dir_links(&dir.join("bin")))
+                .map(|link| link.name)
+                .collect();
+            let mut kept = String::new();
+            for name in copied.lines().filter(|l| !l.is_empty()) {
+                let path = self.bin_dir().join(name);
+                if module_bins.contains(name) && path.is_file() {
+                    fs::remove_file(&path)?;
+                    removed.push(format!("bin/{}", name));
+                } else {
+                    kept.push_str(name);
+                    kept.push('\n');
+                }
+            }
+            fs::write(&manifest, kept)?;
+        }
+
+        let venv_lib = self.lib_dir();
+        if !venv_lib.is_symlink()
+            && let Ok(entries) = fs::read_dir(&venv_lib)
+        {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.is_dir() && is_module_dir(&path) {
+                    fs::remove_dir_all(&path)?;
+                    removed.push(format!("lib/{}", entry.file_name().to_string_lossy()));
+                }
+            }
+        }
+        Ok(removed)
+    }
+
     /// Remove the virtual environment
     pub fn remove(self) -> Result<(), KamError> {
         if self.root.exists() {
@@ -399,31 +682,138 @@ impl KamVenv {
     }
 }
 
-/// Symlink a directory recursively (for Windows)
-#[cfg(not(unix))]
-fn symlink_dir_all(src: &Path, dst: &Path) -> std::io::Result<()> {
-    fs::create_dir_all(dst)?;
-    for entry in fs::read_dir(src)? {
-        let entry = entry?;
-        let src_path = entry.path();
-        let dst_path = dst.join(entry.file_name());
-        let ty = entry.file_type()?;
-        if ty.is_dir() {
-            if std::os::windows::fs::symlink_dir(&src_path, &dst_path).is_err() {
-                symlink_dir_all(&src_path, &dst_path)?;
-            }
-        } else {
-            if std::os::windows::fs::symlink_file(&src_path, &dst_path).is_err() {
-                fs::copy(&src_path, &dst_path)?;
-            }
+/// Cache directory linked as a venv's `lib/`: `lib64` on x86_64, `lib`
+/// elsewhere
+fn cache_lib_dir(cache: &KamCache) -> PathBuf {
+    if std::env::consts::ARCH == "x86_64" {
+        cache.lib64_dir()
+    } else {
+        cache.lib_dir()
+    }
+}
+
+/// Current cache location of a binary that was linked from `old_target`
+fn relocated_binary(cache: &KamCache, name: &str, old_target: Option<&Path>) -> Option<PathBuf> {
+    let mut candidates = Vec::new();
+    // <old cache>/<lib dir>/<id>-<version>/bin/<name>
+    if let Some(module_dir) = old_target
+        .and_then(|t| t.parent())
+        .filter(|bin| bin.file_name().is_some_and(|n| n == "bin"))
+        .and_then(|bin| bin.parent())
+        && let (Some(module), Some(lib)) = (
+            module_dir.file_name(),
+            module_dir.parent().and_then(|p| p.file_name()),
+        )
+    {
+        candidates.push(cache.root().join(lib).join(module).join("bin").join(name));
+    }
+    candidates.push(cache.bin_path(name));
+    if let Ok(entries) = fs::read_dir(cache.lib_dir()) {
+        let mut modules: Vec<PathBuf> = entries.flatten().map(|e| e.path()).collect();
+        modules.sort();
+        candidates.extend(modules.into_iter().map(|m| m.join("bin").join(name)));
+    }
+    candidates.into_iter().find(|p| p.is_file())
+}
+
+/// Point the venv entry at `path` to `source` (a link, or a fresh copy),
+/// recording the outcome under `label`
+fn relink(
+    path: &Path,
+    source: Option<PathBuf>,
+    is_link: bool,
+    label: &str,
+    report: &mut RelocateReport,
+) -> Result<(), KamError> {
+    let Some(source) = source else {
+        report.unresolved.push(label.to_string());
+        return Ok(());
+    };
+    if is_link && link_target(path).as_deref() == Some(source.as_path()) {
+        report.unchanged += 1;
+        return Ok(());
+    }
+
+    if is_link || path.is_file() {
+        fs::remove_file(path)?;
+    } else if path.is_dir() {
+        fs::remove_dir_all(path)?;
+    }
+    match (is_link, source.is_dir()) {
+        (true, true) => symlink_dir(&source, path)?,
+        (true, false) => symlink_file(&source, path)?,
+        (false, true) => copy_dir_all(&source, path)?,
+        (false, false) => {
+            fs::copy(&source, path)?;
         }
     }
+    report.fixed.push((label.to_string(), source));
     Ok(())
 }
 
-/// Copy a directory recursively (for Windows)
-#[cfg(not(unix))]
-fn copy_dir_all(src: &Path, dst: &Path) -> std::io::Result<()> {
+/// Target of the symlink at `path`, made absolute relative to its parent
+fn link_target(path: &Path) -> Option<PathBuf> {
+    let target = fs::read_link(path).ok()?;
+    Some(match path.parent() {
+        Some(parent) if target.is_relative() => parent.join(target),
+        _ => target,
+    })
+}
+
+/// Describe the entries of `dir` (following `dir` itself if it is a link),
+/// skipping hidden files such as the template's `.metadata`
+fn dir_links(dir: &Path) -> Vec<VenvLink> {
+    let dir_target = link_target(dir);
+    let mut links: Vec<VenvLink> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+                .map(|entry| {
+                    let name = entry.file_name().to_string_lossy().to_string();
+                    let path = entry.path();
+                    match link_target(&path) {
+                        Some(target) => VenvLink {
+                            name,
+                            target: Some(target),
+                            exists: path.exists(),
+                        },
+                        None => VenvLink {
+                            target: dir_target.as_ref().map(|d| d.join(&name)),
+                            name,
+                            exists: true,
+                        },
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    links.sort_by(|a, b| a.name.cmp(&b.name));
+    links
+}
+
+#[cfg(unix)]
+fn symlink_file(src: &Path, dst: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(src, dst)
+}
+
+#[cfg(unix)]
+fn symlink_dir(src: &Path, dst: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(src, dst)
+}
+
+#[cfg(windows)]
+fn symlink_file(src: &Path, dst: &Path) -> std::io::Result<()> {
+    std::os::windows::fs::symlink_file(src, dst)
+}
+
+#[cfg(windows)]
+fn symlink_dir(src: &Path, dst: &Path) -> std::io::Result<()> {
+    std::os::windows::fs::symlink_dir(src, dst)
+}
+
+/// Copy a directory recursively (copy link mode, vendoring)
+pub(crate) fn copy_dir_all(src: &Path, dst: &Path) -> std::io::Result<()> {
     fs::create_dir_all(dst)?;
     for entry in fs::read_dir(src)? {
         let entry = entry?;
@@ -0,0 +1,295 @@
+use std::fs;
+use std::path::Path;
+
+/// First line of the block kam maintains in each activation script
+const BLOCK_START: &str = ">>> kam venv >>>";
+/// Last line of the block kam maintains in each activation script
+const BLOCK_END: &str = "<<< kam venv <<<";
+
+/// Make the venv's activation scripts put the absolute `bin/` on `PATH`,
+/// export `KAM_VENV` and restore both on deactivate.
+///
+/// Scripts extracted from the venv template get a kam-managed block (the
+/// POSIX and PowerShell ones at the end, so its `deactivate` wins; the batch
+/// one at the top, before the template's own `SETLOCAL`). A script missing
+/// from the template is generated with just that block. Re-running replaces
+/// the block, so the scripts can be refreshed after the venv moves.
+pub(crate) fn write_activation_scripts(root: &Path) -> std::io::Result<()> {
+    let root = fs::canonicalize(root)?;
+    let root_str = root.to_string_lossy().to_string();
+    let bin_str = root.join("bin").to_string_lossy().to_string();
+
+    let sh = sh_block(&root_str, &bin_str);
+    update_script(
+        &root.join("activate"),
+        &sh,
+        "#",
+        Placement::End,
+        "#!/bin/sh\n",
+    )?;
+    if root.join("activate.sh").exists() {
+        update_script(&root.join("activate.sh"), &sh, "#", Placement::End, "")?;
+    }
+    update_script(
+        &root.join("activate.ps1"),
+        &ps1_block(&root_str, &bin_str),
+        "#",
+        Placement::End,
+        "",
+    )?;
+    update_script(
+        &root.join("activate.bat"),
+        &bat_activate_block(&root_str, &bin_str),
+        "REM",
+        Placement::AfterEchoOff,
+        "@echo off\n",
+    )?;
+    update_script(
+        &root.join("deactivate.bat"),
+        BAT_DEACTIVATE_BLOCK,
+        "REM",
+        Placement::AfterEchoOff,
+        "@echo off\n",
+    )?;
+    Ok(())
+}
+
+/// Where the managed block goes in a script that does not have it yet
+enum Placement {
+    End,
+    AfterEchoOff,
+}
+
+/// Replace the managed block of the script at `path`, insert it, or create
+/// the script as `header` followed by the block
+fn update_script(
+    path: &Path,
+    body: &str,
+    comment: &str,
+    placement: Placement,
+    header: &str,
+) -> std::io::Result<()> {
+    let block = format!(
+        "{c} {}\n{}{c} {}\n",
+        BLOCK_START,
+        body,
+        BLOCK_END,
+        c = comment
+    );
+    let content = match fs::read_to_string(path) {
+        Ok(existing) => insert_block(&existing, &block, placement),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => format!("{}{}", header, block),
+        Err(e) => return Err(e),
+    };
+    fs::write(path, content)
+}
+
+fn insert_block(existing: &str, block: &str, placement: Placement) -> String {
+    // Replace the block from the start of its first line to the end of its
+    // last line
+    if let Some(first) = existing.find(BLOCK_START)
+        && let Some(last) = existing[first..].find(BLOCK_END).map(|i| first + i)
+    {
+        let start = existing[..first].rfind('\n').map_or(0, |i| i + 1);
+        let end = existing[last..]
+            .find('\n')
+            .map_or(existing.len(), |i| last + i + 1);
+        return format!("{}{}{}", &existing[..start], block, &existing[end..]);
+    }
+    match placement {
+        Placement::End => {
+            let sep = if existing.is_empty() || existing.ends_with('\n') {
+                ""
+            } else {
+                "\n"
+            };
+            format!("{}{}\n{}", existing, sep, block)
+        }
+        Placement::AfterEchoOff => {
+            let first_line_end = existing.find('\n').map_or(existing.len(), |i| i + 1);
+            if existing[..first_line_end]
+                .trim()
+                .eq_ignore_ascii_case("@echo off")
+            {
+                format!(
+                    "{}{}{}",
+                    &existing[..first_line_end],
+                    block,
+                    &existing[first_line_end..]
+                )
+            } else {
+                format!("{}{}", block, existing)
+            }
+        }
+    }
+}
+
+/// Single-quote a value for POSIX sh
+fn sh_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Single-quote a value for PowerShell
+fn ps_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+fn sh_block(root: &str, bin: &str) -> String {
+    format!(
+        r#"# Managed by kam: puts bin/ on PATH and sets KAM_VENV; deactivate restores both
+if [ "${{KAM_VENV:-}}" != {root} ]; then
+    KAM_OLD_PATH="${{KAM_OLD_PATH-$PATH}}"
+    export KAM_OLD_PATH
+    if [ -n "${{KAM_VENV+x}}" ]; then
+        KAM_OLD_VENV="$KAM_VENV"
+        export KAM_OLD_VENV
+    fi
+    PATH={bin}:"$KAM_OLD_PATH"
+    KAM_VENV={root}
+    KAM_VENV_ACTIVE=1
+    export PATH KAM_VENV KAM_VENV_ACTIVE
+fi
+hash -r 2>/dev/null || true
+
+deactivate() {{
+    if [ -n "${{KAM_OLD_PATH+x}}" ]; then
+        PATH="$KAM_OLD_PATH"
+        export PATH
+        unset KAM_OLD_PATH
+    fi
+    if [ -n "${{KAM_OLD_PS1+x}}" ]; then
+        PS1="$KAM_OLD_PS1"
+        unset KAM_OLD_PS1
+    fi
+    if [ -n "${{KAM_OLD_VENV+x}}" ]; then
+        KAM_VENV="$KAM_OLD_VENV"
+        export KAM_VENV
+        unset KAM_OLD_VENV
+    else
+        unset KAM_VENV
+    fi
+    unset KAM_VENV_ACTIVE
+    unset -f deactivate
+    hash -r 2>/dev/null || true
+}}
+"#,
+        root = sh_quote(root),
+        bin = sh_quote(bin)
+    )
+}
+
+fn ps1_block(root: &str, bin: &str) -> String {
+    format!(
+        r#"# Managed by kam: puts bin\ on PATH and sets KAM_VENV; deactivate restores both
+if ($env:KAM_VENV -ne {root}) {{
+    if (-not (Test-Path env:KAM_OLD_PATH)) {{ $env:KAM_OLD_PATH = $env:PATH }}
+    if (Test-Path env:KAM_VENV) {{ $env:KAM_OLD_VENV = $env:KAM_VENV }}
+    $env:PATH = {bin} + [IO.Path]::PathSeparator + $env:KAM_OLD_PATH
+    $env:KAM_VENV = {root}
+    $env:KAM_VENV_ACTIVE = '1'
+}}
+
+function global:deactivate {{
+    if (Test-Path env:KAM_OLD_PATH) {{
+        $env:PATH = $env:KAM_OLD_PATH
+        Remove-Item env:KAM_OLD_PATH
+    }}
+    if (Test-Path env:KAM_OLD_VENV) {{
+        $env:KAM_VENV = $env:KAM_OLD_VENV
+        Remove-Item env:KAM_OLD_VENV
+    }} elseif (Test-Path env:KAM_VENV) {{
+        Remove-Item env:KAM_VENV
+    }}
+    if (Test-Path env:KAM_VENV_ACTIVE) {{ Remove-Item env:KAM_VENV_ACTIVE }}
+    Remove-Item function:deactivate
+}}
+"#,
+        root = ps_quote(root),
+        bin = ps_quote(bin)
+    )
+}
+
+fn bat_activate_block(root: &str, bin: &str) -> String {
+    // Each statement is on its own line: variables in a parenthesized block
+    // would be expanded before any of its `set`s run
+    let root = root.replace('%', "%%");
+    let bin = bin.replace('%', "%%");
+    format!(
+        r#"REM Managed by kam: puts bin\ on PATH and sets KAM_VENV; run deactivate.bat to restore both
+if "%KAM_VENV%"=="{root}" goto kam_venv_active
+if not defined KAM_OLD_PATH set "KAM_OLD_PATH=%PATH%"
+if defined KAM_VENV set "KAM_OLD_VENV=%KAM_VENV%"
+set "PATH={bin};%KAM_OLD_PATH%"
+set "KAM_VENV={root}"
+:kam_venv_active
+"#
+    )
+}
+
+const BAT_DEACTIVATE_BLOCK: &str = r#"REM Managed by kam: restores PATH and KAM_VENV saved by activate.bat
+if defined KAM_OLD_PATH set "PATH=%KAM_OLD_PATH%"
+set KAM_OLD_PATH=
+set KAM_VENV=
+if defined KAM_OLD_VENV set "KAM_VENV=%KAM_OLD_VENV%"
+set KAM_OLD_VENV=
+set KAM_VENV_ACTIVE=
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_activate_puts_bin_on_path() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().join(".kam_venv");
+        fs::create_dir_all(root.join("bin")).unwrap();
+        let tool = root.join("bin").join("kam-venv-test-tool");
+        fs::write(&tool, "#!/bin/sh\necho from-venv\n").unwrap();
+        fs::set_permissions(&tool, fs::Permissions::from_mode(0o755)).unwrap();
+
+        // A template script that lacks the block, and a missing activate.ps1
+        fs::write(root.join("activate"), "#!/bin/sh\necho template\n").unwrap();
+        write_activation_scripts(&root).unwrap();
+        // Refreshing replaces the block instead of adding another one
+        write_activation_scripts(&root).unwrap();
+
+        let activate = fs::read_to_string(root.join("activate")).unwrap();
+        assert!(activate.starts_with("#!/bin/sh\necho template\n"));
+        assert_eq!(activate.matches(BLOCK_START).count(), 1);
+        assert!(root.join("activate.ps1").exists());
+        assert!(
+            fs::read_to_string(root.join("activate.bat"))
+                .unwrap()
+                .starts_with("@echo off\nREM >>> kam venv >>>")
+        );
+
+        let script = format!(
+            ". {} >/dev/null && kam-venv-test-tool && echo \"$KAM_VENV\" && deactivate \
+             && echo \"${{KAM_VENV:-unset}}\" && command -v kam-venv-test-tool || echo gone",
+            sh_quote(&root.join("activate").to_string_lossy())
+        );
+        let output = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(script)
+            .env("PATH", "/usr/bin:/bin")
+            .env_remove("KAM_VENV")
+            .env_remove("KAM_OLD_PATH")
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let lines: Vec<&str> = stdout.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                "from-venv",
+                fs::canonicalize(&root).unwrap().to_str().unwrap(),
+                "unset",
+                "gone"
+            ]
+        );
+    }
+}